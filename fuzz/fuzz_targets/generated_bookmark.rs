@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use macos_bookmarks::generator::ArbitraryBookmark;
+use macos_bookmarks::parser::{BookmarkParser, ParseMode};
+
+// Parse structurally valid bookmarks with arbitrary TOC and record header changes
+fuzz_target!(|bookmark: ArbitraryBookmark| {
+    let data = bookmark.to_bytes();
+    let _ = macos_bookmarks::parser::parse_bookmark(&data);
+    let _ = BookmarkParser::new().mode(ParseMode::Lenient).parse(&data);
+    let _ = macos_bookmarks::anonymize::anonymize(&data);
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use macos_bookmarks::parser::{BookmarkParser, ParseMode};
+
+// Parse raw fuzzer input
+fuzz_target!(|data: &[u8]| {
+    let _ = macos_bookmarks::parser::parse_bookmark(data);
+    let _ = BookmarkParser::new().mode(ParseMode::Lenient).parse(data);
+});
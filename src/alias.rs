@@ -0,0 +1,492 @@
+//! Parse classic Alias Manager records (version 2 and 3)
+//!
+//! Older LoginItems, Dock and Finder sidebar artifacts store `alis` records instead of bookmarks. A fixed size
+//! record (volume, parent folder and target information) is followed by tagged extra data (CNID path, POSIX path,
+//! UTF-16 names, high resolution dates). All fields are big endian and dates are seconds since 1904-01-01 UTC.
+//! Layout follows https://mac-alias.readthedocs.io/en/latest/alias_fmt.html
+
+use std::mem::size_of;
+
+use log::{error, warn};
+use nom::{
+    bytes::complete::take,
+    number::complete::{be_i16, be_u16, be_u32, be_u64},
+};
+use serde::Serialize;
+
+use crate::{
+    bookmark::BookmarkData,
+    error::BookmarkError,
+    types::{Cnid, CocoaTime},
+};
+
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+pub struct AliasData {
+    pub version: u16,                       // Alias record version (2 or 3)
+    pub kind: u16,                          // Target kind (0 = file, 1 = folder)
+    pub volume_name: String,                // Name of Volume
+    pub volume_creation: Option<CocoaTime>, // Created timestamp of Volume
+    pub filesystem: String,                 // Filesystem signature (ex: H+)
+    pub disk_type: u16,                     // Disk type (0 = fixed, 1 = network, 5 = ejectable)
+    pub folder_cnid: u32,                   // CNID of the parent folder
+    pub filename: String,                   // Filename of the target
+    pub cnid: u32,                          // CNID of the target
+    pub creation: Option<CocoaTime>,        // Created timestamp of the target
+    pub creator_code: String,               // Classic creator code of the target
+    pub type_code: String,                  // Classic type code of the target
+    pub levels_from: i16,                   // Levels from the alias to the common parent folder
+    pub levels_to: i16,                     // Levels from the common parent folder to the target
+    pub volume_attributes: u32,             // Volume attribute flags
+    pub folder_name: Option<String>,        // Name of the parent folder
+    pub cnid_path: Vec<u32>,                // CNIDs of the folders to the target, parent first
+    pub carbon_path: Option<String>,        // Colon separated path (ex: Macintosh HD:Users:)
+    pub posix_path: Option<String>,         // POSIX path relative to the volume
+    pub mount_point: Option<String>,        // POSIX path to the volume mount point
+    pub user_home_prefix: Option<u16>,      // Length of the user home folder prefix in the path
+    pub other_tags: Vec<AliasTag>,          // Tagged data without a field (ex: AppleShare info)
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct AliasTag {
+    pub tag: i16,      // Tag number
+    pub data: Vec<u8>, // Raw tag data
+}
+
+/// Seconds between 1904-01-01 (HFS epoch) and 2001-01-01 (Cocoa epoch)
+const HFS_EPOCH_OFFSET: f64 = 3061152000.0;
+/// Tag ending the tagged data
+const TAG_END: i16 = -1;
+const TAG_FOLDER_NAME: i16 = 0;
+const TAG_CNID_PATH: i16 = 1;
+const TAG_CARBON_PATH: i16 = 2;
+const TAG_UNICODE_FILENAME: i16 = 14;
+const TAG_UNICODE_VOLUME_NAME: i16 = 15;
+const TAG_VOLUME_CREATION: i16 = 16;
+const TAG_CREATION: i16 = 17;
+const TAG_POSIX_PATH: i16 = 18;
+const TAG_MOUNT_POINT: i16 = 19;
+const TAG_USER_HOME_PREFIX: i16 = 21;
+
+/// Parse a classic alias record
+pub fn parse_alias(data: &[u8]) -> Result<AliasData, BookmarkError> {
+    match alias_record(data) {
+        Ok((_, alias)) => Ok(alias),
+        Err(err) => {
+            error!("Failed to parse alias data: {:?}", err);
+            Err(BookmarkError::InvalidAliasData)
+        }
+    }
+}
+
+impl AliasData {
+    /// Map the alias fields onto the bookmark schema. The path is built from the mount point and POSIX path, or
+    /// from the Carbon path if there is no POSIX path
+    pub fn to_bookmark(&self) -> BookmarkData {
+        let mut path: Vec<String> = Vec::new();
+        match &self.posix_path {
+            Some(posix_path) => {
+                let mount_point = self.mount_point.as_deref().unwrap_or_default();
+                path.extend(path_components(mount_point, '/'));
+                path.extend(path_components(posix_path, '/'));
+            }
+            // First component is the volume name
+            None => {
+                if let Some(carbon_path) = &self.carbon_path {
+                    path.extend(path_components(carbon_path, ':').skip(1));
+                }
+            }
+        }
+        if path.is_empty() && !self.filename.is_empty() {
+            path.push(self.filename.clone());
+        }
+
+        // Alias CNID paths start at the parent folder, bookmark CNID paths start at the root and end at the target
+        let mut cnid_path: Vec<Cnid> = self
+            .cnid_path
+            .iter()
+            .rev()
+            .map(|cnid| Cnid(*cnid as i64))
+            .collect();
+        if self.cnid != 0 {
+            cnid_path.push(Cnid(self.cnid as i64));
+        }
+
+        let (volume_path, volume_url) = match self.mount_point.as_deref() {
+            Some(mount_point) => {
+                let mount_point = mount_point.trim_end_matches('/');
+                (format!("{mount_point}/"), format!("file://{mount_point}/"))
+            }
+            None => (String::new(), String::new()),
+        };
+
+        let mut bookmark = BookmarkData {
+            path,
+            cnid_path,
+            creation: self.creation.unwrap_or_default(),
+            target_filename: (!self.filename.is_empty()).then(|| self.filename.clone()),
+            volume_path,
+            volume_url,
+            volume_name: self.volume_name.clone(),
+            volume_creation: self.volume_creation.unwrap_or_default(),
+            ..Default::default()
+        };
+        bookmark.has_persistent_ids = bookmark.persistent_ids();
+        bookmark
+    }
+}
+
+/// Split a path on a separator, dropping empty components
+fn path_components(path: &str, separator: char) -> impl Iterator<Item = String> + '_ {
+    path.split(separator)
+        .filter(|component| !component.is_empty())
+        .map(str::to_string)
+}
+
+/// Parse the alias header, fixed size record and tagged data
+fn alias_record(data: &[u8]) -> nom::IResult<&[u8], AliasData> {
+    let (input, _app_info) = take(size_of::<u32>())(data)?;
+    let (input, record_size) = be_u16(input)?;
+    let (input, version) = be_u16(input)?;
+
+    // Limit the tagged data to the record size
+    let record_data = data.get(..record_size as usize).unwrap_or(data);
+    let input = input
+        .get(..record_data.len().saturating_sub(8))
+        .unwrap_or(input);
+
+    let (input, mut alias) = match version {
+        2 => alias_v2(input)?,
+        3 => alias_v3(input)?,
+        _ => {
+            warn!("Unsupported alias version: {}", version);
+            return Err(nom::Err::Failure(nom::error::Error::new(
+                data,
+                nom::error::ErrorKind::Tag,
+            )));
+        }
+    };
+    alias.version = version;
+    let (input, ()) = alias_tags(input, &mut alias)?;
+    Ok((input, alias))
+}
+
+/// Parse the version 2 record. Names are Pascal strings and dates are in seconds
+fn alias_v2(data: &[u8]) -> nom::IResult<&[u8], AliasData> {
+    let (input, kind) = be_u16(data)?;
+    let (input, volume_name) = pascal_string(input, 28)?;
+    let (input, volume_creation) = be_u32(input)?;
+    let (input, filesystem) = take(2usize)(input)?;
+    let (input, disk_type) = be_u16(input)?;
+    let (input, folder_cnid) = be_u32(input)?;
+    let (input, filename) = pascal_string(input, 64)?;
+    let (input, cnid) = be_u32(input)?;
+    let (input, creation) = be_u32(input)?;
+    let (input, creator_code) = take(size_of::<u32>())(input)?;
+    let (input, type_code) = take(size_of::<u32>())(input)?;
+    let (input, levels_from) = be_i16(input)?;
+    let (input, levels_to) = be_i16(input)?;
+    let (input, volume_attributes) = be_u32(input)?;
+    let (input, _volume_fs_id) = take(2usize)(input)?;
+    let (input, _reserved) = take(10usize)(input)?;
+
+    let alias = AliasData {
+        kind,
+        volume_name,
+        volume_creation: hfs_time(volume_creation as f64),
+        filesystem: four_char_code(filesystem),
+        disk_type,
+        folder_cnid,
+        filename,
+        cnid,
+        creation: hfs_time(creation as f64),
+        creator_code: four_char_code(creator_code),
+        type_code: four_char_code(type_code),
+        levels_from,
+        levels_to,
+        volume_attributes,
+        ..Default::default()
+    };
+    Ok((input, alias))
+}
+
+/// Parse the version 3 record. Names are only stored in the tagged data and dates are 16.16 fixed point seconds
+fn alias_v3(data: &[u8]) -> nom::IResult<&[u8], AliasData> {
+    let (input, kind) = be_u16(data)?;
+    let (input, volume_creation) = be_u64(input)?;
+    let (input, filesystem) = take(size_of::<u32>())(input)?;
+    let (input, disk_type) = be_u16(input)?;
+    let (input, folder_cnid) = be_u32(input)?;
+    let (input, cnid) = be_u32(input)?;
+    let (input, creation) = be_u64(input)?;
+    let (input, volume_attributes) = be_u32(input)?;
+    let (input, _reserved) = take(14usize)(input)?;
+
+    let alias = AliasData {
+        kind,
+        volume_creation: hfs_time(fixed_point(volume_creation)),
+        filesystem: four_char_code(filesystem),
+        disk_type,
+        folder_cnid,
+        cnid,
+        creation: hfs_time(fixed_point(creation)),
+        volume_attributes,
+        ..Default::default()
+    };
+    Ok((input, alias))
+}
+
+/// Parse the tagged data until the end tag or the end of the record
+fn alias_tags<'a>(data: &'a [u8], alias: &mut AliasData) -> nom::IResult<&'a [u8], ()> {
+    let mut input = data;
+    while !input.is_empty() {
+        let (remaining, tag) = be_i16(input)?;
+        if tag == TAG_END {
+            return Ok((remaining, ()));
+        }
+        let (remaining, length) = be_u16(remaining)?;
+        let (remaining, value) = take(length)(remaining)?;
+        // Tag data is padded to an even length
+        let padding = (length % 2) as usize;
+        input = remaining.get(padding..).unwrap_or_default();
+
+        match tag {
+            TAG_FOLDER_NAME => alias.folder_name = Some(mac_string(value)),
+            TAG_CNID_PATH => {
+                alias.cnid_path = value
+                    .chunks_exact(size_of::<u32>())
+                    .map(|cnid| u32::from_be_bytes([cnid[0], cnid[1], cnid[2], cnid[3]]))
+                    .collect()
+            }
+            TAG_CARBON_PATH => alias.carbon_path = Some(mac_string(value)),
+            TAG_UNICODE_FILENAME => match unicode_string(value) {
+                Some(filename) => alias.filename = filename,
+                None => warn!("Failed to parse alias Unicode filename"),
+            },
+            TAG_UNICODE_VOLUME_NAME => match unicode_string(value) {
+                Some(volume_name) => alias.volume_name = volume_name,
+                None => warn!("Failed to parse alias Unicode volume name"),
+            },
+            TAG_VOLUME_CREATION => {
+                let (_, timestamp) = be_u64(value)?;
+                alias.volume_creation = hfs_time(fixed_point(timestamp));
+            }
+            TAG_CREATION => {
+                let (_, timestamp) = be_u64(value)?;
+                alias.creation = hfs_time(fixed_point(timestamp));
+            }
+            TAG_POSIX_PATH => alias.posix_path = Some(mac_string(value)),
+            TAG_MOUNT_POINT => alias.mount_point = Some(mac_string(value)),
+            TAG_USER_HOME_PREFIX => {
+                let (_, prefix) = be_u16(value)?;
+                alias.user_home_prefix = Some(prefix);
+            }
+            _ => alias.other_tags.push(AliasTag {
+                tag,
+                data: value.to_vec(),
+            }),
+        }
+    }
+    Ok((input, ()))
+}
+
+/// Read a Pascal string (length byte followed by the string) stored in a fixed size field
+fn pascal_string(data: &[u8], size: usize) -> nom::IResult<&[u8], String> {
+    let (input, field) = take(size)(data)?;
+    let length = field.first().copied().unwrap_or_default() as usize;
+    let value = field.get(1..).unwrap_or_default();
+    let value = value.get(..length).unwrap_or(value);
+    Ok((input, mac_string(value)))
+}
+
+/// Read a Unicode string (UTF-16 character count followed by big endian UTF-16)
+fn unicode_string(data: &[u8]) -> Option<String> {
+    let (value, count) = be_u16::<&[u8], ()>(data).ok()?;
+    let units: Vec<u16> = value
+        .chunks_exact(2)
+        .take(count as usize)
+        .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+        .collect();
+    String::from_utf16(&units).ok()
+}
+
+/// Read a classic Mac OS string. Non UTF-8 bytes (MacRoman) are replaced
+fn mac_string(data: &[u8]) -> String {
+    String::from_utf8_lossy(data).into_owned()
+}
+
+/// Read a four character code (ex: creator code), dropping null padding
+fn four_char_code(data: &[u8]) -> String {
+    mac_string(data).trim_end_matches('\0').to_string()
+}
+
+/// Convert a 16.16 fixed point timestamp to seconds
+fn fixed_point(timestamp: u64) -> f64 {
+    timestamp as f64 / 65536.0
+}
+
+/// Convert seconds since 1904-01-01 to a Cocoa timestamp. `None` if the timestamp is not set
+fn hfs_time(seconds: f64) -> Option<CocoaTime> {
+    if seconds == 0.0 {
+        return None;
+    }
+    Some(CocoaTime(seconds - HFS_EPOCH_OFFSET))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_alias, HFS_EPOCH_OFFSET};
+    use crate::types::Cnid;
+
+    // Build an alias record from the fixed size record and tagged data
+    fn build_alias(version: u16, record: &[u8], tags: &[(i16, &[u8])]) -> Vec<u8> {
+        let mut tag_data = Vec::new();
+        for (tag, value) in tags {
+            tag_data.extend_from_slice(&tag.to_be_bytes());
+            tag_data.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            tag_data.extend_from_slice(value);
+            if value.len() % 2 != 0 {
+                tag_data.push(0);
+            }
+        }
+        tag_data.extend_from_slice(&(-1i16).to_be_bytes());
+        tag_data.extend_from_slice(&0u16.to_be_bytes());
+
+        let size = 8 + record.len() + tag_data.len();
+        let mut alias = vec![0; 4];
+        alias.extend_from_slice(&(size as u16).to_be_bytes());
+        alias.extend_from_slice(&version.to_be_bytes());
+        alias.extend_from_slice(record);
+        alias.extend_from_slice(&tag_data);
+        alias
+    }
+
+    fn pascal(value: &str, size: usize) -> Vec<u8> {
+        let mut field = vec![value.len() as u8];
+        field.extend_from_slice(value.as_bytes());
+        field.resize(size, 0);
+        field
+    }
+
+    #[test]
+    fn test_parse_alias_v2() {
+        let creation = 3061152000u32 + 665473989;
+        let mut record = 0u16.to_be_bytes().to_vec();
+        record.extend(pascal("Macintosh HD", 28));
+        record.extend(creation.to_be_bytes());
+        record.extend(b"H+");
+        record.extend(0u16.to_be_bytes());
+        record.extend(706090u32.to_be_bytes());
+        record.extend(pascal("Syncthing.app", 64));
+        record.extend(706091u32.to_be_bytes());
+        record.extend(creation.to_be_bytes());
+        record.extend(b"APPL");
+        record.extend(b"????");
+        record.extend(1i16.to_be_bytes());
+        record.extend(1i16.to_be_bytes());
+        record.extend(0u32.to_be_bytes());
+        record.extend([0; 12]);
+
+        let cnid_path = [706090u32.to_be_bytes(), 103u32.to_be_bytes()].concat();
+        let data = build_alias(
+            2,
+            &record,
+            &[
+                (0, b"Applications"),
+                (1, &cnid_path),
+                (2, b"Macintosh HD:Applications:Syncthing.app"),
+                (18, b"/Applications/Syncthing.app"),
+                (19, b"/"),
+            ],
+        );
+
+        let alias = parse_alias(&data).unwrap();
+        assert_eq!(alias.version, 2);
+        assert_eq!(alias.kind, 0);
+        assert_eq!(alias.volume_name, "Macintosh HD");
+        assert_eq!(alias.filesystem, "H+");
+        assert_eq!(alias.folder_cnid, 706090);
+        assert_eq!(alias.filename, "Syncthing.app");
+        assert_eq!(alias.cnid, 706091);
+        assert_eq!(alias.creation.unwrap(), 665473989.0);
+        assert_eq!(alias.creator_code, "APPL");
+        assert_eq!(alias.folder_name.as_deref(), Some("Applications"));
+        assert_eq!(alias.cnid_path, [706090, 103]);
+        assert_eq!(
+            alias.carbon_path.as_deref(),
+            Some("Macintosh HD:Applications:Syncthing.app")
+        );
+        assert_eq!(
+            alias.posix_path.as_deref(),
+            Some("/Applications/Syncthing.app")
+        );
+        assert_eq!(alias.mount_point.as_deref(), Some("/"));
+
+        let bookmark = alias.to_bookmark();
+        assert_eq!(bookmark.path, ["Applications", "Syncthing.app"]);
+        assert_eq!(bookmark.cnid_path, [Cnid(103), Cnid(706090), Cnid(706091)]);
+        assert_eq!(bookmark.creation, 665473989.0);
+        assert_eq!(bookmark.target_filename.as_deref(), Some("Syncthing.app"));
+        assert_eq!(bookmark.volume_name, "Macintosh HD");
+        assert_eq!(bookmark.volume_path, "/");
+        assert_eq!(bookmark.volume_url, "file:///");
+
+        // Carbon path only
+        let mut alias = alias;
+        alias.posix_path = None;
+        alias.mount_point = None;
+        let bookmark = alias.to_bookmark();
+        assert_eq!(bookmark.path, ["Applications", "Syncthing.app"]);
+        assert_eq!(bookmark.volume_url, "");
+    }
+
+    #[test]
+    fn test_parse_alias_v3() {
+        let creation = ((HFS_EPOCH_OFFSET as u64 + 665473989) << 16) + 0x8000;
+        let mut record = 1u16.to_be_bytes().to_vec();
+        record.extend(creation.to_be_bytes());
+        record.extend(b"H+\0\0");
+        record.extend(0u16.to_be_bytes());
+        record.extend(2u32.to_be_bytes());
+        record.extend(103u32.to_be_bytes());
+        record.extend(creation.to_be_bytes());
+        record.extend(0u32.to_be_bytes());
+        record.extend([0; 14]);
+
+        let unicode = |value: &str| {
+            let units: Vec<u16> = value.encode_utf16().collect();
+            let mut data = (units.len() as u16).to_be_bytes().to_vec();
+            data.extend(units.iter().flat_map(|unit| unit.to_be_bytes()));
+            data
+        };
+        let data = build_alias(
+            3,
+            &record,
+            &[
+                (14, &unicode("Données")),
+                (15, &unicode("Macintosh HD")),
+                (21, &5u16.to_be_bytes()),
+                (4, b"server"),
+            ],
+        );
+
+        let alias = parse_alias(&data).unwrap();
+        assert_eq!(alias.version, 3);
+        assert_eq!(alias.kind, 1);
+        assert_eq!(alias.filename, "Données");
+        assert_eq!(alias.volume_name, "Macintosh HD");
+        assert_eq!(alias.filesystem, "H+");
+        assert_eq!(alias.cnid, 103);
+        assert_eq!(alias.creation.unwrap(), 665473989.5);
+        assert_eq!(alias.user_home_prefix, Some(5));
+        assert_eq!(alias.other_tags.len(), 1);
+        assert_eq!(alias.other_tags[0].data, b"server");
+    }
+
+    #[test]
+    fn test_parse_alias_bad_data() {
+        assert!(parse_alias(&[]).is_err());
+        assert!(parse_alias(&build_alias(4, &[0; 50], &[])).is_err());
+        // Truncated fixed size record
+        assert!(parse_alias(&build_alias(2, &[0; 20], &[])[..28]).is_err());
+    }
+}
@@ -0,0 +1,220 @@
+//! Anonymize bookmark samples before sharing them in bug reports
+//!
+//! String, URL and security extension records are rewritten in place with same-length placeholders, every other
+//! byte (header, TOC, record lengths and types, numbers, dates and flags) is kept as is. The walk does not require
+//! the bookmark to parse successfully, if the TOC cannot be read every printable run in the data is replaced instead.
+
+use std::collections::HashSet;
+
+use log::warn;
+
+use crate::{bookmark::BookmarkData, error::BookmarkError, parser::DEFAULT_MAX_DEPTH};
+
+// Printable runs at least this long are replaced when the structure cannot be walked
+const MIN_FALLBACK_RUN: usize = 4;
+
+/// Rewrite strings in a bookmark to same-length placeholders, preserving the bookmark structure
+pub fn anonymize(data: &[u8]) -> Result<Vec<u8>, BookmarkError> {
+    anonymize_nested(data, DEFAULT_MAX_DEPTH)
+}
+
+/// Anonymize a bookmark, nested bookmarks deeper than `max_depth` have all printable data replaced
+fn anonymize_nested(data: &[u8], max_depth: usize) -> Result<Vec<u8>, BookmarkError> {
+    if !data.starts_with(BookmarkData::BOOKMARK_SIGNATURE)
+        || BookmarkData::parse_bookmark_header(data).is_err()
+    {
+        return Err(BookmarkError::BadHeader);
+    }
+
+    let mut output = data.to_vec();
+    if anonymize_records(&mut output, max_depth).is_none() {
+        warn!("Could not read bookmark TOC, replacing all printable data");
+        scrub_printable(&mut output[BookmarkData::HEADER_SIZE as usize..]);
+    }
+    Ok(output)
+}
+
+/// Walk the TOC and every record it references. `None` if the TOC cannot be read
+fn anonymize_records(output: &mut [u8], max_depth: usize) -> Option<()> {
+    let header_size = BookmarkData::HEADER_SIZE;
+
+    // TOC offsets are relative to the start of the bookmark data
+    let mut next_toc = Some(read_u32(output, header_size)?);
+    let toc_entry_size = 12;
+    let toc_entries_offset = 20;
+
+    let mut pending = Vec::new();
+    let mut tocs = HashSet::new();
+    // Records of chained TOCs are anonymized too
+    while let Some(toc) = next_toc.filter(|toc| tocs.insert(*toc)) {
+        let toc_offset = toc.checked_add(header_size)?;
+        let record_count = read_u32(output, toc_offset.checked_add(16)?)?;
+        for index in 0..record_count {
+            let entry = index
+                .checked_mul(toc_entry_size)?
+                .checked_add(toc_offset.checked_add(toc_entries_offset)?)?;
+            let (Some(record_type), Some(offset)) = (
+                read_u32(output, entry),
+                read_u32(output, entry.checked_add(4)?),
+            ) else {
+                break;
+            };
+            pending.push((record_type, offset));
+        }
+        // The last TOC has a next TOC offset of 0
+        next_toc = read_u32(output, toc_offset.checked_add(12)?).filter(|next| *next != 0);
+    }
+
+    let mut visited = HashSet::new();
+    while let Some((record_type, offset)) = pending.pop() {
+        let Some(record_offset) = offset.checked_add(header_size) else {
+            continue;
+        };
+        if !visited.insert(record_offset) {
+            continue;
+        }
+        let Some((data_type, start, end)) = record_bounds(output, record_offset) else {
+            continue;
+        };
+        let record_data = &mut output[start..end];
+
+        match data_type {
+            BookmarkData::STRING_TYPE => scrub(record_data),
+            BookmarkData::URL => {
+                // Keep the scheme so the URL is still recognizable (ex: file:///)
+                let scheme = record_data
+                    .windows(3)
+                    .position(|window| window == b"://")
+                    .map_or(0, |index| index + 3);
+                scrub(&mut record_data[scheme..]);
+            }
+            BookmarkData::ARRAY_TYPE | BookmarkData::DICTIONARY | BookmarkData::URL_RELATIVE => {
+                for element in record_data.chunks_exact(4) {
+                    let element_offset =
+                        u32::from_le_bytes([element[0], element[1], element[2], element[3]]);
+                    pending.push((record_type, element_offset));
+                }
+            }
+            BookmarkData::DATA_TYPE
+                if record_data.starts_with(BookmarkData::BOOKMARK_SIGNATURE) =>
+            {
+                let nested = match max_depth.checked_sub(1) {
+                    Some(depth) => anonymize_nested(record_data, depth),
+                    None => Err(BookmarkError::BadBookmarkData),
+                };
+                match nested {
+                    Ok(nested) => record_data.copy_from_slice(&nested),
+                    Err(_) => scrub_printable(record_data),
+                }
+            }
+            BookmarkData::DATA_TYPE
+                if record_type == BookmarkData::SECURITY_EXTENSION_RW
+                    || record_type == BookmarkData::SECURITY_EXTENSION_RO =>
+            {
+                scrub(record_data);
+            }
+            _ => {}
+        }
+    }
+    Some(())
+}
+
+/// Get the data type and the start/end of the record data at an offset from the start of the bookmark
+fn record_bounds(data: &[u8], offset: u32) -> Option<(u32, usize, usize)> {
+    let length = read_u32(data, offset)?;
+    let data_type = read_u32(data, offset.checked_add(4)?)?;
+    let start = offset.checked_add(8)? as usize;
+    let end = start.checked_add(length as usize)?;
+    if end > data.len() {
+        return None;
+    }
+    Some((data_type, start, end))
+}
+
+/// Read a little endian u32 at an offset
+fn read_u32(data: &[u8], offset: u32) -> Option<u32> {
+    let start = offset as usize;
+    let bytes = data.get(start..start.checked_add(4)?)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Replace letters, digits and non-ASCII bytes with `x`. Separators (`/`, `.`, `;`, etc) are kept
+fn scrub(data: &mut [u8]) {
+    for byte in data.iter_mut() {
+        if byte.is_ascii_alphanumeric() || !byte.is_ascii() {
+            *byte = b'x';
+        }
+    }
+}
+
+/// Scrub every run of printable ASCII that could be a string
+fn scrub_printable(data: &mut [u8]) {
+    let mut start = 0;
+    for index in 0..=data.len() {
+        let printable = data
+            .get(index)
+            .is_some_and(|byte| byte.is_ascii_graphic() || *byte == b' ');
+        if printable {
+            continue;
+        }
+        if index - start >= MIN_FALLBACK_RUN {
+            scrub(&mut data[start..index]);
+        }
+        start = index + 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{anonymize, scrub_printable};
+    use crate::parser::parse_bookmark;
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn test_anonymize() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+
+        let anonymized = anonymize(&buffer).unwrap();
+        assert_eq!(anonymized.len(), buffer.len());
+        assert!(!anonymized
+            .windows(b"Syncthing".len())
+            .any(|window| window.eq_ignore_ascii_case(b"Syncthing")));
+
+        let original = parse_bookmark(&buffer).unwrap();
+        let bookmark = parse_bookmark(&anonymized).unwrap();
+        assert_eq!(bookmark.path, ["xxxxxxxxxxxx", "xxxxxxxxx.xxx"]);
+        assert_eq!(bookmark.volume_url, "file:///");
+        assert_eq!(bookmark.volume_name, "xxxxxxxxx xx");
+        assert_eq!(bookmark.localized_name.as_deref(), Some("xxxxxxxxx"));
+        assert_eq!(
+            bookmark.extension_path(),
+            Some("/xxxxxxxxxxxx/xxxxxxxxx.xxx")
+        );
+        assert_eq!(bookmark.cnid_path, original.cnid_path);
+        assert_eq!(bookmark.volume_size, original.volume_size);
+        assert_eq!(bookmark.creation, original.creation);
+        assert_eq!(bookmark.record_sizes, original.record_sizes);
+    }
+
+    #[test]
+    fn test_anonymize_bad_data() {
+        assert!(anonymize(b"not a bookmark").is_err());
+
+        let mut data = [0; 60];
+        data[..4].copy_from_slice(b"book");
+        data[52..].copy_from_slice(b"/Users/a");
+        // TOC offset points past the end of the data
+        data[48] = 0xff;
+        let anonymized = anonymize(&data).unwrap();
+        assert_eq!(&anonymized[52..], b"/xxxxx/x");
+    }
+
+    #[test]
+    fn test_scrub_printable() {
+        let mut data = *b"ab\0/Users/lab\0";
+        scrub_printable(&mut data);
+        assert_eq!(&data, b"ab\0/xxxxx/xxx\0");
+    }
+}
@@ -0,0 +1,131 @@
+//! Convert bookmarks to the artemis bookmark artifact schema
+//!
+//! artemis reports macOS bookmarks (LoginItems) with flat field names, Unix epoch timestamps and empty/zero values
+//! instead of missing fields. `ArtemisBookmark` uses the same names and types so results from this crate can be
+//! merged into artemis timelines without remapping fields.
+
+use serde::Serialize;
+
+use crate::{bookmark::BookmarkData, types::CocoaTime};
+
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+pub struct ArtemisBookmark {
+    pub path: Vec<String>,             // Path to binary to run
+    pub cnid_path: Vec<i64>,           // Path represented as Catalog Node ID
+    pub created: i64,                  // Created timestamp of binary target (Unix epoch seconds)
+    pub volume_path: String,           // Root
+    pub volume_url: String,            // URL type
+    pub volume_name: String,           // Name of Volume
+    pub volume_uuid: String,           // Volume UUID string
+    pub volume_size: i64,              // Size of Volume
+    pub volume_created: i64,           // Created timestamp of Volume (Unix epoch seconds)
+    pub volume_flag: Vec<u64>,         // Volume Property flags
+    pub volume_root: bool,             // If Volume is filesystem root
+    pub localized_name: String,        // Optional localized name of target binary
+    pub security_extension_rw: String, // Optional RW Security extension of target binary
+    pub security_extension_ro: String, // Optional RO Security extension of target binary
+    pub target_flags: Vec<u64>,        // Resource property flags
+    pub username: String,              // Username related to bookmark
+    pub folder_index: i64,             // Folder index number
+    pub uid: i32,                      // User UID
+    pub creation_options: i32,         // Bookmark creation options
+    pub is_bundled: bool,              // Login item is bundled inside an application
+    pub app_id: String,                // Bundle ID of the app containing a bundled login item
+    pub app_binary: String,            // Binary of the app containing a bundled login item
+    pub is_executable: bool,           // Is binary excutable
+    pub file_ref_flag: bool,           // Has a file reference flag
+    pub source_path: String,           // File the bookmark was read from
+}
+
+impl ArtemisBookmark {
+    /// Convert a bookmark read from `source_path`. Missing optional fields become empty strings, zero or false
+    pub fn new(bookmark: &BookmarkData, source_path: &str) -> ArtemisBookmark {
+        ArtemisBookmark {
+            path: bookmark.path.clone(),
+            cnid_path: bookmark.cnid_path.iter().map(|cnid| cnid.value()).collect(),
+            created: unix_seconds(bookmark.creation),
+            volume_path: bookmark.volume_path.clone(),
+            volume_url: bookmark.volume_url.clone(),
+            volume_name: bookmark.volume_name.clone(),
+            volume_uuid: bookmark.volume_uuid.clone(),
+            volume_size: bookmark.volume_size,
+            volume_created: unix_seconds(bookmark.volume_creation),
+            volume_flag: bookmark.volume_flag.clone(),
+            volume_root: bookmark.volume_root.unwrap_or_default(),
+            localized_name: bookmark.localized_name.clone().unwrap_or_default(),
+            security_extension_rw: bookmark.security_extension_rw.clone().unwrap_or_default(),
+            security_extension_ro: bookmark.security_extension_ro.clone().unwrap_or_default(),
+            target_flags: bookmark.target_flags.clone(),
+            username: bookmark.username.clone().unwrap_or_default(),
+            folder_index: bookmark.folder_index,
+            uid: bookmark.uid.unwrap_or_default(),
+            creation_options: bookmark.creation_options.unwrap_or_default(),
+            is_bundled: false,
+            app_id: String::new(),
+            app_binary: String::new(),
+            is_executable: bookmark.is_executable.unwrap_or_default(),
+            file_ref_flag: bookmark.file_ref_flag.unwrap_or_default(),
+            source_path: source_path.to_string(),
+        }
+    }
+
+    /// Mark the bookmark as a login item bundled inside an application (`Contents/Library/LoginItems`)
+    pub fn bundled(mut self, app_id: &str, app_binary: &str) -> ArtemisBookmark {
+        self.is_bundled = true;
+        self.app_id = app_id.to_string();
+        self.app_binary = app_binary.to_string();
+        self
+    }
+}
+
+/// Convert a timestamp to whole seconds since the Unix epoch, missing (zero) timestamps stay zero
+fn unix_seconds(time: CocoaTime) -> i64 {
+    if time == CocoaTime::default() {
+        return 0;
+    }
+    time.to_unix().floor() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArtemisBookmark;
+    use crate::{bookmark::BookmarkData, parser::parse_bookmark};
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn test_artemis_bookmark() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/poisonapple.bookmark");
+        let buffer = fs::read(&test_location).unwrap();
+        let bookmark = parse_bookmark(&buffer).unwrap();
+
+        let source = test_location.to_str().unwrap();
+        let artemis = ArtemisBookmark::new(&bookmark, source);
+        assert_eq!(artemis.path.len(), 11);
+        assert_eq!(artemis.cnid_path[0], 12884925338);
+        assert_eq!(artemis.created, 1656555374);
+        assert_eq!(artemis.volume_created, 1577865600);
+        assert_eq!(artemis.volume_uuid, "0A81F3B1-51D9-3335-B3E3-169C3640360D");
+        assert!(artemis.volume_root);
+        assert_eq!(artemis.localized_name, "testing");
+        assert_eq!(artemis.security_extension_rw, "");
+        assert_eq!(artemis.uid, 501);
+        assert_eq!(artemis.creation_options, 536870912);
+        assert!(!artemis.is_bundled);
+        assert!(!artemis.file_ref_flag);
+        assert_eq!(artemis.source_path, source);
+
+        let json = serde_json::to_value(&artemis).unwrap();
+        assert_eq!(json.as_object().unwrap().len(), 25);
+        assert_eq!(json["volume_flag"][0], 4294967425u64);
+        assert_eq!(json["username"], "sur");
+
+        let bundled = ArtemisBookmark::new(&BookmarkData::default(), "").bundled(
+            "com.example.helper",
+            "/Applications/Example.app/Contents/MacOS/Example",
+        );
+        assert!(bundled.is_bundled);
+        assert_eq!(bundled.app_id, "com.example.helper");
+        assert_eq!(bundled.created, 0);
+    }
+}
@@ -0,0 +1,130 @@
+//! Common interface for forensic artifact parsers
+//!
+//! `ForensicArtifact` lets bookmarks be handled the same way as other artifact types in a multi-artifact
+//! framework. `Artifact` is the object safe part of the trait, collectors can register `parse_artifact::<T>`
+//! as an `ArtifactParseFn` and keep the results as `Box<dyn Artifact>`.
+
+use std::error::Error;
+
+use serde::Serialize;
+
+use crate::{bookmark::BookmarkData, parser::parse_bookmark, types::CocoaTime};
+
+/// Error returned by artifact parsers
+pub type ArtifactError = Box<dyn Error + Send + Sync>;
+
+/// Parse raw bytes into a boxed artifact (for plugin style collectors)
+pub type ArtifactParseFn = fn(&[u8]) -> Result<Box<dyn Artifact>, ArtifactError>;
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ArtifactTimestamp {
+    pub description: &'static str, // What the timestamp records (ex: target_created)
+    pub unix_seconds: f64,         // Seconds since the Unix epoch
+}
+
+/// Object safe artifact interface
+pub trait Artifact {
+    /// Name of the artifact type
+    fn name(&self) -> &'static str;
+
+    /// Serialize the artifact to JSON
+    fn to_json(&self) -> Result<String, ArtifactError>;
+
+    /// Get every timestamp in the artifact
+    fn timestamps(&self) -> Vec<ArtifactTimestamp>;
+}
+
+/// Artifact that can be parsed from raw bytes
+pub trait ForensicArtifact: Artifact + Sized {
+    /// Name of the artifact type
+    const NAME: &'static str;
+
+    /// Parse the artifact from raw bytes
+    fn parse(data: &[u8]) -> Result<Self, ArtifactError>;
+}
+
+/// Parse raw bytes into a boxed artifact
+pub fn parse_artifact<T: ForensicArtifact + 'static>(
+    data: &[u8],
+) -> Result<Box<dyn Artifact>, ArtifactError> {
+    Ok(Box::new(T::parse(data)?))
+}
+
+/// Bookmark parser for plugin style collectors
+pub const BOOKMARK_PARSER: ArtifactParseFn = parse_artifact::<BookmarkData>;
+
+impl Artifact for BookmarkData {
+    fn name(&self) -> &'static str {
+        BookmarkData::NAME
+    }
+
+    fn to_json(&self) -> Result<String, ArtifactError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Get the target and volume creation timestamps, missing (zero) timestamps are skipped
+    fn timestamps(&self) -> Vec<ArtifactTimestamp> {
+        [
+            ("target_created", self.creation),
+            ("volume_created", self.volume_creation),
+        ]
+        .into_iter()
+        .filter(|(_, time)| *time != CocoaTime::default())
+        .map(|(description, time)| ArtifactTimestamp {
+            description,
+            unix_seconds: time.to_unix(),
+        })
+        .collect()
+    }
+}
+
+impl ForensicArtifact for BookmarkData {
+    const NAME: &'static str = "macos_bookmark";
+
+    fn parse(data: &[u8]) -> Result<Self, ArtifactError> {
+        Ok(parse_bookmark(data)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_artifact, Artifact, ArtifactParseFn, ForensicArtifact, BOOKMARK_PARSER};
+    use crate::bookmark::BookmarkData;
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn test_forensic_artifact() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+
+        let bookmark = BookmarkData::parse(&buffer).unwrap();
+        assert_eq!(bookmark.name(), "macos_bookmark");
+        let timestamps = bookmark.timestamps();
+        assert_eq!(timestamps.len(), 2);
+        assert_eq!(timestamps[0].description, "target_created");
+        assert_eq!(timestamps[0].unix_seconds, bookmark.creation.to_unix());
+
+        let json: serde_json::Value =
+            serde_json::from_str(&Artifact::to_json(&bookmark).unwrap()).unwrap();
+        assert_eq!(json["localized_name"], "Syncthing");
+
+        assert!(BookmarkData::parse(b"not a bookmark").is_err());
+    }
+
+    #[test]
+    fn test_parse_artifact() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+
+        let parsers: Vec<ArtifactParseFn> = vec![BOOKMARK_PARSER, parse_artifact::<BookmarkData>];
+        let artifacts: Vec<_> = parsers
+            .iter()
+            .filter_map(|parse| parse(&buffer).ok())
+            .collect();
+        assert_eq!(artifacts.len(), 2);
+        assert_eq!(artifacts[0].name(), "macos_bookmark");
+        assert_eq!(artifacts[0].timestamps().len(), 2);
+    }
+}
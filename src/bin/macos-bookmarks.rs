@@ -0,0 +1,713 @@
+//! Command line bookmark parser
+//!
+//! `macos-bookmarks parse [--format table|json|jsonl|csv] [--timestamps raw|converted|both] [<output>] <file>...`
+//! parses bookmark files and writes them as an aligned field table (see `BookmarkData::to_flat_map`), pretty printed
+//! JSON, JSON Lines or CSV. CSV always has the same columns in the same order so output from different runs can be
+//! combined. Timestamps are the raw seconds since the Cocoa epoch unless converted, `both` keeps the raw value in a
+//! `<name>_raw` field next to the converted timestamp.
+//!
+//! `macos-bookmarks scan [--carve] [--snapshot <mount point>]... [--local-snapshots] [--group-by volume|user --output
+//! <directory> | [<output>] [--checkpoint <file>]] <directory>` scans a directory tree (see `scan::scan_directory`) and
+//! writes every bookmark found as JSON Lines, or writes them grouped into one JSON file per volume or user with an
+//! index (see `group::write_grouped`). With `--checkpoint` the results of each file are written as soon as it is
+//! scanned and the file is recorded in the checkpoint file, running the same command again resumes an interrupted scan.
+//! `--snapshot` (repeatable) also scans the same directory in a mounted APFS snapshot and `--local-snapshots` in every
+//! mounted snapshot (macOS only), results are tagged with their snapshot.
+//!
+//! Output goes to stdout unless `<output>` is `--output-file <file>` (a single file), `--output-dir <directory>` (a
+//! file per input, see `sink::DirectorySink`) or `--socket <path>` (a UNIX socket another process listens on).
+//! `--compress gzip|zstd` compresses the output (`gzip` and `zstd` features). With the `kafka` feature `--kafka
+//! <brokers> --kafka-topic <topic>` produces each JSON Lines result as a Kafka message instead (see `kafka::KafkaSink`,
+//! `parse` needs `--format jsonl`). Scan results cannot be grouped and sent to a sink at the same time.
+//!
+//! `macos-bookmarks serve [--address <address>]` (`serve` feature) serves the parser over HTTP on `127.0.0.1:8080` by
+//! default, see `serve::ParseServer`.
+
+use std::{
+    collections::BTreeMap,
+    env,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use macos_bookmarks::{
+    checkpoint::Checkpoint,
+    error::BookmarkError,
+    group::{write_grouped, GroupBy},
+    parser::parse_bookmark_file,
+    scan::{
+        scan_directory, scan_directory_checkpointed, scan_snapshots, ScanOptions, ScannedBookmark,
+    },
+    sink::{Compression, DirectorySink, Sink, StreamSink},
+    snapshot::Snapshot,
+    types::TimestampFormat,
+};
+use serde::Serialize;
+use serde_json::Value;
+
+const USAGE: &str = "Usage: macos-bookmarks parse [--format table|json|jsonl|csv] [--timestamps raw|converted|both] [<output>] <file>...
+       macos-bookmarks scan [--carve] [--snapshot <mount point>]... [--local-snapshots] [--group-by volume|user --output <directory> | [<output>] [--checkpoint <file>]] <directory>
+       macos-bookmarks serve [--address <address>]
+Output: [--output-file <file> | --output-dir <directory> | --socket <path>] [--compress none|gzip|zstd] | --kafka <brokers> --kafka-topic <topic>";
+
+/// CSV columns, in output order
+const CSV_COLUMNS: [&str; 29] = [
+    "file",
+    "path",
+    "cnid_path",
+    "creation",
+    "volume_path",
+    "volume_url",
+    "volume_name",
+    "volume_uuid",
+    "volume_size",
+    "volume_creation",
+    "volume_flag",
+    "volume_root",
+    "target_filename",
+    "toc_path",
+    "volume_mount_point",
+    "localized_name",
+    "display_name",
+    "security_extension_rw",
+    "security_extension_ro",
+    "target_flags",
+    "username",
+    "folder_index",
+    "uid",
+    "creation_options",
+    "is_executable",
+    "file_ref_flag",
+    "has_persistent_ids",
+    "creation_raw",
+    "volume_creation_raw",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Table,
+    Json,
+    Jsonl,
+    Csv,
+}
+
+impl Format {
+    /// Extension of the files written for each input
+    fn extension(&self) -> &'static str {
+        match self {
+            Format::Table => "txt",
+            Format::Json => "json",
+            Format::Jsonl => "jsonl",
+            Format::Csv => "csv",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Output<'a> {
+    file: &'a str,
+    #[serde(flatten)]
+    bookmark: Value,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let Some((command, args)) = args.split_first() else {
+        eprintln!("{USAGE}");
+        return ExitCode::from(2);
+    };
+    match command.as_str() {
+        "parse" => parse(args),
+        "scan" => scan(args),
+        "serve" => serve(args),
+        _ => {
+            eprintln!("Unknown command {command}\n{USAGE}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Parse bookmark files and write them to the output in the requested format
+fn parse(args: &[String]) -> ExitCode {
+    let Some(parse_args) = parse_args(args) else {
+        eprintln!("{USAGE}");
+        return ExitCode::from(2);
+    };
+    let format = parse_args.format;
+    let header = match format {
+        Format::Csv => format!("{}\n", CSV_COLUMNS.join(",")),
+        _ => String::new(),
+    };
+    let mut status = ExitCode::SUCCESS;
+    let Some(mut sink) = parse_args
+        .sink
+        .open(format.extension(), header.as_bytes(), &mut status)
+    else {
+        return status;
+    };
+
+    for file in parse_args.files {
+        let bookmark = match parse_bookmark_file(file) {
+            Ok(bookmark) => bookmark,
+            Err(err) => {
+                eprintln!("{err}");
+                status = ExitCode::FAILURE;
+                continue;
+            }
+        };
+        let timestamps = parse_args.timestamps;
+        let output = Output {
+            file,
+            bookmark: bookmark.to_json_value(timestamps),
+        };
+        let mut formatted = Vec::new();
+        let written = match format {
+            Format::Table => {
+                write_table(&mut formatted, file, &bookmark.to_flat_map_with(timestamps))
+            }
+            Format::Json => serde_json::to_writer_pretty(&mut formatted, &output)
+                .map_err(io::Error::from)
+                .and_then(|_| writeln!(formatted)),
+            Format::Jsonl => serde_json::to_writer(&mut formatted, &output)
+                .map_err(io::Error::from)
+                .and_then(|_| writeln!(formatted)),
+            Format::Csv => serde_json::to_value(&output)
+                .map_err(io::Error::from)
+                .and_then(|value| writeln!(formatted, "{}", csv_row(&value).join(","))),
+        };
+        if let Err(err) = written {
+            eprintln!("Failed to format {file}: {err}");
+            status = ExitCode::FAILURE;
+            continue;
+        }
+        if !sink.write(Path::new(file), &formatted, &mut status) {
+            break;
+        }
+    }
+    sink.finish(&mut status);
+    status
+}
+
+/// Scan a directory and write the results to the output as JSON Lines or grouped to an output directory
+fn scan(args: &[String]) -> ExitCode {
+    let Some(scan_args) = scan_args(args) else {
+        eprintln!("{USAGE}");
+        return ExitCode::from(2);
+    };
+    if let Some(path) = &scan_args.checkpoint {
+        return scan_checkpointed(&scan_args, path);
+    }
+    let scanned = if scan_args.snapshots.is_empty() {
+        scan_directory(&scan_args.root, &scan_args.options)
+    } else {
+        scan_snapshots(&scan_args.root, &scan_args.snapshots, &scan_args.options)
+    };
+    let results = match scanned {
+        Ok(results) => results,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if let Some((group_by, output)) = scan_args.grouped {
+        return match write_grouped(&results, group_by, &output) {
+            Ok(entries) => {
+                eprintln!(
+                    "Wrote {} bookmarks in {} groups to {}",
+                    results.len(),
+                    entries.len(),
+                    output.display()
+                );
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("{err}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+    let mut status = ExitCode::SUCCESS;
+    let Some(mut sink) = scan_args.sink.open("jsonl", b"", &mut status) else {
+        return status;
+    };
+    for results in results.chunk_by(|first, second| first.path == second.path) {
+        if !write_results(&mut sink, results, &mut status) {
+            break;
+        }
+    }
+    sink.finish(&mut status);
+    status
+}
+
+/// Scan a directory, writing the results of each file as JSON Lines before recording it in the checkpoint file
+fn scan_checkpointed(scan_args: &ScanArgs, path: &Path) -> ExitCode {
+    let mut checkpoint = match Checkpoint::open(path) {
+        Ok(checkpoint) => checkpoint,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let skipped = checkpoint.len();
+    let mut status = ExitCode::SUCCESS;
+    let Some(mut sink) = scan_args.sink.open("jsonl", b"", &mut status) else {
+        return status;
+    };
+    let scanned = scan_directory_checkpointed(
+        &scan_args.root,
+        &scan_args.options,
+        &mut checkpoint,
+        |results| write_results(&mut sink, &results, &mut status),
+    );
+    sink.finish(&mut status);
+    match scanned {
+        Ok(scanned) => {
+            eprintln!("Scanned {scanned} files, skipped {skipped} files from the checkpoint");
+            status
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Serve the parser over HTTP until the process is stopped
+#[cfg(feature = "serve")]
+fn serve(args: &[String]) -> ExitCode {
+    let mut address = "127.0.0.1:8080";
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match option_value(arg, "--address", &mut args) {
+            Some(Some(value)) => address = value,
+            _ => {
+                eprintln!("{USAGE}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+    let server = match macos_bookmarks::serve::ParseServer::bind(address) {
+        Ok(server) => server,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match server.local_addr() {
+        Some(address) => eprintln!("Listening on {address}"),
+        None => eprintln!("Listening on {address}"),
+    }
+    server.run();
+    ExitCode::SUCCESS
+}
+
+/// The HTTP service is only built with the `serve` feature
+#[cfg(not(feature = "serve"))]
+fn serve(_args: &[String]) -> ExitCode {
+    eprintln!("The serve command requires the serve feature\n{USAGE}");
+    ExitCode::from(2)
+}
+
+/// Write the scan results of one file to the sink as JSON Lines. Files without results are skipped
+fn write_results(
+    sink: &mut OutputSink,
+    results: &[ScannedBookmark],
+    status: &mut ExitCode,
+) -> bool {
+    let Some(first) = results.first() else {
+        return true;
+    };
+    let mut output = Vec::new();
+    for result in results {
+        if let Err(err) = serde_json::to_writer(&mut output, result) {
+            eprintln!(
+                "Failed to serialize result for {}: {err}",
+                result.path.display()
+            );
+            *status = ExitCode::FAILURE;
+            continue;
+        }
+        output.push(b'\n');
+    }
+    sink.write(&first.path, &output, status)
+}
+
+/// Output options shared by the commands
+#[derive(Default)]
+struct SinkArgs {
+    file: Option<PathBuf>,       // Single output file
+    directory: Option<PathBuf>,  // Directory for a file per input
+    socket: Option<PathBuf>,     // UNIX socket to write to
+    kafka: Option<String>,       // Kafka bootstrap servers
+    kafka_topic: Option<String>, // Kafka topic to produce to
+    compression: Compression,    // Output compression
+}
+
+impl SinkArgs {
+    /// Get an output option. `Some(false)` if the argument is not an output option, `None` if the value is missing or
+    /// not supported (compression or Kafka without its feature, sockets on Windows)
+    fn parse<'a>(
+        &mut self,
+        arg: &'a str,
+        args: &mut impl Iterator<Item = &'a String>,
+    ) -> Option<bool> {
+        if let Some(value) = option_value(arg, "--output-file", args)? {
+            self.file = Some(PathBuf::from(value));
+        } else if let Some(value) = option_value(arg, "--output-dir", args)? {
+            self.directory = Some(PathBuf::from(value));
+        } else if let Some(value) = option_value(arg, "--socket", args)? {
+            if cfg!(not(unix)) {
+                return None;
+            }
+            self.socket = Some(PathBuf::from(value));
+        } else if let Some(value) = option_value(arg, "--kafka", args)? {
+            if cfg!(not(feature = "kafka")) {
+                return None;
+            }
+            self.kafka = Some(value.to_string());
+        } else if let Some(value) = option_value(arg, "--kafka-topic", args)? {
+            self.kafka_topic = Some(value.to_string());
+        } else if let Some(value) = option_value(arg, "--compress", args)? {
+            self.compression = match value {
+                "none" => Compression::None,
+                #[cfg(feature = "gzip")]
+                "gzip" => Compression::Gzip,
+                #[cfg(feature = "zstd")]
+                "zstd" => Compression::Zstd,
+                _ => return None,
+            };
+        } else {
+            return Some(false);
+        }
+        Some(true)
+    }
+
+    /// Check that at most one destination is used. Kafka needs a topic and compresses messages itself
+    fn is_valid(&self) -> bool {
+        let destinations = [
+            self.file.is_some(),
+            self.directory.is_some(),
+            self.socket.is_some(),
+            self.kafka.is_some(),
+        ];
+        destinations.iter().filter(|used| **used).count() <= 1
+            && self.kafka.is_some() == self.kafka_topic.is_some()
+            && (self.kafka.is_none() || self.compression == Compression::None)
+    }
+
+    /// Check if any output option is used
+    fn is_set(&self) -> bool {
+        !self.is_stdout() || self.compression != Compression::None
+    }
+
+    /// Check if the output goes to stdout
+    fn is_stdout(&self) -> bool {
+        self.file.is_none()
+            && self.directory.is_none()
+            && self.socket.is_none()
+            && self.kafka.is_none()
+    }
+
+    /// Open the output sink. Files in an output directory get the extension, the header is written at the start of the
+    /// output (or of each file). `None` and a failure status if the sink cannot be opened
+    fn open(&self, extension: &str, header: &[u8], status: &mut ExitCode) -> Option<OutputSink> {
+        let opened: Result<Box<dyn Sink>, BookmarkError> = if let Some(file) = &self.file {
+            StreamSink::create(file, self.compression, header).map(|sink| Box::new(sink) as _)
+        } else if let Some(directory) = &self.directory {
+            DirectorySink::new(directory, extension, self.compression, header)
+                .map(|sink| Box::new(sink) as _)
+        } else if let Some(socket) = &self.socket {
+            connect(socket, self.compression, header)
+        } else if let (Some(brokers), Some(topic)) = (&self.kafka, &self.kafka_topic) {
+            kafka(brokers, topic)
+        } else {
+            StreamSink::new(io::stdout().lock(), self.compression, header)
+                .map(|sink| Box::new(sink) as _)
+        };
+        match opened {
+            Ok(sink) => Some(OutputSink {
+                sink,
+                stdout: self.is_stdout(),
+            }),
+            Err(err) => {
+                eprintln!("{err}");
+                *status = ExitCode::FAILURE;
+                None
+            }
+        }
+    }
+}
+
+/// Open a sink writing to a UNIX socket
+#[cfg(unix)]
+fn connect(
+    socket: &Path,
+    compression: Compression,
+    header: &[u8],
+) -> Result<Box<dyn Sink>, BookmarkError> {
+    StreamSink::connect(socket, compression, header).map(|sink| Box::new(sink) as _)
+}
+
+/// Sockets are rejected when parsing the arguments on platforms without UNIX sockets
+#[cfg(not(unix))]
+fn connect(
+    _socket: &Path,
+    _compression: Compression,
+    _header: &[u8],
+) -> Result<Box<dyn Sink>, BookmarkError> {
+    Err(BookmarkError::OutputWriteError)
+}
+
+/// Open a sink producing each output line as a message to a Kafka topic
+#[cfg(feature = "kafka")]
+fn kafka(brokers: &str, topic: &str) -> Result<Box<dyn Sink>, BookmarkError> {
+    use macos_bookmarks::kafka::{KafkaOptions, KafkaSink};
+
+    KafkaSink::new(&KafkaOptions::new(brokers, topic)).map(|sink| Box::new(sink) as _)
+}
+
+/// Kafka is rejected when parsing the arguments without the `kafka` feature
+#[cfg(not(feature = "kafka"))]
+fn kafka(_brokers: &str, _topic: &str) -> Result<Box<dyn Sink>, BookmarkError> {
+    Err(BookmarkError::OutputWriteError)
+}
+
+/// Opened output sink
+struct OutputSink {
+    sink: Box<dyn Sink>, // Output destination
+    stdout: bool,        // Output closed early on stdout (ex: piped to head) is not an error
+}
+
+impl OutputSink {
+    /// Write the output of an input. `false` if the output failed, the status is set to failure unless stdout closed
+    fn write(&mut self, input: &Path, output: &[u8], status: &mut ExitCode) -> bool {
+        match self.sink.write(input, output) {
+            Ok(()) => true,
+            Err(err) => {
+                self.fail(err, status);
+                false
+            }
+        }
+    }
+
+    /// Finish the output
+    fn finish(&mut self, status: &mut ExitCode) {
+        if let Err(err) = self.sink.finish() {
+            self.fail(err, status);
+        }
+    }
+
+    /// Report an output error
+    fn fail(&self, err: BookmarkError, status: &mut ExitCode) {
+        if !self.stdout {
+            eprintln!("{err}");
+            *status = ExitCode::FAILURE;
+        }
+    }
+}
+
+/// Options of the scan command
+struct ScanArgs {
+    root: PathBuf,                       // Directory to scan
+    options: ScanOptions,                // Scan options
+    grouped: Option<(GroupBy, PathBuf)>, // Group results into files in an output directory
+    checkpoint: Option<PathBuf>,         // Checkpoint file of scanned inputs
+    snapshots: Vec<Snapshot>,            // Snapshots to scan in addition to the live volume
+    sink: SinkArgs,                      // Output of ungrouped results
+}
+
+/// Options of the parse command
+struct ParseArgs<'a> {
+    format: Format,              // Output format
+    timestamps: TimestampFormat, // Timestamp format
+    files: Vec<&'a str>,         // Bookmark files to parse
+    sink: SinkArgs,              // Output of the parsed bookmarks
+}
+
+/// Get the scan root and options. `--group-by` and `--output` must be used together, grouped results are only written
+/// once the scan is done so they cannot be combined with `--checkpoint`. Checkpoints only cover the live volume and
+/// cannot be combined with snapshots. Grouped results are written by group instead of to an output sink.
+/// `--local-snapshots` is only available on macOS
+fn scan_args(args: &[String]) -> Option<ScanArgs> {
+    let mut sink = SinkArgs::default();
+    let mut snapshots = Vec::new();
+    let mut options = ScanOptions::default();
+    let mut group_by = None;
+    let mut output = None;
+    let mut checkpoint = None;
+    let mut root = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if sink.parse(arg, &mut args)? {
+            continue;
+        }
+        if arg == "--carve" {
+            options.carve_unknown = true;
+        } else if let Some(value) = option_value(arg, "--group-by", &mut args)? {
+            group_by = Some(match value {
+                "volume" => GroupBy::Volume,
+                "user" => GroupBy::User,
+                _ => return None,
+            });
+        } else if let Some(value) = option_value(arg, "--output", &mut args)? {
+            output = Some(PathBuf::from(value));
+        } else if let Some(value) = option_value(arg, "--checkpoint", &mut args)? {
+            checkpoint = Some(PathBuf::from(value));
+        } else if let Some(value) = option_value(arg, "--snapshot", &mut args)? {
+            snapshots.push(Snapshot::new(value));
+        } else if arg == "--local-snapshots" {
+            #[cfg(target_os = "macos")]
+            snapshots.extend(macos_bookmarks::snapshot::mounted_snapshots());
+            #[cfg(not(target_os = "macos"))]
+            return None;
+        } else if root.replace(PathBuf::from(arg)).is_some() {
+            return None;
+        }
+    }
+    let grouped = match (group_by, output) {
+        (Some(group_by), Some(output)) => Some((group_by, output)),
+        (None, None) => None,
+        _ => return None,
+    };
+    if checkpoint.is_some() && (grouped.is_some() || !snapshots.is_empty()) {
+        return None;
+    }
+    if !sink.is_valid() || (grouped.is_some() && sink.is_set()) {
+        return None;
+    }
+    Some(ScanArgs {
+        root: root?,
+        options,
+        grouped,
+        checkpoint,
+        snapshots,
+        sink,
+    })
+}
+
+/// Get the output format, timestamp format, output and files. `--json` is kept as a shorthand for `--format json`
+fn parse_args(args: &[String]) -> Option<ParseArgs<'_>> {
+    let mut sink = SinkArgs::default();
+    let mut format = Format::Table;
+    let mut timestamps = TimestampFormat::Raw;
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if sink.parse(arg, &mut args)? {
+            continue;
+        }
+        if let Some(value) = option_value(arg, "--timestamps", &mut args)? {
+            timestamps = match value {
+                "raw" => TimestampFormat::Raw,
+                "converted" => TimestampFormat::Converted,
+                "both" => TimestampFormat::ConvertedWithRaw,
+                _ => return None,
+            };
+            continue;
+        }
+        let value = match arg.as_str() {
+            "--json" => "json",
+            arg => match option_value(arg, "--format", &mut args)? {
+                Some(value) => value,
+                None => {
+                    files.push(arg);
+                    continue;
+                }
+            },
+        };
+        format = match value {
+            "table" => Format::Table,
+            "json" => Format::Json,
+            "jsonl" => Format::Jsonl,
+            "csv" => Format::Csv,
+            _ => return None,
+        };
+    }
+    // Kafka messages are output lines
+    if files.is_empty() || !sink.is_valid() || (sink.kafka.is_some() && format != Format::Jsonl) {
+        return None;
+    }
+    Some(ParseArgs {
+        format,
+        timestamps,
+        files,
+        sink,
+    })
+}
+
+/// Get the value of an `--option value` or `--option=value` argument. `Some(None)` if the argument is a different
+/// option or a file, `None` if the value is missing
+fn option_value<'a>(
+    arg: &'a str,
+    option: &str,
+    args: &mut impl Iterator<Item = &'a String>,
+) -> Option<Option<&'a str>> {
+    if arg == option {
+        return args.next().map(|value| Some(value.as_str()));
+    }
+    Some(
+        arg.strip_prefix(option)
+            .and_then(|value| value.strip_prefix('=')),
+    )
+}
+
+/// Write the flattened fields as a two column table aligned on the longest field name
+fn write_table<W: Write>(
+    writer: &mut W,
+    file: &str,
+    fields: &BTreeMap<String, String>,
+) -> io::Result<()> {
+    let width = fields.keys().map(String::len).max().unwrap_or_default();
+    writeln!(writer, "{file}")?;
+    for (key, value) in fields {
+        writeln!(writer, "  {key:<width$}  {value}")?;
+    }
+    Ok(())
+}
+
+/// Get the CSV fields of a serialized bookmark in `CSV_COLUMNS` order. The path is joined with `/`, other lists with
+/// `;` and missing values are empty
+fn csv_row(output: &Value) -> Vec<String> {
+    CSV_COLUMNS
+        .iter()
+        .map(|column| {
+            let field = match output.get(column) {
+                Some(Value::Array(values)) if *column == "path" => {
+                    format!("/{}", join(values, "/"))
+                }
+                Some(Value::Array(values)) => join(values, ";"),
+                Some(value) => scalar(value),
+                None => String::new(),
+            };
+            csv_field(&field)
+        })
+        .collect()
+}
+
+/// Join serialized list values with a separator
+fn join(values: &[Value], separator: &str) -> String {
+    values
+        .iter()
+        .map(scalar)
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Format a serialized value without JSON string quoting, `null` is empty
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        return format!("\"{}\"", value.replace('"', "\"\""));
+    }
+    value.to_string()
+}
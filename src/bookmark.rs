@@ -3,6 +3,7 @@
 //! Provides a library to parse Bookmark data.
 
 use std::{
+    collections::BTreeMap,
     fmt::Debug,
     mem::size_of,
     str::{from_utf8, Utf8Error},
@@ -11,36 +12,216 @@ use std::{
 use log::{debug, warn};
 use nom::{
     bytes::complete::take,
-    number::complete::{be_f64, be_u32, le_i32, le_i64, le_u16, le_u32, le_u64},
+    error::{Error, ErrorKind},
+    number::complete::{
+        be_f64, be_u32, le_f32, le_f64, le_i16, le_i32, le_i64, le_i8, le_u16, le_u32, le_u64,
+    },
 };
 use serde::Serialize;
 
+use crate::{
+    error::{BookmarkError, ParseFailure, RecordContext},
+    locale::{self, Localization},
+    parser::{DuplicatePolicy, LengthLimits, ParseMode, ParserOptions},
+    path_policy::PathAction,
+    types::{Cnid, CocoaTime, RecordSet},
+    url::RelativeUrl,
+    value::{decode_record, uuid_string, BookmarkValue},
+};
+
 // Bookmark documentation:
 // https://mac-alias.readthedocs.io/en/latest/bookmark_fmt.html
 // http://michaellynn.github.io/2015/10/24/apples-bookmarkdata-exposed/
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Clone, Default)]
 pub struct BookmarkData {
-    pub path: Vec<String>,             // Path to binary to run
-    pub cnid_path: Vec<i64>,           // Path represented as Catalog Node ID
-    pub creation: f64,                 // Created timestamp of binary target
-    pub volume_path: String,           // Root
-    pub volume_url: String,            // URL type
-    pub volume_name: String,           // Name of Volume
-    pub volume_uuid: String,           // Volume UUID string
-    pub volume_size: i64,              // Size of Volume
-    pub volume_creation: f64,          // Created timestamp of Volume
-    pub volume_flag: Vec<u64>,         // Volume Property flags
-    pub volume_root: bool,             // If Volume is filesystem root
-    pub localized_name: String,        // Optional localized name of target binary
-    pub security_extension_rw: String, // Optional RW Security extension of target binary
-    pub security_extension_ro: String, // Optional RO Security extension of target binary
-    pub target_flags: Vec<u64>,        // Resource property flags
-    pub username: String,              // Username related to bookmark
-    pub folder_index: i64,             // Folder index number
-    pub uid: i32,                      // User UID
-    pub creation_options: i32,         // Bookmark creation options
-    pub is_executable: bool,           // Is binary excutable
-    pub file_ref_flag: bool,           // Has a file reference flag
+    pub path: Vec<String>,                     // Path to binary to run
+    pub cnid_path: Vec<Cnid>,                  // Path represented as Catalog Node ID
+    pub creation: CocoaTime,                   // Created timestamp of binary target
+    pub target_filename: Option<String>,       // Filename of the target record
+    pub url_lengths: Vec<i64>,                 // Length of each path component in the original URL
+    pub toc_path: Option<String>,              // Full target path record
+    pub volume_path: String,                   // Root
+    pub volume_url: String,                    // URL type
+    pub volume_name: String,                   // Name of Volume
+    pub volume_uuid: String,                   // Volume UUID string
+    pub volume_size: i64,                      // Size of Volume
+    pub volume_creation: CocoaTime,            // Created timestamp of Volume
+    pub volume_flag: Vec<u64>,                 // Volume Property flags
+    pub volume_root: Option<bool>,             // If Volume is filesystem root
+    pub volume_mount_point: Option<String>,    // Mount point URL of removable/network Volumes
+    pub localized_name: Option<String>,        // Optional localized name of target binary
+    pub display_name: Option<String>,          // Optional alternate display name of target
+    pub security_extension_rw: Option<String>, // Optional RW Security extension of target binary
+    pub security_extension_ro: Option<String>, // Optional RO Security extension of target binary
+    pub target_flags: Vec<u64>,                // Resource property flags
+    pub username: Option<String>,              // Username related to bookmark
+    pub folder_index: i64,                     // Folder index number
+    pub uid: Option<i32>,                      // User UID
+    pub creation_options: Option<i32>,         // Bookmark creation options
+    pub is_executable: Option<bool>,           // Is binary excutable
+    pub file_ref_flag: Option<bool>,           // Has a file reference flag
+    pub has_persistent_ids: bool,              // Volume has persistent CNIDs (false for FAT/exFAT)
+    pub record_set: RecordSet,                 // Hint if volume records are missing
+    pub relative_url: Option<RelativeUrl>,     // URL stored relative to a base URL
+    pub nested_bookmarks: Vec<NestedBookmark>, // Bookmarks embedded inside record data
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub file_metadata: Vec<FileMetadata>, // Additional file metadata records (0x1054-0x1057)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub duplicate_records: Vec<DuplicateRecord>, // Duplicated records (DuplicatePolicy::CollectAll)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unknown_records: Vec<UnknownRecord>, // Records that did not populate a field (if preserved)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub truncated_records: Vec<TruncatedRecord>, // Records longer than the configured length limits
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub lossy_strings: Vec<LossyString>, // Strings with invalid UTF-8 (if decoded lossily)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<ParseWarning>, // Records that could not be parsed or have an unknown data type
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub confidence: BTreeMap<&'static str, FieldConfidence>, // Fields decoded with heuristics or recovered
+    #[serde(skip)]
+    pub(crate) record_sizes: Vec<(u32, u32)>, // Record type and data length of every TOC record
+    #[serde(skip)]
+    pub(crate) rejected_path: bool, // A path component was rejected by the path policy
+    #[serde(skip)]
+    pub(crate) failed_record: Option<RecordContext>, // First TOC record that could not be read
+    #[serde(skip)]
+    pub(crate) offset_before_data: bool, // A record offset points before the bookmark data
+    #[serde(skip)]
+    pub(crate) exceeded_limit: bool, // Record count or size is over the resource limits
+    #[serde(skip)]
+    pub(crate) decoded_bytes: usize, // Record data decoded for array elements
+    #[serde(skip)]
+    pub(crate) provenance: Vec<FieldProvenance>, // TOC record behind each populated field
+    #[serde(skip)]
+    pub(crate) record_confidence: FieldConfidence, // Confidence of the fields of the record being parsed
+    #[serde(skip)]
+    pub(crate) recovered_toc: bool, // TOC record count was clamped in lenient mode
+    #[serde(skip)]
+    pub(crate) conflicts: Vec<RecordConflict>, // Record types that appear more than once
+    #[serde(skip)]
+    pub(crate) toc_headers: Vec<TocHeaderMagic>, // Header magic of every TOC in chain order
+    #[serde(skip)]
+    pub(crate) raw_records: BTreeMap<u32, RawRecord>, // Raw data of every TOC record
+    #[serde(skip)]
+    pub(crate) values: Vec<(u32, BookmarkValue)>, // Typed value of every TOC record in TOC order
+    #[serde(skip)]
+    pub(crate) record_offsets: Vec<RecordOffset>, // File offsets of every TOC record in TOC order
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct FieldProvenance {
+    pub field: &'static str,         // BookmarkData field name
+    pub record_type: u32,            // Record/Key type that produced the field
+    pub data_type: u32,              // Data type of the record
+    pub offset: u32,                 // Offset to the record from the start of the bookmark
+    pub confidence: FieldConfidence, // How the field value was decoded
+}
+
+/// How a field value was decoded, ordered from most to least reliable
+#[derive(Debug, Serialize, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FieldConfidence {
+    /// Decoded as stored
+    #[default]
+    Exact,
+    /// Decoded with a guess (ex: UTF-16 byte order without a BOM)
+    Heuristic,
+    /// Lossy decoded or read from a TOC clamped in lenient mode
+    Recovered,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct NestedBookmark {
+    pub record_type: u32, // Record/Key type that contained the embedded bookmark
+    pub bookmark: BookmarkData, // Parsed embedded bookmark
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DuplicateRecord {
+    pub record_type: u32,       // Record/Key type that was duplicated
+    pub offset: u32,            // Offset to the record from the start of the bookmark
+    pub bookmark: BookmarkData, // Fields parsed from only this record
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct RawRecord {
+    pub data_type: u32, // Data type of the record
+    pub offset: u32,    // Offset to the record from the start of the bookmark
+    pub data: Vec<u8>,  // Raw record data (offsets to the element records for arrays)
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct UnknownRecord {
+    pub record_type: u32, // Record/Key type
+    pub data_type: u32,   // Data type of the record
+    pub offset: u32,      // Offset to the record from the start of the bookmark
+    pub data: Vec<u8>,    // Raw record data
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct FileMetadata {
+    pub record_type: u32,     // Record/Key type (0x1054-0x1057)
+    pub value: BookmarkValue, // Date, integer, bool, UUID or string value
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct PathLengthMismatch {
+    pub index: usize,          // Index of the path component
+    pub expected: Option<i64>, // Length from the URL length array
+    pub actual: Option<usize>, // Length of the path component in bytes
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub struct UrlComponentCounts {
+    pub system: usize, // Leading path components not in the URL (ex: System/Volumes/Data)
+    pub visible: usize, // Path components covered by the URL length array
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct TruncatedRecord {
+    pub record_type: u32, // Record/Key type
+    pub data_type: u32,   // Data type of the record
+    pub offset: u32,      // Offset to the record from the start of the bookmark
+    pub length: u32,      // Length of the record data
+    pub kept_length: u32, // Length of the record data that was parsed
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct LossyString {
+    pub record_type: u32, // Record/Key type
+    pub offset: u32,      // Offset to the record from the start of the bookmark
+    pub raw: Vec<u8>,     // Original string bytes, the field has the lossy decoded string
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct RecordOffset {
+    pub record_type: u32,   // Record/Key type
+    pub data_type: u32,     // Data type of the record
+    pub offset: u32,        // Offset to the record (length field) from the start of the bookmark
+    pub data_offset: u32,   // Offset to the record data from the start of the bookmark
+    pub data_length: u32,   // Length of the record data
+    pub padded_length: u32, // Length of the record data including the padding to a 4 byte boundary
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct RecordConflict {
+    pub record_type: u32,  // Record/Key type that appears more than once
+    pub offsets: Vec<u32>, // Offset to each record from the start of the bookmark
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ParseWarning {
+    pub record_type: u32,                 // Record/Key type of the record
+    pub offset: u32,                      // Offset to the record from the start of the bookmark
+    pub key: String,                      // Locale key of the message
+    pub values: BTreeMap<String, String>, // Values of the message placeholders
+    pub message: String,                  // Description of the problem (English)
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub struct TocHeaderMagic {
+    pub offset: u32,      // Offset to the TOC from the start of the bookmark
+    pub record_type: u16, // TOC header record type (0xfffe)
+    pub flags: u16,       // TOC header flags (0xffff)
 }
 
 #[derive(Debug)]
@@ -60,8 +241,8 @@ struct TableOfContentsOffset {
 #[derive(Debug)]
 struct TableOfContentsHeader {
     data_length: u32, // Size of TOC
-    record_type: u16, // Unused TOC record/key type (Possible magic number along side flags (0xfffffffe))
-    flags: u16,       // Unused flag (Possible magic number along side record_type (0xfffffffe))
+    record_type: u16, // TOC record/key type, magic number along side flags (0xfffe)
+    flags: u16,       // TOC flags, magic number along side record_type (0xffff)
 }
 
 #[derive(Debug)]
@@ -79,68 +260,232 @@ struct TableOfContentsDataRecord {
 }
 
 #[derive(Debug)]
-struct StandardDataRecord {
-    data_length: u32,     // Length of data
-    data_type: u32,       // Type of data
-    record_data: Vec<u8>, // Data
-    record_type: u32,     // Record type (from TableOfContentsDataRecord)
+struct StandardDataRecord<'a> {
+    data_length: u32,      // Length of data
+    data_type: u32,        // Type of data
+    record_data: &'a [u8], // Data (within the length limits)
+    record_type: u32,      // Record type (from TableOfContentsDataRecord)
+}
+
+impl BookmarkHeader {
+    /// Check the header signature, version and data offset. Fails with `WrongSignature` or `UnsupportedVersion` and
+    /// the observed value, or `BadHeader` if the bookmark data does not start after the header
+    pub fn validate(&self) -> Result<(), BookmarkError> {
+        let signature = self.signature.to_le_bytes();
+        if signature != BookmarkData::BOOKMARK_SIGNATURE
+            && signature != BookmarkData::ALIAS_SIGNATURE
+        {
+            return Err(BookmarkError::WrongSignature(self.signature));
+        }
+        if self.version != BookmarkData::HEADER_VERSION {
+            return Err(BookmarkError::UnsupportedVersion(self.version));
+        }
+        if self.bookmark_data_offset != BookmarkData::HEADER_SIZE {
+            return Err(BookmarkError::BadHeader);
+        }
+        Ok(())
+    }
+}
+
+impl ParseWarning {
+    /// Get the description of the problem from a message table
+    pub fn message_localized(&self, locale: &Localization) -> String {
+        let values: Vec<(&str, &str)> = self
+            .values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        locale.format(&self.key, &values)
+    }
+}
+
+impl TocHeaderMagic {
+    /// Check if the header has the record type and flags written by macOS
+    pub fn is_standard(&self) -> bool {
+        self.record_type == BookmarkData::TOC_MAGIC_RECORD_TYPE
+            && self.flags == BookmarkData::TOC_MAGIC_FLAGS
+    }
+
+    fn new(offset: u32, header: &TableOfContentsHeader) -> TocHeaderMagic {
+        let magic = TocHeaderMagic {
+            offset: offset.saturating_add(BookmarkData::HEADER_SIZE),
+            record_type: header.record_type,
+            flags: header.flags,
+        };
+        if !magic.is_standard() {
+            warn!(
+                "Nonstandard TOC header magic at offset {}: {:#x}/{:#x}",
+                magic.offset, magic.record_type, magic.flags
+            );
+        }
+        magic
+    }
+}
+
+impl RecordOffset {
+    /// Get the record offsets from the start of the bookmark
+    fn new(record: &TableOfContentsDataRecord, standard_data: &StandardDataRecord) -> RecordOffset {
+        // Record length and data type
+        let record_header_size = 8;
+        let alignment = 4;
+
+        let offset = record.data_offset.saturating_add(BookmarkData::HEADER_SIZE);
+        let padding = (alignment - standard_data.data_length % alignment) % alignment;
+        RecordOffset {
+            record_type: record.record_type,
+            data_type: standard_data.data_type,
+            offset,
+            data_offset: offset.saturating_add(record_header_size),
+            data_length: standard_data.data_length,
+            padded_length: standard_data.data_length.saturating_add(padding),
+        }
+    }
 }
 
 impl BookmarkData {
+    pub(crate) const BOOKMARK_SIGNATURE: &'static [u8] = b"book";
+    /// Alternate signature of bookmark data, also accepted by mac_alias
+    pub(crate) const ALIAS_SIGNATURE: &'static [u8] = b"alis";
+    /// Header version of bookmarks created by macOS (bytes `00 00 04 10`)
+    pub(crate) const HEADER_VERSION: u32 = 0x410;
+    // Bookmark data starts after the 48 byte header
+    pub(crate) const HEADER_SIZE: u32 = 48;
+
     // Data types
-    const STRING_TYPE: u32 = 0x0101;
-    const DATA_TYPE: u32 = 0x0201;
-    const _NUMBER_ONE_BYTE: u32 = 0x0301;
-    const _NUMBER_TWO_BYTE: u32 = 0x0302;
-    const NUMBER_FOUR_BYTE: u32 = 0x0303;
-    const NUMBER_EIGHT_BYTE: u32 = 0x0304;
-    const _NUMBER_FLOAT: u32 = 0x0305;
-    const _NUMBERBER_FLOAT64: u32 = 0x0306;
-    const DATE: u32 = 0x0400;
-    const BOOL_FALSE: u32 = 0x0500;
-    const BOOL_TRUE: u32 = 0x0501;
-    const ARRAY_TYPE: u32 = 0x0601;
-    const _DICTIONARY: u32 = 0x0701;
-    const _UUID: u32 = 0x0801;
-    const URL: u32 = 0x0901;
-    const _URL_RELATIVE: u32 = 0x0902;
+    pub(crate) const STRING_TYPE: u32 = 0x0101;
+    pub(crate) const DATA_TYPE: u32 = 0x0201;
+    pub(crate) const NUMBER_ONE_BYTE: u32 = 0x0301;
+    pub(crate) const NUMBER_TWO_BYTE: u32 = 0x0302;
+    pub(crate) const NUMBER_FOUR_BYTE: u32 = 0x0303;
+    pub(crate) const NUMBER_EIGHT_BYTE: u32 = 0x0304;
+    pub(crate) const NUMBER_FLOAT: u32 = 0x0305;
+    pub(crate) const NUMBER_FLOAT64: u32 = 0x0306;
+    pub(crate) const DATE: u32 = 0x0400;
+    pub(crate) const BOOL_FALSE: u32 = 0x0500;
+    pub(crate) const BOOL_TRUE: u32 = 0x0501;
+    pub(crate) const ARRAY_TYPE: u32 = 0x0601;
+    pub(crate) const DICTIONARY: u32 = 0x0701;
+    pub(crate) const UUID: u32 = 0x0801;
+    pub(crate) const URL: u32 = 0x0901;
+    pub(crate) const URL_RELATIVE: u32 = 0x0902;
 
     // Table of Contents Key types
     const _UNKNOWN: u32 = 0x1003;
-    const TARGET_PATH: u32 = 0x1004;
-    const TARGET_CNID_PATH: u32 = 0x1005;
-    const TARGET_FLAGS: u32 = 0x1010;
-    const _TARGET_FILENAME: u32 = 0x1020;
-    const TARGET_CREATION_DATE: u32 = 0x1040;
-    const _UKNOWN2: u32 = 0x1054;
-    const _UNKNOWN3: u32 = 0x1055;
-    const _UNKNOWN4: u32 = 0x1056;
-    const _UNKNOWN5: u32 = 0x1057;
+    pub(crate) const TARGET_PATH: u32 = 0x1004;
+    pub(crate) const TARGET_CNID_PATH: u32 = 0x1005;
+    pub(crate) const TARGET_FLAGS: u32 = 0x1010;
+    pub(crate) const TARGET_FILENAME: u32 = 0x1020;
+    pub(crate) const TARGET_CREATION_DATE: u32 = 0x1040;
+    pub(crate) const FILE_METADATA_1054: u32 = 0x1054;
+    pub(crate) const FILE_METADATA_1055: u32 = 0x1055;
+    pub(crate) const FILE_METADATA_1056: u32 = 0x1056;
+    pub(crate) const FILE_METADATA_1057: u32 = 0x1057;
     const _UNKNOWN6: u32 = 0x1101;
     const _UNKNOWN7: u32 = 0x1102;
-    const _TOC_PATH: u32 = 0x2000;
-    const VOLUME_PATH: u32 = 0x2002;
-    const VOLUME_URL: u32 = 0x2005;
-    const VOLUME_NAME: u32 = 0x2010;
-    const VOLUME_UUID: u32 = 0x2011;
-    const VOLUME_SIZE: u32 = 0x2012;
-    const VOLUME_CREATION: u32 = 0x2013;
-    const _VOLUME_BOOKMARK: u32 = 0x2040;
-    const VOLUME_FLAGS: u32 = 0x2020;
-    const VOLUME_ROOT: u32 = 0x2030;
-    const _VOLUME_MOUNT_POINT: u32 = 0x2050;
+    pub(crate) const TOC_PATH: u32 = 0x2000;
+    pub(crate) const VOLUME_PATH: u32 = 0x2002;
+    pub(crate) const VOLUME_URL: u32 = 0x2005;
+    pub(crate) const VOLUME_NAME: u32 = 0x2010;
+    pub(crate) const VOLUME_UUID: u32 = 0x2011;
+    pub(crate) const VOLUME_SIZE: u32 = 0x2012;
+    pub(crate) const VOLUME_CREATION: u32 = 0x2013;
+    pub(crate) const VOLUME_BOOKMARK: u32 = 0x2040;
+    pub(crate) const VOLUME_FLAGS: u32 = 0x2020;
+    pub(crate) const VOLUME_ROOT: u32 = 0x2030;
+    pub(crate) const VOLUME_MOUNT_POINT: u32 = 0x2050;
     const _UNKNOWN8: u32 = 0x2070;
-    const CONTAIN_FOLDER_INDEX: u32 = 0xc001;
-    const CREATOR_USERNAME: u32 = 0xc011;
-    const CREATOR_UID: u32 = 0xc012;
-    const FILE_REF_FLAG: u32 = 0xd001;
-    const CREATION_OPTIONS: u32 = 0xd010;
-    const _URL_LENGTH_ARRAY: u32 = 0xe003;
-    const LOCALIZED_NAME: u32 = 0xf017;
-    const _UNKNOWN9: u32 = 0xf022;
-    const SECURITY_EXTENSION_RW: u32 = 0xf080;
-    const SECURITY_EXTENSION_RO: u32 = 0xf081;
-    const IS_EXECUTABLE: u32 = 0xf000f;
+    pub(crate) const CONTAIN_FOLDER_INDEX: u32 = 0xc001;
+    pub(crate) const CREATOR_USERNAME: u32 = 0xc011;
+    pub(crate) const CREATOR_UID: u32 = 0xc012;
+    pub(crate) const FILE_REF_FLAG: u32 = 0xd001;
+    pub(crate) const CREATION_OPTIONS: u32 = 0xd010;
+    pub(crate) const URL_LENGTH_ARRAY: u32 = 0xe003;
+    pub(crate) const LOCALIZED_NAME: u32 = 0xf017;
+    pub(crate) const DISPLAY_NAME: u32 = 0xf022;
+    pub(crate) const SECURITY_EXTENSION_RW: u32 = 0xf080;
+    pub(crate) const SECURITY_EXTENSION_RO: u32 = 0xf081;
+    pub(crate) const IS_EXECUTABLE: u32 = 0xf000f;
+
+    // TOC header magic written by macOS
+    const TOC_MAGIC_RECORD_TYPE: u16 = 0xfffe;
+    const TOC_MAGIC_FLAGS: u16 = 0xffff;
+
+    // Size of a TOC record (record type, data offset and reserved)
+    const TOC_RECORD_SIZE: usize = 12;
+
+    // Maximum number of chained TOCs to follow
+    const MAX_TOC_COUNT: usize = 64;
+
+    // Volume property flags
+    const VOLUME_SUPPORTS_PERSISTENT_IDS: u64 = 0x100000000;
+
+    /// Fields populated in every bookmark sample created by current macOS releases
+    const FULL_RECORD_SET_FIELDS: [&'static str; 5] = [
+        "target_flags",
+        "volume_uuid",
+        "volume_creation",
+        "volume_flag",
+        "volume_root",
+    ];
+
+    /// Table of Contents Key types this library knows about
+    pub(crate) const KNOWN_RECORD_TYPES: [u32; 35] = [
+        BookmarkData::_UNKNOWN,
+        BookmarkData::TARGET_PATH,
+        BookmarkData::TARGET_CNID_PATH,
+        BookmarkData::TARGET_FLAGS,
+        BookmarkData::TARGET_FILENAME,
+        BookmarkData::TARGET_CREATION_DATE,
+        BookmarkData::FILE_METADATA_1054,
+        BookmarkData::FILE_METADATA_1055,
+        BookmarkData::FILE_METADATA_1056,
+        BookmarkData::FILE_METADATA_1057,
+        BookmarkData::_UNKNOWN6,
+        BookmarkData::_UNKNOWN7,
+        BookmarkData::TOC_PATH,
+        BookmarkData::VOLUME_PATH,
+        BookmarkData::VOLUME_URL,
+        BookmarkData::VOLUME_NAME,
+        BookmarkData::VOLUME_UUID,
+        BookmarkData::VOLUME_SIZE,
+        BookmarkData::VOLUME_CREATION,
+        BookmarkData::VOLUME_BOOKMARK,
+        BookmarkData::VOLUME_FLAGS,
+        BookmarkData::VOLUME_ROOT,
+        BookmarkData::VOLUME_MOUNT_POINT,
+        BookmarkData::_UNKNOWN8,
+        BookmarkData::CONTAIN_FOLDER_INDEX,
+        BookmarkData::CREATOR_USERNAME,
+        BookmarkData::CREATOR_UID,
+        BookmarkData::FILE_REF_FLAG,
+        BookmarkData::CREATION_OPTIONS,
+        BookmarkData::URL_LENGTH_ARRAY,
+        BookmarkData::LOCALIZED_NAME,
+        BookmarkData::DISPLAY_NAME,
+        BookmarkData::SECURITY_EXTENSION_RW,
+        BookmarkData::SECURITY_EXTENSION_RO,
+        BookmarkData::IS_EXECUTABLE,
+    ];
+
+    /// Standard data types this library knows about
+    pub(crate) const KNOWN_DATA_TYPES: [u32; 16] = [
+        BookmarkData::STRING_TYPE,
+        BookmarkData::DATA_TYPE,
+        BookmarkData::NUMBER_ONE_BYTE,
+        BookmarkData::NUMBER_TWO_BYTE,
+        BookmarkData::NUMBER_FOUR_BYTE,
+        BookmarkData::NUMBER_EIGHT_BYTE,
+        BookmarkData::NUMBER_FLOAT,
+        BookmarkData::NUMBER_FLOAT64,
+        BookmarkData::DATE,
+        BookmarkData::BOOL_FALSE,
+        BookmarkData::BOOL_TRUE,
+        BookmarkData::ARRAY_TYPE,
+        BookmarkData::DICTIONARY,
+        BookmarkData::UUID,
+        BookmarkData::URL,
+        BookmarkData::URL_RELATIVE,
+    ];
 
     /// Parse bookmark header
     pub fn parse_bookmark_header(data: &[u8]) -> nom::IResult<&[u8], BookmarkHeader> {
@@ -173,6 +518,18 @@ impl BookmarkData {
 
     /// Parse the core bookmark data
     pub fn parse_bookmark_data(data: &[u8]) -> nom::IResult<&[u8], BookmarkData> {
+        let settings = ParserOptions {
+            max_depth: 0,
+            ..Default::default()
+        };
+        BookmarkData::parse_bookmark_data_nested(data, &settings)
+    }
+
+    /// Parse the core bookmark data, parsing embedded bookmarks until the max depth is reached
+    pub(crate) fn parse_bookmark_data_nested<'a>(
+        data: &'a [u8],
+        settings: &ParserOptions,
+    ) -> nom::IResult<&'a [u8], BookmarkData> {
         let mut book_data = TableOfContentsOffset {
             table_of_contents_offset: 0,
         };
@@ -182,263 +539,1182 @@ impl BookmarkData {
 
         book_data.table_of_contents_offset = toc_offset;
         let toc_offset_size: u32 = 4;
-        let (input, core_data) = take(book_data.table_of_contents_offset - toc_offset_size)(input)?;
+        let core_size = book_data
+            .table_of_contents_offset
+            .checked_sub(toc_offset_size)
+            .ok_or_else(|| offset_before_data(data))?;
+        let (input, core_data) = take(core_size)(input)?;
 
         let (input, toc_header) = BookmarkData::table_of_contents_header(input)?;
 
         let (toc_record_data, toc_content_data) =
             BookmarkData::table_of_contents_data(input, toc_header.data_length)?;
 
-        let (_, toc_content_data_record) = BookmarkData::table_of_contents_record(
-            toc_record_data,
-            &toc_content_data.number_of_records,
-        )?;
-
-        let mut bookmark_data = BookmarkData {
-            path: Vec::new(),
-            cnid_path: Vec::new(),
-            target_flags: Vec::new(),
-            creation: 0.0,
-            volume_path: String::new(),
-            volume_url: String::new(),
-            volume_name: String::new(),
-            volume_uuid: String::new(),
-            volume_size: 0,
-            volume_creation: 0.0,
-            volume_flag: Vec::new(),
-            volume_root: false,
-            localized_name: String::new(),
-            security_extension_rw: String::new(),
-            username: String::new(),
-            uid: 0,
-            creation_options: 0,
-            folder_index: 0,
-            is_executable: false,
-            security_extension_ro: String::new(),
-            file_ref_flag: false,
-        };
+        let mut bookmark_data = BookmarkData::default();
+
+        let mut record_count = toc_content_data.number_of_records;
+        // TOC data length covers the TOC header (12 bytes) and every record
+        let expected_length = record_count
+            .saturating_add(1)
+            .saturating_mul(BookmarkData::TOC_RECORD_SIZE as u32);
+        if settings.mode == ParseMode::Strict && toc_header.data_length != expected_length {
+            bookmark_data.add_warning(
+                0,
+                book_data.table_of_contents_offset,
+                "warning.toc_length",
+                &[
+                    ("length", &toc_header.data_length.to_string()),
+                    ("count", &record_count.to_string()),
+                ],
+            );
+        }
+        let available = (toc_record_data.len() / BookmarkData::TOC_RECORD_SIZE) as u32;
+        if settings.mode == ParseMode::Lenient && record_count > available {
+            bookmark_data.add_warning(
+                0,
+                book_data.table_of_contents_offset,
+                "warning.toc_records",
+                &[
+                    ("count", &record_count.to_string()),
+                    ("available", &available.to_string()),
+                ],
+            );
+            record_count = available;
+            bookmark_data.recovered_toc = true;
+        }
+        let max_records = settings.resource_limits.max_records;
+        if record_count as usize > max_records {
+            warn!("TOC lists {record_count} records, limit is {max_records}");
+            bookmark_data.exceeded_limit = true;
+            return Ok((input, bookmark_data));
+        }
+        let (_, mut toc_content_data_record) =
+            BookmarkData::table_of_contents_record(toc_record_data, &record_count)?;
+        bookmark_data.toc_headers.push(TocHeaderMagic::new(
+            book_data.table_of_contents_offset,
+            &toc_header,
+        ));
+
+        // Merge the records of every chained TOC, a chain pointing back to an earlier TOC is only followed once
+        let mut toc_offsets = vec![book_data.table_of_contents_offset];
+        let mut next_toc = toc_content_data.next_record_offset;
+        while next_toc != 0 {
+            if toc_offsets.contains(&next_toc) || toc_offsets.len() >= BookmarkData::MAX_TOC_COUNT {
+                warn!("Stopping TOC chain at offset: {}", next_toc);
+                bookmark_data.add_warning(0, next_toc, "warning.toc_chain_stopped", &[]);
+                break;
+            }
+            toc_offsets.push(next_toc);
+            match BookmarkData::chained_table_of_contents(data, next_toc) {
+                Ok((_, (header, next, records))) => {
+                    bookmark_data
+                        .toc_headers
+                        .push(TocHeaderMagic::new(next_toc, &header));
+                    toc_content_data_record.extend(records);
+                    next_toc = next;
+                    if toc_content_data_record.len() > max_records {
+                        warn!("Chained TOCs list more than {max_records} records");
+                        bookmark_data.exceeded_limit = true;
+                        return Ok((input, bookmark_data));
+                    }
+                }
+                Err(err) => {
+                    warn!(
+                        "Failed to parse chained TOC at offset {}: {:?}",
+                        next_toc, err
+                    );
+                    bookmark_data.add_warning(0, next_toc, "warning.chained_toc", &[]);
+                    break;
+                }
+            }
+        }
+
+        let mut record_offsets: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+        // Check the declared length of every record before any record data is copied
+        let max_record_size = settings.resource_limits.max_record_size;
+        if let Some(record) = toc_content_data_record.iter().find(|record| {
+            BookmarkData::record_length(core_data, record.data_offset)
+                .is_some_and(|length| length as usize > max_record_size)
+        }) {
+            warn!(
+                "Record Type: {} at offset: {} is larger than the limit {max_record_size}",
+                record.record_type, record.data_offset
+            );
+            bookmark_data.exceeded_limit = true;
+            return Ok((input, bookmark_data));
+        }
 
         for record in toc_content_data_record {
-            let (_, standard_data) = BookmarkData::bookmark_standard_data(core_data, &record)?;
-            let record_data = standard_data.record_data;
-            let mut standard_data_vec: Vec<StandardDataRecord> = Vec::new();
-
-            // If data type is ARRAY, standard_data data points to offsets that contain actual bookmark data
-            if standard_data.data_type == BookmarkData::ARRAY_TYPE {
-                let results_data = BookmarkData::bookmark_array(&record_data);
-                match results_data {
-                    Ok((_, results)) => {
-                        if results.is_empty() {
-                            continue;
-                        }
+            // Record header is read once, the record data is borrowed from the bookmark data
+            let standard_data =
+                BookmarkData::bookmark_standard_data(core_data, &record, &settings.limits)
+                    .map(|(_, standard_data)| standard_data);
+            if let Ok(standard_data) = &standard_data {
+                bookmark_data
+                    .record_offsets
+                    .push(RecordOffset::new(&record, standard_data));
+            }
+            if settings.preserve_raw {
+                if let Some(value) = decode_record(core_data, record.data_offset, &settings.limits)
+                {
+                    bookmark_data.values.push((record.record_type, value));
+                }
+            }
+
+            let offsets = record_offsets.entry(record.record_type).or_default();
+            offsets.push(record.data_offset.saturating_add(BookmarkData::HEADER_SIZE));
+            let results = if offsets.len() == 1 {
+                bookmark_data.parse_record_lenient(core_data, &record, standard_data, settings)
+            } else {
+                warn!(
+                    "Duplicate Record Type: {} at offset: {}",
+                    record.record_type, record.data_offset
+                );
+                match settings.duplicate_policy {
+                    DuplicatePolicy::First => Ok(()),
+                    DuplicatePolicy::Last | DuplicatePolicy::Error => {
+                        bookmark_data.clear_record_fields(record.record_type);
+                        bookmark_data.parse_record_lenient(
+                            core_data,
+                            &record,
+                            standard_data,
+                            settings,
+                        )
+                    }
+                    DuplicatePolicy::CollectAll => {
+                        let mut duplicate = BookmarkData {
+                            recovered_toc: bookmark_data.recovered_toc,
+                            decoded_bytes: bookmark_data.decoded_bytes,
+                            ..Default::default()
+                        };
+                        let results = duplicate.parse_record_lenient(
+                            core_data,
+                            &record,
+                            standard_data,
+                            settings,
+                        );
+                        bookmark_data.offset_before_data |= duplicate.offset_before_data;
+                        bookmark_data.exceeded_limit |= duplicate.exceeded_limit;
+                        bookmark_data.decoded_bytes = duplicate.decoded_bytes;
+                        results.map(|()| {
+                            bookmark_data.duplicate_records.push(DuplicateRecord {
+                                record_type: record.record_type,
+                                offset: record
+                                    .data_offset
+                                    .saturating_add(BookmarkData::HEADER_SIZE),
+                                bookmark: duplicate,
+                            })
+                        })
+                    }
+                }
+            };
+            // Stop at the first record that cannot be read, the parser reports it as BookmarkError::BadRecord
+            if let Err(context) = results {
+                bookmark_data.failed_record = Some(context);
+                return Ok((input, bookmark_data));
+            }
+            if bookmark_data.exceeded_limit {
+                return Ok((input, bookmark_data));
+            }
+        }
 
-                        let (_, std_data_vec) =
-                            BookmarkData::bookmark_array_data(core_data, results, &record)?;
+        bookmark_data.has_persistent_ids = bookmark_data.persistent_ids();
+        bookmark_data.record_set = bookmark_data.detect_record_set();
+        bookmark_data.conflicts = record_offsets
+            .into_iter()
+            .filter(|(_, offsets)| offsets.len() > 1)
+            .map(|(record_type, offsets)| RecordConflict {
+                record_type,
+                offsets,
+            })
+            .collect();
+        Ok((input, bookmark_data))
+    }
 
-                        // Now we have data for actual bookmark data
-                        standard_data_vec = std_data_vec;
+    /// Parse a single TOC record. In lenient mode a record that cannot be read is listed in `warnings` instead of
+    /// failing the parse, otherwise the location of the record is returned
+    fn parse_record_lenient<'a>(
+        &mut self,
+        core_data: &'a [u8],
+        record: &TableOfContentsDataRecord,
+        standard_data: Result<StandardDataRecord<'a>, nom::Err<Error<&'a [u8]>>>,
+        settings: &ParserOptions,
+    ) -> Result<(), RecordContext> {
+        let results = standard_data.and_then(|standard_data| {
+            self.parse_record(core_data, record, &standard_data, settings)
+        });
+        let Err(err) = results else {
+            return Ok(());
+        };
+        warn!(
+            "Failed to read Record Type: {} at offset: {}: {:?}",
+            record.record_type, record.data_offset, err
+        );
+        if settings.mode != ParseMode::Lenient {
+            self.offset_before_data = is_offset_before_data(&err);
+            // Core data starts after the header and the TOC offset
+            let core_offset = BookmarkData::HEADER_SIZE as usize + 4;
+            return Err(RecordContext {
+                record_type: record.record_type,
+                data_type: BookmarkData::record_data_type(core_data, record.data_offset),
+                offset: record.data_offset.saturating_add(BookmarkData::HEADER_SIZE),
+                failure: ParseFailure::from_nom(core_data, core_offset, &err),
+            });
+        }
+        self.add_warning(
+            record.record_type,
+            record.data_offset,
+            "warning.read_record",
+            &[("error", &err.to_string())],
+        );
+        Ok(())
+    }
+
+    /// List a warning in `warnings`. The offset is relative to the start of the bookmark data
+    fn add_warning(&mut self, record_type: u32, offset: u32, key: &str, values: &[(&str, &str)]) {
+        self.warnings.push(ParseWarning {
+            record_type,
+            offset: offset.saturating_add(BookmarkData::HEADER_SIZE),
+            key: key.to_string(),
+            values: values
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            message: locale::english_format(key, values),
+        });
+    }
+
+    /// Log a record that could not be parsed and list it in `warnings`
+    fn record_warning(
+        &mut self,
+        record: &TableOfContentsDataRecord,
+        key: &str,
+        values: &[(&str, &str)],
+    ) {
+        self.add_warning(record.record_type, record.data_offset, key, values);
+        if let Some(warning) = self.warnings.last() {
+            warn!("{}", warning.message);
+        }
+    }
+
+    /// Parse a single TOC record, preserving the record if it did not populate a field. The raw record data is only
+    /// copied if requested with `preserve_unknown` or `preserve_raw`
+    fn parse_record<'a>(
+        &mut self,
+        core_data: &'a [u8],
+        record: &TableOfContentsDataRecord,
+        standard_data: &StandardDataRecord<'a>,
+        settings: &ParserOptions,
+    ) -> Result<(), nom::Err<Error<&'a [u8]>>> {
+        let offset = record.data_offset.saturating_add(BookmarkData::HEADER_SIZE);
+        let fields = self.provenance.len();
+        self.record_confidence = if self.recovered_toc {
+            FieldConfidence::Recovered
+        } else {
+            FieldConfidence::Exact
+        };
+        self.parse_record_fields(core_data, record, standard_data, settings)?;
+        if settings.preserve_unknown && self.provenance.len() == fields {
+            self.unknown_records.push(UnknownRecord {
+                record_type: record.record_type,
+                data_type: standard_data.data_type,
+                offset,
+                data: standard_data.record_data.to_vec(),
+            });
+        }
+        if settings.preserve_raw {
+            self.raw_records.insert(
+                record.record_type,
+                RawRecord {
+                    data_type: standard_data.data_type,
+                    offset,
+                    data: standard_data.record_data.to_vec(),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Parse a single TOC record into the bookmark fields
+    fn parse_record_fields<'a>(
+        &mut self,
+        core_data: &'a [u8],
+        record: &TableOfContentsDataRecord,
+        standard_data: &StandardDataRecord<'a>,
+        settings: &ParserOptions,
+    ) -> Result<(), nom::Err<Error<&'a [u8]>>> {
+        self.record_sizes
+            .push((standard_data.record_type, standard_data.data_length));
+        self.flag_truncated(standard_data, record.data_offset);
+        let record_data = standard_data.record_data;
+        let warnings = self.warnings.len();
+        let nested = standard_data.data_type == BookmarkData::DATA_TYPE
+            && record_data.starts_with(BookmarkData::BOOKMARK_SIGNATURE);
+        let mut standard_data_vec: Vec<StandardDataRecord> = Vec::new();
+
+        // If data type is ARRAY, standard_data data points to offsets that contain actual bookmark data
+        if standard_data.data_type == BookmarkData::ARRAY_TYPE {
+            let results_data = BookmarkData::bookmark_array(record_data);
+            match results_data {
+                Ok((_, results)) => {
+                    if results.is_empty() {
+                        return Ok(());
                     }
-                    Err(err) => warn!("Failed to get bookmark standard data: {:?}", err),
+                    let max_elements = settings.resource_limits.max_array_elements;
+                    if results.len() > max_elements {
+                        warn!(
+                            "Array Record Type: {} has {} elements, limit is {max_elements}",
+                            record.record_type,
+                            results.len()
+                        );
+                        self.exceeded_limit = true;
+                        return Ok(());
+                    }
+
+                    let element_offsets = results.clone();
+                    let (_, std_data_vec) = BookmarkData::bookmark_array_data(
+                        core_data,
+                        results,
+                        record,
+                        &settings.limits,
+                    )?;
+                    for (element, offset) in std_data_vec.iter().zip(element_offsets) {
+                        self.flag_truncated(element, offset);
+                    }
+                    // Elements can all point to the same record, every copy counts against the budget
+                    self.decoded_bytes = std_data_vec
+                        .iter()
+                        .fold(self.decoded_bytes, |total, element| {
+                            total.saturating_add(element.record_data.len())
+                        });
+                    let max_decoded = settings.resource_limits.max_decoded_bytes;
+                    if self.decoded_bytes > max_decoded {
+                        warn!(
+                            "Array elements decode to {} bytes, limit is {max_decoded}",
+                            self.decoded_bytes
+                        );
+                        self.exceeded_limit = true;
+                        return Ok(());
+                    }
+
+                    // Now we have data for actual bookmark data
+                    standard_data_vec = std_data_vec;
+                }
+                Err(err) => {
+                    self.record_warning(
+                        record,
+                        "warning.array_data",
+                        &[("error", &format!("{err:?}"))],
+                    );
                 }
             }
+        }
 
-            // If we did not have to parse array data, get bookmark data based on record and data types
-            if standard_data_vec.is_empty() {
-                if standard_data.record_type == BookmarkData::TARGET_FLAGS
-                    && standard_data.data_type == BookmarkData::DATA_TYPE
-                {
-                    let flag_data = BookmarkData::bookmark_target_flags(&record_data);
-                    match flag_data {
-                        Ok((_, flags)) => {
-                            if flags.is_empty() {
-                                continue;
-                            }
-                            bookmark_data.target_flags = flags;
+        // If we did not have to parse array data, get bookmark data based on record and data types
+        if standard_data_vec.is_empty() {
+            // Some records (ex: VOLUME_BOOKMARK) contain a complete bookmark
+            if nested {
+                if settings.max_depth == 0 {
+                    self.record_warning(
+                        record,
+                        "warning.max_depth",
+                        &[("record_type", &standard_data.record_type.to_string())],
+                    );
+                    return Ok(());
+                }
+                let nested_settings = ParserOptions {
+                    max_depth: settings.max_depth - 1,
+                    ..*settings
+                };
+                let nested_data =
+                    crate::parser::parse_nested_bookmark(record_data, &nested_settings);
+                match nested_data {
+                    Ok(nested) => self.nested_bookmarks.push(NestedBookmark {
+                        record_type: standard_data.record_type,
+                        bookmark: nested,
+                    }),
+                    Err(err) => {
+                        self.record_warning(
+                            record,
+                            "warning.nested_bookmark",
+                            &[("error", &format!("{err:?}"))],
+                        );
+                        self.exceeded_limit |= matches!(err, BookmarkError::ResourceLimit);
+                    }
+                }
+            } else if standard_data.data_type == BookmarkData::URL_RELATIVE {
+                let relative_data =
+                    BookmarkData::bookmark_relative_url(core_data, record_data, record, settings);
+                match relative_data {
+                    Some(relative_url) => {
+                        if standard_data.record_type == BookmarkData::VOLUME_URL {
+                            self.volume_url = relative_url.resolve();
                         }
-                        Err(err) => warn!("Failed to parse Target Flags: {:?}", err),
+                        self.relative_url = Some(relative_url);
                     }
-                } else if standard_data.record_type == BookmarkData::TARGET_CREATION_DATE
-                    && standard_data.data_type == BookmarkData::DATE
-                {
-                    let creation_data = BookmarkData::bookmark_data_type_date(&record_data);
-                    match creation_data {
-                        Ok((_, creation)) => bookmark_data.creation = creation,
-                        Err(err) => warn!("Failed to parse Target creation timestamp: {:?}", err),
+                    None => {
+                        self.record_warning(
+                            record,
+                            "warning.relative_url",
+                            &[("record_type", &standard_data.record_type.to_string())],
+                        );
                     }
-                } else if standard_data.record_type == BookmarkData::VOLUME_PATH
-                    && standard_data.data_type == BookmarkData::STRING_TYPE
-                {
-                    let volume_root = BookmarkData::bookmark_data_type_string(&record_data);
-                    match volume_root {
-                        Ok(volume_root_data) => bookmark_data.volume_path = volume_root_data,
-                        Err(err) => warn!("Failed to parse Volume Path: {:?}", err),
+                }
+            } else if standard_data.record_type == BookmarkData::TARGET_FLAGS
+                && standard_data.data_type == BookmarkData::DATA_TYPE
+            {
+                let flag_data = BookmarkData::bookmark_target_flags(record_data);
+                match flag_data {
+                    Ok((_, flags)) => {
+                        if flags.is_empty() {
+                            return Ok(());
+                        }
+                        self.target_flags = flags;
                     }
-                } else if standard_data.record_type == BookmarkData::VOLUME_URL
-                    && standard_data.data_type == BookmarkData::URL
-                {
-                    let volume_url_data = BookmarkData::bookmark_data_type_string(&record_data);
-                    match volume_url_data {
-                        Ok(volume_url) => bookmark_data.volume_url = volume_url,
-                        Err(err) => warn!("Failed to parse Volume URL data: {:?}", err),
+                    Err(err) => {
+                        self.record_warning(
+                            record,
+                            "warning.target_flags",
+                            &[("error", &format!("{err:?}"))],
+                        );
                     }
-                } else if standard_data.record_type == BookmarkData::VOLUME_NAME
-                    && standard_data.data_type == BookmarkData::STRING_TYPE
-                {
-                    let volume_name_data = BookmarkData::bookmark_data_type_string(&record_data);
-                    match volume_name_data {
-                        Ok(volume_name) => bookmark_data.volume_name = volume_name,
-                        Err(err) => warn!("Failed to parse Volume Name data: {:?}", err),
+                }
+            } else if standard_data.record_type == BookmarkData::TARGET_CREATION_DATE
+                && standard_data.data_type == BookmarkData::DATE
+            {
+                let creation_data = BookmarkData::bookmark_data_type_date(record_data);
+                match creation_data {
+                    Ok((_, creation)) => self.creation = creation,
+                    Err(err) => {
+                        self.record_warning(
+                            record,
+                            "warning.creation",
+                            &[("error", &format!("{err:?}"))],
+                        );
                     }
-                } else if standard_data.record_type == BookmarkData::VOLUME_UUID
-                    && standard_data.data_type == BookmarkData::STRING_TYPE
-                {
-                    let volume_uuid_data = BookmarkData::bookmark_data_type_string(&record_data);
-                    match volume_uuid_data {
-                        Ok(volume_uuid) => bookmark_data.volume_uuid = volume_uuid,
-                        Err(err) => warn!("Failed to parse Volume UUID: {:?}", err),
+                }
+            } else if standard_data.record_type == BookmarkData::VOLUME_PATH
+                && standard_data.data_type == BookmarkData::STRING_TYPE
+            {
+                let volume_root = self.record_string(record_data, record, settings);
+                match volume_root {
+                    Ok(volume_root_data) => self.volume_path = volume_root_data,
+                    Err(err) => {
+                        self.record_warning(
+                            record,
+                            "warning.volume_path",
+                            &[("error", &format!("{err:?}"))],
+                        );
                     }
-                } else if standard_data.record_type == BookmarkData::VOLUME_SIZE
-                    && standard_data.data_type == BookmarkData::NUMBER_EIGHT_BYTE
-                {
-                    let test = BookmarkData::bookmark_data_type_number_eight(&record_data);
-                    match test {
-                        Ok((_, size)) => bookmark_data.volume_size = size,
-                        Err(err) => warn!("Failed to parse Volume size: {:?}", err),
+                }
+            } else if standard_data.record_type == BookmarkData::VOLUME_URL
+                && standard_data.data_type == BookmarkData::URL
+            {
+                let volume_url_data = self.record_string(record_data, record, settings);
+                match volume_url_data {
+                    Ok(volume_url) => self.volume_url = volume_url,
+                    Err(err) => {
+                        self.record_warning(
+                            record,
+                            "warning.volume_url",
+                            &[("error", &format!("{err:?}"))],
+                        );
                     }
-                } else if standard_data.record_type == BookmarkData::VOLUME_CREATION
-                    && standard_data.data_type == BookmarkData::DATE
-                {
-                    let creation_data = BookmarkData::bookmark_data_type_date(&record_data);
-                    match creation_data {
-                        Ok((_, creation)) => bookmark_data.volume_creation = creation,
-                        Err(err) => warn!("Failed to parse Volume Creation timestamp: {:?}", err),
+                }
+            } else if standard_data.record_type == BookmarkData::VOLUME_MOUNT_POINT
+                && (standard_data.data_type == BookmarkData::URL
+                    || standard_data.data_type == BookmarkData::STRING_TYPE)
+            {
+                let mount_point_data = self.record_string(record_data, record, settings);
+                match mount_point_data {
+                    Ok(mount_point) => self.volume_mount_point = Some(mount_point),
+                    Err(err) => {
+                        self.record_warning(
+                            record,
+                            "warning.volume_mount_point",
+                            &[("error", &format!("{err:?}"))],
+                        );
                     }
-                } else if standard_data.record_type == BookmarkData::VOLUME_FLAGS
-                    && standard_data.data_type == BookmarkData::DATA_TYPE
-                {
-                    let flags_data = BookmarkData::bookmark_target_flags(&record_data);
-                    match flags_data {
-                        Ok((_, flags)) => bookmark_data.volume_flag = flags,
-                        Err(err) => warn!("Failed to parse Volume Flags: {:?}", err),
+                }
+            } else if standard_data.record_type == BookmarkData::VOLUME_NAME
+                && standard_data.data_type == BookmarkData::STRING_TYPE
+            {
+                let volume_name_data = self.record_string(record_data, record, settings);
+                match volume_name_data {
+                    Ok(volume_name) => self.volume_name = volume_name,
+                    Err(err) => {
+                        self.record_warning(
+                            record,
+                            "warning.volume_name",
+                            &[("error", &format!("{err:?}"))],
+                        );
                     }
-                } else if standard_data.record_type == BookmarkData::VOLUME_ROOT
-                    && standard_data.data_type == BookmarkData::BOOL_TRUE
-                {
-                    bookmark_data.volume_root = true;
-                } else if standard_data.record_type == BookmarkData::VOLUME_ROOT
-                    && standard_data.data_type == BookmarkData::BOOL_FALSE
-                {
-                    bookmark_data.volume_root = false;
-                } else if standard_data.record_type == BookmarkData::FILE_REF_FLAG
-                    && standard_data.data_type == BookmarkData::BOOL_TRUE
-                {
-                    bookmark_data.file_ref_flag = true;
-                } else if standard_data.record_type == BookmarkData::IS_EXECUTABLE
-                    && standard_data.data_type == BookmarkData::BOOL_TRUE
-                {
-                    bookmark_data.is_executable = true;
-                } else if standard_data.record_type == BookmarkData::IS_EXECUTABLE
-                    && standard_data.data_type == BookmarkData::BOOL_FALSE
-                {
-                    bookmark_data.is_executable = false;
-                } else if standard_data.record_type == BookmarkData::LOCALIZED_NAME
-                    && standard_data.data_type == BookmarkData::STRING_TYPE
-                {
-                    let local_name_data = BookmarkData::bookmark_data_type_string(&record_data);
-                    match local_name_data {
-                        Ok(local_name) => bookmark_data.localized_name = local_name,
-                        Err(err) => warn!("Failed to parse Localized Name: {:?}", err),
+                }
+            } else if standard_data.record_type == BookmarkData::VOLUME_NAME
+                && standard_data.data_type == BookmarkData::DATA_TYPE
+            {
+                match self.record_utf16(record_data, record, settings) {
+                    Some(volume_name) => self.volume_name = volume_name,
+                    None => {
+                        self.record_warning(record, "warning.volume_name_utf16", &[]);
                     }
-                } else if standard_data.record_type == BookmarkData::SECURITY_EXTENSION_RW
-                    && standard_data.data_type == BookmarkData::DATA_TYPE
-                {
-                    let extension_data = BookmarkData::bookmark_data_type_string(&record_data);
-                    match extension_data {
-                        Ok(extension) => bookmark_data.security_extension_rw = extension,
-                        Err(err) => warn!("Failed to parse Security Extension RW: {:?}", err),
+                }
+            } else if standard_data.record_type == BookmarkData::VOLUME_UUID
+                && standard_data.data_type == BookmarkData::STRING_TYPE
+            {
+                let volume_uuid_data = self.record_string(record_data, record, settings);
+                match volume_uuid_data {
+                    Ok(volume_uuid) => self.volume_uuid = volume_uuid,
+                    Err(err) => {
+                        self.record_warning(
+                            record,
+                            "warning.volume_uuid",
+                            &[("error", &format!("{err:?}"))],
+                        );
                     }
-                } else if standard_data.record_type == BookmarkData::SECURITY_EXTENSION_RO
-                    && standard_data.data_type == BookmarkData::DATA_TYPE
-                {
-                    let extension_data = BookmarkData::bookmark_data_type_string(&record_data);
-                    match extension_data {
-                        Ok(extension) => bookmark_data.security_extension_ro = extension,
-                        Err(err) => warn!("Failed to parse Security Extension RO: {:?}", err),
+                }
+            } else if standard_data.record_type == BookmarkData::VOLUME_UUID
+                && standard_data.data_type == BookmarkData::UUID
+            {
+                let volume_uuid_data = BookmarkData::bookmark_data_type_uuid(record_data);
+                match volume_uuid_data {
+                    Ok((_, volume_uuid)) => self.volume_uuid = volume_uuid,
+                    Err(err) => {
+                        self.record_warning(
+                            record,
+                            "warning.volume_uuid_binary",
+                            &[("error", &format!("{err:?}"))],
+                        );
                     }
-                } else if standard_data.record_type == BookmarkData::CREATOR_USERNAME
-                    && standard_data.data_type == BookmarkData::STRING_TYPE
-                {
-                    let username_data = BookmarkData::bookmark_data_type_string(&record_data);
-                    match username_data {
-                        Ok(username) => bookmark_data.username = username,
-                        Err(err) => warn!("Failed to parse bookmark username: {:?}", err),
+                }
+            } else if standard_data.record_type == BookmarkData::VOLUME_SIZE
+                && standard_data.data_type == BookmarkData::NUMBER_EIGHT_BYTE
+            {
+                let test = BookmarkData::bookmark_data_type_number_eight(record_data);
+                match test {
+                    Ok((_, size)) => self.volume_size = size,
+                    Err(err) => {
+                        self.record_warning(
+                            record,
+                            "warning.volume_size",
+                            &[("error", &format!("{err:?}"))],
+                        );
                     }
-                } else if standard_data.record_type == BookmarkData::CONTAIN_FOLDER_INDEX
-                    && standard_data.data_type == BookmarkData::NUMBER_FOUR_BYTE
-                {
-                    let index_data = BookmarkData::bookmark_data_type_number_four(&record_data);
-                    match index_data {
-                        Ok((_, index)) => bookmark_data.folder_index = index as i64,
-                        Err(err) => warn!("Failed to parse bookmark folder index: {:?}", err),
+                }
+            } else if standard_data.record_type == BookmarkData::VOLUME_CREATION
+                && standard_data.data_type == BookmarkData::DATE
+            {
+                let creation_data = BookmarkData::bookmark_data_type_date(record_data);
+                match creation_data {
+                    Ok((_, creation)) => self.volume_creation = creation,
+                    Err(err) => {
+                        self.record_warning(
+                            record,
+                            "warning.volume_creation",
+                            &[("error", &format!("{err:?}"))],
+                        );
                     }
-                } else if standard_data.record_type == BookmarkData::CONTAIN_FOLDER_INDEX
-                    && standard_data.data_type == BookmarkData::NUMBER_EIGHT_BYTE
-                {
-                    let index_data = BookmarkData::bookmark_data_type_number_eight(&record_data);
-                    match index_data {
-                        Ok((_, index)) => bookmark_data.folder_index = index,
-                        Err(err) => warn!("Failed to parse bookmark folder index: {:?}", err),
+                }
+            } else if standard_data.record_type == BookmarkData::VOLUME_FLAGS
+                && standard_data.data_type == BookmarkData::DATA_TYPE
+            {
+                let flags_data = BookmarkData::bookmark_target_flags(record_data);
+                match flags_data {
+                    Ok((_, flags)) => self.volume_flag = flags,
+                    Err(err) => {
+                        self.record_warning(
+                            record,
+                            "warning.volume_flags",
+                            &[("error", &format!("{err:?}"))],
+                        );
                     }
-                } else if standard_data.record_type == BookmarkData::CREATOR_UID
-                    && standard_data.data_type == BookmarkData::NUMBER_FOUR_BYTE
-                {
-                    let uid_data = BookmarkData::bookmark_data_type_number_four(&record_data);
-                    match uid_data {
-                        Ok((_, uid)) => bookmark_data.uid = uid,
-                        Err(err) => warn!("Failed to parse bookmark Creator UID: {:?}", err),
+                }
+            } else if standard_data.record_type == BookmarkData::VOLUME_ROOT
+                && standard_data.data_type == BookmarkData::BOOL_TRUE
+            {
+                self.volume_root = Some(true);
+            } else if standard_data.record_type == BookmarkData::VOLUME_ROOT
+                && standard_data.data_type == BookmarkData::BOOL_FALSE
+            {
+                self.volume_root = Some(false);
+            } else if standard_data.record_type == BookmarkData::FILE_REF_FLAG
+                && standard_data.data_type == BookmarkData::BOOL_TRUE
+            {
+                self.file_ref_flag = Some(true);
+            } else if standard_data.record_type == BookmarkData::FILE_REF_FLAG
+                && standard_data.data_type == BookmarkData::BOOL_FALSE
+            {
+                self.file_ref_flag = Some(false);
+            } else if standard_data.record_type == BookmarkData::IS_EXECUTABLE
+                && standard_data.data_type == BookmarkData::BOOL_TRUE
+            {
+                self.is_executable = Some(true);
+            } else if standard_data.record_type == BookmarkData::IS_EXECUTABLE
+                && standard_data.data_type == BookmarkData::BOOL_FALSE
+            {
+                self.is_executable = Some(false);
+            } else if standard_data.record_type == BookmarkData::TARGET_FILENAME
+                && standard_data.data_type == BookmarkData::STRING_TYPE
+            {
+                let filename_data = self.record_string(record_data, record, settings);
+                match filename_data {
+                    Ok(filename) => self.target_filename = Some(filename),
+                    Err(err) => {
+                        self.record_warning(
+                            record,
+                            "warning.target_filename",
+                            &[("error", &format!("{err:?}"))],
+                        );
                     }
-                } else if standard_data.record_type == BookmarkData::CREATION_OPTIONS
-                    && standard_data.data_type == BookmarkData::NUMBER_FOUR_BYTE
-                {
-                    let creation_options_data =
-                        BookmarkData::bookmark_data_type_number_four(&record_data);
-                    match creation_options_data {
-                        Ok((_, options)) => bookmark_data.creation_options = options,
-                        Err(err) => warn!("Failed to parse bookmark Creation options: {:?}", err),
+                }
+            } else if standard_data.record_type == BookmarkData::TOC_PATH
+                && standard_data.data_type == BookmarkData::STRING_TYPE
+            {
+                let toc_path_data = self.record_string(record_data, record, settings);
+                match toc_path_data {
+                    Ok(toc_path) => self.toc_path = Some(toc_path),
+                    Err(err) => {
+                        self.record_warning(
+                            record,
+                            "warning.toc_path",
+                            &[("error", &format!("{err:?}"))],
+                        );
                     }
+                }
+            } else if standard_data.record_type == BookmarkData::LOCALIZED_NAME
+                && standard_data.data_type == BookmarkData::STRING_TYPE
+            {
+                let local_name_data = self.record_string(record_data, record, settings);
+                match local_name_data {
+                    Ok(local_name) => self.localized_name = Some(local_name),
+                    Err(err) => {
+                        self.record_warning(
+                            record,
+                            "warning.localized_name",
+                            &[("error", &format!("{err:?}"))],
+                        );
+                    }
+                }
+            } else if standard_data.record_type == BookmarkData::LOCALIZED_NAME
+                && standard_data.data_type == BookmarkData::DATA_TYPE
+            {
+                match self.record_utf16(record_data, record, settings) {
+                    Some(local_name) => self.localized_name = Some(local_name),
+                    None => {
+                        self.record_warning(record, "warning.localized_name_utf16", &[]);
+                    }
+                }
+            } else if standard_data.record_type == BookmarkData::DISPLAY_NAME
+                && standard_data.data_type == BookmarkData::STRING_TYPE
+            {
+                let display_name_data = self.record_string(record_data, record, settings);
+                match display_name_data {
+                    Ok(display_name) => self.display_name = Some(display_name),
+                    Err(err) => {
+                        self.record_warning(
+                            record,
+                            "warning.display_name",
+                            &[("error", &format!("{err:?}"))],
+                        );
+                    }
+                }
+            } else if standard_data.record_type == BookmarkData::SECURITY_EXTENSION_RW
+                && standard_data.data_type == BookmarkData::DATA_TYPE
+            {
+                let extension_data = self.record_string(record_data, record, settings);
+                match extension_data {
+                    Ok(extension) => self.security_extension_rw = Some(extension),
+                    Err(err) => {
+                        self.record_warning(
+                            record,
+                            "warning.security_extension_rw",
+                            &[("error", &format!("{err:?}"))],
+                        );
+                    }
+                }
+            } else if standard_data.record_type == BookmarkData::SECURITY_EXTENSION_RO
+                && standard_data.data_type == BookmarkData::DATA_TYPE
+            {
+                let extension_data = self.record_string(record_data, record, settings);
+                match extension_data {
+                    Ok(extension) => self.security_extension_ro = Some(extension),
+                    Err(err) => {
+                        self.record_warning(
+                            record,
+                            "warning.security_extension_ro",
+                            &[("error", &format!("{err:?}"))],
+                        );
+                    }
+                }
+            } else if standard_data.record_type == BookmarkData::CREATOR_USERNAME
+                && standard_data.data_type == BookmarkData::STRING_TYPE
+            {
+                let username_data = self.record_string(record_data, record, settings);
+                match username_data {
+                    Ok(username) => self.username = Some(username),
+                    Err(err) => {
+                        self.record_warning(
+                            record,
+                            "warning.username",
+                            &[("error", &format!("{err:?}"))],
+                        );
+                    }
+                }
+            } else if standard_data.record_type == BookmarkData::CONTAIN_FOLDER_INDEX
+                && BookmarkData::is_integer_type(standard_data.data_type)
+            {
+                let index_data =
+                    BookmarkData::bookmark_data_type_integer(record_data, standard_data.data_type);
+                match index_data {
+                    Ok((_, index)) => self.folder_index = index,
+                    Err(err) => {
+                        self.record_warning(
+                            record,
+                            "warning.folder_index",
+                            &[("error", &format!("{err:?}"))],
+                        );
+                    }
+                }
+            } else if standard_data.record_type == BookmarkData::CREATOR_UID
+                && BookmarkData::is_integer_type(standard_data.data_type)
+            {
+                let uid_data = BookmarkData::bookmark_data_type_integer_i32(
+                    record_data,
+                    standard_data.data_type,
+                );
+                match uid_data {
+                    Ok((_, uid)) => self.uid = Some(uid),
+                    Err(err) => {
+                        self.record_warning(
+                            record,
+                            "warning.uid",
+                            &[("error", &format!("{err:?}"))],
+                        );
+                    }
+                }
+            } else if standard_data.record_type == BookmarkData::CREATION_OPTIONS
+                && BookmarkData::is_integer_type(standard_data.data_type)
+            {
+                let creation_options_data = BookmarkData::bookmark_data_type_integer_i32(
+                    record_data,
+                    standard_data.data_type,
+                );
+                match creation_options_data {
+                    Ok((_, options)) => self.creation_options = Some(options),
+                    Err(err) => {
+                        self.record_warning(
+                            record,
+                            "warning.creation_options",
+                            &[("error", &format!("{err:?}"))],
+                        );
+                    }
+                }
+            } else if (BookmarkData::FILE_METADATA_1054..=BookmarkData::FILE_METADATA_1057)
+                .contains(&standard_data.record_type)
+            {
+                match BookmarkData::bookmark_file_metadata(record_data, standard_data.data_type) {
+                    Some(value) => self.file_metadata.push(FileMetadata {
+                        record_type: standard_data.record_type,
+                        value,
+                    }),
+                    None => {
+                        self.record_warning(
+                            record,
+                            "warning.file_metadata",
+                            &[
+                                ("record_type", &standard_data.record_type.to_string()),
+                                ("data_type", &standard_data.data_type.to_string()),
+                            ],
+                        );
+                    }
+                }
+            } else if standard_data.data_type == BookmarkData::NUMBER_FLOAT
+                || standard_data.data_type == BookmarkData::NUMBER_FLOAT64
+            {
+                let number_data = if standard_data.data_type == BookmarkData::NUMBER_FLOAT {
+                    BookmarkData::bookmark_data_type_float(record_data)
                 } else {
-                    warn!(
-                        "Unknown Record Type: {} and Data type: {}",
-                        standard_data.record_type, standard_data.data_type
-                    );
-                    debug!("Record data: {:?}", record_data);
+                    BookmarkData::bookmark_data_type_float64(record_data)
+                };
+                match number_data {
+                    Ok((_, number)) => match standard_data.record_type {
+                        BookmarkData::VOLUME_SIZE => self.volume_size = number as i64,
+                        BookmarkData::CONTAIN_FOLDER_INDEX => self.folder_index = number as i64,
+                        // Value is still available from the raw/typed record APIs
+                        _ => {
+                            debug!(
+                                "Float Record Type: {} with value: {}",
+                                standard_data.record_type, number
+                            );
+                            return Ok(());
+                        }
+                    },
+                    Err(err) => {
+                        self.record_warning(
+                            record,
+                            "warning.float",
+                            &[("error", &format!("{err:?}"))],
+                        );
+                    }
                 }
-                continue;
+            } else {
+                debug!("Record data: {:?}", record_data);
+                self.record_warning(
+                    record,
+                    "warning.unknown_record",
+                    &[
+                        ("record_type", &standard_data.record_type.to_string()),
+                        ("data_type", &standard_data.data_type.to_string()),
+                    ],
+                );
+            }
+            if self.warnings.len() == warnings {
+                self.add_provenance(record, standard_data.data_type, nested);
             }
+            return Ok(());
+        }
 
-            // Get bookmark array data based on data and record types
-            for standard_data in standard_data_vec {
-                if standard_data.data_type == BookmarkData::STRING_TYPE
-                    && standard_data.record_type == BookmarkData::TARGET_PATH
-                {
-                    let path_data =
-                        BookmarkData::bookmark_data_type_string(&standard_data.record_data);
-                    match path_data {
-                        Ok(path) => bookmark_data.path.push(path),
-                        Err(_err) => continue,
-                    }
-                } else if standard_data.data_type == BookmarkData::NUMBER_EIGHT_BYTE
-                    && standard_data.record_type == BookmarkData::TARGET_CNID_PATH
+        let components = (
+            self.path.len(),
+            self.cnid_path.len(),
+            self.url_lengths.len(),
+        );
+
+        // Get bookmark array data based on data and record types
+        for standard_data in standard_data_vec {
+            if standard_data.data_type == BookmarkData::STRING_TYPE
+                && standard_data.record_type == BookmarkData::TARGET_PATH
+            {
+                let path_data = self.record_string(standard_data.record_data, record, settings);
+                let path = match path_data {
+                    Ok(path) => path,
+                    Err(_err) => continue,
+                };
+                match settings
+                    .path_policy
+                    .map_or(PathAction::Keep, |policy| policy(&path))
                 {
-                    let cnid_data = BookmarkData::bookmark_cnid(&standard_data.record_data);
-                    match cnid_data {
-                        Ok((_, cnid)) => bookmark_data.cnid_path.push(cnid),
-                        Err(_err) => continue,
+                    PathAction::Keep => self.path.push(path),
+                    PathAction::Replace(replacement) => self.path.push(replacement),
+                    PathAction::Reject => {
+                        warn!("Path component rejected by path policy: {:?}", path);
+                        self.rejected_path = true;
                     }
                 }
+            } else if standard_data.data_type == BookmarkData::NUMBER_EIGHT_BYTE
+                && standard_data.record_type == BookmarkData::TARGET_CNID_PATH
+            {
+                let cnid_data = BookmarkData::bookmark_cnid(standard_data.record_data);
+                match cnid_data {
+                    Ok((_, cnid)) => self.cnid_path.push(cnid),
+                    Err(_err) => continue,
+                }
+            } else if standard_data.record_type == BookmarkData::URL_LENGTH_ARRAY
+                && BookmarkData::is_integer_type(standard_data.data_type)
+            {
+                let length_data = BookmarkData::bookmark_data_type_integer(
+                    standard_data.record_data,
+                    standard_data.data_type,
+                );
+                match length_data {
+                    Ok((_, length)) => self.url_lengths.push(length),
+                    Err(_err) => continue,
+                }
             }
         }
-        Ok((input, bookmark_data))
+        if (
+            self.path.len(),
+            self.cnid_path.len(),
+            self.url_lengths.len(),
+        ) != components
+        {
+            self.add_provenance(record, standard_data.data_type, false);
+        }
+        Ok(())
+    }
+
+    /// Get the TOC record that produced each populated field
+    pub fn field_provenance(&self) -> &[FieldProvenance] {
+        &self.provenance
+    }
+
+    /// Get the raw data type and bytes of every TOC record, ordered by record type. Duplicate record types keep the record used for the fields.
+    /// Empty unless parsed with `BookmarkParser::preserve_raw`
+    pub fn raw_records(&self) -> &BTreeMap<u32, RawRecord> {
+        &self.raw_records
+    }
+
+    /// Iterate over the record type and typed value of every TOC record, in TOC order. Empty unless parsed with
+    /// `BookmarkParser::preserve_raw`
+    pub fn records(&self) -> impl Iterator<Item = (u32, &BookmarkValue)> {
+        self.values
+            .iter()
+            .map(|(record_type, value)| (*record_type, value))
+    }
+
+    /// Iterate over the offsets of every TOC record from the start of the bookmark (including the 48 byte header),
+    /// in TOC order
+    pub fn offsets(&self) -> impl Iterator<Item = &RecordOffset> {
+        self.record_offsets.iter()
+    }
+
+    /// Get the header magic values of every TOC, in chain order
+    pub fn toc_headers(&self) -> &[TocHeaderMagic] {
+        &self.toc_headers
+    }
+
+    /// Check if any TOC header has magic values other than 0xfffe/0xffff. Nonstandard values are a possible sign of
+    /// a bookmark written by a third party tool
+    pub fn has_nonstandard_toc_header(&self) -> bool {
+        self.toc_headers.iter().any(|header| !header.is_standard())
+    }
+
+    /// Get every record type that appears more than once. Duplicate keys are a possible tampering signal
+    pub fn record_conflicts(&self) -> &[RecordConflict] {
+        &self.conflicts
+    }
+
+    /// Compare the byte length of each user visible path component against the URL length array. Mismatches are a
+    /// possible tampering signal. Empty if the bookmark has no URL length array
+    pub fn path_length_mismatches(&self) -> Vec<PathLengthMismatch> {
+        let Some(counts) = self.url_component_counts() else {
+            return Vec::new();
+        };
+        let components = self.path.len().max(counts.system + counts.visible);
+        (counts.system..components)
+            .map(|index| PathLengthMismatch {
+                index,
+                expected: self.url_lengths.get(index - counts.system).copied(),
+                actual: self.path.get(index).map(String::len),
+            })
+            .filter(|component| component.expected != component.actual.map(|length| length as i64))
+            .collect()
+    }
+
+    /// Split a full path (ex: `/Users/lab/file.txt`) into components using the URL length array. `None` if the
+    /// lengths do not line up with the `/` separators
+    pub fn split_with_url_lengths(&self, path: &str) -> Option<Vec<String>> {
+        let mut remaining = path.strip_prefix('/').unwrap_or(path);
+        let mut components = Vec::new();
+        for (index, length) in self.url_lengths.iter().enumerate() {
+            let length = usize::try_from(*length).ok()?;
+            let component = remaining.get(..length)?;
+            remaining = remaining.get(length..)?;
+            if index + 1 < self.url_lengths.len() {
+                remaining = remaining.strip_prefix('/')?;
+            }
+            components.push(component.to_string());
+        }
+        // Allow a trailing slash for directories
+        if !remaining.is_empty() && remaining != "/" {
+            return None;
+        }
+        Some(components)
+    }
+
+    /// Get how the path array splits into system and user visible components using the URL length array. `None` if
+    /// the bookmark has no URL length array
+    pub fn url_component_counts(&self) -> Option<UrlComponentCounts> {
+        if self.url_lengths.is_empty() {
+            return None;
+        }
+        let visible = self.url_lengths.len();
+        Some(UrlComponentCounts {
+            system: self.path.len().saturating_sub(visible),
+            visible,
+        })
+    }
+
+    /// Get the user visible path components with `.` and `..` resolved (ex: relative mac_alias style paths).
+    /// `None` if the bookmark has no URL length array
+    pub fn visible_path(&self) -> Option<Vec<&str>> {
+        let counts = self.url_component_counts()?;
+        let mut components = Vec::new();
+        for component in self.path.iter().skip(counts.system) {
+            match component.as_str() {
+                "." => {}
+                ".." => {
+                    components.pop();
+                }
+                _ => components.push(component.as_str()),
+            }
+        }
+        Some(components)
+    }
+
+    /// Compare the TOC path record against the path array. `None` if the bookmark has no TOC path record, a
+    /// mismatch is a possible tampering signal
+    pub fn toc_path_matches(&self) -> Option<bool> {
+        let toc_path = self.toc_path.as_deref()?;
+        let components: Vec<&str> = toc_path
+            .split('/')
+            .filter(|component| !component.is_empty())
+            .collect();
+        Some(components == self.path)
+    }
+
+    /// Get the embedded bookmark describing the volume (ex: a network share or external disk). The volume
+    /// bookmark is parsed into `nested_bookmarks` like any other embedded bookmark
+    pub fn volume_bookmark(&self) -> Option<&BookmarkData> {
+        self.nested_bookmarks
+            .iter()
+            .find(|nested| nested.record_type == BookmarkData::VOLUME_BOOKMARK)
+            .map(|nested| &nested.bookmark)
+    }
+
+    /// Get the target filename, falling back to the last path component if the bookmark has no filename record
+    pub fn filename(&self) -> Option<&str> {
+        self.target_filename
+            .as_deref()
+            .or(self.path.last().map(String::as_str))
+    }
+
+    /// Check if the CNID path can be relied on. FAT/exFAT volumes do not have persistent IDs, their CNIDs are
+    /// generated when mounted
+    pub(crate) fn persistent_ids(&self) -> bool {
+        // Volume flags are the property values followed by a mask of the valid properties
+        if let [properties, valid, ..] = self.volume_flag[..] {
+            if valid & BookmarkData::VOLUME_SUPPORTS_PERSISTENT_IDS != 0 {
+                return properties & BookmarkData::VOLUME_SUPPORTS_PERSISTENT_IDS != 0;
+            }
+        }
+        !self.cnid_path.is_empty() && self.cnid_path.len() == self.path.len()
+    }
+
+    /// Check if the bookmark has the full record set. Bookmarks without a path and volume path, or with records that
+    /// could not be read, are `Unknown`. Records with an unknown data type do not populate a field and are ignored
+    pub(crate) fn detect_record_set(&self) -> RecordSet {
+        let populated = |field: &str| self.provenance.iter().any(|entry| entry.field == field);
+        let failed = self
+            .warnings
+            .iter()
+            .any(|warning| warning.key != "warning.unknown_record");
+        if !populated("path") || !populated("volume_path") || failed {
+            return RecordSet::Unknown;
+        }
+        if BookmarkData::FULL_RECORD_SET_FIELDS
+            .iter()
+            .all(|field| populated(field))
+        {
+            return RecordSet::Full;
+        }
+        RecordSet::Reduced
+    }
+
+    /// Reset the list fields populated by a record type, so a duplicate record replaces the earlier value instead of
+    /// extending it
+    fn clear_record_fields(&mut self, record_type: u32) {
+        match record_type {
+            BookmarkData::TARGET_PATH => {
+                self.path.clear();
+                self.rejected_path = false;
+            }
+            BookmarkData::TARGET_CNID_PATH => self.cnid_path.clear(),
+            BookmarkData::URL_LENGTH_ARRAY => self.url_lengths.clear(),
+            BookmarkData::TARGET_FLAGS => self.target_flags.clear(),
+            BookmarkData::VOLUME_FLAGS => self.volume_flag.clear(),
+            _ => {}
+        }
+        self.file_metadata
+            .retain(|metadata| metadata.record_type != record_type);
+        self.nested_bookmarks
+            .retain(|nested| nested.record_type != record_type);
+
+        let mut cleared = Vec::new();
+        self.provenance.retain(|entry| {
+            let keep = entry.record_type != record_type;
+            if !keep {
+                cleared.push(entry.field);
+            }
+            keep
+        });
+        for field in cleared {
+            self.confidence.remove(field);
+        }
+    }
+
+    /// Track the record that populated a field
+    fn add_provenance(&mut self, record: &TableOfContentsDataRecord, data_type: u32, nested: bool) {
+        let field = match (record.record_type, data_type) {
+            _ if nested => "nested_bookmarks",
+            (BookmarkData::TARGET_PATH, _) => "path",
+            (BookmarkData::TARGET_CNID_PATH, _) => "cnid_path",
+            (BookmarkData::TARGET_FLAGS, _) => "target_flags",
+            (BookmarkData::TARGET_CREATION_DATE, _) => "creation",
+            (BookmarkData::VOLUME_PATH, _) => "volume_path",
+            (BookmarkData::VOLUME_URL, _) => "volume_url",
+            (BookmarkData::VOLUME_MOUNT_POINT, _) => "volume_mount_point",
+            (BookmarkData::VOLUME_NAME, _) => "volume_name",
+            (BookmarkData::VOLUME_UUID, _) => "volume_uuid",
+            (BookmarkData::VOLUME_SIZE, _) => "volume_size",
+            (BookmarkData::VOLUME_CREATION, _) => "volume_creation",
+            (BookmarkData::VOLUME_FLAGS, _) => "volume_flag",
+            (BookmarkData::VOLUME_ROOT, _) => "volume_root",
+            (BookmarkData::FILE_REF_FLAG, _) => "file_ref_flag",
+            (BookmarkData::IS_EXECUTABLE, _) => "is_executable",
+            (BookmarkData::TARGET_FILENAME, _) => "target_filename",
+            (BookmarkData::URL_LENGTH_ARRAY, _) => "url_lengths",
+            (BookmarkData::TOC_PATH, _) => "toc_path",
+            (BookmarkData::FILE_METADATA_1054..=BookmarkData::FILE_METADATA_1057, _) => {
+                "file_metadata"
+            }
+            (BookmarkData::LOCALIZED_NAME, _) => "localized_name",
+            (BookmarkData::DISPLAY_NAME, _) => "display_name",
+            (BookmarkData::SECURITY_EXTENSION_RW, _) => "security_extension_rw",
+            (BookmarkData::SECURITY_EXTENSION_RO, _) => "security_extension_ro",
+            (BookmarkData::CREATOR_USERNAME, _) => "username",
+            (BookmarkData::CONTAIN_FOLDER_INDEX, _) => "folder_index",
+            (BookmarkData::CREATOR_UID, _) => "uid",
+            (BookmarkData::CREATION_OPTIONS, _) => "creation_options",
+            (_, BookmarkData::URL_RELATIVE) => "relative_url",
+            _ => return,
+        };
+
+        self.provenance.push(FieldProvenance {
+            field,
+            record_type: record.record_type,
+            data_type,
+            offset: record.data_offset.saturating_add(BookmarkData::HEADER_SIZE),
+            confidence: self.record_confidence,
+        });
+        self.track_confidence(field, self.record_confidence);
+    }
+
+    /// List a field decoded with heuristics or recovered in `confidence`, keeping the lowest confidence
+    pub(crate) fn track_confidence(&mut self, field: &'static str, confidence: FieldConfidence) {
+        if confidence == FieldConfidence::Exact {
+            return;
+        }
+        let entry = self.confidence.entry(field).or_default();
+        *entry = (*entry).max(confidence);
+    }
+
+    /// Get how a field was decoded. `None` if the field was not populated by a record
+    pub fn field_confidence(&self, field: &str) -> Option<FieldConfidence> {
+        self.provenance
+            .iter()
+            .filter(|entry| entry.field == field)
+            .map(|entry| entry.confidence)
+            .max()
+    }
+
+    /// Track a record that was truncated to the configured length limits
+    fn flag_truncated(&mut self, standard_data: &StandardDataRecord, data_offset: u32) {
+        let kept_length = standard_data.record_data.len() as u32;
+        if kept_length >= standard_data.data_length {
+            return;
+        }
+        warn!(
+            "Record Type: {} truncated from {} to {} bytes",
+            standard_data.record_type, standard_data.data_length, kept_length
+        );
+        self.truncated_records.push(TruncatedRecord {
+            record_type: standard_data.record_type,
+            data_type: standard_data.data_type,
+            offset: data_offset.saturating_add(BookmarkData::HEADER_SIZE),
+            length: standard_data.data_length,
+            kept_length,
+        });
     }
 
     /// Parse the bookmark array data
@@ -446,7 +1722,8 @@ impl BookmarkData {
         data: &'a [u8],
         array_offsets: Vec<u32>,
         record: &TableOfContentsDataRecord,
-    ) -> nom::IResult<&'a [u8], Vec<StandardDataRecord>> {
+        limits: &LengthLimits,
+    ) -> nom::IResult<&'a [u8], Vec<StandardDataRecord<'a>>> {
         let mut standard_data_vec: Vec<StandardDataRecord> = Vec::new();
 
         for offset in array_offsets {
@@ -455,7 +1732,7 @@ impl BookmarkData {
                 data_offset: offset,
                 reserved: 0,
             };
-            let (_, results) = BookmarkData::bookmark_standard_data(data, &data_record)?;
+            let (_, results) = BookmarkData::bookmark_standard_data(data, &data_record, limits)?;
             standard_data_vec.push(results);
         }
 
@@ -510,18 +1787,40 @@ impl BookmarkData {
         toc_data.next_record_offset = toc_next_record;
         toc_data.number_of_records = toc_number_records;
 
-        let record_size = 12;
-        let record_data = record_size * toc_data.number_of_records;
+        let record_size: u32 = 12;
+        let record_data = toc_data
+            .number_of_records
+            .checked_mul(record_size)
+            .ok_or_else(|| invalid_offset(data))?;
 
         // Verify TOC data length is equal to number of records (Number of Records * Record Size (12 bytes))
         // Some TOC headers may give incorrect? data length (they are 8 bytes short, https://mac-alias.readthedocs.io/en/latest/bookmark_fmt.html)
+        // A TOC listing more records than are present fails when the records are read (or is clamped in lenient mode)
         if record_data > data_length {
-            let (_, actual_record_data) = take(record_data)(input)?;
-            final_input = actual_record_data;
+            if let Ok((_, actual_record_data)) =
+                take::<u32, &[u8], Error<&[u8]>>(record_data)(input)
+            {
+                final_input = actual_record_data;
+            }
         }
         Ok((final_input, toc_data))
     }
 
+    /// Parse a chained TOC at an offset from the start of the bookmark data. Returns the TOC header, the offset to the
+    /// next TOC (0 if last) and the TOC records
+    fn chained_table_of_contents(
+        data: &[u8],
+        offset: u32,
+    ) -> nom::IResult<&[u8], (TableOfContentsHeader, u32, Vec<TableOfContentsDataRecord>)> {
+        let (input, _) = take(offset)(data)?;
+        let (input, toc_header) = BookmarkData::table_of_contents_header(input)?;
+        let (toc_record_data, toc_data) =
+            BookmarkData::table_of_contents_data(input, toc_header.data_length)?;
+        let (input, records) =
+            BookmarkData::table_of_contents_record(toc_record_data, &toc_data.number_of_records)?;
+        Ok((input, (toc_header, toc_data.next_record_offset, records)))
+    }
+
     /// Parse the TOC data record
     fn table_of_contents_record<'a>(
         data: &'a [u8],
@@ -564,17 +1863,21 @@ impl BookmarkData {
     fn bookmark_standard_data<'a>(
         bookmark_data: &'a [u8],
         toc_record: &TableOfContentsDataRecord,
-    ) -> nom::IResult<&'a [u8], StandardDataRecord> {
+        limits: &LengthLimits,
+    ) -> nom::IResult<&'a [u8], StandardDataRecord<'a>> {
         let mut toc_standard_data = StandardDataRecord {
             data_length: 0,
-            record_data: Vec::new(),
+            record_data: &[],
             data_type: 0,
             record_type: 0,
         };
         let toc_offset_value: u32 = 4;
 
         // Subtract toc offset value from data offset since we already nom'd the value
-        let offset = (toc_record.data_offset - toc_offset_value) as usize;
+        let offset = toc_record
+            .data_offset
+            .checked_sub(toc_offset_value)
+            .ok_or_else(|| offset_before_data(bookmark_data))?;
 
         // Nom data til standard data info
         let (input, _) = take(offset)(bookmark_data)?;
@@ -589,18 +1892,39 @@ impl BookmarkData {
 
         toc_standard_data.data_length = standard_length;
         toc_standard_data.data_type = standard_data_type;
-        toc_standard_data.record_data = record_data.to_vec();
+        // Only copy the data within the length limits
+        toc_standard_data.record_data = limits.truncate(standard_data_type, record_data);
         toc_standard_data.record_type = toc_record.record_type;
 
         Ok((input, toc_standard_data))
     }
 
+    /// Get the declared data length of a record. `None` if the offset is outside of the data
+    fn record_length(core_data: &[u8], data_offset: u32) -> Option<u32> {
+        let toc_offset_value: u32 = 4;
+        let offset = data_offset.checked_sub(toc_offset_value)? as usize;
+        let (_, length) = le_u32::<&[u8], ()>(core_data.get(offset..)?).ok()?;
+        Some(length)
+    }
+
+    /// Get the data type of a record. `None` if the record header is outside of the data
+    fn record_data_type(core_data: &[u8], data_offset: u32) -> Option<u32> {
+        let toc_offset_value: u32 = 4;
+        let offset = data_offset.checked_sub(toc_offset_value)? as usize;
+        let (_, data_type) = le_u32::<&[u8], ()>(core_data.get(offset.checked_add(4)?..)?).ok()?;
+        Some(data_type)
+    }
+
     /// Get the offsets for the array data
     fn bookmark_array(standard_data: &[u8]) -> nom::IResult<&[u8], Vec<u32>> {
         let mut array_offsets: Vec<u32> = Vec::new();
         let mut input = standard_data;
         let offset_size: u32 = 4;
 
+        // Empty arrays (ex: no CNIDs) have no offsets
+        if input.is_empty() {
+            return Ok((input, array_offsets));
+        }
         loop {
             let (input_data, offset) = take(offset_size)(input)?;
             let (_, data_offsets) = le_u32(offset)?;
@@ -614,16 +1938,172 @@ impl BookmarkData {
         Ok((input, array_offsets))
     }
 
+    /// Get the base URL and relative URL records that a relative URL points to
+    fn bookmark_relative_url(
+        core_data: &[u8],
+        record_data: &[u8],
+        record: &TableOfContentsDataRecord,
+        settings: &ParserOptions,
+    ) -> Option<RelativeUrl> {
+        let (_, offsets) = BookmarkData::bookmark_array(record_data).ok()?;
+        if offsets.len() != 2 {
+            return None;
+        }
+        let (_, parts) =
+            BookmarkData::bookmark_array_data(core_data, offsets, record, &settings.limits).ok()?;
+        let [base, relative] = &parts[..] else {
+            return None;
+        };
+        Some(RelativeUrl {
+            record_type: record.record_type,
+            base: BookmarkData::bookmark_data_type_string(base.record_data).ok()?,
+            relative: BookmarkData::bookmark_data_type_string(relative.record_data).ok()?,
+        })
+    }
+
+    /// Get a string record. If `lossy_strings` is set invalid UTF-8 is replaced instead of failing and the original
+    /// bytes are listed in `lossy_strings`
+    fn record_string(
+        &mut self,
+        standard_data: &[u8],
+        record: &TableOfContentsDataRecord,
+        settings: &ParserOptions,
+    ) -> Result<String, Utf8Error> {
+        match BookmarkData::bookmark_data_type_string(standard_data) {
+            Err(err) if settings.lossy_strings => {
+                warn!(
+                    "Lossy decoding Record Type: {} with invalid UTF-8: {:?}",
+                    record.record_type, err
+                );
+                self.lossy_strings.push(LossyString {
+                    record_type: record.record_type,
+                    offset: record.data_offset.saturating_add(BookmarkData::HEADER_SIZE),
+                    raw: standard_data.to_vec(),
+                });
+                self.record_confidence = FieldConfidence::Recovered;
+                Ok(String::from_utf8_lossy(standard_data).into_owned())
+            }
+            results => results,
+        }
+    }
+
+    /// Get a UTF-16 string stored in a data record. If `lossy_strings` is set invalid UTF-16 is replaced instead of
+    /// failing and the original bytes are listed in `lossy_strings`
+    fn record_utf16(
+        &mut self,
+        standard_data: &[u8],
+        record: &TableOfContentsDataRecord,
+        settings: &ParserOptions,
+    ) -> Option<String> {
+        let units = BookmarkData::bookmark_utf16_units(standard_data)?;
+        // Byte order is guessed when there is no BOM
+        if !matches!(standard_data, [0xff, 0xfe, ..] | [0xfe, 0xff, ..]) {
+            self.record_confidence = self.record_confidence.max(FieldConfidence::Heuristic);
+        }
+        match String::from_utf16(&units) {
+            Ok(value) => Some(value),
+            Err(err) if settings.lossy_strings => {
+                warn!(
+                    "Lossy decoding Record Type: {} with invalid UTF-16: {:?}",
+                    record.record_type, err
+                );
+                self.lossy_strings.push(LossyString {
+                    record_type: record.record_type,
+                    offset: record.data_offset.saturating_add(BookmarkData::HEADER_SIZE),
+                    raw: standard_data.to_vec(),
+                });
+                self.record_confidence = FieldConfidence::Recovered;
+                Some(String::from_utf16_lossy(&units))
+            }
+            Err(_err) => None,
+        }
+    }
+
+    /// Get the UTF-16 code units of data, without the byte order mark and trailing nulls. The byte order is taken
+    /// from the BOM, or from the position of the zero bytes of ASCII characters. `None` if the data does not look
+    /// like UTF-16
+    fn bookmark_utf16_units(standard_data: &[u8]) -> Option<Vec<u16>> {
+        if standard_data.is_empty() || !standard_data.len().is_multiple_of(2) {
+            return None;
+        }
+        let (data, little_endian) = match standard_data {
+            [0xff, 0xfe, rest @ ..] => (rest, true),
+            [0xfe, 0xff, rest @ ..] => (rest, false),
+            _ => {
+                let zeros = |start: usize| {
+                    standard_data
+                        .iter()
+                        .skip(start)
+                        .step_by(2)
+                        .filter(|byte| **byte == 0)
+                        .count()
+                };
+                let (even, odd) = (zeros(0), zeros(1));
+                if even == odd {
+                    return None;
+                }
+                (standard_data, odd > even)
+            }
+        };
+        let mut units: Vec<u16> = data
+            .chunks_exact(2)
+            .map(|unit| {
+                let bytes = [unit[0], unit[1]];
+                if little_endian {
+                    u16::from_le_bytes(bytes)
+                } else {
+                    u16::from_be_bytes(bytes)
+                }
+            })
+            .collect();
+        while units.last() == Some(&0) {
+            units.pop();
+        }
+        Some(units)
+    }
+
     /// Get the path/strings related to bookmark
     fn bookmark_data_type_string(standard_data: &[u8]) -> Result<String, Utf8Error> {
         let path = from_utf8(standard_data)?;
         Ok(path.to_string())
     }
 
+    /// Get the value of an additional file metadata record. Only date, integer, bool, UUID and string values are
+    /// expected
+    fn bookmark_file_metadata(standard_data: &[u8], data_type: u32) -> Option<BookmarkValue> {
+        let value = match data_type {
+            BookmarkData::DATE => {
+                BookmarkValue::Date(BookmarkData::bookmark_data_type_date(standard_data).ok()?.1)
+            }
+            BookmarkData::BOOL_TRUE => BookmarkValue::Bool(true),
+            BookmarkData::BOOL_FALSE => BookmarkValue::Bool(false),
+            BookmarkData::UUID => {
+                BookmarkValue::Uuid(BookmarkData::bookmark_data_type_uuid(standard_data).ok()?.1)
+            }
+            BookmarkData::STRING_TYPE => {
+                BookmarkValue::String(BookmarkData::bookmark_data_type_string(standard_data).ok()?)
+            }
+            _ if BookmarkData::is_integer_type(data_type) => BookmarkValue::Int(
+                BookmarkData::bookmark_data_type_integer(standard_data, data_type)
+                    .ok()?
+                    .1,
+            ),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// Get the canonical hyphenated string of a binary (16 byte) UUID
+    fn bookmark_data_type_uuid(standard_data: &[u8]) -> nom::IResult<&[u8], String> {
+        let uuid_size: u8 = 16;
+        let (data, uuid) = take(uuid_size)(standard_data)?;
+        Ok((data, uuid_string(uuid)))
+    }
+
     /// Get the CNID path for the target
-    fn bookmark_cnid(standard_data: &[u8]) -> nom::IResult<&[u8], i64> {
+    fn bookmark_cnid(standard_data: &[u8]) -> nom::IResult<&[u8], Cnid> {
         let (data, cnid) = le_i64(standard_data)?;
-        Ok((data, cnid))
+        Ok((data, Cnid(cnid)))
     }
 
     /// Get bookmark target flags
@@ -651,24 +2131,102 @@ impl BookmarkData {
         Ok((data, size))
     }
 
-    /// Get bookmark folder index
-    fn bookmark_data_type_number_four(standard_data: &[u8]) -> nom::IResult<&[u8], i32> {
-        let (data, index) = le_i32(standard_data)?;
-        Ok((data, index))
+    /// Check if the data type is a 1, 2, 4 or 8 byte integer
+    fn is_integer_type(data_type: u32) -> bool {
+        matches!(
+            data_type,
+            BookmarkData::NUMBER_ONE_BYTE
+                | BookmarkData::NUMBER_TWO_BYTE
+                | BookmarkData::NUMBER_FOUR_BYTE
+                | BookmarkData::NUMBER_EIGHT_BYTE
+        )
+    }
+
+    /// Get an integer of any width (folder index, uid, creation options)
+    fn bookmark_data_type_integer(
+        standard_data: &[u8],
+        data_type: u32,
+    ) -> nom::IResult<&[u8], i64> {
+        match data_type {
+            BookmarkData::NUMBER_ONE_BYTE => {
+                let (data, number) = le_i8(standard_data)?;
+                Ok((data, i64::from(number)))
+            }
+            BookmarkData::NUMBER_TWO_BYTE => {
+                let (data, number) = le_i16(standard_data)?;
+                Ok((data, i64::from(number)))
+            }
+            BookmarkData::NUMBER_FOUR_BYTE => {
+                let (data, number) = le_i32(standard_data)?;
+                Ok((data, i64::from(number)))
+            }
+            _ => le_i64(standard_data),
+        }
+    }
+
+    /// Get an integer of any width that must fit in an i32
+    fn bookmark_data_type_integer_i32(
+        standard_data: &[u8],
+        data_type: u32,
+    ) -> nom::IResult<&[u8], i32> {
+        let (data, number) = BookmarkData::bookmark_data_type_integer(standard_data, data_type)?;
+        match i32::try_from(number) {
+            Ok(number) => Ok((data, number)),
+            Err(_err) => Err(nom::Err::Failure(Error::new(
+                standard_data,
+                ErrorKind::TooLarge,
+            ))),
+        }
+    }
+
+    /// Get a float32 number as a float64
+    fn bookmark_data_type_float(standard_data: &[u8]) -> nom::IResult<&[u8], f64> {
+        let (data, number) = le_f32(standard_data)?;
+        Ok((data, number as f64))
+    }
+
+    /// Get a float64 number
+    fn bookmark_data_type_float64(standard_data: &[u8]) -> nom::IResult<&[u8], f64> {
+        let (data, number) = le_f64(standard_data)?;
+        Ok((data, number))
     }
 
     /// Get bookmark creation timestamps
-    fn bookmark_data_type_date(standard_data: &[u8]) -> nom::IResult<&[u8], f64> {
+    fn bookmark_data_type_date(standard_data: &[u8]) -> nom::IResult<&[u8], CocoaTime> {
         //Apple stores timestamps as Big Endian Float64
         let (data, creation) = be_f64(standard_data)?;
-        Ok((data, creation))
+        Ok((data, CocoaTime(creation)))
     }
 }
 
+/// Error for offsets and sizes that point outside of the bookmark data
+fn invalid_offset(input: &[u8]) -> nom::Err<Error<&[u8]>> {
+    nom::Err::Error(Error::new(input, ErrorKind::Verify))
+}
+
+/// Error for TOC and record offsets smaller than the 4 byte TOC offset field, reported as
+/// `BookmarkError::BadBookmarkData`
+fn offset_before_data(input: &[u8]) -> nom::Err<Error<&[u8]>> {
+    nom::Err::Failure(Error::new(input, ErrorKind::Verify))
+}
+
+/// Check if an offset pointed before the start of the bookmark data
+pub(crate) fn is_offset_before_data(err: &nom::Err<Error<&[u8]>>) -> bool {
+    matches!(err, nom::Err::Failure(err) if err.code == ErrorKind::Verify)
+}
+
 #[cfg(test)]
 mod tests {
 
-    use super::{BookmarkData, TableOfContentsDataRecord};
+    use super::{
+        BookmarkData, FieldConfidence, FieldProvenance, ParseWarning, TableOfContentsDataRecord,
+    };
+    use crate::{
+        parser::{BookmarkParser, LengthLimits},
+        types::RecordSet,
+        value::BookmarkValue,
+    };
+    use std::{fs, path::PathBuf};
     #[test]
     fn test_bookmark_header() {
         let test_header = [
@@ -746,6 +2304,133 @@ mod tests {
         assert_eq!(bookmark.target_flags.len(), target_flags_len);
     }
 
+    #[test]
+    fn test_field_provenance() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/downloads.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+        let (bookmark_data, _) = BookmarkData::parse_bookmark_header(&buffer).unwrap();
+        let (_, bookmark) = BookmarkData::parse_bookmark_data(bookmark_data).unwrap();
+
+        let provenance = bookmark.field_provenance();
+        assert_eq!(provenance.len(), 16);
+        assert_eq!(
+            provenance[0],
+            FieldProvenance {
+                field: "path",
+                record_type: 0x1004,
+                data_type: 0x0601,
+                offset: 152,
+                confidence: FieldConfidence::Exact
+            }
+        );
+
+        // Folder index is stored as an 8 byte number
+        let folder_index = provenance
+            .iter()
+            .find(|value| value.field == "folder_index")
+            .unwrap();
+        assert_eq!(folder_index.data_type, 0x0304);
+        assert_eq!(folder_index.offset, 312);
+        assert_eq!(&buffer[312..320], [8, 0, 0, 0, 4, 3, 0, 0]);
+    }
+
+    #[test]
+    fn test_raw_records() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/downloads.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+        // Records are only copied when requested
+        let bookmark = BookmarkParser::new().parse(&buffer).unwrap();
+        assert!(bookmark.raw_records().is_empty());
+        assert_eq!(bookmark.records().count(), 0);
+
+        let bookmark = BookmarkParser::new()
+            .preserve_raw(true)
+            .parse(&buffer)
+            .unwrap();
+        let records = bookmark.raw_records();
+        assert_eq!(records.len(), bookmark.record_sizes.len());
+        // Iteration order is stable across runs
+        assert!(records.keys().is_sorted());
+
+        let volume_name = &records[&BookmarkData::VOLUME_NAME];
+        assert_eq!(volume_name.data_type, BookmarkData::STRING_TYPE);
+        assert_eq!(volume_name.data, b"Macintosh HD");
+        assert_eq!(volume_name.offset, 356);
+
+        // Array records contain offsets to each element record
+        let path = &records[&BookmarkData::TARGET_PATH];
+        assert_eq!(path.data_type, BookmarkData::ARRAY_TYPE);
+        assert_eq!(path.data.len(), 16);
+    }
+
+    #[test]
+    fn test_offsets() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/downloads.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+        let (bookmark_data, _) = BookmarkData::parse_bookmark_header(&buffer).unwrap();
+        let (_, bookmark) = BookmarkData::parse_bookmark_data(bookmark_data).unwrap();
+
+        let offsets: Vec<_> = bookmark.offsets().collect();
+        assert_eq!(offsets.len(), bookmark.record_sizes.len());
+
+        let volume_name = offsets
+            .iter()
+            .find(|record| record.record_type == BookmarkData::VOLUME_NAME)
+            .unwrap();
+        assert_eq!(volume_name.offset, 356);
+        assert_eq!(volume_name.data_offset, 364);
+        assert_eq!(volume_name.data_length, 12);
+        assert_eq!(volume_name.padded_length, 12);
+        let start = volume_name.data_offset as usize;
+        assert_eq!(&buffer[start..start + 12], b"Macintosh HD");
+
+        // Every record starts on a 4 byte boundary
+        for record in offsets {
+            assert!(record.offset.is_multiple_of(4));
+            assert!(record.padded_length.is_multiple_of(4));
+            assert!(record.padded_length - record.data_length < 4);
+        }
+    }
+
+    #[test]
+    fn test_records() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/downloads.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+        let bookmark = BookmarkParser::new()
+            .preserve_raw(true)
+            .parse(&buffer)
+            .unwrap();
+
+        let records: Vec<_> = bookmark.records().collect();
+        assert_eq!(records.len(), bookmark.record_sizes.len());
+
+        let (_, path) = records
+            .iter()
+            .find(|(record_type, _)| *record_type == BookmarkData::TARGET_PATH)
+            .unwrap();
+        assert_eq!(
+            **path,
+            BookmarkValue::Array(vec![
+                BookmarkValue::String(String::from("Users")),
+                BookmarkValue::String(String::from("puffycid")),
+                BookmarkValue::String(String::from("Downloads")),
+                BookmarkValue::String(String::from("powershell-7.2.4-osx-x64.pkg"))
+            ])
+        );
+        let (_, volume_name) = records
+            .iter()
+            .find(|(record_type, _)| *record_type == BookmarkData::VOLUME_NAME)
+            .unwrap();
+        assert_eq!(
+            **volume_name,
+            BookmarkValue::String(String::from("Macintosh HD"))
+        );
+    }
+
     #[test]
     fn test_table_of_contents_data() {
         let test_data = [
@@ -818,8 +2503,12 @@ mod tests {
             data_offset: 228,
             reserved: 0,
         };
-        let (_, std_data) =
-            BookmarkData::bookmark_standard_data(&bookmark_data, &toc_record).unwrap();
+        let (_, std_data) = BookmarkData::bookmark_standard_data(
+            &bookmark_data,
+            &toc_record,
+            &LengthLimits::default(),
+        )
+        .unwrap();
 
         let data_length = 36;
         let data_type = 257;
@@ -872,8 +2561,9 @@ mod tests {
 
         let (_, std_record) = BookmarkData::bookmark_array_data(
             &test_data,
-            (&test_array_offsets).to_vec(),
+            test_array_offsets.to_vec(),
             &toc_record,
+            &LengthLimits::default(),
         )
         .unwrap();
         let record_type = 4100;
@@ -913,6 +2603,43 @@ mod tests {
         assert_eq!(book_path, path);
     }
 
+    #[test]
+    fn test_bookmark_utf16_units() {
+        let expected: Vec<u16> = "Café".encode_utf16().collect();
+        let little_endian: Vec<u8> = expected
+            .iter()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect();
+        let big_endian: Vec<u8> = expected
+            .iter()
+            .flat_map(|unit| unit.to_be_bytes())
+            .collect();
+
+        let units = BookmarkData::bookmark_utf16_units(&little_endian).unwrap();
+        assert_eq!(units, expected);
+        let units = BookmarkData::bookmark_utf16_units(&big_endian).unwrap();
+        assert_eq!(units, expected);
+
+        let with_bom = [&[0xfe, 0xff][..], &big_endian, &[0, 0]].concat();
+        let units = BookmarkData::bookmark_utf16_units(&with_bom).unwrap();
+        assert_eq!(units, expected);
+
+        // Odd length or no hint of the byte order
+        assert!(BookmarkData::bookmark_utf16_units(b"Caf").is_none());
+        assert!(BookmarkData::bookmark_utf16_units(b"Cafe").is_none());
+    }
+
+    #[test]
+    fn test_bookmark_data_type_uuid() {
+        let test_uuid = [
+            10, 129, 243, 177, 81, 217, 51, 53, 179, 227, 22, 156, 54, 64, 54, 13,
+        ];
+
+        let (_, uuid) = BookmarkData::bookmark_data_type_uuid(&test_uuid).unwrap();
+        assert_eq!(uuid, "0A81F3B1-51D9-3335-B3E3-169C3640360D");
+        assert!(BookmarkData::bookmark_data_type_uuid(&test_uuid[..8]).is_err());
+    }
+
     #[test]
     fn test_bookmark_cnid() {
         let test_cnid = [42, 198, 10, 0, 0, 0, 0, 0];
@@ -941,6 +2668,17 @@ mod tests {
         assert_eq!(book_flags[2], flag_3);
     }
 
+    #[test]
+    fn test_bookmark_data_type_float() {
+        let test_float = [0, 0, 192, 63];
+        let (_, number) = BookmarkData::bookmark_data_type_float(&test_float).unwrap();
+        assert_eq!(number, 1.5);
+
+        let test_float64 = [0, 0, 0, 0, 0, 0, 248, 63];
+        let (_, number) = BookmarkData::bookmark_data_type_float64(&test_float64).unwrap();
+        assert_eq!(number, 1.5);
+    }
+
     #[test]
     fn test_bookmark_data_type_number_eight() {
         let test_volume_size = [0, 96, 127, 115, 37, 0, 0, 0];
@@ -963,13 +2701,32 @@ mod tests {
     }
 
     #[test]
-    fn test_bookmark_data_type_number_four() {
+    fn test_bookmark_data_type_integer() {
         let test_creation = [0, 0, 0, 32];
 
-        let (_, creation_options) =
-            BookmarkData::bookmark_data_type_number_four(&test_creation).unwrap();
+        let (_, creation_options) = BookmarkData::bookmark_data_type_integer_i32(
+            &test_creation,
+            BookmarkData::NUMBER_FOUR_BYTE,
+        )
+        .unwrap();
         let options = 536870912;
         assert_eq!(creation_options, options);
+
+        let (_, number) =
+            BookmarkData::bookmark_data_type_integer(&[0xfe], BookmarkData::NUMBER_ONE_BYTE)
+                .unwrap();
+        assert_eq!(number, -2);
+        let (_, number) =
+            BookmarkData::bookmark_data_type_integer(&[0xf5, 1], BookmarkData::NUMBER_TWO_BYTE)
+                .unwrap();
+        assert_eq!(number, 501);
+
+        let too_large = 0x1_0000_0000i64.to_le_bytes();
+        assert!(BookmarkData::bookmark_data_type_integer_i32(
+            &too_large,
+            BookmarkData::NUMBER_EIGHT_BYTE
+        )
+        .is_err());
     }
 
     #[test]
@@ -1031,14 +2788,14 @@ mod tests {
         let volume_uuid = "96FB41C0-6CE9-4DA2-8435-35BC19C735A3";
         let volume_size = 2000662327296;
         let volume_flag = [4294967425, 4294972399, 0];
-        let volume_root = true;
-        let localized_name = String::new();
+        let volume_root = Some(true);
+        let localized_name = None;
         let target_flags = [1, 15, 0];
-        let username = "puffycid";
+        let username = Some("puffycid");
         let folder_index = 2;
-        let uid = 501;
-        let creation_options = 671094784;
-        let security_extension = String::new();
+        let uid = Some(501);
+        let creation_options = Some(671094784);
+        let security_extension = None;
 
         let cnid_path_len = 4;
         let target_creation = 677388100.0747445;
@@ -1060,14 +2817,43 @@ mod tests {
         assert_eq!(bookmark.volume_size, volume_size);
         assert_eq!(bookmark.volume_flag, volume_flag);
         assert_eq!(bookmark.volume_root, volume_root);
-        assert_eq!(bookmark.localized_name, localized_name);
+        assert_eq!(bookmark.localized_name.as_deref(), localized_name);
         assert_eq!(bookmark.target_flags, target_flags);
-        assert_eq!(bookmark.username, username);
+        assert_eq!(bookmark.username.as_deref(), username);
         assert_eq!(bookmark.folder_index, folder_index);
         assert_eq!(bookmark.uid, uid);
         assert_eq!(bookmark.creation_options, creation_options);
-        assert_eq!(bookmark.security_extension_rw, security_extension);
-        assert_eq!(bookmark.security_extension_ro, security_extension);
-        assert_eq!(bookmark.file_ref_flag, false);
+        assert_eq!(
+            bookmark.security_extension_rw.as_deref(),
+            security_extension
+        );
+        assert_eq!(
+            bookmark.security_extension_ro.as_deref(),
+            security_extension
+        );
+        assert_eq!(bookmark.file_ref_flag, None);
+    }
+
+    #[test]
+    fn test_detect_record_set_warnings() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+        let mut bookmark = crate::parser::parse_bookmark(&buffer).unwrap();
+        assert_eq!(bookmark.detect_record_set(), RecordSet::Full);
+
+        let warning = |key: &str| ParseWarning {
+            record_type: 0x9999,
+            offset: 0,
+            key: key.to_string(),
+            values: Default::default(),
+            message: String::new(),
+        };
+        // Records of an unknown data type do not hide the record set
+        bookmark.warnings.push(warning("warning.unknown_record"));
+        assert_eq!(bookmark.detect_record_set(), RecordSet::Full);
+
+        bookmark.warnings.push(warning("warning.read_record"));
+        assert_eq!(bookmark.detect_record_set(), RecordSet::Unknown);
     }
 }
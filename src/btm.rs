@@ -0,0 +1,369 @@
+//! Parse login item persistence from `backgrounditems.btm`
+//!
+//! `~/Library/Application Support/com.apple.backgroundtaskmanagementagent/backgrounditems.btm` is an
+//! NSKeyedArchiver binary plist. Objects reference each other with UIDs into the `$objects` array, login items are
+//! dictionaries with a `name` and a `bookmark` (bookmark data directly, or wrapped in a `data`/`NS.data` object).
+//! Bookmark data not referenced by an item (ex: container bookmarks) is also returned.
+//!
+//! macOS 13+ moved login items, agents and daemons to
+//! `/private/var/db/com.apple.backgroundtaskmanagement/BackgroundItems-v*.btm`. It is still a keyed archive, every
+//! item record has a type, disposition, developer name and bookmark of the item.
+
+use std::{collections::HashSet, fs, path::Path};
+
+use log::{error, warn};
+use plist::{Dictionary, Value};
+use serde::Serialize;
+
+use crate::{
+    bookmark::BookmarkData,
+    error::BookmarkError,
+    keyed_archive::{bookmark_index, is_bookmark_data, keyed_objects, resolve},
+    parser::parse_bookmark,
+};
+
+#[derive(Debug, Serialize)]
+pub struct BackgroundItem {
+    pub name: Option<String>,   // Name of the item referencing the bookmark
+    pub object_index: usize,    // Index of the bookmark data in $objects
+    pub bookmark: BookmarkData, // Parsed bookmark
+}
+
+#[derive(Debug, Serialize)]
+pub struct BtmItem {
+    pub uuid: Option<String>,           // Item UUID
+    pub name: Option<String>,           // Item name
+    pub developer_name: Option<String>, // Developer name shown in Login Items settings
+    pub identifier: Option<String>,     // Bundle or launchd label identifier
+    pub url: Option<String>,            // URL of the item
+    pub item_type: u64,                 // Item type flags
+    pub types: Vec<String>,             // Names of the item type flags
+    pub disposition: u64,               // Disposition flags
+    pub dispositions: Vec<String>,      // Names of the disposition flags
+    pub bookmark: Option<BookmarkData>, // Parsed bookmark of the item
+}
+
+/// Item type flags used by `sfltool dumpbtm`
+const ITEM_TYPES: [(u64, &str); 8] = [
+    (0x1, "user item"),
+    (0x2, "app"),
+    (0x4, "login item"),
+    (0x8, "agent"),
+    (0x10, "daemon"),
+    (0x20, "developer"),
+    (0x10000, "legacy"),
+    (0x80000, "curated"),
+];
+
+/// Disposition flags used by `sfltool dumpbtm`
+const DISPOSITIONS: [(u64, &str); 4] = [
+    (0x1, "enabled"),
+    (0x2, "allowed"),
+    (0x4, "hidden"),
+    (0x8, "notified"),
+];
+
+/// Parse every bookmark in a `backgrounditems.btm` keyed archive. Bookmark data that fails to parse is skipped
+pub fn parse_background_items(data: &[u8]) -> Result<Vec<BackgroundItem>, BookmarkError> {
+    let objects = &keyed_objects(data)?;
+
+    let mut items = Vec::new();
+    let mut seen = HashSet::new();
+    // Items with a name first, then any bookmark data left over
+    for object in objects.iter().filter_map(Value::as_dictionary) {
+        let Some(index) = object
+            .get("bookmark")
+            .and_then(|bookmark| bookmark_index(objects, bookmark))
+        else {
+            continue;
+        };
+        let name = object
+            .get("name")
+            .and_then(|name| resolve(objects, name))
+            .and_then(Value::as_string)
+            .map(str::to_string);
+        if seen.insert(index) {
+            push_item(&mut items, objects, index, name);
+        }
+    }
+    for (index, object) in objects.iter().enumerate() {
+        if is_bookmark_data(object) && seen.insert(index) {
+            push_item(&mut items, objects, index, None);
+        }
+    }
+    Ok(items)
+}
+
+/// Read a `backgrounditems.btm` file and parse every bookmark
+pub fn parse_background_items_file(
+    path: impl AsRef<Path>,
+) -> Result<Vec<BackgroundItem>, BookmarkError> {
+    match fs::read(path.as_ref()) {
+        Ok(data) => parse_background_items(&data),
+        Err(err) => {
+            error!(
+                "failed to read backgrounditems.btm {:?}: {:?}",
+                path.as_ref(),
+                err
+            );
+            Err(BookmarkError::InvalidPlistData)
+        }
+    }
+}
+
+/// Parse every item record in a Ventura+ `BackgroundItems-v*.btm` database. Items with bookmark data that fails to
+/// parse are kept without a bookmark
+pub fn parse_btm_database(data: &[u8]) -> Result<Vec<BtmItem>, BookmarkError> {
+    let objects = keyed_objects(data)?;
+    // Item records are the only objects with both a type and a disposition
+    Ok(objects
+        .iter()
+        .filter_map(Value::as_dictionary)
+        .filter(|object| object.contains_key("type") && object.contains_key("disposition"))
+        .map(|record| btm_item(&objects, record))
+        .collect())
+}
+
+/// Read a `BackgroundItems-v*.btm` file and parse every item record
+pub fn parse_btm_database_file(path: impl AsRef<Path>) -> Result<Vec<BtmItem>, BookmarkError> {
+    match fs::read(path.as_ref()) {
+        Ok(data) => parse_btm_database(&data),
+        Err(err) => {
+            error!("failed to read BTM database {:?}: {:?}", path.as_ref(), err);
+            Err(BookmarkError::InvalidPlistData)
+        }
+    }
+}
+
+/// Parse a single item record
+fn btm_item(objects: &[Value], record: &Dictionary) -> BtmItem {
+    let string = |key: &str| {
+        record
+            .get(key)
+            .and_then(|value| resolve(objects, value))
+            .and_then(Value::as_string)
+            .filter(|value| *value != "$null")
+            .map(str::to_string)
+    };
+    let integer = |key: &str| {
+        record
+            .get(key)
+            .and_then(|value| resolve(objects, value))
+            .and_then(Value::as_unsigned_integer)
+            .unwrap_or_default()
+    };
+    let flag_names = |value: u64, names: &[(u64, &str)]| {
+        names
+            .iter()
+            .filter(|(flag, _)| value & flag == *flag)
+            .map(|(_, name)| name.to_string())
+            .collect()
+    };
+
+    let name = string("name");
+    let bookmark = match record
+        .get("bookmark")
+        .and_then(|bookmark| bookmark_index(objects, bookmark))
+        .and_then(|index| objects.get(index))
+        .and_then(Value::as_data)
+        .map(parse_bookmark)
+    {
+        Some(Ok(bookmark)) => Some(bookmark),
+        Some(Err(err)) => {
+            warn!(
+                "Failed to parse bookmark for BTM item {:?}: {:?}",
+                name, err
+            );
+            None
+        }
+        None => None,
+    };
+    let item_type = integer("type");
+    let disposition = integer("disposition");
+
+    BtmItem {
+        uuid: string("uuid"),
+        developer_name: string("developerName"),
+        identifier: string("identifier"),
+        url: string("url"),
+        types: flag_names(item_type, &ITEM_TYPES),
+        dispositions: flag_names(disposition, &DISPOSITIONS),
+        item_type,
+        disposition,
+        name,
+        bookmark,
+    }
+}
+
+/// Parse the bookmark data at an index of `$objects` and add it to the items
+fn push_item(
+    items: &mut Vec<BackgroundItem>,
+    objects: &[Value],
+    index: usize,
+    name: Option<String>,
+) {
+    let Some(data) = objects.get(index).and_then(Value::as_data) else {
+        return;
+    };
+    match parse_bookmark(data) {
+        Ok(bookmark) => items.push(BackgroundItem {
+            name,
+            object_index: index,
+            bookmark,
+        }),
+        Err(err) => warn!("Failed to parse bookmark at object {}: {:?}", index, err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse_background_items, parse_background_items_file, parse_btm_database,
+        parse_btm_database_file,
+    };
+    use plist::{Dictionary, Uid, Value};
+    use std::{fs, path::PathBuf};
+
+    fn test_archive() -> Vec<u8> {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let login_item = fs::read(test_location).unwrap();
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/systemevents.bookmark");
+        let container = fs::read(test_location).unwrap();
+
+        let uid = |index: u64| Value::Uid(Uid::new(index));
+        let mut item = Dictionary::new();
+        item.insert(String::from("name"), uid(2));
+        item.insert(String::from("bookmark"), uid(3));
+        item.insert(String::from("$class"), uid(6));
+        let mut wrapper = Dictionary::new();
+        wrapper.insert(String::from("data"), uid(4));
+        let mut class = Dictionary::new();
+        class.insert(
+            String::from("$classname"),
+            Value::String(String::from("Storage.BTMBookmark")),
+        );
+
+        let objects = vec![
+            Value::String(String::from("$null")),
+            Value::Dictionary(item),
+            Value::String(String::from("Syncthing")),
+            Value::Dictionary(wrapper),
+            Value::Data(login_item),
+            Value::Data(container),
+            Value::Dictionary(class),
+        ];
+        let mut top = Dictionary::new();
+        top.insert(String::from("root"), uid(1));
+        let mut archive = Dictionary::new();
+        archive.insert(
+            String::from("$archiver"),
+            Value::String(String::from("NSKeyedArchiver")),
+        );
+        archive.insert(String::from("$top"), Value::Dictionary(top));
+        archive.insert(String::from("$objects"), Value::Array(objects));
+
+        let mut data = Vec::new();
+        Value::Dictionary(archive)
+            .to_writer_binary(&mut data)
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn test_parse_background_items() {
+        let items = parse_background_items(&test_archive()).unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].name.as_deref(), Some("Syncthing"));
+        assert_eq!(items[0].object_index, 4);
+        assert_eq!(items[0].bookmark.path, ["Applications", "Syncthing.app"]);
+        assert_eq!(items[1].name, None);
+        assert_eq!(items[1].object_index, 5);
+
+        let path = std::env::temp_dir().join("macos_bookmarks_test_backgrounditems.btm");
+        fs::write(&path, test_archive()).unwrap();
+        assert_eq!(parse_background_items_file(&path).unwrap().len(), 2);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_background_items_bad_data() {
+        assert!(parse_background_items(b"not a plist").is_err());
+        assert!(parse_background_items_file("/does/not/exist.btm").is_err());
+
+        let mut data = Vec::new();
+        Value::Dictionary(Dictionary::new())
+            .to_writer_binary(&mut data)
+            .unwrap();
+        assert!(parse_background_items(&data).is_err());
+    }
+
+    fn test_btm_database() -> Vec<u8> {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let login_item = fs::read(test_location).unwrap();
+
+        let uid = |index: u64| Value::Uid(Uid::new(index));
+        let mut record = Dictionary::new();
+        record.insert(String::from("uuid"), uid(2));
+        record.insert(String::from("name"), uid(3));
+        record.insert(String::from("developerName"), uid(0));
+        record.insert(String::from("identifier"), uid(4));
+        record.insert(String::from("type"), Value::Integer(0x4.into()));
+        record.insert(String::from("disposition"), Value::Integer(0xb.into()));
+        record.insert(String::from("bookmark"), uid(5));
+        let mut broken = Dictionary::new();
+        broken.insert(String::from("name"), uid(4));
+        broken.insert(String::from("type"), Value::Integer(0x10008.into()));
+        broken.insert(String::from("disposition"), Value::Integer(0x1.into()));
+        broken.insert(String::from("bookmark"), uid(6));
+
+        let objects = vec![
+            Value::String(String::from("$null")),
+            Value::Dictionary(record),
+            Value::String(String::from("6F7C6B4A-2D3E-4F5A-8B9C-0D1E2F3A4B5C")),
+            Value::String(String::from("Syncthing")),
+            Value::String(String::from("com.github.syncthing")),
+            Value::Data(login_item),
+            Value::Data(b"book corrupted".to_vec()),
+            Value::Dictionary(broken),
+        ];
+        let mut archive = Dictionary::new();
+        archive.insert(
+            String::from("$archiver"),
+            Value::String(String::from("NSKeyedArchiver")),
+        );
+        archive.insert(String::from("$objects"), Value::Array(objects));
+
+        let mut data = Vec::new();
+        Value::Dictionary(archive)
+            .to_writer_binary(&mut data)
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn test_parse_btm_database() {
+        let items = parse_btm_database(&test_btm_database()).unwrap();
+        assert_eq!(items.len(), 2);
+        let item = &items[0];
+        assert_eq!(item.name.as_deref(), Some("Syncthing"));
+        assert_eq!(item.developer_name, None);
+        assert_eq!(item.identifier.as_deref(), Some("com.github.syncthing"));
+        assert_eq!(item.types, ["login item"]);
+        assert_eq!(item.dispositions, ["enabled", "allowed", "notified"]);
+        assert_eq!(
+            item.bookmark.as_ref().unwrap().path,
+            ["Applications", "Syncthing.app"]
+        );
+        assert_eq!(items[1].types, ["agent", "legacy"]);
+        assert!(items[1].bookmark.is_none());
+
+        let path = std::env::temp_dir().join("macos_bookmarks_test_BackgroundItems-v4.btm");
+        fs::write(&path, test_btm_database()).unwrap();
+        assert_eq!(parse_btm_database_file(&path).unwrap().len(), 2);
+        fs::remove_file(&path).unwrap();
+        assert!(parse_btm_database(b"not a plist").is_err());
+    }
+}
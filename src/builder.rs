@@ -0,0 +1,836 @@
+//! Build binary bookmark data from scratch
+//!
+//! Useful for generating test corpora and lab data. Only fields that are set are written. By default TOC records are
+//! sorted by record type and record data is written in the order used by macOS (see `KeyOrder` for other orders).
+//! Records are padded with zeros to 4 bytes like macOS does, `RecordLayout` changes the alignment and padding byte.
+//! `build_conforming` additionally checks the output with `conformance::check_conformance` and refuses data native
+//! frameworks may reject.
+
+use log::error;
+
+use crate::{
+    bookmark::BookmarkData,
+    conformance::check_conformance,
+    error::BookmarkError,
+    types::{Cnid, CocoaTime},
+};
+
+#[derive(Debug, Default, Clone)]
+pub struct BookmarkBuilder {
+    path: Vec<String>,
+    cnid_path: Vec<Cnid>,
+    creation: Option<CocoaTime>,
+    target_flags: Option<Vec<u64>>,
+    volume_path: Option<String>,
+    volume_url: Option<String>,
+    volume_name: Option<String>,
+    volume_uuid: Option<String>,
+    volume_size: Option<i64>,
+    volume_creation: Option<CocoaTime>,
+    volume_flags: Option<Vec<u64>>,
+    volume_root: Option<bool>,
+    volume_mount_point: Option<String>,
+    localized_name: Option<String>,
+    display_name: Option<String>,
+    target_filename: Option<String>,
+    security_extension_rw: Option<String>,
+    security_extension_ro: Option<String>,
+    username: Option<String>,
+    folder_index: Option<i64>,
+    uid: Option<i32>,
+    creation_options: Option<i32>,
+    is_executable: Option<bool>,
+    file_ref_flag: Option<bool>,
+    key_order: KeyOrder,
+    layout: RecordLayout,
+    original_toc: Vec<u32>, // Record types in the TOC order of the bookmark the builder was created from
+    original_data: Vec<u32>, // Record types in the record data order of the bookmark the builder was created from
+}
+
+/// Alignment and padding of the records written by `BookmarkBuilder`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecordLayout {
+    pub alignment: usize, // Records are padded to a multiple of this many bytes (0 and 1 disable padding)
+    pub padding: u8,      // Byte used to pad records
+}
+
+impl Default for RecordLayout {
+    fn default() -> Self {
+        RecordLayout {
+            alignment: 4,
+            padding: 0,
+        }
+    }
+}
+
+/// Order of the TOC entries and record data written by `BookmarkBuilder`
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum KeyOrder {
+    /// TOC sorted by record type, record data in the order CFURLCreateBookmarkData writes it
+    #[default]
+    Apple,
+    /// TOC and record data sorted by record type
+    Sorted,
+    /// TOC and record data in the order of the bookmark passed to `BookmarkBuilder::from_bookmark`, records it did not
+    /// have follow in Apple order
+    Original,
+}
+
+impl BookmarkBuilder {
+    /// Create a builder with no fields set
+    pub fn new() -> BookmarkBuilder {
+        BookmarkBuilder::default()
+    }
+
+    /// Create a builder with the fields of a parsed bookmark, remembering its record order for `KeyOrder::Original`.
+    /// Records the builder cannot write (file metadata, URL lengths, TOC path, nested and relative URL records) are
+    /// dropped
+    pub fn from_bookmark(bookmark: &BookmarkData) -> BookmarkBuilder {
+        let populated = |field: &str| {
+            bookmark
+                .field_provenance()
+                .iter()
+                .any(|entry| entry.field == field)
+        };
+        let string = |field: &str, value: &String| populated(field).then(|| value.clone());
+
+        let mut data_order = bookmark.record_offsets.clone();
+        data_order.sort_by_key(|record| record.offset);
+        BookmarkBuilder {
+            path: bookmark.path.clone(),
+            cnid_path: bookmark.cnid_path.clone(),
+            creation: populated("creation").then_some(bookmark.creation),
+            target_flags: populated("target_flags").then(|| bookmark.target_flags.clone()),
+            volume_path: string("volume_path", &bookmark.volume_path),
+            volume_url: string("volume_url", &bookmark.volume_url),
+            volume_name: string("volume_name", &bookmark.volume_name),
+            volume_uuid: string("volume_uuid", &bookmark.volume_uuid),
+            volume_size: populated("volume_size").then_some(bookmark.volume_size),
+            volume_creation: populated("volume_creation").then_some(bookmark.volume_creation),
+            volume_flags: populated("volume_flag").then(|| bookmark.volume_flag.clone()),
+            volume_root: bookmark.volume_root,
+            volume_mount_point: bookmark.volume_mount_point.clone(),
+            localized_name: bookmark.localized_name.clone(),
+            display_name: bookmark.display_name.clone(),
+            target_filename: bookmark.target_filename.clone(),
+            security_extension_rw: bookmark.security_extension_rw.clone(),
+            security_extension_ro: bookmark.security_extension_ro.clone(),
+            username: bookmark.username.clone(),
+            folder_index: populated("folder_index").then_some(bookmark.folder_index),
+            uid: bookmark.uid,
+            creation_options: bookmark.creation_options,
+            is_executable: bookmark.is_executable,
+            file_ref_flag: bookmark.file_ref_flag,
+            key_order: KeyOrder::default(),
+            layout: RecordLayout::default(),
+            original_toc: bookmark
+                .record_offsets
+                .iter()
+                .map(|record| record.record_type)
+                .collect(),
+            original_data: data_order.iter().map(|record| record.record_type).collect(),
+        }
+    }
+
+    /// Set the target path (ex: `/Applications/Foo.app`)
+    pub fn target_path(mut self, path: &str) -> BookmarkBuilder {
+        self.path = path
+            .split('/')
+            .filter(|component| !component.is_empty())
+            .map(String::from)
+            .collect();
+        self
+    }
+
+    /// Set the Catalog Node IDs for each path component
+    pub fn cnid_path(mut self, cnid_path: &[Cnid]) -> BookmarkBuilder {
+        self.cnid_path = cnid_path.to_vec();
+        self
+    }
+
+    /// Set the target creation timestamp
+    pub fn creation(mut self, creation: CocoaTime) -> BookmarkBuilder {
+        self.creation = Some(creation);
+        self
+    }
+
+    /// Set the target resource property flags
+    pub fn target_flags(mut self, flags: &[u64]) -> BookmarkBuilder {
+        self.target_flags = Some(flags.to_vec());
+        self
+    }
+
+    /// Set the volume path (ex: `/`)
+    pub fn volume_path(mut self, volume_path: &str) -> BookmarkBuilder {
+        self.volume_path = Some(volume_path.to_string());
+        self
+    }
+
+    /// Set the volume URL (ex: `file:///`)
+    pub fn volume_url(mut self, volume_url: &str) -> BookmarkBuilder {
+        self.volume_url = Some(volume_url.to_string());
+        self
+    }
+
+    /// Set the volume name
+    pub fn volume_name(mut self, volume_name: &str) -> BookmarkBuilder {
+        self.volume_name = Some(volume_name.to_string());
+        self
+    }
+
+    /// Set the volume UUID string
+    pub fn volume_uuid(mut self, volume_uuid: &str) -> BookmarkBuilder {
+        self.volume_uuid = Some(volume_uuid.to_string());
+        self
+    }
+
+    /// Set the volume size
+    pub fn volume_size(mut self, volume_size: i64) -> BookmarkBuilder {
+        self.volume_size = Some(volume_size);
+        self
+    }
+
+    /// Set the volume creation timestamp
+    pub fn volume_creation(mut self, volume_creation: CocoaTime) -> BookmarkBuilder {
+        self.volume_creation = Some(volume_creation);
+        self
+    }
+
+    /// Set the volume property flags
+    pub fn volume_flags(mut self, flags: &[u64]) -> BookmarkBuilder {
+        self.volume_flags = Some(flags.to_vec());
+        self
+    }
+
+    /// Set if the volume is the filesystem root
+    pub fn volume_root(mut self, volume_root: bool) -> BookmarkBuilder {
+        self.volume_root = Some(volume_root);
+        self
+    }
+
+    /// Set the mount point URL of the volume (ex: `file:///Volumes/USB/`)
+    pub fn volume_mount_point(mut self, mount_point: &str) -> BookmarkBuilder {
+        self.volume_mount_point = Some(mount_point.to_string());
+        self
+    }
+
+    /// Set the localized name of the target
+    pub fn localized_name(mut self, localized_name: &str) -> BookmarkBuilder {
+        self.localized_name = Some(localized_name.to_string());
+        self
+    }
+
+    /// Set the alternate display name of the target
+    pub fn display_name(mut self, display_name: &str) -> BookmarkBuilder {
+        self.display_name = Some(display_name.to_string());
+        self
+    }
+
+    /// Set the filename of the target
+    pub fn target_filename(mut self, target_filename: &str) -> BookmarkBuilder {
+        self.target_filename = Some(target_filename.to_string());
+        self
+    }
+
+    /// Set the RW security extension
+    pub fn security_extension_rw(mut self, extension: &str) -> BookmarkBuilder {
+        self.security_extension_rw = Some(extension.to_string());
+        self
+    }
+
+    /// Set the RO security extension
+    pub fn security_extension_ro(mut self, extension: &str) -> BookmarkBuilder {
+        self.security_extension_ro = Some(extension.to_string());
+        self
+    }
+
+    /// Set the creator username
+    pub fn username(mut self, username: &str) -> BookmarkBuilder {
+        self.username = Some(username.to_string());
+        self
+    }
+
+    /// Set the containing folder index
+    pub fn folder_index(mut self, folder_index: i64) -> BookmarkBuilder {
+        self.folder_index = Some(folder_index);
+        self
+    }
+
+    /// Set the creator UID
+    pub fn uid(mut self, uid: i32) -> BookmarkBuilder {
+        self.uid = Some(uid);
+        self
+    }
+
+    /// Set the bookmark creation options
+    pub fn creation_options(mut self, creation_options: i32) -> BookmarkBuilder {
+        self.creation_options = Some(creation_options);
+        self
+    }
+
+    /// Set if the target is executable
+    pub fn is_executable(mut self, is_executable: bool) -> BookmarkBuilder {
+        self.is_executable = Some(is_executable);
+        self
+    }
+
+    /// Set the file reference flag
+    pub fn file_ref_flag(mut self, file_ref_flag: bool) -> BookmarkBuilder {
+        self.file_ref_flag = Some(file_ref_flag);
+        self
+    }
+
+    /// Set the order of the TOC entries and record data
+    pub fn key_order(mut self, key_order: KeyOrder) -> BookmarkBuilder {
+        self.key_order = key_order;
+        self
+    }
+
+    /// Set the record alignment and padding byte. Anything other than the default is rejected by `build_conforming`
+    /// unless the alignment is a multiple of 4 and the padding is zero
+    pub fn record_layout(mut self, layout: RecordLayout) -> BookmarkBuilder {
+        self.layout = layout;
+        self
+    }
+
+    /// Build the binary bookmark data
+    pub fn build(&self) -> Vec<u8> {
+        let mut records: Vec<(u32, PendingRecord)> = Vec::new();
+        let mut data = |record_type: u32, data_type: u32, data: Vec<u8>| {
+            records.push((record_type, PendingRecord::Data(data_type, data)));
+        };
+
+        if let Some(flags) = &self.target_flags {
+            data(
+                BookmarkData::TARGET_FLAGS,
+                BookmarkData::DATA_TYPE,
+                flag_bytes(flags),
+            );
+        }
+        if let Some(creation) = self.creation {
+            let timestamp = creation.0.to_be_bytes().to_vec();
+            data(
+                BookmarkData::TARGET_CREATION_DATE,
+                BookmarkData::DATE,
+                timestamp,
+            );
+        }
+
+        let strings = [
+            (
+                BookmarkData::VOLUME_PATH,
+                BookmarkData::STRING_TYPE,
+                &self.volume_path,
+            ),
+            (
+                BookmarkData::VOLUME_URL,
+                BookmarkData::URL,
+                &self.volume_url,
+            ),
+            (
+                BookmarkData::VOLUME_MOUNT_POINT,
+                BookmarkData::URL,
+                &self.volume_mount_point,
+            ),
+            (
+                BookmarkData::VOLUME_NAME,
+                BookmarkData::STRING_TYPE,
+                &self.volume_name,
+            ),
+            (
+                BookmarkData::VOLUME_UUID,
+                BookmarkData::STRING_TYPE,
+                &self.volume_uuid,
+            ),
+            (
+                BookmarkData::CREATOR_USERNAME,
+                BookmarkData::STRING_TYPE,
+                &self.username,
+            ),
+            (
+                BookmarkData::LOCALIZED_NAME,
+                BookmarkData::STRING_TYPE,
+                &self.localized_name,
+            ),
+            (
+                BookmarkData::DISPLAY_NAME,
+                BookmarkData::STRING_TYPE,
+                &self.display_name,
+            ),
+            (
+                BookmarkData::TARGET_FILENAME,
+                BookmarkData::STRING_TYPE,
+                &self.target_filename,
+            ),
+            (
+                BookmarkData::SECURITY_EXTENSION_RW,
+                BookmarkData::DATA_TYPE,
+                &self.security_extension_rw,
+            ),
+            (
+                BookmarkData::SECURITY_EXTENSION_RO,
+                BookmarkData::DATA_TYPE,
+                &self.security_extension_ro,
+            ),
+        ];
+        for (record_type, data_type, value) in strings {
+            if let Some(value) = value {
+                data(record_type, data_type, value.as_bytes().to_vec());
+            }
+        }
+
+        if let Some(size) = self.volume_size {
+            let size = size.to_le_bytes().to_vec();
+            data(
+                BookmarkData::VOLUME_SIZE,
+                BookmarkData::NUMBER_EIGHT_BYTE,
+                size,
+            );
+        }
+        if let Some(creation) = self.volume_creation {
+            let timestamp = creation.0.to_be_bytes().to_vec();
+            data(BookmarkData::VOLUME_CREATION, BookmarkData::DATE, timestamp);
+        }
+        if let Some(flags) = &self.volume_flags {
+            data(
+                BookmarkData::VOLUME_FLAGS,
+                BookmarkData::DATA_TYPE,
+                flag_bytes(flags),
+            );
+        }
+        if let Some(index) = self.folder_index {
+            let index = index.to_le_bytes().to_vec();
+            data(
+                BookmarkData::CONTAIN_FOLDER_INDEX,
+                BookmarkData::NUMBER_EIGHT_BYTE,
+                index,
+            );
+        }
+        if let Some(uid) = self.uid {
+            let uid = uid.to_le_bytes().to_vec();
+            data(
+                BookmarkData::CREATOR_UID,
+                BookmarkData::NUMBER_FOUR_BYTE,
+                uid,
+            );
+        }
+        if let Some(options) = self.creation_options {
+            let options = options.to_le_bytes().to_vec();
+            data(
+                BookmarkData::CREATION_OPTIONS,
+                BookmarkData::NUMBER_FOUR_BYTE,
+                options,
+            );
+        }
+
+        // Booleans are stored in the data type
+        let bools = [
+            (BookmarkData::VOLUME_ROOT, self.volume_root),
+            (BookmarkData::FILE_REF_FLAG, self.file_ref_flag),
+            (BookmarkData::IS_EXECUTABLE, self.is_executable),
+        ];
+        for (record_type, value) in bools {
+            match value {
+                Some(true) => data(record_type, BookmarkData::BOOL_TRUE, Vec::new()),
+                Some(false) => data(record_type, BookmarkData::BOOL_FALSE, Vec::new()),
+                None => {}
+            }
+        }
+
+        if !self.path.is_empty() {
+            let elements = self
+                .path
+                .iter()
+                .map(|component| component.as_bytes().to_vec())
+                .collect();
+            records.push((
+                BookmarkData::TARGET_PATH,
+                PendingRecord::Array(BookmarkData::STRING_TYPE, elements),
+            ));
+        }
+        if !self.cnid_path.is_empty() {
+            let elements = self
+                .cnid_path
+                .iter()
+                .map(|cnid| cnid.0.to_le_bytes().to_vec())
+                .collect();
+            records.push((
+                BookmarkData::TARGET_CNID_PATH,
+                PendingRecord::Array(BookmarkData::NUMBER_EIGHT_BYTE, elements),
+            ));
+        }
+
+        records.sort_by_key(|(record_type, _)| self.data_rank(*record_type));
+        let mut writer = RecordWriter {
+            body: Vec::new(),
+            layout: self.layout,
+        };
+        let mut toc: Vec<(u32, u32)> = records
+            .into_iter()
+            .map(|(record_type, record)| (record_type, writer.write_pending(record)))
+            .collect();
+        toc.sort_by_key(|(record_type, _)| self.toc_rank(*record_type));
+        writer.finish(&toc)
+    }
+
+    /// Position of a record in the record data
+    fn data_rank(&self, record_type: u32) -> (usize, usize, u32) {
+        match self.key_order {
+            KeyOrder::Apple => (0, apple_rank(record_type), record_type),
+            KeyOrder::Sorted => (0, 0, record_type),
+            KeyOrder::Original => {
+                original_rank(&self.original_data, record_type, apple_rank(record_type))
+            }
+        }
+    }
+
+    /// Position of a record in the TOC
+    fn toc_rank(&self, record_type: u32) -> (usize, usize, u32) {
+        match self.key_order {
+            KeyOrder::Apple | KeyOrder::Sorted => (0, 0, record_type),
+            KeyOrder::Original => original_rank(&self.original_toc, record_type, 0),
+        }
+    }
+
+    /// Build the binary bookmark data and check it against the invariants of bookmarks created by macOS. Use `build`
+    /// to emit nonconforming data anyway
+    pub fn build_conforming(&self) -> Result<Vec<u8>, BookmarkError> {
+        let data = self.build();
+        let issues = check_conformance(&data);
+        if !issues.is_empty() {
+            error!("Built bookmark does not conform: {:?}", issues);
+            return Err(BookmarkError::NonConforming);
+        }
+        Ok(data)
+    }
+}
+
+#[derive(Debug, Default)]
+struct RecordWriter {
+    body: Vec<u8>,        // Record data written after the TOC offset
+    layout: RecordLayout, // Record alignment and padding byte
+}
+
+impl RecordWriter {
+    /// Write a record and return its offset (relative to the start of the bookmark data)
+    fn write(&mut self, data_type: u32, data: &[u8]) -> u32 {
+        // Offsets include the 4 byte TOC offset at the start of the bookmark data
+        let offset = self.body.len() as u32 + 4;
+        self.body
+            .extend_from_slice(&(data.len() as u32).to_le_bytes());
+        self.body.extend_from_slice(&data_type.to_le_bytes());
+        self.body.extend_from_slice(data);
+
+        let alignment = self.layout.alignment.max(1);
+        while !self.body.len().is_multiple_of(alignment) {
+            self.body.push(self.layout.padding);
+        }
+        offset
+    }
+
+    /// Write a pending record and return its offset. Array elements are written before the array
+    fn write_pending(&mut self, record: PendingRecord) -> u32 {
+        match record {
+            PendingRecord::Data(data_type, data) => self.write(data_type, &data),
+            PendingRecord::Array(data_type, elements) => {
+                let data: Vec<u8> = elements
+                    .iter()
+                    .flat_map(|element| self.write(data_type, element).to_le_bytes())
+                    .collect();
+                self.write(BookmarkData::ARRAY_TYPE, &data)
+            }
+        }
+    }
+
+    /// Write the TOC and header, returning the complete bookmark
+    fn finish(self, toc: &[(u32, u32)]) -> Vec<u8> {
+        let toc_offset = self.body.len() as u32 + 4;
+        let record_size = 12;
+        let record_count = toc.len() as u32;
+
+        let mut data = toc_offset.to_le_bytes().to_vec();
+        data.extend_from_slice(&self.body);
+        data.extend_from_slice(&(record_size + record_size * record_count).to_le_bytes());
+        // TOC record type (0xfffe) and flags (0xffff)
+        data.extend_from_slice(&[254, 255, 255, 255]);
+        // TOC level and next TOC offset
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&record_count.to_le_bytes());
+        for (record_type, offset) in toc {
+            data.extend_from_slice(&record_type.to_le_bytes());
+            data.extend_from_slice(&offset.to_le_bytes());
+            data.extend_from_slice(&0u32.to_le_bytes());
+        }
+
+        let mut bookmark = BookmarkData::BOOKMARK_SIGNATURE.to_vec();
+        bookmark.extend_from_slice(&(data.len() as u32 + BookmarkData::HEADER_SIZE).to_le_bytes());
+        bookmark.extend_from_slice(&[0, 0, 4, 16]);
+        bookmark.extend_from_slice(&BookmarkData::HEADER_SIZE.to_le_bytes());
+        bookmark.extend_from_slice(&[0; 32]);
+        bookmark.extend_from_slice(&data);
+        bookmark
+    }
+}
+
+/// Record data waiting to be written
+#[derive(Debug)]
+enum PendingRecord {
+    Data(u32, Vec<u8>),       // Data type and record data
+    Array(u32, Vec<Vec<u8>>), // Data type and record data of every element
+}
+
+/// Order CFURLCreateBookmarkData writes record data in (observed in native bookmarks). Other records follow, sorted by
+/// record type
+const APPLE_DATA_ORDER: [u32; 20] = [
+    BookmarkData::CREATION_OPTIONS,
+    BookmarkData::CREATOR_USERNAME,
+    BookmarkData::TARGET_PATH,
+    BookmarkData::TARGET_CNID_PATH,
+    BookmarkData::TARGET_CREATION_DATE,
+    BookmarkData::TARGET_FLAGS,
+    BookmarkData::CONTAIN_FOLDER_INDEX,
+    BookmarkData::CREATOR_UID,
+    BookmarkData::VOLUME_URL,
+    BookmarkData::VOLUME_NAME,
+    BookmarkData::VOLUME_SIZE,
+    BookmarkData::VOLUME_CREATION,
+    BookmarkData::VOLUME_UUID,
+    BookmarkData::VOLUME_FLAGS,
+    BookmarkData::VOLUME_PATH,
+    BookmarkData::VOLUME_ROOT,
+    BookmarkData::IS_EXECUTABLE,
+    BookmarkData::LOCALIZED_NAME,
+    BookmarkData::SECURITY_EXTENSION_RW,
+    BookmarkData::SECURITY_EXTENSION_RO,
+];
+
+/// Position of a record type in `APPLE_DATA_ORDER`
+fn apple_rank(record_type: u32) -> usize {
+    APPLE_DATA_ORDER
+        .iter()
+        .position(|known| *known == record_type)
+        .unwrap_or(APPLE_DATA_ORDER.len())
+}
+
+/// Position of a record type in the original bookmark, records the original did not have follow at `fallback` rank
+fn original_rank(original: &[u32], record_type: u32, fallback: usize) -> (usize, usize, u32) {
+    match original.iter().position(|known| *known == record_type) {
+        Some(position) => (0, position, record_type),
+        None => (1, fallback, record_type),
+    }
+}
+
+/// Encode property flags as 8 byte values
+fn flag_bytes(flags: &[u64]) -> Vec<u8> {
+    flags.iter().flat_map(|flag| flag.to_le_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BookmarkBuilder, KeyOrder, RecordLayout};
+    use crate::{
+        conformance::check_conformance,
+        parser::parse_bookmark,
+        types::{Cnid, CocoaTime},
+    };
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn test_build() {
+        let data = BookmarkBuilder::new()
+            .target_path("/Applications/Foo.app")
+            .cnid_path(&[Cnid(103), Cnid(706090)])
+            .creation(CocoaTime(665473989.0))
+            .target_flags(&[2, 15, 0])
+            .volume_path("/")
+            .volume_url("file:///")
+            .volume_mount_point("file:///Volumes/USB/")
+            .volume_name("Macintosh HD")
+            .volume_uuid("0A81F3B1-51D9-3335-B3E3-169C3640360D")
+            .volume_size(160851517440)
+            .volume_creation(CocoaTime(241134516.0))
+            .volume_flags(&[4294967425, 4294972399, 0])
+            .volume_root(true)
+            .localized_name("Foo")
+            .display_name("Foo Helper")
+            .target_filename("Foo.app")
+            .username("lab")
+            .uid(501)
+            .folder_index(2)
+            .creation_options(536870912)
+            .is_executable(true)
+            .file_ref_flag(true)
+            .build();
+
+        let bookmark = parse_bookmark(&data).unwrap();
+        assert_eq!(bookmark.path, ["Applications", "Foo.app"]);
+        assert_eq!(bookmark.cnid_path, [103, 706090]);
+        assert_eq!(bookmark.creation, 665473989.0);
+        assert_eq!(bookmark.target_flags, [2, 15, 0]);
+        assert_eq!(bookmark.volume_path, "/");
+        assert_eq!(bookmark.volume_url, "file:///");
+        assert_eq!(
+            bookmark.volume_mount_point.as_deref(),
+            Some("file:///Volumes/USB/")
+        );
+        assert_eq!(bookmark.volume_name, "Macintosh HD");
+        assert_eq!(bookmark.volume_uuid, "0A81F3B1-51D9-3335-B3E3-169C3640360D");
+        assert_eq!(bookmark.volume_size, 160851517440);
+        assert_eq!(bookmark.volume_creation, 241134516.0);
+        assert_eq!(bookmark.volume_flag, [4294967425, 4294972399, 0]);
+        assert_eq!(bookmark.volume_root, Some(true));
+        assert_eq!(bookmark.localized_name.as_deref(), Some("Foo"));
+        assert_eq!(bookmark.display_name.as_deref(), Some("Foo Helper"));
+        assert_eq!(bookmark.target_filename.as_deref(), Some("Foo.app"));
+        assert_eq!(bookmark.username.as_deref(), Some("lab"));
+        assert_eq!(bookmark.uid, Some(501));
+        assert_eq!(bookmark.folder_index, 2);
+        assert_eq!(bookmark.creation_options, Some(536870912));
+        assert_eq!(bookmark.is_executable, Some(true));
+        assert_eq!(bookmark.file_ref_flag, Some(true));
+        assert!(bookmark.warnings.is_empty());
+        assert!(bookmark.record_conflicts().is_empty());
+        assert!(check_conformance(&data).is_empty());
+    }
+
+    #[test]
+    fn test_build_empty() {
+        let data = BookmarkBuilder::new().build();
+        let bookmark = parse_bookmark(&data).unwrap();
+        assert!(bookmark.path.is_empty());
+        assert_eq!(data.len(), 48 + 4 + 20);
+        assert_eq!(BookmarkBuilder::new().build_conforming().unwrap(), data);
+    }
+
+    #[test]
+    fn test_key_order() {
+        let builder = BookmarkBuilder::new()
+            .target_path("/Applications/Foo.app")
+            .volume_path("/")
+            .volume_url("file:///")
+            .volume_name("Macintosh HD")
+            .creation_options(536870912);
+
+        // Record types in record data order
+        let data_order = |data: &[u8]| {
+            let mut records = parse_bookmark(data).unwrap().record_offsets;
+            records.sort_by_key(|record| record.offset);
+            records
+                .iter()
+                .map(|record| record.record_type)
+                .collect::<Vec<_>>()
+        };
+
+        let apple = builder.build();
+        assert_eq!(data_order(&apple), [0xd010, 0x1004, 0x2005, 0x2010, 0x2002]);
+        assert!(check_conformance(&apple).is_empty());
+
+        let sorted = builder.clone().key_order(KeyOrder::Sorted).build();
+        assert_eq!(
+            data_order(&sorted),
+            [0x1004, 0x2002, 0x2005, 0x2010, 0xd010]
+        );
+        assert!(check_conformance(&sorted).is_empty());
+
+        // Without an original bookmark records are written in Apple order
+        let original = builder.key_order(KeyOrder::Original).build();
+        assert_eq!(original, apple);
+    }
+
+    #[test]
+    fn test_from_bookmark() {
+        let read_sample = |name: &str| {
+            let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            test_location.push("tests/test_data");
+            test_location.push(name);
+            fs::read(test_location).unwrap()
+        };
+
+        // Bookmarks created by macOS are rebuilt byte for byte
+        for sample in ["loginitem.bookmark", "ventura.bookmark"] {
+            let buffer = read_sample(sample);
+            let bookmark = parse_bookmark(&buffer).unwrap();
+            assert_eq!(BookmarkBuilder::from_bookmark(&bookmark).build(), buffer);
+        }
+
+        // macAlias writes record data in a different order than macOS
+        let buffer = read_sample("macAlias.bookmark");
+        let bookmark = parse_bookmark(&buffer).unwrap();
+        let record_types = |bookmark: &crate::BookmarkData, by_offset: bool| {
+            let mut records = bookmark.record_offsets.clone();
+            if by_offset {
+                records.sort_by_key(|record| record.offset);
+            }
+            records
+                .iter()
+                .map(|record| record.record_type)
+                .collect::<Vec<_>>()
+        };
+        let data = BookmarkBuilder::from_bookmark(&bookmark)
+            .key_order(KeyOrder::Original)
+            .build();
+        let rebuilt = parse_bookmark(&data).unwrap();
+        assert_eq!(
+            record_types(&rebuilt, false),
+            record_types(&bookmark, false)
+        );
+        assert_eq!(record_types(&rebuilt, true), record_types(&bookmark, true));
+        assert_eq!(rebuilt.path, bookmark.path);
+        assert_eq!(rebuilt.file_ref_flag, bookmark.file_ref_flag);
+
+        let data = BookmarkBuilder::from_bookmark(&bookmark).build();
+        let rebuilt = parse_bookmark(&data).unwrap();
+        assert_ne!(record_types(&rebuilt, true), record_types(&bookmark, true));
+    }
+
+    #[test]
+    fn test_record_layout() {
+        let builder = BookmarkBuilder::new()
+            .target_path("/Applications/Foo.app")
+            .volume_name("Macintosh HD")
+            .creation_options(536870912);
+        assert_eq!(
+            builder
+                .clone()
+                .record_layout(RecordLayout::default())
+                .build(),
+            builder.build()
+        );
+
+        let rules = |data: &[u8]| {
+            check_conformance(data)
+                .iter()
+                .map(|issue| issue.rule)
+                .collect::<Vec<_>>()
+        };
+        let layouts = [
+            (8, 0, None),
+            (1, 0, Some("alignment")),
+            (4, 0xff, Some("padding")),
+        ];
+        for (alignment, padding, expected) in layouts {
+            let layout = RecordLayout { alignment, padding };
+            let data = builder.clone().record_layout(layout).build();
+            match expected {
+                Some(rule) => assert!(rules(&data).contains(&rule)),
+                None => assert!(rules(&data).is_empty()),
+            }
+            assert_eq!(
+                builder
+                    .clone()
+                    .record_layout(layout)
+                    .build_conforming()
+                    .is_ok(),
+                expected.is_none()
+            );
+
+            // Nonconforming layouts still read back cleanly
+            let bookmark = parse_bookmark(&data).unwrap();
+            assert_eq!(bookmark.path, ["Applications", "Foo.app"]);
+            assert_eq!(bookmark.volume_name, "Macintosh HD");
+            assert_eq!(bookmark.creation_options, Some(536870912));
+            assert!(bookmark.warnings.is_empty());
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Export parsed bookmarks as a content-addressed evidence bundle
+//!
+//! Every file is stored under `objects/<sha256>` where the name is the SHA256 of the file contents. Each bookmark
+//! gets three objects: the raw bookmark data, the parsed bookmark (JSON) and the TOC record behind each parsed field
+//! (JSON). `index.json` maps each source to its objects. Identical files are only stored once.
+
+use std::{fs, path::Path};
+
+use log::error;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::{error::BookmarkError, parser::parse_bookmark};
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct BundleEntry {
+    pub source: String,         // Where the bookmark data came from (ex: file path)
+    pub blob: String,           // SHA256 of the raw bookmark data
+    pub parsed: Option<String>, // SHA256 of the parsed bookmark JSON
+    pub provenance: Option<String>, // SHA256 of the field provenance JSON
+    pub error: Option<String>,  // Parser error if the bookmark could not be parsed
+}
+
+/// Parse each `(source, data)` bookmark and write the evidence bundle to a directory. Bookmarks that fail to parse
+/// still have their raw data preserved
+pub fn export_bundle(
+    bookmarks: &[(&str, &[u8])],
+    directory: &Path,
+) -> Result<Vec<BundleEntry>, BookmarkError> {
+    let objects = directory.join("objects");
+    if let Err(err) = fs::create_dir_all(&objects) {
+        error!("failed to create bundle directory: {:?}", err);
+        return Err(BookmarkError::BundleWriteError);
+    }
+
+    let mut entries = Vec::new();
+    for (source, data) in bookmarks {
+        let mut entry = BundleEntry {
+            source: source.to_string(),
+            blob: write_object(&objects, data)?,
+            parsed: None,
+            provenance: None,
+            error: None,
+        };
+
+        match parse_bookmark(data) {
+            Ok(bookmark) => {
+                entry.parsed = Some(write_object(&objects, &to_json(&bookmark)?)?);
+                entry.provenance = Some(write_object(
+                    &objects,
+                    &to_json(&bookmark.field_provenance())?,
+                )?);
+            }
+            Err(err) => entry.error = Some(err.to_string()),
+        }
+        entries.push(entry);
+    }
+
+    if let Err(err) = fs::write(directory.join("index.json"), to_json(&entries)?) {
+        error!("failed to write bundle index: {:?}", err);
+        return Err(BookmarkError::BundleWriteError);
+    }
+    Ok(entries)
+}
+
+/// Write data to the objects directory, named by its SHA256. Returns the hash
+fn write_object(objects: &Path, data: &[u8]) -> Result<String, BookmarkError> {
+    let hash: String = Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    let path = objects.join(&hash);
+    if path.exists() {
+        return Ok(hash);
+    }
+
+    if let Err(err) = fs::write(path, data) {
+        error!("failed to write bundle object {}: {:?}", hash, err);
+        return Err(BookmarkError::BundleWriteError);
+    }
+    Ok(hash)
+}
+
+/// Serialize a value to pretty JSON
+fn to_json<T: Serialize>(value: &T) -> Result<Vec<u8>, BookmarkError> {
+    match serde_json::to_vec_pretty(value) {
+        Ok(json) => Ok(json),
+        Err(err) => {
+            error!("failed to serialize bundle data: {:?}", err);
+            Err(BookmarkError::BundleWriteError)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::export_bundle;
+    use std::{env, fs, path::PathBuf};
+
+    #[test]
+    fn test_export_bundle() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+
+        let directory = env::temp_dir().join("macos_bookmarks_bundle_test");
+        let bookmarks = [
+            ("loginitem.bookmark", buffer.as_slice()),
+            ("copy.bookmark", buffer.as_slice()),
+            ("bad.bookmark", b"not a bookmark".as_slice()),
+        ];
+        let entries = export_bundle(&bookmarks, &directory).unwrap();
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].blob, entries[1].blob);
+        assert_eq!(entries[0].parsed, entries[1].parsed);
+        assert!(entries[0].error.is_none());
+        assert!(entries[2].parsed.is_none());
+        assert!(entries[2].error.is_some());
+
+        let blob = fs::read(directory.join("objects").join(&entries[0].blob)).unwrap();
+        assert_eq!(blob, buffer);
+        let parsed: serde_json::Value = serde_json::from_slice(
+            &fs::read(
+                directory
+                    .join("objects")
+                    .join(entries[0].parsed.as_ref().unwrap()),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(parsed["localized_name"], "Syncthing");
+
+        // Bad data, raw data, parsed bookmark and provenance
+        assert_eq!(fs::read_dir(directory.join("objects")).unwrap().count(), 4);
+        let index: Vec<serde_json::Value> =
+            serde_json::from_slice(&fs::read(directory.join("index.json")).unwrap()).unwrap();
+        assert_eq!(index.len(), 3);
+        assert_eq!(index[2]["source"], "bad.bookmark");
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,114 @@
+//! Carve bookmarks from arbitrary binary data
+//!
+//! Memory dumps, unallocated space and unknown containers can hold bookmarks without any structure pointing at them.
+//! The data is scanned for the `book` signature, hits with a valid header (version, data offset and a plausible total
+//! size) are parsed. Bookmarks cut off by the end of the data are still parsed and usually fail with `TruncatedData`.
+
+use crate::{
+    bookmark::BookmarkData,
+    error::BookmarkError,
+    extract::bookmark_range,
+    parser::{BookmarkParser, ResourceLimits},
+};
+
+/// Offset of a bookmark hit and the parse result
+pub type CarvedBookmark = (usize, Result<BookmarkData, BookmarkError>);
+
+/// Find and parse every bookmark in the data. Returns the offset of each hit with a valid header and the parse result.
+/// Bookmarks are parsed with `ResourceLimits::BOUNDED`
+pub fn find_bookmarks(data: &[u8]) -> Vec<CarvedBookmark> {
+    find_bookmarks_with_parser(
+        data,
+        &BookmarkParser::new().resource_limits(ResourceLimits::BOUNDED),
+    )
+}
+
+/// Find every bookmark in the data and parse it with the provided parser
+pub fn find_bookmarks_with_parser(data: &[u8], parser: &BookmarkParser) -> Vec<CarvedBookmark> {
+    let max_size = parser.options().resource_limits.max_size;
+    let mut found = Vec::new();
+    let mut offset = 0;
+    while let Some(position) = data
+        .get(offset..)
+        .and_then(|rest| find_signature(rest, BookmarkData::BOOKMARK_SIGNATURE))
+    {
+        let start = offset + position;
+        offset = start + 1;
+        if !valid_header(data, start, max_size) {
+            continue;
+        }
+        let Some(range) = bookmark_range(data, start) else {
+            continue;
+        };
+        let Some(bookmark_data) = range.slice(data) else {
+            continue;
+        };
+        let result = parser.parse(bookmark_data);
+        // Skip past parsed bookmarks, a failed hit may overlap the real bookmark
+        if result.is_ok() {
+            offset = range.end.max(offset);
+        }
+        found.push((start, result));
+    }
+    found
+}
+
+/// Check the bookmark header at an offset. The total size must include the TOC offset and be within the size limit
+fn valid_header(data: &[u8], offset: usize, max_size: usize) -> bool {
+    let Some(Ok((_, header))) = data.get(offset..).map(BookmarkData::parse_bookmark_header) else {
+        return false;
+    };
+    let min_size = BookmarkData::HEADER_SIZE + 4;
+    header.validate().is_ok()
+        && header.bookmark_data_length >= min_size
+        && header.bookmark_data_length as usize <= max_size
+}
+
+/// Find the first occurrence of the signature
+fn find_signature(data: &[u8], signature: &[u8]) -> Option<usize> {
+    data.windows(signature.len())
+        .position(|window| window == signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_bookmarks;
+    use crate::error::BookmarkError;
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn test_find_bookmarks() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let login_item = fs::read(test_location).unwrap();
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/downloads.bookmark");
+        let download = fs::read(test_location).unwrap();
+
+        // Noise, a false positive signature, two bookmarks and a truncated copy at the end
+        let mut dump = vec![0xaa; 37];
+        dump.extend_from_slice(b"book not a header");
+        let first = dump.len();
+        dump.extend_from_slice(&login_item);
+        dump.extend_from_slice(&[0; 5]);
+        let second = dump.len();
+        dump.extend_from_slice(&download);
+        let truncated = dump.len();
+        dump.extend_from_slice(&login_item[..100]);
+
+        let found = find_bookmarks(&dump);
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[0].0, first);
+        assert_eq!(
+            found[0].1.as_ref().unwrap().path,
+            ["Applications", "Syncthing.app"]
+        );
+        assert_eq!(found[1].0, second);
+        assert!(found[1].1.is_ok());
+        assert_eq!(found[2].0, truncated);
+        assert!(matches!(found[2].1, Err(BookmarkError::TruncatedData(_))));
+
+        assert!(find_bookmarks(b"").is_empty());
+        assert!(find_bookmarks(b"book").is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+//! Match bookmarks against known abuse patterns
+//!
+//! Provides a small signature set describing bookmarks commonly abused for persistence.
+
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::{bookmark::BookmarkData, error::BookmarkError, locale::Localization};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Signature {
+    pub id: String,              // Rule ID
+    pub name: String,            // Short rule name
+    pub description: String,     // Description of the abuse pattern
+    pub references: Vec<String>, // Links describing the abuse pattern
+    #[serde(default)]
+    pub path_contains: Vec<String>, // Target path must contain one of these (case insensitive)
+    #[serde(default)]
+    pub target_names: Vec<String>, // Target name must be one of these (case insensitive)
+}
+
+#[derive(Debug, Serialize)]
+pub struct SignatureMatch {
+    pub id: String,              // Matched rule ID
+    pub name: String,            // Matched rule name
+    pub description: String,     // Description of the abuse pattern
+    pub references: Vec<String>, // Links describing the abuse pattern
+}
+
+/// Load the signature set embedded in the library
+pub fn default_signatures() -> Result<Vec<Signature>, BookmarkError> {
+    load_signatures(include_str!("data/signatures.json"))
+}
+
+/// Load a signature set from JSON data
+pub fn load_signatures(data: &str) -> Result<Vec<Signature>, BookmarkError> {
+    let signatures_result = serde_json::from_str(data);
+    match signatures_result {
+        Ok(signatures) => Ok(signatures),
+        Err(err) => {
+            error!("failed to parse signature data: {:?}", err);
+            Err(BookmarkError::InvalidSignatureData)
+        }
+    }
+}
+
+/// Match a parsed bookmark against a signature set
+pub fn match_bookmark(bookmark: &BookmarkData, signatures: &[Signature]) -> Vec<SignatureMatch> {
+    match_bookmark_localized(bookmark, signatures, &Localization::english())
+}
+
+/// Match a parsed bookmark against a signature set, using translated descriptions when available
+pub fn match_bookmark_localized(
+    bookmark: &BookmarkData,
+    signatures: &[Signature],
+    locale: &Localization,
+) -> Vec<SignatureMatch> {
+    let full_path = format!("/{}", bookmark.path.join("/")).to_lowercase();
+    let target_name = bookmark
+        .path
+        .last()
+        .map(|name| name.to_lowercase())
+        .unwrap_or_default();
+
+    let mut matches: Vec<SignatureMatch> = Vec::new();
+    for signature in signatures {
+        // A signature without any conditions never matches
+        if signature.path_contains.is_empty() && signature.target_names.is_empty() {
+            continue;
+        }
+
+        let path_match = signature.path_contains.is_empty()
+            || signature
+                .path_contains
+                .iter()
+                .any(|pattern| full_path.contains(&pattern.to_lowercase()));
+        let name_match = signature.target_names.is_empty()
+            || signature
+                .target_names
+                .iter()
+                .any(|name| name.to_lowercase() == target_name);
+
+        if path_match && name_match {
+            let description_key = format!("signature.{}", signature.id);
+            let description = if locale.contains(&description_key) {
+                locale.message(&description_key)
+            } else {
+                signature.description.clone()
+            };
+
+            matches.push(SignatureMatch {
+                id: signature.id.clone(),
+                name: signature.name.clone(),
+                description,
+                references: signature.references.clone(),
+            });
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{default_signatures, load_signatures, match_bookmark, match_bookmark_localized};
+    use crate::{locale::Localization, parser::parse_bookmark};
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn test_default_signatures() {
+        let signatures = default_signatures().unwrap();
+        assert_eq!(signatures.len(), 3);
+        assert_eq!(signatures[0].id, "BM-0001");
+    }
+
+    #[test]
+    fn test_match_bookmark() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/poisonapple.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+        let bookmark = parse_bookmark(&buffer).unwrap();
+
+        let signatures = default_signatures().unwrap();
+        let matches = match_bookmark(&bookmark, &signatures);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].id, "BM-0001");
+        assert_eq!(
+            matches[0].references,
+            ["https://github.com/CyborgSecurity/PoisonApple"]
+        );
+
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+        let bookmark = parse_bookmark(&buffer).unwrap();
+        assert!(match_bookmark(&bookmark, &signatures).is_empty());
+    }
+
+    #[test]
+    fn test_load_signatures() {
+        let data = r#"[{"id": "TEST-1", "name": "Test", "description": "Test rule", "references": [], "target_names": ["SYNCTHING.APP"]}]"#;
+        let signatures = load_signatures(data).unwrap();
+
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+        let bookmark = parse_bookmark(&buffer).unwrap();
+
+        let matches = match_bookmark(&bookmark, &signatures);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].id, "TEST-1");
+    }
+
+    #[test]
+    fn test_match_bookmark_localized() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/poisonapple.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+        let bookmark = parse_bookmark(&buffer).unwrap();
+
+        let signatures = default_signatures().unwrap();
+        let matches = match_bookmark(&bookmark, &signatures);
+        assert_eq!(
+            matches[0].description,
+            "Bookmark targets an application bundled with the PoisonApple persistence tool"
+        );
+
+        let locale =
+            Localization::from_json(r#"{"signature.BM-0001": "Application de PoisonApple"}"#)
+                .unwrap();
+        let matches = match_bookmark_localized(&bookmark, &signatures, &locale);
+        assert_eq!(matches[0].description, "Application de PoisonApple");
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidSignatureData")]
+    fn test_bad_signatures() {
+        let _ = load_signatures("not json").unwrap();
+    }
+}
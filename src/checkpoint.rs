@@ -0,0 +1,139 @@
+//! Checkpoint files for resumable directory scans
+//!
+//! A checkpoint lists the input files a scan has finished, one JSON object (path and size) per line. Each entry is
+//! appended and flushed as soon as the file is done, so after an interruption the checkpoint holds every completed
+//! file and at most a partial last line, which is ignored when the checkpoint is opened again. Files are identified
+//! by path and size: a file that changed size since it was recorded is scanned again.
+
+use std::{
+    collections::HashSet,
+    fs::{self, File, OpenOptions},
+    io::{ErrorKind, Write},
+    path::Path,
+};
+
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::error::BookmarkError;
+
+#[derive(Debug)]
+pub struct Checkpoint {
+    file: File,                     // Checkpoint file opened for appending
+    done: HashSet<CheckpointEntry>, // Inputs recorded in the checkpoint
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Hash)]
+struct CheckpointEntry {
+    path: String, // Input file (invalid UTF-8 replaced)
+    size: u64,    // Size of the input file when it was recorded
+}
+
+impl Checkpoint {
+    /// Open a checkpoint file, creating it if it does not exist. Inputs recorded by earlier runs are skipped
+    pub fn open(path: impl AsRef<Path>) -> Result<Checkpoint, BookmarkError> {
+        let path = path.as_ref();
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+            Err(err) => {
+                error!("failed to read checkpoint {:?}: {:?}", path, err);
+                return Err(BookmarkError::CheckpointError);
+            }
+        };
+
+        let mut done = HashSet::new();
+        for line in contents.lines().filter(|line| !line.is_empty()) {
+            match serde_json::from_str(line) {
+                Ok(entry) => {
+                    done.insert(entry);
+                }
+                Err(err) => warn!("Skipping bad checkpoint entry in {:?}: {:?}", path, err),
+            }
+        }
+
+        let mut file = match OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => file,
+            Err(err) => {
+                error!("failed to open checkpoint {:?}: {:?}", path, err);
+                return Err(BookmarkError::CheckpointError);
+            }
+        };
+        // Terminate a partial line left by an interrupted run
+        if !contents.is_empty() && !contents.ends_with('\n') && writeln!(file).is_err() {
+            error!("failed to write checkpoint {:?}", path);
+            return Err(BookmarkError::CheckpointError);
+        }
+        Ok(Checkpoint { file, done })
+    }
+
+    /// Check if an input was recorded with the same size
+    pub fn contains(&self, path: &Path, size: u64) -> bool {
+        self.done.contains(&CheckpointEntry {
+            path: path.to_string_lossy().to_string(),
+            size,
+        })
+    }
+
+    /// Record a finished input. The entry is flushed to the checkpoint file before returning
+    pub fn record(&mut self, path: &Path, size: u64) -> Result<(), BookmarkError> {
+        let entry = CheckpointEntry {
+            path: path.to_string_lossy().to_string(),
+            size,
+        };
+        let written = serde_json::to_string(&entry)
+            .map_err(std::io::Error::from)
+            .and_then(|line| writeln!(self.file, "{line}"))
+            .and_then(|_| self.file.flush());
+        if let Err(err) = written {
+            error!("failed to write checkpoint entry for {:?}: {:?}", path, err);
+            return Err(BookmarkError::CheckpointError);
+        }
+        self.done.insert(entry);
+        Ok(())
+    }
+
+    /// Number of inputs recorded in the checkpoint
+    pub fn len(&self) -> usize {
+        self.done.len()
+    }
+
+    /// Check if no inputs are recorded
+    pub fn is_empty(&self) -> bool {
+        self.done.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Checkpoint;
+    use std::{env, fs, path::Path};
+
+    #[test]
+    fn test_checkpoint() {
+        let path = env::temp_dir().join("macos_bookmarks_test_checkpoint.jsonl");
+        let _ = fs::remove_file(&path);
+
+        let mut checkpoint = Checkpoint::open(&path).unwrap();
+        assert!(checkpoint.is_empty());
+        checkpoint.record(Path::new("/a.bookmark"), 10).unwrap();
+        checkpoint.record(Path::new("/b\nc.plist"), 20).unwrap();
+        assert!(checkpoint.contains(Path::new("/a.bookmark"), 10));
+        assert!(!checkpoint.contains(Path::new("/a.bookmark"), 11));
+
+        // Interrupted while writing an entry
+        let mut contents = fs::read_to_string(&path).unwrap();
+        contents.push_str("{\"path\":\"/d.bo");
+        fs::write(&path, contents).unwrap();
+
+        let mut checkpoint = Checkpoint::open(&path).unwrap();
+        assert_eq!(checkpoint.len(), 2);
+        assert!(checkpoint.contains(Path::new("/b\nc.plist"), 20));
+        checkpoint.record(Path::new("/d.bookmark"), 30).unwrap();
+
+        let checkpoint = Checkpoint::open(&path).unwrap();
+        assert_eq!(checkpoint.len(), 3);
+        assert!(checkpoint.contains(Path::new("/d.bookmark"), 30));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,340 @@
+//! Enrich application bookmarks with code signing and notarization status
+//!
+//! Bookmarks whose target is an application (`.app` bundle) are checked against the live system with the Security
+//! framework (`SecStaticCode`). Unsigned and ad-hoc signed targets are flagged, an unsigned LoginItem target is one of
+//! the strongest persistence signals. The status check is only available on macOS, `enrich_with` accepts any checker.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::{bookmark::BookmarkData, locale::Localization};
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub enum SigningStatus {
+    /// Target does not exist on this system
+    Missing,
+    /// Target has no code signature
+    Unsigned,
+    /// Target is signed without a signing identity
+    AdHoc,
+    /// Signature does not validate (ex: modified after signing)
+    Invalid,
+    /// Signed with a Developer ID or Apple certificate but not notarized
+    Signed,
+    /// Signed and notarized by Apple
+    Notarized,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct CodeSigningContext {
+    pub path: String,          // Full path to the application
+    pub status: SigningStatus, // Code signing status of the application
+    pub suspicious: bool,      // Unsigned, ad-hoc signed or invalid signature
+}
+
+impl SigningStatus {
+    /// Check if the status should be reviewed during triage
+    pub fn is_suspicious(&self) -> bool {
+        matches!(
+            self,
+            SigningStatus::Unsigned | SigningStatus::AdHoc | SigningStatus::Invalid
+        )
+    }
+
+    /// Get the English description of the status
+    pub fn description(&self) -> String {
+        self.description_localized(&Localization::english())
+    }
+
+    /// Get the description of the status from a message table
+    pub fn description_localized(&self, locale: &Localization) -> String {
+        let key = match self {
+            SigningStatus::Missing => "codesign.missing",
+            SigningStatus::Unsigned => "codesign.unsigned",
+            SigningStatus::AdHoc => "codesign.adhoc",
+            SigningStatus::Invalid => "codesign.invalid",
+            SigningStatus::Signed => "codesign.signed",
+            SigningStatus::Notarized => "codesign.notarized",
+        };
+        locale.message(key)
+    }
+}
+
+/// Check if the bookmark target is an application bundle
+pub fn is_application(bookmark: &BookmarkData) -> bool {
+    bookmark.path.last().is_some_and(|name| {
+        Path::new(name)
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("app"))
+    })
+}
+
+/// Get the code signing status of an application bookmark target with the provided checker. `None` if the target
+/// is not an application
+pub fn enrich_with(
+    bookmark: &BookmarkData,
+    check: fn(&Path) -> SigningStatus,
+) -> Option<CodeSigningContext> {
+    if !is_application(bookmark) {
+        return None;
+    }
+    let path = format!("/{}", bookmark.path.join("/"));
+    let status = if Path::new(&path).exists() {
+        check(Path::new(&path))
+    } else {
+        SigningStatus::Missing
+    };
+    Some(CodeSigningContext {
+        path,
+        status,
+        suspicious: status.is_suspicious(),
+    })
+}
+
+/// Get the code signing status of an application bookmark target on the live system. `None` if the target is not
+/// an application
+#[cfg(target_os = "macos")]
+pub fn enrich(bookmark: &BookmarkData) -> Option<CodeSigningContext> {
+    enrich_with(bookmark, security::signing_status)
+}
+
+/// Get the code signing status of an application on the live system
+#[cfg(target_os = "macos")]
+pub fn signing_status(path: &Path) -> SigningStatus {
+    security::signing_status(path)
+}
+
+#[cfg(target_os = "macos")]
+mod security {
+    use std::{ffi::c_void, os::unix::ffi::OsStrExt, path::Path, ptr};
+
+    use log::warn;
+
+    use super::SigningStatus;
+
+    type CFTypeRef = *const c_void;
+    type OSStatus = i32;
+
+    const NO_ERR: OSStatus = 0;
+    // errSecCSUnsigned
+    const ERR_UNSIGNED: OSStatus = -67062;
+    // errSecCSReqFailed
+    const ERR_REQUIREMENT_FAILED: OSStatus = -67050;
+    const SIGNING_INFORMATION: u32 = 1 << 1;
+    const SIGNATURE_ADHOC: i64 = 0x0002;
+    const NUMBER_SINT64: isize = 4;
+    const STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFURLCreateFromFileSystemRepresentation(
+            allocator: CFTypeRef,
+            buffer: *const u8,
+            length: isize,
+            is_directory: u8,
+        ) -> CFTypeRef;
+        fn CFStringCreateWithBytes(
+            allocator: CFTypeRef,
+            bytes: *const u8,
+            length: isize,
+            encoding: u32,
+            is_external: u8,
+        ) -> CFTypeRef;
+        fn CFDictionaryGetValue(dictionary: CFTypeRef, key: CFTypeRef) -> CFTypeRef;
+        fn CFNumberGetValue(number: CFTypeRef, number_type: isize, value: *mut c_void) -> u8;
+        fn CFRelease(object: CFTypeRef);
+    }
+
+    #[link(name = "Security", kind = "framework")]
+    extern "C" {
+        static kSecCodeInfoFlags: CFTypeRef;
+
+        fn SecStaticCodeCreateWithPath(
+            path: CFTypeRef,
+            flags: u32,
+            static_code: *mut CFTypeRef,
+        ) -> OSStatus;
+        fn SecStaticCodeCheckValidity(
+            static_code: CFTypeRef,
+            flags: u32,
+            requirement: CFTypeRef,
+        ) -> OSStatus;
+        fn SecCodeCopySigningInformation(
+            code: CFTypeRef,
+            flags: u32,
+            information: *mut CFTypeRef,
+        ) -> OSStatus;
+        fn SecRequirementCreateWithString(
+            text: CFTypeRef,
+            flags: u32,
+            requirement: *mut CFTypeRef,
+        ) -> OSStatus;
+    }
+
+    /// Release a Core Foundation object when dropped
+    struct Owned(CFTypeRef);
+
+    impl Drop for Owned {
+        fn drop(&mut self) {
+            if !self.0.is_null() {
+                unsafe { CFRelease(self.0) };
+            }
+        }
+    }
+
+    /// Validate the code signature of an application bundle and check if it is notarized
+    pub(super) fn signing_status(path: &Path) -> SigningStatus {
+        let bytes = path.as_os_str().as_bytes();
+        let url = Owned(unsafe {
+            CFURLCreateFromFileSystemRepresentation(
+                ptr::null(),
+                bytes.as_ptr(),
+                bytes.len() as isize,
+                1,
+            )
+        });
+        if url.0.is_null() {
+            return SigningStatus::Missing;
+        }
+
+        let mut code = ptr::null();
+        let status = unsafe { SecStaticCodeCreateWithPath(url.0, 0, &mut code) };
+        let code = Owned(code);
+        if status != NO_ERR {
+            warn!("Failed to open code at {:?}: {}", path, status);
+            return SigningStatus::Missing;
+        }
+
+        match unsafe { SecStaticCodeCheckValidity(code.0, 0, ptr::null()) } {
+            NO_ERR => {}
+            ERR_UNSIGNED => return SigningStatus::Unsigned,
+            status => {
+                warn!("Invalid code signature for {:?}: {}", path, status);
+                return SigningStatus::Invalid;
+            }
+        }
+
+        if is_adhoc(&code) {
+            return SigningStatus::AdHoc;
+        }
+        match check_requirement(&code, "notarized") {
+            Some(true) => SigningStatus::Notarized,
+            _ => SigningStatus::Signed,
+        }
+    }
+
+    /// Check if the code signature flags include the ad-hoc flag
+    fn is_adhoc(code: &Owned) -> bool {
+        let mut information = ptr::null();
+        let status =
+            unsafe { SecCodeCopySigningInformation(code.0, SIGNING_INFORMATION, &mut information) };
+        let information = Owned(information);
+        if status != NO_ERR || information.0.is_null() {
+            return false;
+        }
+
+        let flags = unsafe { CFDictionaryGetValue(information.0, kSecCodeInfoFlags) };
+        if flags.is_null() {
+            return false;
+        }
+        let mut value: i64 = 0;
+        let converted = unsafe {
+            CFNumberGetValue(flags, NUMBER_SINT64, &mut value as *mut i64 as *mut c_void)
+        };
+        converted != 0 && value & SIGNATURE_ADHOC != 0
+    }
+
+    /// Check the code against a code signing requirement (ex: `notarized`). `None` if the requirement is invalid
+    fn check_requirement(code: &Owned, requirement_text: &str) -> Option<bool> {
+        let text = Owned(unsafe {
+            CFStringCreateWithBytes(
+                ptr::null(),
+                requirement_text.as_ptr(),
+                requirement_text.len() as isize,
+                STRING_ENCODING_UTF8,
+                0,
+            )
+        });
+        if text.0.is_null() {
+            return None;
+        }
+
+        let mut requirement = ptr::null();
+        let status = unsafe { SecRequirementCreateWithString(text.0, 0, &mut requirement) };
+        let requirement = Owned(requirement);
+        if status != NO_ERR {
+            return None;
+        }
+
+        match unsafe { SecStaticCodeCheckValidity(code.0, 0, requirement.0) } {
+            NO_ERR => Some(true),
+            ERR_REQUIREMENT_FAILED => Some(false),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{enrich_with, is_application, SigningStatus};
+    use crate::{locale::Localization, parser::parse_bookmark};
+    use std::{env, fs, path::PathBuf};
+
+    #[test]
+    fn test_enrich_with() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+
+        let mut bookmark = parse_bookmark(&buffer).unwrap();
+        assert!(is_application(&bookmark));
+
+        // Target does not exist on the test system, the checker is not called
+        let context = enrich_with(&bookmark, |_| SigningStatus::Notarized).unwrap();
+        assert_eq!(context.path, "/Applications/Syncthing.app");
+        assert_eq!(context.status, SigningStatus::Missing);
+        assert!(!context.suspicious);
+
+        let app = env::temp_dir().join("macos_bookmarks_codesign_test.app");
+        fs::create_dir_all(&app).unwrap();
+        bookmark.path = app
+            .to_str()
+            .unwrap()
+            .split('/')
+            .filter(|component| !component.is_empty())
+            .map(String::from)
+            .collect();
+        let context = enrich_with(&bookmark, |_| SigningStatus::Unsigned).unwrap();
+        fs::remove_dir(&app).unwrap();
+        assert_eq!(context.status, SigningStatus::Unsigned);
+        assert!(context.suspicious);
+
+        bookmark.path.push(String::from("Contents"));
+        assert!(!is_application(&bookmark));
+        assert!(enrich_with(&bookmark, |_| SigningStatus::Unsigned).is_none());
+    }
+
+    #[test]
+    fn test_signing_status() {
+        assert!(SigningStatus::Unsigned.is_suspicious());
+        assert!(SigningStatus::AdHoc.is_suspicious());
+        assert!(!SigningStatus::Notarized.is_suspicious());
+        assert!(!SigningStatus::Missing.is_suspicious());
+
+        assert_eq!(
+            SigningStatus::Unsigned.description(),
+            "Target has no code signature"
+        );
+        let locale =
+            Localization::from_json(r#"{"codesign.unsigned": "Cible non signée"}"#).unwrap();
+        assert_eq!(
+            SigningStatus::Unsigned.description_localized(&locale),
+            "Cible non signée"
+        );
+        assert_eq!(
+            SigningStatus::Notarized.description_localized(&locale),
+            "Signed and notarized by Apple"
+        );
+    }
+}
@@ -0,0 +1,271 @@
+//! Check bookmark data against invariants observed in bookmarks created by macOS
+//!
+//! `CFURLCreateBookmarkData` writes the same header (version `0x10040000`, data offset `0x30`), 4 byte aligned records
+//! and array elements with zeroed padding and TOC entries sorted by record type. The reserved header bytes are zeroed in
+//! most bookmarks, but Safari download bookmarks store data in them. The parser accepts data that breaks these rules,
+//! native frameworks may not. Used by `BookmarkBuilder::build_conforming` to refuse nonconforming output.
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::bookmark::BookmarkData;
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ConformanceIssue {
+    pub rule: &'static str, // Name of the broken rule (ex: key_order)
+    pub offset: usize,      // Offset from the start of the bookmark
+    pub message: String,    // Description of the problem
+}
+
+/// Bytes 8-12 of the header in every observed bookmark
+const HEADER_VERSION: [u8; 4] = [0, 0, 4, 16];
+/// Size of the TOC header after the length field (record type, flags, level, next TOC offset, count)
+const TOC_HEADER_SIZE: usize = 16;
+/// Size of each TOC entry (record type, record offset, reserved)
+const TOC_ENTRY_SIZE: usize = 12;
+
+/// Check bookmark data against the invariants of bookmarks created by macOS. Empty if the data conforms
+pub fn check_conformance(data: &[u8]) -> Vec<ConformanceIssue> {
+    let mut issues = Vec::new();
+    let mut issue = |rule, offset, message: String| {
+        issues.push(ConformanceIssue {
+            rule,
+            offset,
+            message,
+        })
+    };
+    let header_size = BookmarkData::HEADER_SIZE as usize;
+
+    if !data.starts_with(BookmarkData::BOOKMARK_SIGNATURE) {
+        issue("signature", 0, String::from("Missing book signature"));
+    }
+    if data.len() < header_size + 4 {
+        issue("length", 0, format!("Only {} bytes of data", data.len()));
+        return issues;
+    }
+    match read_u32(data, 4) {
+        Some(length) if length as usize == data.len() => {}
+        length => issue(
+            "length",
+            4,
+            format!(
+                "Header length {length:?} does not match {} bytes of data",
+                data.len()
+            ),
+        ),
+    }
+    if data.get(8..12) != Some(&HEADER_VERSION[..]) {
+        issue("version", 8, String::from("Unexpected header version"));
+    }
+    if read_u32(data, 12) != Some(BookmarkData::HEADER_SIZE) {
+        issue("header_offset", 12, String::from("Data offset is not 0x30"));
+    }
+    if data[16..header_size].iter().any(|byte| *byte != 0) {
+        issue(
+            "reserved",
+            16,
+            String::from("Reserved header bytes are not zero"),
+        );
+    }
+
+    let mut next_toc = read_u32(data, header_size);
+    let mut seen = HashSet::new();
+    while let Some(toc_offset) = next_toc {
+        let toc = header_size + toc_offset as usize;
+        if !seen.insert(toc) {
+            issue("toc", toc, String::from("TOC chain loops"));
+            break;
+        }
+        if !toc.is_multiple_of(4) {
+            issue("alignment", toc, String::from("TOC is not 4 byte aligned"));
+        }
+        let (Some(length), Some(magic), Some(next), Some(count)) = (
+            read_u32(data, toc),
+            read_u32(data, toc + 4),
+            read_u32(data, toc + 12),
+            read_u32(data, toc + 16),
+        ) else {
+            issue("toc", toc, String::from("TOC is outside of the data"));
+            break;
+        };
+        if magic != 0xffff_fffe {
+            issue(
+                "toc_magic",
+                toc + 4,
+                format!("Unexpected TOC magic {magic:#x}"),
+            );
+        }
+        let expected_length = (count as usize)
+            .saturating_add(1)
+            .saturating_mul(TOC_ENTRY_SIZE);
+        if length as usize != expected_length {
+            issue(
+                "toc",
+                toc,
+                format!("TOC length {length} does not match {count} records"),
+            );
+        }
+
+        let entries = toc + 4 + TOC_HEADER_SIZE;
+        let mut previous_type = None;
+        for index in 0..count as usize {
+            let entry = entries.saturating_add(index.saturating_mul(TOC_ENTRY_SIZE));
+            let (Some(record_type), Some(record_offset), Some(reserved)) = (
+                read_u32(data, entry),
+                read_u32(data, entry + 4),
+                read_u32(data, entry + 8),
+            ) else {
+                issue(
+                    "toc",
+                    entry,
+                    String::from("TOC entry is outside of the data"),
+                );
+                break;
+            };
+            if previous_type.is_some_and(|previous| record_type <= previous) {
+                issue(
+                    "key_order",
+                    entry,
+                    format!("Record type {record_type:#x} is not sorted after {previous_type:#x?}"),
+                );
+            }
+            previous_type = Some(record_type);
+            if reserved != 0 {
+                issue(
+                    "reserved",
+                    entry + 8,
+                    String::from("Reserved TOC bytes are not zero"),
+                );
+            }
+            check_record(data, header_size, record_offset, &mut issue);
+        }
+
+        next_toc = Some(next).filter(|next| *next != 0);
+    }
+    issues
+}
+
+/// Check that a record and the elements of an array record are aligned, inside the data and padded with zeros
+fn check_record(
+    data: &[u8],
+    header_size: usize,
+    record_offset: u32,
+    issue: &mut impl FnMut(&'static str, usize, String),
+) {
+    let offset = header_size.saturating_add(record_offset as usize);
+    if !check_layout(data, offset, issue)
+        || read_u32(data, offset.saturating_add(4)) != Some(BookmarkData::ARRAY_TYPE)
+    {
+        return;
+    }
+    let length = read_u32(data, offset).unwrap_or_default() as usize;
+    let Some(elements) =
+        data.get(offset.saturating_add(8)..offset.saturating_add(8).saturating_add(length))
+    else {
+        return;
+    };
+    for element in elements.chunks_exact(4) {
+        let element_offset = u32::from_le_bytes([element[0], element[1], element[2], element[3]]);
+        check_layout(
+            data,
+            header_size.saturating_add(element_offset as usize),
+            issue,
+        );
+    }
+}
+
+/// Check that a single record is aligned, inside the data and padded with zeros. Returns false if the record is
+/// outside of the data
+fn check_layout(
+    data: &[u8],
+    offset: usize,
+    issue: &mut impl FnMut(&'static str, usize, String),
+) -> bool {
+    if !offset.is_multiple_of(4) {
+        issue(
+            "alignment",
+            offset,
+            String::from("Record is not 4 byte aligned"),
+        );
+    }
+    let Some(length) = read_u32(data, offset) else {
+        issue(
+            "record",
+            offset,
+            String::from("Record is outside of the data"),
+        );
+        return false;
+    };
+    let end = offset.saturating_add(8).saturating_add(length as usize);
+    let padded_end = end.saturating_add(3) / 4 * 4;
+    match data.get(end..padded_end) {
+        Some(padding) if padding.iter().all(|byte| *byte == 0) => true,
+        Some(_) => {
+            issue("padding", end, String::from("Record padding is not zero"));
+            true
+        }
+        None => {
+            issue(
+                "record",
+                offset,
+                String::from("Record is outside of the data"),
+            );
+            false
+        }
+    }
+}
+
+/// Read a little endian u32 at an offset. `None` if the offset is outside of the data
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_conformance;
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn test_check_conformance() {
+        for (sample, expected) in [
+            // Safari stores data in the reserved header bytes
+            ("downloads.bookmark", &["reserved"][..]),
+            ("loginitem.bookmark", &[]),
+            // Written by the mac_alias Python library instead of macOS, the TOC length does not match the records
+            ("macAlias.bookmark", &["toc"]),
+            ("poisonapple.bookmark", &[]),
+            ("systemevents.bookmark", &[]),
+            ("ventura.bookmark", &[]),
+        ] {
+            let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            test_location.push("tests/test_data");
+            test_location.push(sample);
+            let buffer = fs::read(test_location).unwrap();
+            let rules: Vec<&str> = check_conformance(&buffer)
+                .iter()
+                .map(|issue| issue.rule)
+                .collect();
+            assert_eq!(rules, expected, "{sample}");
+        }
+    }
+
+    #[test]
+    fn test_check_conformance_issues() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let mut buffer = fs::read(test_location).unwrap();
+
+        buffer[12] = 0x40;
+        buffer[20] = 1;
+        let rules: Vec<&str> = check_conformance(&buffer)
+            .iter()
+            .map(|issue| issue.rule)
+            .collect();
+        assert_eq!(rules, ["header_offset", "reserved"]);
+
+        assert_eq!(check_conformance(b"book")[0].rule, "length");
+        assert_eq!(check_conformance(&buffer[..100])[0].rule, "length");
+    }
+}
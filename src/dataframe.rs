@@ -0,0 +1,229 @@
+//! Convert parsed bookmarks into a Polars DataFrame
+//!
+//! Each bookmark becomes one row with typed columns, array fields are stored as List columns.
+
+use polars::prelude::{Column, DataFrame, NamedFrom, PolarsResult, Series};
+
+use crate::bookmark::BookmarkData;
+
+/// Convert parsed bookmarks into a DataFrame with one row per bookmark
+pub fn to_dataframe(bookmarks: &[BookmarkData]) -> PolarsResult<DataFrame> {
+    let path: Vec<Series> = bookmarks
+        .iter()
+        .map(|bookmark| Series::new("".into(), &bookmark.path))
+        .collect();
+    let cnid_path: Vec<Series> = bookmarks
+        .iter()
+        .map(|bookmark| {
+            let cnids: Vec<i64> = bookmark.cnid_path.iter().map(|cnid| cnid.0).collect();
+            Series::new("".into(), cnids)
+        })
+        .collect();
+    let volume_flag: Vec<Series> = bookmarks
+        .iter()
+        .map(|bookmark| Series::new("".into(), &bookmark.volume_flag))
+        .collect();
+    let target_flags: Vec<Series> = bookmarks
+        .iter()
+        .map(|bookmark| Series::new("".into(), &bookmark.target_flags))
+        .collect();
+
+    let columns = vec![
+        Column::new("path".into(), path),
+        Column::new("cnid_path".into(), cnid_path),
+        Column::new(
+            "creation".into(),
+            bookmarks.iter().map(|b| b.creation.0).collect::<Vec<f64>>(),
+        ),
+        Column::new(
+            "volume_path".into(),
+            bookmarks
+                .iter()
+                .map(|b| b.volume_path.as_str())
+                .collect::<Vec<&str>>(),
+        ),
+        Column::new(
+            "volume_url".into(),
+            bookmarks
+                .iter()
+                .map(|b| b.volume_url.as_str())
+                .collect::<Vec<&str>>(),
+        ),
+        Column::new(
+            "volume_name".into(),
+            bookmarks
+                .iter()
+                .map(|b| b.volume_name.as_str())
+                .collect::<Vec<&str>>(),
+        ),
+        Column::new(
+            "volume_uuid".into(),
+            bookmarks
+                .iter()
+                .map(|b| b.volume_uuid.as_str())
+                .collect::<Vec<&str>>(),
+        ),
+        Column::new(
+            "volume_size".into(),
+            bookmarks
+                .iter()
+                .map(|b| b.volume_size)
+                .collect::<Vec<i64>>(),
+        ),
+        Column::new(
+            "volume_creation".into(),
+            bookmarks
+                .iter()
+                .map(|b| b.volume_creation.0)
+                .collect::<Vec<f64>>(),
+        ),
+        Column::new("volume_flag".into(), volume_flag),
+        Column::new(
+            "volume_root".into(),
+            bookmarks
+                .iter()
+                .map(|b| b.volume_root)
+                .collect::<Vec<Option<bool>>>(),
+        ),
+        Column::new(
+            "target_filename".into(),
+            bookmarks
+                .iter()
+                .map(|b| b.target_filename.as_deref())
+                .collect::<Vec<Option<&str>>>(),
+        ),
+        Column::new(
+            "toc_path".into(),
+            bookmarks
+                .iter()
+                .map(|b| b.toc_path.as_deref())
+                .collect::<Vec<Option<&str>>>(),
+        ),
+        Column::new(
+            "volume_mount_point".into(),
+            bookmarks
+                .iter()
+                .map(|b| b.volume_mount_point.as_deref())
+                .collect::<Vec<Option<&str>>>(),
+        ),
+        Column::new(
+            "localized_name".into(),
+            bookmarks
+                .iter()
+                .map(|b| b.localized_name.as_deref())
+                .collect::<Vec<Option<&str>>>(),
+        ),
+        Column::new(
+            "display_name".into(),
+            bookmarks
+                .iter()
+                .map(|b| b.display_name.as_deref())
+                .collect::<Vec<Option<&str>>>(),
+        ),
+        Column::new(
+            "security_extension_rw".into(),
+            bookmarks
+                .iter()
+                .map(|b| b.security_extension_rw.as_deref())
+                .collect::<Vec<Option<&str>>>(),
+        ),
+        Column::new(
+            "security_extension_ro".into(),
+            bookmarks
+                .iter()
+                .map(|b| b.security_extension_ro.as_deref())
+                .collect::<Vec<Option<&str>>>(),
+        ),
+        Column::new("target_flags".into(), target_flags),
+        Column::new(
+            "username".into(),
+            bookmarks
+                .iter()
+                .map(|b| b.username.as_deref())
+                .collect::<Vec<Option<&str>>>(),
+        ),
+        Column::new(
+            "folder_index".into(),
+            bookmarks
+                .iter()
+                .map(|b| b.folder_index)
+                .collect::<Vec<i64>>(),
+        ),
+        Column::new(
+            "uid".into(),
+            bookmarks
+                .iter()
+                .map(|b| b.uid)
+                .collect::<Vec<Option<i32>>>(),
+        ),
+        Column::new(
+            "creation_options".into(),
+            bookmarks
+                .iter()
+                .map(|b| b.creation_options)
+                .collect::<Vec<Option<i32>>>(),
+        ),
+        Column::new(
+            "is_executable".into(),
+            bookmarks
+                .iter()
+                .map(|b| b.is_executable)
+                .collect::<Vec<Option<bool>>>(),
+        ),
+        Column::new(
+            "file_ref_flag".into(),
+            bookmarks
+                .iter()
+                .map(|b| b.file_ref_flag)
+                .collect::<Vec<Option<bool>>>(),
+        ),
+        Column::new(
+            "has_persistent_ids".into(),
+            bookmarks
+                .iter()
+                .map(|b| b.has_persistent_ids)
+                .collect::<Vec<bool>>(),
+        ),
+    ];
+    DataFrame::new(bookmarks.len(), columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_dataframe;
+    use crate::parser::parse_bookmark;
+    use polars::prelude::DataType;
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn test_to_dataframe() {
+        let mut bookmarks = Vec::new();
+        for file in ["loginitem.bookmark", "poisonapple.bookmark"] {
+            let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            test_location.push("tests/test_data");
+            test_location.push(file);
+            let buffer = fs::read(test_location).unwrap();
+            bookmarks.push(parse_bookmark(&buffer).unwrap());
+        }
+
+        let frame = to_dataframe(&bookmarks).unwrap();
+        assert_eq!(frame.shape(), (2, 26));
+        assert_eq!(
+            frame.column("volume_size").unwrap().dtype(),
+            &DataType::Int64
+        );
+        assert_eq!(
+            frame.column("cnid_path").unwrap().dtype(),
+            &DataType::List(Box::new(DataType::Int64))
+        );
+        assert_eq!(
+            frame
+                .column("localized_name")
+                .unwrap()
+                .str()
+                .unwrap()
+                .get(1),
+            Some("testing")
+        );
+    }
+}
@@ -0,0 +1,240 @@
+//! Parse Dock tiles from `com.apple.dock.plist`
+//!
+//! Tiles are listed under `persistent-apps`, `persistent-others` and `recent-apps`. Each tile has a `tile-data`
+//! dictionary with the label shown in the Dock (`file-label`) and the target stored as bookmark data (`book`) or in
+//! the `file-data` dictionary (bookmark or classic alias record). A label that does not match the target name can
+//! indicate a tile pointing somewhere other than the app it claims to be.
+
+use std::{fs, io::Cursor, path::Path};
+
+use log::{error, warn};
+use plist::{Dictionary, Value};
+use serde::Serialize;
+
+use crate::{
+    alias::{parse_alias, AliasData},
+    bookmark::BookmarkData,
+    error::BookmarkError,
+    parser::parse_bookmark,
+};
+
+#[derive(Debug, Serialize)]
+pub struct DockItem {
+    pub section: String,                   // Dock section (ex: persistent-apps)
+    pub label: Option<String>,             // Label shown in the Dock
+    pub bundle_identifier: Option<String>, // Bundle identifier of the target
+    pub url: Option<String>,               // Target URL from file-data
+    pub bookmark: Option<BookmarkData>,    // Target bookmark
+    pub alias: Option<AliasData>,          // Classic alias record of the target
+    pub label_mismatch: bool,              // Label does not match the target
+}
+
+/// Dock sections that contain tiles
+const SECTIONS: [&str; 3] = ["persistent-apps", "persistent-others", "recent-apps"];
+
+/// Parse every tile in a `com.apple.dock.plist` file (XML or binary). Tiles with target data that fails to parse are
+/// kept without a bookmark
+pub fn parse_dock(data: &[u8]) -> Result<Vec<DockItem>, BookmarkError> {
+    let plist = match Value::from_reader(Cursor::new(data)) {
+        Ok(Value::Dictionary(plist)) => plist,
+        Ok(_) => {
+            error!("Dock plist is not a dictionary");
+            return Err(BookmarkError::InvalidPlistData);
+        }
+        Err(err) => {
+            error!("failed to parse Dock plist: {:?}", err);
+            return Err(BookmarkError::InvalidPlistData);
+        }
+    };
+
+    let mut items = Vec::new();
+    for section in SECTIONS {
+        let tiles = plist
+            .get(section)
+            .and_then(Value::as_array)
+            .map_or(&[][..], Vec::as_slice);
+        items.extend(
+            tiles
+                .iter()
+                .filter_map(Value::as_dictionary)
+                .map(|tile| dock_item(section, tile)),
+        );
+    }
+    Ok(items)
+}
+
+/// Read a `com.apple.dock.plist` file and parse every tile
+pub fn parse_dock_file(path: impl AsRef<Path>) -> Result<Vec<DockItem>, BookmarkError> {
+    match fs::read(path.as_ref()) {
+        Ok(data) => parse_dock(&data),
+        Err(err) => {
+            error!("failed to read Dock plist {:?}: {:?}", path.as_ref(), err);
+            Err(BookmarkError::InvalidPlistData)
+        }
+    }
+}
+
+/// Parse a single Dock tile
+fn dock_item(section: &str, tile: &Dictionary) -> DockItem {
+    let tile_data = tile.get("tile-data").and_then(Value::as_dictionary);
+    let file_data = tile_data
+        .and_then(|tile_data| tile_data.get("file-data"))
+        .and_then(Value::as_dictionary);
+    let string = |value: Option<&Value>| value.and_then(Value::as_string).map(str::to_string);
+
+    let mut item = DockItem {
+        section: section.to_string(),
+        label: string(tile_data.and_then(|tile_data| tile_data.get("file-label"))),
+        bundle_identifier: string(
+            tile_data.and_then(|tile_data| tile_data.get("bundle-identifier")),
+        ),
+        url: string(file_data.and_then(|file_data| file_data.get("_CFURLString"))),
+        bookmark: None,
+        alias: None,
+        label_mismatch: false,
+    };
+
+    // Bookmark data in tile-data, then any bookmark or alias blob in file-data
+    let blobs = tile_data
+        .and_then(|tile_data| tile_data.get("book"))
+        .into_iter()
+        .chain(
+            file_data
+                .into_iter()
+                .flat_map(|file_data| file_data.values()),
+        )
+        .filter_map(Value::as_data);
+    for data in blobs {
+        if data.starts_with(BookmarkData::BOOKMARK_SIGNATURE) {
+            match parse_bookmark(data) {
+                Ok(bookmark) => item.bookmark = Some(bookmark),
+                Err(err) => warn!(
+                    "Failed to parse Dock tile {:?} bookmark: {:?}",
+                    item.label, err
+                ),
+            }
+        } else {
+            match parse_alias(data) {
+                Ok(alias) => {
+                    item.bookmark = Some(alias.to_bookmark());
+                    item.alias = Some(alias);
+                }
+                Err(err) => warn!(
+                    "Failed to parse Dock tile {:?} alias: {:?}",
+                    item.label, err
+                ),
+            }
+        }
+        if item.bookmark.is_some() {
+            break;
+        }
+    }
+
+    item.label_mismatch = match (&item.label, &item.bookmark) {
+        (Some(label), Some(bookmark)) => bookmark
+            .path
+            .last()
+            .is_some_and(|name| !label_matches(label, name)),
+        _ => false,
+    };
+    item
+}
+
+/// Compare a Dock label with the target name, ignoring case and the `.app` extension
+fn label_matches(label: &str, name: &str) -> bool {
+    let strip = |value: &str| {
+        let value = value.to_lowercase();
+        value.strip_suffix(".app").unwrap_or(&value).to_string()
+    };
+    strip(label) == strip(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{label_matches, parse_dock, parse_dock_file};
+    use plist::{Dictionary, Value};
+    use std::{fs, path::PathBuf};
+
+    fn tile(label: &str, key: &str, data: Vec<u8>) -> Value {
+        let mut file_data = Dictionary::new();
+        file_data.insert(
+            String::from("_CFURLString"),
+            Value::String(String::from("file:///Applications/Syncthing.app/")),
+        );
+        let mut tile_data = Dictionary::new();
+        tile_data.insert(
+            String::from("file-label"),
+            Value::String(String::from(label)),
+        );
+        if key == "book" {
+            tile_data.insert(String::from(key), Value::Data(data));
+        } else {
+            file_data.insert(String::from(key), Value::Data(data));
+        }
+        tile_data.insert(String::from("file-data"), Value::Dictionary(file_data));
+        let mut tile = Dictionary::new();
+        tile.insert(String::from("tile-data"), Value::Dictionary(tile_data));
+        Value::Dictionary(tile)
+    }
+
+    fn test_dock() -> Vec<u8> {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+
+        let mut plist = Dictionary::new();
+        plist.insert(
+            String::from("persistent-apps"),
+            Value::Array(vec![
+                tile("Syncthing", "book", buffer.clone()),
+                tile("Safari", "_CFURLAliasData", buffer),
+            ]),
+        );
+        plist.insert(
+            String::from("persistent-others"),
+            Value::Array(vec![tile("Downloads", "_CFURLAliasData", vec![0; 10])]),
+        );
+        let mut data = Vec::new();
+        Value::Dictionary(plist).to_writer_xml(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_parse_dock() {
+        let items = parse_dock(&test_dock()).unwrap();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].section, "persistent-apps");
+        assert_eq!(items[0].label.as_deref(), Some("Syncthing"));
+        assert_eq!(
+            items[0].url.as_deref(),
+            Some("file:///Applications/Syncthing.app/")
+        );
+        assert_eq!(
+            items[0].bookmark.as_ref().unwrap().path,
+            ["Applications", "Syncthing.app"]
+        );
+        assert!(!items[0].label_mismatch);
+        assert!(items[1].label_mismatch);
+        assert_eq!(items[2].section, "persistent-others");
+        assert!(items[2].bookmark.is_none());
+        assert!(!items[2].label_mismatch);
+
+        let path = std::env::temp_dir().join("macos_bookmarks_test_dock.plist");
+        fs::write(&path, test_dock()).unwrap();
+        assert_eq!(parse_dock_file(&path).unwrap().len(), 3);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_dock_bad_data() {
+        assert!(parse_dock(b"not a plist").is_err());
+        assert!(parse_dock_file("/does/not/exist.plist").is_err());
+    }
+
+    #[test]
+    fn test_label_matches() {
+        assert!(label_matches("Syncthing", "Syncthing.app"));
+        assert!(label_matches("syncthing", "Syncthing"));
+        assert!(!label_matches("Safari", "Syncthing.app"));
+    }
+}
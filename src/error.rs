@@ -1,18 +1,184 @@
-use std::fmt;
+use std::{fmt, path::PathBuf};
+
+use nom::error::ErrorKind;
 
 #[derive(Debug)]
 pub enum BookmarkError {
     BadHeader,
+    /// Bookmark data could not be parsed (ex: TOC or record offset before the start of the data)
     BadBookmarkData,
+    /// Header signature is not `book` or `alis`
+    WrongSignature(u32),
+    /// Header version is not `00 00 04 10`. Not reported in lenient mode
+    UnsupportedVersion(u32),
+    /// Bookmark data ends before the TOC could be read
+    TruncatedData(ParseFailure),
+    /// TOC or record offset points outside of the bookmark data
+    InvalidOffset(ParseFailure),
+    /// TOC could not be read
+    BadToc(ParseFailure),
+    /// TOC record could not be read
+    BadRecord(RecordContext),
+    /// Bookmark file could not be read or parsed
+    BadFile(FileContext),
+    InvalidSignatureData,
+    InvalidLocalizationData,
+    InvalidPlistData,
+    DuplicateRecord,
+    BundleWriteError,
+    InvalidSqliteData,
+    InvalidAliasData,
+    RejectedPath,
+    NonConforming,
+    StrictViolation,
+    ResourceLimit,
+    InvalidScanRoot,
+    /// Output could not be written
+    OutputWriteError,
+    /// Scan checkpoint file could not be read or written
+    CheckpointError,
+    /// HTTP parse service could not listen on its address
+    ServeError,
+    InvalidMappedFile,
+    ReadError,
+    InvalidBase64Data,
+}
+
+/// Underlying nom error of a failed parse, returned by `BookmarkError::source`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParseFailure {
+    pub kind: ErrorKind, // nom parser that failed
+    pub offset: usize,   // Offset where reading failed from the start of the bookmark
 }
 
-impl std::error::Error for BookmarkError {}
+/// Location of a TOC record that could not be read
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecordContext {
+    pub record_type: u32,       // Record/Key type from the TOC
+    pub data_type: Option<u32>, // Data type of the record (None if the record header is outside of the data)
+    pub offset: u32,            // Offset to the record from the start of the bookmark
+    pub failure: ParseFailure,  // Underlying nom error
+}
+
+/// Bookmark file that could not be read or parsed
+#[derive(Debug)]
+pub struct FileContext {
+    pub path: PathBuf,             // Path to the bookmark file
+    pub error: Box<BookmarkError>, // Read, size or parse error
+}
+
+impl ParseFailure {
+    /// Get the failure from a nom error. `base` starts at `base_offset` in the bookmark, an error input outside of
+    /// `base` is reported at the end of `base`
+    pub(crate) fn from_nom(
+        base: &[u8],
+        base_offset: usize,
+        err: &nom::Err<nom::error::Error<&[u8]>>,
+    ) -> ParseFailure {
+        let (kind, input) = match err {
+            nom::Err::Error(err) | nom::Err::Failure(err) => (err.code, Some(err.input)),
+            nom::Err::Incomplete(_) => (ErrorKind::Eof, None),
+        };
+        let relative = input
+            .and_then(|input| (input.as_ptr() as usize).checked_sub(base.as_ptr() as usize))
+            .filter(|offset| *offset <= base.len())
+            .unwrap_or(base.len());
+        ParseFailure {
+            kind,
+            offset: base_offset.saturating_add(relative),
+        }
+    }
+}
+
+impl std::error::Error for ParseFailure {}
+
+impl fmt::Display for ParseFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} error at offset {:#x}",
+            self.kind.description(),
+            self.offset
+        )
+    }
+}
+
+impl std::error::Error for BookmarkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BookmarkError::TruncatedData(failure)
+            | BookmarkError::InvalidOffset(failure)
+            | BookmarkError::BadToc(failure) => Some(failure),
+            BookmarkError::BadRecord(context) => Some(&context.failure),
+            BookmarkError::BadFile(context) => Some(context.error.as_ref()),
+            _ => None,
+        }
+    }
+}
 
 impl fmt::Display for BookmarkError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             BookmarkError::BadHeader => write!(f, "Incorrect bookmark header"),
             BookmarkError::BadBookmarkData => write!(f, "Failed to parse bookmark data"),
+            BookmarkError::WrongSignature(signature) => {
+                write!(f, "Unexpected bookmark signature {signature:#010x}")
+            }
+            BookmarkError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported bookmark version {version:#x}")
+            }
+            BookmarkError::TruncatedData(failure) => {
+                write!(f, "Bookmark data truncated at offset {:#x}", failure.offset)
+            }
+            BookmarkError::InvalidOffset(failure) => {
+                write!(
+                    f,
+                    "Invalid offset in bookmark TOC at offset {:#x}",
+                    failure.offset
+                )
+            }
+            BookmarkError::BadToc(failure) => {
+                write!(
+                    f,
+                    "Failed to parse bookmark TOC at offset {:#x}",
+                    failure.offset
+                )
+            }
+            BookmarkError::BadRecord(context) => match context.data_type {
+                Some(data_type) => write!(
+                    f,
+                    "Failed to parse Record Type {:#x} (Data type {data_type:#x}) at offset {:#x}",
+                    context.record_type, context.offset
+                ),
+                None => write!(
+                    f,
+                    "Failed to parse Record Type {:#x} at offset {:#x}",
+                    context.record_type, context.offset
+                ),
+            },
+            BookmarkError::BadFile(context) => write!(f, "{:?}: {}", context.path, context.error),
+            BookmarkError::InvalidSignatureData => write!(f, "Failed to parse signature data"),
+            BookmarkError::InvalidLocalizationData => {
+                write!(f, "Failed to parse localization data")
+            }
+            BookmarkError::InvalidPlistData => write!(f, "Failed to parse plist data"),
+            BookmarkError::DuplicateRecord => write!(f, "Bookmark contains duplicate records"),
+            BookmarkError::BundleWriteError => write!(f, "Failed to write evidence bundle"),
+            BookmarkError::InvalidSqliteData => write!(f, "Failed to read SQLite database"),
+            BookmarkError::InvalidAliasData => write!(f, "Failed to parse alias data"),
+            BookmarkError::RejectedPath => write!(f, "Bookmark path rejected by path policy"),
+            BookmarkError::NonConforming => {
+                write!(f, "Bookmark does not conform to macOS bookmark invariants")
+            }
+            BookmarkError::StrictViolation => write!(f, "Bookmark failed strict validation"),
+            BookmarkError::ResourceLimit => write!(f, "Bookmark exceeds the resource limits"),
+            BookmarkError::InvalidScanRoot => write!(f, "Failed to read scan directory"),
+            BookmarkError::OutputWriteError => write!(f, "Failed to write output"),
+            BookmarkError::CheckpointError => write!(f, "Failed to read or write checkpoint file"),
+            BookmarkError::ServeError => write!(f, "Failed to start parse service"),
+            BookmarkError::InvalidMappedFile => write!(f, "Failed to memory map file"),
+            BookmarkError::ReadError => write!(f, "Failed to read file"),
+            BookmarkError::InvalidBase64Data => write!(f, "Failed to decode base64 data"),
         }
     }
 }
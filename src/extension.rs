@@ -0,0 +1,56 @@
+//! Access the target path stored in sandbox security extensions
+//!
+//! Security extensions end with the target path lowercased, while TARGET_PATH preserves the original case.
+//! A path that does not match the target indicates the bookmark was edited after it was created.
+
+use crate::{bookmark::BookmarkData, normalize::nfc};
+
+impl BookmarkData {
+    /// Get the target path with its original case (ex: `/Applications/Syncthing.app`)
+    pub fn target_path(&self) -> String {
+        format!("/{}", self.path.join("/"))
+    }
+
+    /// Get the (lowercased) path stored in the RW security extension, or the RO extension if there is no RW extension
+    pub fn extension_path(&self) -> Option<&str> {
+        [&self.security_extension_rw, &self.security_extension_ro]
+            .into_iter()
+            .flatten()
+            .find_map(|extension| extension.trim_end_matches('\0').rsplit(';').next())
+    }
+
+    /// Check if the security extension path matches the target path, ignoring case. `None` if there is no extension
+    pub fn extension_path_matches_target(&self) -> Option<bool> {
+        let extension_path = self.extension_path()?;
+        Some(nfc(extension_path).to_lowercase() == nfc(&self.target_path()).to_lowercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_bookmark;
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn test_extension_path_matches_target() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+        let mut bookmark = parse_bookmark(&buffer).unwrap();
+
+        assert_eq!(bookmark.target_path(), "/Applications/Syncthing.app");
+        assert_eq!(
+            bookmark.extension_path(),
+            Some("/applications/syncthing.app")
+        );
+        assert_eq!(bookmark.extension_path_matches_target(), Some(true));
+
+        // Target path edited after the bookmark was created
+        bookmark.path[1] = String::from("Evil.app");
+        assert_eq!(bookmark.extension_path_matches_target(), Some(false));
+
+        bookmark.security_extension_rw = None;
+        assert_eq!(bookmark.extension_path(), None);
+        assert_eq!(bookmark.extension_path_matches_target(), None);
+    }
+}
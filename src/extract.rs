@@ -0,0 +1,85 @@
+//! Locate the original bytes of bookmarks embedded in containers
+//!
+//! Container parsers report the byte range of each bookmark so the exact blob can be re-extracted and hashed. A
+//! bookmark is only found in the container file if it is stored verbatim, bookmarks encoded by the container (ex:
+//! base64 `<data>` in XML plists) or split across SQLite overflow pages have no file range.
+
+use serde::Serialize;
+
+use crate::bookmark::BookmarkData;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub struct ByteRange {
+    pub start: usize, // Offset to the first byte
+    pub end: usize,   // Offset past the last byte
+}
+
+impl ByteRange {
+    /// Number of bytes in the range
+    pub fn len(&self) -> usize {
+        self.end.saturating_sub(self.start)
+    }
+
+    /// Check if the range is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the bytes in the range. `None` if the range is outside of the data
+    pub fn slice<'a>(&self, data: &'a [u8]) -> Option<&'a [u8]> {
+        data.get(self.start..self.end)
+    }
+}
+
+/// Get the byte range of the bookmark starting at an offset using the total size in the bookmark header. The range
+/// is limited to the end of the data. `None` if there is no bookmark header at the offset
+pub fn bookmark_range(data: &[u8], offset: usize) -> Option<ByteRange> {
+    let (_, header) = BookmarkData::parse_bookmark_header(data.get(offset..)?).ok()?;
+    if !data[offset..].starts_with(BookmarkData::BOOKMARK_SIGNATURE) {
+        return None;
+    }
+    let end = offset
+        .saturating_add(header.bookmark_data_length as usize)
+        .min(data.len());
+    Some(ByteRange { start: offset, end })
+}
+
+/// Find the first occurrence of a blob in container data. `None` if the blob is not stored verbatim
+pub fn locate_blob(container: &[u8], blob: &[u8]) -> Option<ByteRange> {
+    if blob.is_empty() {
+        return None;
+    }
+    let start = container
+        .windows(blob.len())
+        .position(|window| window == blob)?;
+    Some(ByteRange {
+        start,
+        end: start + blob.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bookmark_range, locate_blob, ByteRange};
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn test_bookmark_range() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+
+        let mut container = b"header".to_vec();
+        container.extend_from_slice(&buffer);
+        container.extend_from_slice(b"trailer");
+
+        let range = bookmark_range(&container, 6).unwrap();
+        assert_eq!(range, ByteRange { start: 6, end: 762 });
+        assert_eq!(range.slice(&container).unwrap(), buffer);
+        assert_eq!(locate_blob(&container, &buffer), Some(range));
+
+        assert!(bookmark_range(&container, 0).is_none());
+        assert!(bookmark_range(&container, 1000).is_none());
+        assert!(locate_blob(&container, b"missing").is_none());
+    }
+}
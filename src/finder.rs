@@ -0,0 +1,179 @@
+//! Parse Finder recent folders from `com.apple.finder.plist`
+//!
+//! `FXRecentFolders` lists the folders shown in Go -> Recent Folders, every entry has a `name` and the folder as
+//! bookmark data (`file-bookmark`). `RecentMoveAndCopyDestinations` lists recent Move To / Copy To destinations,
+//! stored as bookmark entries like `FXRecentFolders` or as plain URL strings.
+
+use std::{fs, io::Cursor, path::Path};
+
+use log::{error, warn};
+use plist::{Dictionary, Value};
+use serde::Serialize;
+
+use crate::{bookmark::BookmarkData, error::BookmarkError, parser::parse_bookmark};
+
+#[derive(Debug, Serialize)]
+pub struct FinderFolder {
+    pub list: String,                   // Finder list containing the folder
+    pub name: Option<String>,           // Folder name
+    pub url: Option<String>,            // Folder URL (plain URL entries)
+    pub bookmark: Option<BookmarkData>, // Parsed folder bookmark
+}
+
+/// Finder lists that contain recent folders
+const LISTS: [&str; 2] = ["FXRecentFolders", "RecentMoveAndCopyDestinations"];
+
+/// Keys that can hold the folder bookmark
+const BOOKMARK_KEYS: [&str; 2] = ["file-bookmark", "bookmark"];
+
+/// Parse every recent folder in a `com.apple.finder.plist` file (XML or binary). Folders with bookmark data that fails
+/// to parse are kept without a bookmark
+pub fn parse_finder_recents(data: &[u8]) -> Result<Vec<FinderFolder>, BookmarkError> {
+    let plist = match Value::from_reader(Cursor::new(data)) {
+        Ok(Value::Dictionary(plist)) => plist,
+        Ok(_) => {
+            error!("Finder plist is not a dictionary");
+            return Err(BookmarkError::InvalidPlistData);
+        }
+        Err(err) => {
+            error!("failed to parse Finder plist: {:?}", err);
+            return Err(BookmarkError::InvalidPlistData);
+        }
+    };
+
+    let mut folders = Vec::new();
+    for list in LISTS {
+        let entries = plist
+            .get(list)
+            .and_then(Value::as_array)
+            .map_or(&[][..], Vec::as_slice);
+        for entry in entries {
+            match entry {
+                Value::Dictionary(entry) => folders.push(finder_folder(list, entry)),
+                Value::String(url) => folders.push(FinderFolder {
+                    list: list.to_string(),
+                    name: None,
+                    url: Some(url.clone()),
+                    bookmark: None,
+                }),
+                _ => {}
+            }
+        }
+    }
+    Ok(folders)
+}
+
+/// Read a `com.apple.finder.plist` file and parse every recent folder
+pub fn parse_finder_recents_file(
+    path: impl AsRef<Path>,
+) -> Result<Vec<FinderFolder>, BookmarkError> {
+    match fs::read(path.as_ref()) {
+        Ok(data) => parse_finder_recents(&data),
+        Err(err) => {
+            error!("failed to read Finder plist {:?}: {:?}", path.as_ref(), err);
+            Err(BookmarkError::InvalidPlistData)
+        }
+    }
+}
+
+/// Parse a single recent folder entry
+fn finder_folder(list: &str, entry: &Dictionary) -> FinderFolder {
+    let name = entry
+        .get("name")
+        .and_then(Value::as_string)
+        .map(str::to_string);
+    let bookmark = match BOOKMARK_KEYS
+        .iter()
+        .find_map(|key| entry.get(key).and_then(Value::as_data))
+        .map(parse_bookmark)
+    {
+        Some(Ok(bookmark)) => Some(bookmark),
+        Some(Err(err)) => {
+            warn!("Failed to parse bookmark for folder {:?}: {:?}", name, err);
+            None
+        }
+        None => None,
+    };
+
+    FinderFolder {
+        list: list.to_string(),
+        url: entry
+            .get("url")
+            .and_then(Value::as_string)
+            .map(str::to_string),
+        name,
+        bookmark,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_finder_recents, parse_finder_recents_file};
+    use plist::{Dictionary, Value};
+    use std::{fs, path::PathBuf};
+
+    fn test_finder() -> Vec<u8> {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+
+        let mut folder = Dictionary::new();
+        folder.insert(
+            String::from("name"),
+            Value::String(String::from("Applications")),
+        );
+        folder.insert(String::from("file-bookmark"), Value::Data(buffer));
+        let mut broken = Dictionary::new();
+        broken.insert(String::from("name"), Value::String(String::from("Old")));
+        broken.insert(
+            String::from("file-bookmark"),
+            Value::Data(b"book corrupted".to_vec()),
+        );
+
+        let mut plist = Dictionary::new();
+        plist.insert(
+            String::from("FXRecentFolders"),
+            Value::Array(vec![Value::Dictionary(folder), Value::Dictionary(broken)]),
+        );
+        plist.insert(
+            String::from("RecentMoveAndCopyDestinations"),
+            Value::Array(vec![Value::String(String::from(
+                "file:///Users/puffycid/Documents/",
+            ))]),
+        );
+        let mut data = Vec::new();
+        Value::Dictionary(plist)
+            .to_writer_binary(&mut data)
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn test_parse_finder_recents() {
+        let folders = parse_finder_recents(&test_finder()).unwrap();
+        assert_eq!(folders.len(), 3);
+        assert_eq!(folders[0].list, "FXRecentFolders");
+        assert_eq!(folders[0].name.as_deref(), Some("Applications"));
+        assert_eq!(
+            folders[0].bookmark.as_ref().unwrap().path,
+            ["Applications", "Syncthing.app"]
+        );
+        assert!(folders[1].bookmark.is_none());
+        assert_eq!(folders[2].list, "RecentMoveAndCopyDestinations");
+        assert_eq!(
+            folders[2].url.as_deref(),
+            Some("file:///Users/puffycid/Documents/")
+        );
+
+        let path = std::env::temp_dir().join("macos_bookmarks_test_finder.plist");
+        fs::write(&path, test_finder()).unwrap();
+        assert_eq!(parse_finder_recents_file(&path).unwrap().len(), 3);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_finder_recents_bad_data() {
+        assert!(parse_finder_recents(b"not a plist").is_err());
+        assert!(parse_finder_recents_file("/does/not/exist.plist").is_err());
+    }
+}
@@ -0,0 +1,592 @@
+//! Serialize parsed bookmarks to Flatbuffers
+//!
+//! Output follows the schema in `schema/bookmark.fbs` and can be read by any Flatbuffers implementation.
+//! `BookmarkTable` reads the serialized data in place without copying.
+
+use flatbuffers::{
+    FlatBufferBuilder, Follow, ForwardsUOffset, InvalidFlatbuffer, Table, Vector, Verifiable,
+    Verifier, WIPOffset,
+};
+
+use crate::{
+    bookmark::BookmarkData,
+    types::{Cnid, CocoaTime},
+};
+
+pub const FILE_IDENTIFIER: &str = "MBKB";
+
+// Bookmark table vtable offsets (4 + 2 * field index)
+const VT_PATH: u16 = 4;
+const VT_CNID_PATH: u16 = 6;
+const VT_CREATION: u16 = 8;
+const VT_VOLUME_PATH: u16 = 10;
+const VT_VOLUME_URL: u16 = 12;
+const VT_VOLUME_NAME: u16 = 14;
+const VT_VOLUME_UUID: u16 = 16;
+const VT_VOLUME_SIZE: u16 = 18;
+const VT_VOLUME_CREATION: u16 = 20;
+const VT_VOLUME_FLAG: u16 = 22;
+const VT_VOLUME_ROOT: u16 = 24;
+const VT_LOCALIZED_NAME: u16 = 26;
+const VT_SECURITY_EXTENSION_RW: u16 = 28;
+const VT_SECURITY_EXTENSION_RO: u16 = 30;
+const VT_TARGET_FLAGS: u16 = 32;
+const VT_USERNAME: u16 = 34;
+const VT_FOLDER_INDEX: u16 = 36;
+const VT_UID: u16 = 38;
+const VT_CREATION_OPTIONS: u16 = 40;
+const VT_IS_EXECUTABLE: u16 = 42;
+const VT_FILE_REF_FLAG: u16 = 44;
+const VT_NESTED_BOOKMARKS: u16 = 46;
+const VT_HAS_PERSISTENT_IDS: u16 = 48;
+const VT_TARGET_FILENAME: u16 = 50;
+const VT_TOC_PATH: u16 = 52;
+const VT_VOLUME_MOUNT_POINT: u16 = 54;
+const VT_DISPLAY_NAME: u16 = 56;
+const VT_VOLUME_ROOT_VALUE: u16 = 58;
+const VT_UID_VALUE: u16 = 60;
+const VT_CREATION_OPTIONS_VALUE: u16 = 62;
+const VT_IS_EXECUTABLE_VALUE: u16 = 64;
+const VT_FILE_REF_FLAG_VALUE: u16 = 66;
+
+// NestedBookmark table vtable offsets
+const VT_NESTED_RECORD_TYPE: u16 = 4;
+const VT_NESTED_BOOKMARK: u16 = 6;
+
+type StringVector<'a> = Vector<'a, ForwardsUOffset<&'a str>>;
+
+/// Serialize a parsed bookmark to a Flatbuffer
+pub fn to_flatbuffer(bookmark: &BookmarkData) -> Vec<u8> {
+    let mut builder = FlatBufferBuilder::new();
+    let root = write_bookmark(&mut builder, bookmark);
+    builder.finish(root, Some(FILE_IDENTIFIER));
+    builder.finished_data().to_vec()
+}
+
+/// Verify and read a serialized bookmark without copying
+pub fn read_flatbuffer(data: &[u8]) -> Result<BookmarkTable<'_>, InvalidFlatbuffer> {
+    flatbuffers::root::<BookmarkTable>(data)
+}
+
+/// Write a bookmark table (and all of its children) to the builder
+fn write_bookmark<'a>(
+    builder: &mut FlatBufferBuilder<'a>,
+    bookmark: &BookmarkData,
+) -> WIPOffset<BookmarkTable<'a>> {
+    // Child objects must be written before the table that references them
+    let nested: Vec<WIPOffset<NestedBookmarkTable>> = bookmark
+        .nested_bookmarks
+        .iter()
+        .map(|nested| {
+            let child = write_bookmark(builder, &nested.bookmark);
+            let start = builder.start_table();
+            builder.push_slot::<u32>(VT_NESTED_RECORD_TYPE, nested.record_type, 0);
+            builder.push_slot_always(VT_NESTED_BOOKMARK, child);
+            WIPOffset::new(builder.end_table(start).value())
+        })
+        .collect();
+    let nested = builder.create_vector(&nested);
+
+    let path: Vec<WIPOffset<&str>> = bookmark
+        .path
+        .iter()
+        .map(|component| builder.create_string(component))
+        .collect();
+    let path = builder.create_vector(&path);
+    let cnids: Vec<i64> = bookmark.cnid_path.iter().map(|cnid| cnid.0).collect();
+    let cnid_path = builder.create_vector(&cnids);
+    let volume_flag = builder.create_vector(&bookmark.volume_flag);
+    let target_flags = builder.create_vector(&bookmark.target_flags);
+    let volume_path = builder.create_string(&bookmark.volume_path);
+    let volume_url = builder.create_string(&bookmark.volume_url);
+    let volume_name = builder.create_string(&bookmark.volume_name);
+    let volume_uuid = builder.create_string(&bookmark.volume_uuid);
+    // Missing optional fields are left out of the table
+    let localized_name = bookmark
+        .localized_name
+        .as_deref()
+        .map(|value| builder.create_string(value));
+    let target_filename = bookmark
+        .target_filename
+        .as_deref()
+        .map(|value| builder.create_string(value));
+    let volume_mount_point = bookmark
+        .volume_mount_point
+        .as_deref()
+        .map(|value| builder.create_string(value));
+    let display_name = bookmark
+        .display_name
+        .as_deref()
+        .map(|value| builder.create_string(value));
+    let toc_path = bookmark
+        .toc_path
+        .as_deref()
+        .map(|value| builder.create_string(value));
+    let security_extension_rw = bookmark
+        .security_extension_rw
+        .as_deref()
+        .map(|value| builder.create_string(value));
+    let security_extension_ro = bookmark
+        .security_extension_ro
+        .as_deref()
+        .map(|value| builder.create_string(value));
+    let username = bookmark
+        .username
+        .as_deref()
+        .map(|value| builder.create_string(value));
+
+    let start = builder.start_table();
+    builder.push_slot_always(VT_PATH, path);
+    builder.push_slot_always(VT_CNID_PATH, cnid_path);
+    builder.push_slot::<f64>(VT_CREATION, bookmark.creation.0, 0.0);
+    builder.push_slot_always(VT_VOLUME_PATH, volume_path);
+    builder.push_slot_always(VT_VOLUME_URL, volume_url);
+    builder.push_slot_always(VT_VOLUME_NAME, volume_name);
+    builder.push_slot_always(VT_VOLUME_UUID, volume_uuid);
+    builder.push_slot::<i64>(VT_VOLUME_SIZE, bookmark.volume_size, 0);
+    builder.push_slot::<f64>(VT_VOLUME_CREATION, bookmark.volume_creation.0, 0.0);
+    builder.push_slot_always(VT_VOLUME_FLAG, volume_flag);
+    builder.push_slot::<bool>(
+        VT_VOLUME_ROOT,
+        bookmark.volume_root.unwrap_or_default(),
+        false,
+    );
+    if let Some(localized_name) = localized_name {
+        builder.push_slot_always(VT_LOCALIZED_NAME, localized_name);
+    }
+    if let Some(extension) = security_extension_rw {
+        builder.push_slot_always(VT_SECURITY_EXTENSION_RW, extension);
+    }
+    if let Some(extension) = security_extension_ro {
+        builder.push_slot_always(VT_SECURITY_EXTENSION_RO, extension);
+    }
+    builder.push_slot_always(VT_TARGET_FLAGS, target_flags);
+    if let Some(username) = username {
+        builder.push_slot_always(VT_USERNAME, username);
+    }
+    builder.push_slot::<i64>(VT_FOLDER_INDEX, bookmark.folder_index, 0);
+    builder.push_slot::<i32>(VT_UID, bookmark.uid.unwrap_or_default(), 0);
+    builder.push_slot::<i32>(
+        VT_CREATION_OPTIONS,
+        bookmark.creation_options.unwrap_or_default(),
+        0,
+    );
+    builder.push_slot::<bool>(
+        VT_IS_EXECUTABLE,
+        bookmark.is_executable.unwrap_or_default(),
+        false,
+    );
+    builder.push_slot::<bool>(
+        VT_FILE_REF_FLAG,
+        bookmark.file_ref_flag.unwrap_or_default(),
+        false,
+    );
+    builder.push_slot_always(VT_NESTED_BOOKMARKS, nested);
+    builder.push_slot::<bool>(VT_HAS_PERSISTENT_IDS, bookmark.has_persistent_ids, false);
+    if let Some(filename) = target_filename {
+        builder.push_slot_always(VT_TARGET_FILENAME, filename);
+    }
+    if let Some(toc_path) = toc_path {
+        builder.push_slot_always(VT_TOC_PATH, toc_path);
+    }
+    if let Some(mount_point) = volume_mount_point {
+        builder.push_slot_always(VT_VOLUME_MOUNT_POINT, mount_point);
+    }
+    if let Some(display_name) = display_name {
+        builder.push_slot_always(VT_DISPLAY_NAME, display_name);
+    }
+    // Optional scalars are only written if the bookmark contains the record
+    if let Some(volume_root) = bookmark.volume_root {
+        builder.push_slot_always::<bool>(VT_VOLUME_ROOT_VALUE, volume_root);
+    }
+    if let Some(uid) = bookmark.uid {
+        builder.push_slot_always::<i32>(VT_UID_VALUE, uid);
+    }
+    if let Some(options) = bookmark.creation_options {
+        builder.push_slot_always::<i32>(VT_CREATION_OPTIONS_VALUE, options);
+    }
+    if let Some(is_executable) = bookmark.is_executable {
+        builder.push_slot_always::<bool>(VT_IS_EXECUTABLE_VALUE, is_executable);
+    }
+    if let Some(file_ref_flag) = bookmark.file_ref_flag {
+        builder.push_slot_always::<bool>(VT_FILE_REF_FLAG_VALUE, file_ref_flag);
+    }
+    WIPOffset::new(builder.end_table(start).value())
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BookmarkTable<'a> {
+    table: Table<'a>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct NestedBookmarkTable<'a> {
+    table: Table<'a>,
+}
+
+impl<'a> Follow<'a> for BookmarkTable<'a> {
+    type Inner = BookmarkTable<'a>;
+    unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        BookmarkTable {
+            table: unsafe { Table::new(buf, loc) },
+        }
+    }
+}
+
+impl<'a> Follow<'a> for NestedBookmarkTable<'a> {
+    type Inner = NestedBookmarkTable<'a>;
+    unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        NestedBookmarkTable {
+            table: unsafe { Table::new(buf, loc) },
+        }
+    }
+}
+
+impl Verifiable for BookmarkTable<'_> {
+    fn run_verifier(verifier: &mut Verifier, pos: usize) -> Result<(), InvalidFlatbuffer> {
+        verifier
+            .visit_table(pos)?
+            .visit_field::<ForwardsUOffset<StringVector>>("path", VT_PATH, false)?
+            .visit_field::<ForwardsUOffset<Vector<i64>>>("cnid_path", VT_CNID_PATH, false)?
+            .visit_field::<f64>("creation", VT_CREATION, false)?
+            .visit_field::<ForwardsUOffset<&str>>("volume_path", VT_VOLUME_PATH, false)?
+            .visit_field::<ForwardsUOffset<&str>>("volume_url", VT_VOLUME_URL, false)?
+            .visit_field::<ForwardsUOffset<&str>>("volume_name", VT_VOLUME_NAME, false)?
+            .visit_field::<ForwardsUOffset<&str>>("volume_uuid", VT_VOLUME_UUID, false)?
+            .visit_field::<i64>("volume_size", VT_VOLUME_SIZE, false)?
+            .visit_field::<f64>("volume_creation", VT_VOLUME_CREATION, false)?
+            .visit_field::<ForwardsUOffset<Vector<u64>>>("volume_flag", VT_VOLUME_FLAG, false)?
+            .visit_field::<bool>("volume_root", VT_VOLUME_ROOT, false)?
+            .visit_field::<ForwardsUOffset<&str>>("localized_name", VT_LOCALIZED_NAME, false)?
+            .visit_field::<ForwardsUOffset<&str>>(
+                "security_extension_rw",
+                VT_SECURITY_EXTENSION_RW,
+                false,
+            )?
+            .visit_field::<ForwardsUOffset<&str>>(
+                "security_extension_ro",
+                VT_SECURITY_EXTENSION_RO,
+                false,
+            )?
+            .visit_field::<ForwardsUOffset<Vector<u64>>>("target_flags", VT_TARGET_FLAGS, false)?
+            .visit_field::<ForwardsUOffset<&str>>("username", VT_USERNAME, false)?
+            .visit_field::<i64>("folder_index", VT_FOLDER_INDEX, false)?
+            .visit_field::<i32>("uid", VT_UID, false)?
+            .visit_field::<i32>("creation_options", VT_CREATION_OPTIONS, false)?
+            .visit_field::<bool>("is_executable", VT_IS_EXECUTABLE, false)?
+            .visit_field::<bool>("file_ref_flag", VT_FILE_REF_FLAG, false)?
+            .visit_field::<ForwardsUOffset<Vector<ForwardsUOffset<NestedBookmarkTable>>>>(
+                "nested_bookmarks",
+                VT_NESTED_BOOKMARKS,
+                false,
+            )?
+            .visit_field::<bool>("has_persistent_ids", VT_HAS_PERSISTENT_IDS, false)?
+            .visit_field::<ForwardsUOffset<&str>>("target_filename", VT_TARGET_FILENAME, false)?
+            .visit_field::<ForwardsUOffset<&str>>("toc_path", VT_TOC_PATH, false)?
+            .visit_field::<ForwardsUOffset<&str>>(
+                "volume_mount_point",
+                VT_VOLUME_MOUNT_POINT,
+                false,
+            )?
+            .visit_field::<ForwardsUOffset<&str>>("display_name", VT_DISPLAY_NAME, false)?
+            .visit_field::<bool>("volume_root_value", VT_VOLUME_ROOT_VALUE, false)?
+            .visit_field::<i32>("uid_value", VT_UID_VALUE, false)?
+            .visit_field::<i32>("creation_options_value", VT_CREATION_OPTIONS_VALUE, false)?
+            .visit_field::<bool>("is_executable_value", VT_IS_EXECUTABLE_VALUE, false)?
+            .visit_field::<bool>("file_ref_flag_value", VT_FILE_REF_FLAG_VALUE, false)?
+            .finish();
+        Ok(())
+    }
+}
+
+impl Verifiable for NestedBookmarkTable<'_> {
+    fn run_verifier(verifier: &mut Verifier, pos: usize) -> Result<(), InvalidFlatbuffer> {
+        verifier
+            .visit_table(pos)?
+            .visit_field::<u32>("record_type", VT_NESTED_RECORD_TYPE, false)?
+            .visit_field::<ForwardsUOffset<BookmarkTable>>("bookmark", VT_NESTED_BOOKMARK, false)?
+            .finish();
+        Ok(())
+    }
+}
+
+// Safety: tables are only created by `read_flatbuffer`, which verifies every field type first
+impl<'a> BookmarkTable<'a> {
+    fn string(&self, slot: u16) -> &'a str {
+        self.optional_string(slot).unwrap_or_default()
+    }
+
+    fn optional_string(&self, slot: u16) -> Option<&'a str> {
+        unsafe { self.table.get::<ForwardsUOffset<&str>>(slot, None) }
+    }
+
+    /// Read an optional scalar. Data written before the optional field was added only has the field with a default,
+    /// a default value there is treated as missing
+    fn optional_scalar<T: Follow<'a, Inner = T> + 'a>(
+        &self,
+        slot: u16,
+        legacy_slot: u16,
+    ) -> Option<T> {
+        unsafe { self.table.get::<T>(slot, None) }
+            .or_else(|| unsafe { self.table.get::<T>(legacy_slot, None) })
+    }
+
+    pub fn path(&self) -> Vec<&'a str> {
+        unsafe {
+            self.table
+                .get::<ForwardsUOffset<StringVector>>(VT_PATH, None)
+        }
+        .map(|path| path.iter().collect())
+        .unwrap_or_default()
+    }
+
+    pub fn cnid_path(&self) -> Vec<Cnid> {
+        unsafe {
+            self.table
+                .get::<ForwardsUOffset<Vector<i64>>>(VT_CNID_PATH, None)
+        }
+        .map(|cnid| cnid.iter().map(Cnid).collect())
+        .unwrap_or_default()
+    }
+
+    pub fn creation(&self) -> CocoaTime {
+        CocoaTime(unsafe { self.table.get::<f64>(VT_CREATION, Some(0.0)) }.unwrap_or_default())
+    }
+
+    pub fn volume_path(&self) -> &'a str {
+        self.string(VT_VOLUME_PATH)
+    }
+
+    pub fn volume_url(&self) -> &'a str {
+        self.string(VT_VOLUME_URL)
+    }
+
+    pub fn volume_name(&self) -> &'a str {
+        self.string(VT_VOLUME_NAME)
+    }
+
+    pub fn volume_uuid(&self) -> &'a str {
+        self.string(VT_VOLUME_UUID)
+    }
+
+    pub fn volume_size(&self) -> i64 {
+        unsafe { self.table.get::<i64>(VT_VOLUME_SIZE, Some(0)) }.unwrap_or_default()
+    }
+
+    pub fn volume_creation(&self) -> CocoaTime {
+        CocoaTime(
+            unsafe { self.table.get::<f64>(VT_VOLUME_CREATION, Some(0.0)) }.unwrap_or_default(),
+        )
+    }
+
+    pub fn volume_flag(&self) -> Vec<u64> {
+        unsafe {
+            self.table
+                .get::<ForwardsUOffset<Vector<u64>>>(VT_VOLUME_FLAG, None)
+        }
+        .map(|flags| flags.iter().collect())
+        .unwrap_or_default()
+    }
+
+    pub fn volume_root(&self) -> Option<bool> {
+        self.optional_scalar(VT_VOLUME_ROOT_VALUE, VT_VOLUME_ROOT)
+    }
+
+    pub fn localized_name(&self) -> Option<&'a str> {
+        self.optional_string(VT_LOCALIZED_NAME)
+    }
+
+    pub fn target_filename(&self) -> Option<&'a str> {
+        self.optional_string(VT_TARGET_FILENAME)
+    }
+
+    pub fn volume_mount_point(&self) -> Option<&'a str> {
+        self.optional_string(VT_VOLUME_MOUNT_POINT)
+    }
+
+    pub fn display_name(&self) -> Option<&'a str> {
+        self.optional_string(VT_DISPLAY_NAME)
+    }
+
+    pub fn toc_path(&self) -> Option<&'a str> {
+        self.optional_string(VT_TOC_PATH)
+    }
+
+    pub fn security_extension_rw(&self) -> Option<&'a str> {
+        self.optional_string(VT_SECURITY_EXTENSION_RW)
+    }
+
+    pub fn security_extension_ro(&self) -> Option<&'a str> {
+        self.optional_string(VT_SECURITY_EXTENSION_RO)
+    }
+
+    pub fn target_flags(&self) -> Vec<u64> {
+        unsafe {
+            self.table
+                .get::<ForwardsUOffset<Vector<u64>>>(VT_TARGET_FLAGS, None)
+        }
+        .map(|flags| flags.iter().collect())
+        .unwrap_or_default()
+    }
+
+    pub fn username(&self) -> Option<&'a str> {
+        self.optional_string(VT_USERNAME)
+    }
+
+    pub fn folder_index(&self) -> i64 {
+        unsafe { self.table.get::<i64>(VT_FOLDER_INDEX, Some(0)) }.unwrap_or_default()
+    }
+
+    pub fn uid(&self) -> Option<i32> {
+        self.optional_scalar(VT_UID_VALUE, VT_UID)
+    }
+
+    pub fn creation_options(&self) -> Option<i32> {
+        self.optional_scalar(VT_CREATION_OPTIONS_VALUE, VT_CREATION_OPTIONS)
+    }
+
+    pub fn is_executable(&self) -> Option<bool> {
+        self.optional_scalar(VT_IS_EXECUTABLE_VALUE, VT_IS_EXECUTABLE)
+    }
+
+    pub fn file_ref_flag(&self) -> Option<bool> {
+        self.optional_scalar(VT_FILE_REF_FLAG_VALUE, VT_FILE_REF_FLAG)
+    }
+
+    pub fn has_persistent_ids(&self) -> bool {
+        unsafe { self.table.get::<bool>(VT_HAS_PERSISTENT_IDS, Some(false)) }.unwrap_or_default()
+    }
+
+    pub fn nested_bookmarks(&self) -> Vec<NestedBookmarkTable<'a>> {
+        unsafe {
+            self.table
+                .get::<ForwardsUOffset<Vector<ForwardsUOffset<NestedBookmarkTable>>>>(
+                    VT_NESTED_BOOKMARKS,
+                    None,
+                )
+        }
+        .map(|nested| nested.iter().collect())
+        .unwrap_or_default()
+    }
+}
+
+// Safety: tables are only created by `read_flatbuffer`, which verifies every field type first
+impl<'a> NestedBookmarkTable<'a> {
+    pub fn record_type(&self) -> u32 {
+        unsafe { self.table.get::<u32>(VT_NESTED_RECORD_TYPE, Some(0)) }.unwrap_or_default()
+    }
+
+    pub fn bookmark(&self) -> Option<BookmarkTable<'a>> {
+        unsafe {
+            self.table
+                .get::<ForwardsUOffset<BookmarkTable>>(VT_NESTED_BOOKMARK, None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        read_flatbuffer, to_flatbuffer, BookmarkTable, FILE_IDENTIFIER, VT_IS_EXECUTABLE, VT_UID,
+        VT_VOLUME_ROOT,
+    };
+    use crate::parser::parse_bookmark;
+    use flatbuffers::{FlatBufferBuilder, WIPOffset};
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn test_flatbuffer_round_trip() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/systemevents.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+        let bookmark = parse_bookmark(&buffer).unwrap();
+
+        let data = to_flatbuffer(&bookmark);
+        assert!(flatbuffers::buffer_has_identifier(
+            &data,
+            FILE_IDENTIFIER,
+            false
+        ));
+
+        let table = read_flatbuffer(&data).unwrap();
+        assert_eq!(table.path(), bookmark.path);
+        assert_eq!(table.cnid_path(), bookmark.cnid_path);
+        assert_eq!(table.creation(), bookmark.creation);
+        assert_eq!(table.volume_path(), bookmark.volume_path);
+        assert_eq!(table.volume_url(), bookmark.volume_url);
+        assert_eq!(table.volume_name(), bookmark.volume_name);
+        assert_eq!(table.volume_uuid(), bookmark.volume_uuid);
+        assert_eq!(table.volume_size(), bookmark.volume_size);
+        assert_eq!(table.volume_creation(), bookmark.volume_creation);
+        assert_eq!(table.volume_flag(), bookmark.volume_flag);
+        assert_eq!(table.volume_root(), bookmark.volume_root);
+        assert_eq!(table.localized_name(), bookmark.localized_name.as_deref());
+        assert_eq!(table.target_filename(), bookmark.target_filename.as_deref());
+        assert_eq!(table.toc_path(), bookmark.toc_path.as_deref());
+        assert_eq!(
+            table.volume_mount_point(),
+            bookmark.volume_mount_point.as_deref()
+        );
+        assert_eq!(table.display_name(), bookmark.display_name.as_deref());
+        assert_eq!(
+            table.security_extension_rw(),
+            bookmark.security_extension_rw.as_deref()
+        );
+        assert_eq!(
+            table.security_extension_ro(),
+            bookmark.security_extension_ro.as_deref()
+        );
+        assert_eq!(table.target_flags(), bookmark.target_flags);
+        assert_eq!(table.username(), bookmark.username.as_deref());
+        assert_eq!(table.folder_index(), bookmark.folder_index);
+        assert_eq!(table.uid(), bookmark.uid);
+        assert_eq!(table.creation_options(), bookmark.creation_options);
+        assert_eq!(table.is_executable(), bookmark.is_executable);
+        assert_eq!(table.file_ref_flag(), bookmark.file_ref_flag);
+        assert_eq!(table.has_persistent_ids(), bookmark.has_persistent_ids);
+        assert!(table.nested_bookmarks().is_empty());
+    }
+
+    #[test]
+    fn test_flatbuffer_optional_scalars() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+        let mut bookmark = parse_bookmark(&buffer).unwrap();
+        bookmark.uid = Some(0);
+        bookmark.creation_options = None;
+        bookmark.file_ref_flag = Some(false);
+
+        let data = to_flatbuffer(&bookmark);
+        let table = read_flatbuffer(&data).unwrap();
+        assert_eq!(table.uid(), Some(0));
+        assert_eq!(table.creation_options(), None);
+        assert_eq!(table.file_ref_flag(), Some(false));
+        assert_eq!(table.volume_root(), Some(true));
+        // Older readers only see the original fields with their defaults
+        assert_eq!(
+            unsafe { table.table.get::<bool>(VT_VOLUME_ROOT, Some(false)) },
+            Some(true)
+        );
+        assert_eq!(unsafe { table.table.get::<i32>(VT_UID, Some(0)) }, Some(0));
+
+        // Data written before the optional fields were appended
+        let mut builder = FlatBufferBuilder::new();
+        let start = builder.start_table();
+        builder.push_slot::<i32>(VT_UID, 501, 0);
+        builder.push_slot::<bool>(VT_VOLUME_ROOT, true, false);
+        builder.push_slot::<bool>(VT_IS_EXECUTABLE, false, false);
+        let root: WIPOffset<BookmarkTable> = WIPOffset::new(builder.end_table(start).value());
+        builder.finish(root, Some(FILE_IDENTIFIER));
+        let table = read_flatbuffer(builder.finished_data()).unwrap();
+        assert_eq!(table.uid(), Some(501));
+        assert_eq!(table.volume_root(), Some(true));
+        assert_eq!(table.is_executable(), None);
+        assert_eq!(table.creation_options(), None);
+    }
+
+    #[test]
+    fn test_read_bad_flatbuffer() {
+        assert!(read_flatbuffer(&[1, 2, 3]).is_err());
+    }
+}
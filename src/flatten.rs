@@ -0,0 +1,216 @@
+//! Flatten bookmark data into a single level key/value map
+//!
+//! Keys are dotted paths (`volume.uuid`, `target.path.0`), list values get one key per entry.
+//! Nested bookmarks are flattened under `nested.<index>`.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::{bookmark::BookmarkData, types::TimestampFormat, value::BookmarkValue};
+
+impl BookmarkData {
+    /// Flatten the bookmark into dotted keys and string values. Timestamps are the raw seconds since the Cocoa epoch
+    pub fn to_flat_map(&self) -> BTreeMap<String, String> {
+        self.to_flat_map_with(TimestampFormat::Raw)
+    }
+
+    /// Flatten the bookmark into dotted keys and string values, writing timestamps in the provided format
+    pub fn to_flat_map_with(&self, timestamps: TimestampFormat) -> BTreeMap<String, String> {
+        let mut map = BTreeMap::new();
+        self.flatten_into("", &mut map, timestamps);
+        map
+    }
+
+    /// Serialize the bookmark to JSON, writing timestamps (including nested bookmarks) in the provided format
+    pub fn to_json_value(&self, timestamps: TimestampFormat) -> Value {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        self.timestamps_into(&mut value, timestamps);
+        value
+    }
+
+    /// Replace the serialized timestamps of the bookmark and its nested bookmarks
+    fn timestamps_into(&self, value: &mut Value, timestamps: TimestampFormat) {
+        let Value::Object(map) = value else {
+            return;
+        };
+        for (key, time) in [
+            ("creation", self.creation),
+            ("volume_creation", self.volume_creation),
+        ] {
+            map.extend(timestamps.json_entries(key, time));
+        }
+        if let Some(Value::Array(nested)) = map.get_mut("nested_bookmarks") {
+            for (entry, nested) in nested.iter_mut().zip(&self.nested_bookmarks) {
+                if let Some(bookmark) = entry.get_mut("bookmark") {
+                    nested.bookmark.timestamps_into(bookmark, timestamps);
+                }
+            }
+        }
+    }
+
+    /// Insert every field into the map with the provided key prefix
+    fn flatten_into(
+        &self,
+        prefix: &str,
+        map: &mut BTreeMap<String, String>,
+        timestamps: TimestampFormat,
+    ) {
+        let mut insert = |key: &str, value: String| {
+            map.insert(format!("{prefix}{key}"), value);
+        };
+
+        for (index, component) in self.path.iter().enumerate() {
+            insert(&format!("target.path.{index}"), component.clone());
+        }
+        for (index, cnid) in self.cnid_path.iter().enumerate() {
+            insert(&format!("target.cnid_path.{index}"), cnid.to_string());
+        }
+        for (index, flag) in self.target_flags.iter().enumerate() {
+            insert(&format!("target.flags.{index}"), flag.to_string());
+        }
+        for (key, value) in timestamps.entries("target.creation", self.creation) {
+            insert(&key, value);
+        }
+        // Optional fields are only inserted if the bookmark contains the record
+        if let Some(toc_path) = &self.toc_path {
+            insert("target.toc_path", toc_path.clone());
+        }
+        if let Some(filename) = &self.target_filename {
+            insert("target.filename", filename.clone());
+        }
+        if let Some(localized_name) = &self.localized_name {
+            insert("target.localized_name", localized_name.clone());
+        }
+        if let Some(display_name) = &self.display_name {
+            insert("target.display_name", display_name.clone());
+        }
+        if let Some(extension) = &self.security_extension_rw {
+            insert("target.security_extension_rw", extension.clone());
+        }
+        if let Some(extension) = &self.security_extension_ro {
+            insert("target.security_extension_ro", extension.clone());
+        }
+        if let Some(relative_url) = self.resolve_relative_url() {
+            insert("target.relative_url", relative_url);
+        }
+        for metadata in &self.file_metadata {
+            let key = format!("target.metadata.{:#x}", metadata.record_type);
+            let value = match &metadata.value {
+                BookmarkValue::Date(date) => {
+                    for (key, value) in timestamps.entries(&key, *date) {
+                        insert(&key, value);
+                    }
+                    continue;
+                }
+                BookmarkValue::Int(number) => number.to_string(),
+                BookmarkValue::Bool(flag) => flag.to_string(),
+                BookmarkValue::Uuid(text) | BookmarkValue::String(text) => text.clone(),
+                _ => continue,
+            };
+            insert(&key, value);
+        }
+        insert("target.folder_index", self.folder_index.to_string());
+        if let Some(is_executable) = self.is_executable {
+            insert("target.is_executable", is_executable.to_string());
+        }
+        if let Some(file_ref_flag) = self.file_ref_flag {
+            insert("target.file_ref_flag", file_ref_flag.to_string());
+        }
+
+        insert("volume.path", self.volume_path.clone());
+        insert("volume.url", self.volume_url.clone());
+        if let Some(mount_point) = &self.volume_mount_point {
+            insert("volume.mount_point", mount_point.clone());
+        }
+        insert("volume.name", self.volume_name.clone());
+        insert("volume.uuid", self.volume_uuid.clone());
+        insert("volume.size", self.volume_size.to_string());
+        for (key, value) in timestamps.entries("volume.creation", self.volume_creation) {
+            insert(&key, value);
+        }
+        if let Some(volume_root) = self.volume_root {
+            insert("volume.root", volume_root.to_string());
+        }
+        insert(
+            "volume.has_persistent_ids",
+            self.has_persistent_ids.to_string(),
+        );
+        for (index, flag) in self.volume_flag.iter().enumerate() {
+            insert(&format!("volume.flags.{index}"), flag.to_string());
+        }
+
+        if let Some(username) = &self.username {
+            insert("user.name", username.clone());
+        }
+        if let Some(uid) = self.uid {
+            insert("user.uid", uid.to_string());
+        }
+        if let Some(options) = self.creation_options {
+            insert("creation_options", options.to_string());
+        }
+        insert("record_set", self.record_set.to_string());
+
+        for (index, nested) in self.nested_bookmarks.iter().enumerate() {
+            let nested_prefix = format!("{prefix}nested.{index}.");
+            map.insert(
+                format!("{nested_prefix}record_type"),
+                nested.record_type.to_string(),
+            );
+            nested
+                .bookmark
+                .flatten_into(&nested_prefix, map, timestamps);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parser::parse_bookmark, types::TimestampFormat};
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn test_to_flat_map() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+        let bookmark = parse_bookmark(&buffer).unwrap();
+
+        let map = bookmark.to_flat_map();
+        assert_eq!(map["target.path.0"], "Applications");
+        assert_eq!(map["target.path.1"], "Syncthing.app");
+        assert_eq!(map["target.localized_name"], "Syncthing");
+        assert_eq!(map["volume.uuid"], bookmark.volume_uuid);
+        assert_eq!(map["volume.root"], "true");
+        assert!(!map.contains_key("user.uid"));
+        assert!(!map.contains_key("target.path.2"));
+        assert!(!map.keys().any(|key| key.starts_with("nested.")));
+        assert_eq!(map["target.creation"], "665473989");
+        assert!(!map.contains_key("target.creation_raw"));
+
+        let map = bookmark.to_flat_map_with(TimestampFormat::ConvertedWithRaw);
+        assert_eq!(map["target.creation"], "2022-02-02T05:53:09Z");
+        assert_eq!(map["target.creation_raw"], "665473989");
+        assert_eq!(map["volume.creation_raw"], "241134516");
+    }
+
+    #[test]
+    fn test_to_json_value() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+        let bookmark = parse_bookmark(&buffer).unwrap();
+
+        let json = bookmark.to_json_value(TimestampFormat::Raw);
+        assert_eq!(json, serde_json::to_value(&bookmark).unwrap());
+
+        let json = bookmark.to_json_value(TimestampFormat::ConvertedWithRaw);
+        assert_eq!(json["creation"], "2022-02-02T05:53:09Z");
+        assert_eq!(json["creation_raw"], 665473989.0);
+        assert_eq!(json["volume_creation"], "2008-08-22T21:48:36Z");
+        assert_eq!(json["volume_creation_raw"], 241134516.0);
+
+        let json = bookmark.to_json_value(TimestampFormat::Converted);
+        assert!(json.get("creation_raw").is_none());
+    }
+}
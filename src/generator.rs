@@ -0,0 +1,242 @@
+//! Generate structurally valid but unusual bookmarks for fuzzing
+//!
+//! `ArbitraryBookmark` builds a valid bookmark with `BookmarkBuilder` from arbitrary field values and then applies
+//! arbitrary tweaks to the TOC and record headers (offsets, lengths, data types, record counts, TOC chains). Random
+//! bytes rarely get past the header checks, generated bookmarks exercise the offset handling of the parser instead.
+
+use arbitrary::Arbitrary;
+
+use crate::{
+    bookmark::BookmarkData,
+    builder::BookmarkBuilder,
+    types::{Cnid, CocoaTime},
+};
+
+#[derive(Debug, Clone, Arbitrary)]
+pub struct ArbitraryBookmark {
+    pub path: Vec<String>,                  // Target path components
+    pub cnid_path: Vec<i64>,                // Target CNID path
+    pub creation: Option<f64>,              // Target creation timestamp
+    pub volume_path: Option<String>,        // Volume path
+    pub volume_url: Option<String>,         // Volume URL
+    pub volume_name: Option<String>,        // Volume name
+    pub volume_flags: Option<Vec<u64>>,     // Volume property flags
+    pub localized_name: Option<String>,     // Localized name
+    pub security_extension: Option<String>, // RW security extension
+    pub uid: Option<i32>,                   // Creator UID
+    pub is_executable: Option<bool>,        // Executable flag
+    pub tweaks: Vec<Tweak>,                 // Changes applied to the built bookmark
+}
+
+/// Change applied to a built bookmark. Entry indexes wrap around the TOC record count
+#[derive(Debug, Clone, Copy, Arbitrary)]
+pub enum Tweak {
+    /// Point a TOC entry at another offset
+    RecordOffset { entry: u8, offset: u32 },
+    /// Change the record type of a TOC entry
+    RecordType { entry: u8, record_type: u32 },
+    /// Change the declared data length of a record
+    RecordLength { entry: u8, length: u32 },
+    /// Change the data type of a record
+    RecordDataType { entry: u8, data_type: u32 },
+    /// Copy a TOC entry over another entry (duplicate record types)
+    DuplicateEntry { from: u8, to: u8 },
+    /// Change the TOC record count
+    RecordCount(u32),
+    /// Point the TOC at a next TOC
+    NextToc(u32),
+    /// Change the offset to the TOC
+    TocOffset(u32),
+    /// Drop bytes from the end of the bookmark
+    Truncate(u16),
+}
+
+/// Offset of the TOC entries after the TOC length, magic, level, next TOC offset and record count
+const TOC_ENTRIES: usize = 20;
+/// Size of each TOC entry
+const TOC_ENTRY_SIZE: usize = 12;
+
+impl ArbitraryBookmark {
+    /// Build the bookmark and apply the tweaks
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut builder = BookmarkBuilder::new().target_path(&self.path.join("/"));
+        let cnid_path: Vec<Cnid> = self.cnid_path.iter().map(|cnid| Cnid(*cnid)).collect();
+        builder = builder.cnid_path(&cnid_path);
+        if let Some(creation) = self.creation {
+            builder = builder.creation(CocoaTime(creation));
+        }
+        if let Some(volume_path) = &self.volume_path {
+            builder = builder.volume_path(volume_path);
+        }
+        if let Some(volume_url) = &self.volume_url {
+            builder = builder.volume_url(volume_url);
+        }
+        if let Some(volume_name) = &self.volume_name {
+            builder = builder.volume_name(volume_name);
+        }
+        if let Some(flags) = &self.volume_flags {
+            builder = builder.volume_flags(flags);
+        }
+        if let Some(localized_name) = &self.localized_name {
+            builder = builder.localized_name(localized_name);
+        }
+        if let Some(extension) = &self.security_extension {
+            builder = builder.security_extension_rw(extension);
+        }
+        if let Some(uid) = self.uid {
+            builder = builder.uid(uid);
+        }
+        if let Some(is_executable) = self.is_executable {
+            builder = builder.is_executable(is_executable);
+        }
+
+        let mut data = builder.build();
+        for tweak in &self.tweaks {
+            apply_tweak(&mut data, *tweak);
+        }
+        data
+    }
+}
+
+/// Apply a tweak to bookmark data. Tweaks pointing outside of the data are ignored
+fn apply_tweak(data: &mut Vec<u8>, tweak: Tweak) {
+    let header_size = BookmarkData::HEADER_SIZE as usize;
+    let Some(toc) = read_u32(data, header_size).map(|offset| header_size + offset as usize) else {
+        return;
+    };
+    let count = read_u32(data, toc + 16).unwrap_or_default() as usize;
+    let entry = |index: u8| {
+        if count == 0 {
+            return None;
+        }
+        Some(toc + TOC_ENTRIES + (index as usize % count) * TOC_ENTRY_SIZE)
+    };
+    let record = |data: &[u8], index: u8| {
+        let offset = read_u32(data, entry(index)? + 4)?;
+        Some(header_size + offset as usize)
+    };
+
+    match tweak {
+        Tweak::RecordOffset {
+            entry: index,
+            offset,
+        } => {
+            if let Some(entry) = entry(index) {
+                write_u32(data, entry + 4, offset);
+            }
+        }
+        Tweak::RecordType {
+            entry: index,
+            record_type,
+        } => {
+            if let Some(entry) = entry(index) {
+                write_u32(data, entry, record_type);
+            }
+        }
+        Tweak::RecordLength {
+            entry: index,
+            length,
+        } => {
+            if let Some(record) = record(data, index) {
+                write_u32(data, record, length);
+            }
+        }
+        Tweak::RecordDataType {
+            entry: index,
+            data_type,
+        } => {
+            if let Some(record) = record(data, index) {
+                write_u32(data, record + 4, data_type);
+            }
+        }
+        Tweak::DuplicateEntry { from, to } => {
+            if let (Some(from), Some(to)) = (entry(from), entry(to)) {
+                if from.max(to) + TOC_ENTRY_SIZE <= data.len() {
+                    data.copy_within(from..from + TOC_ENTRY_SIZE, to);
+                }
+            }
+        }
+        Tweak::RecordCount(count) => write_u32(data, toc + 16, count),
+        Tweak::NextToc(offset) => write_u32(data, toc + 12, offset),
+        Tweak::TocOffset(offset) => write_u32(data, header_size, offset),
+        Tweak::Truncate(size) => {
+            let size = data.len().saturating_sub(size as usize).max(header_size);
+            data.truncate(size);
+        }
+    }
+}
+
+/// Read a little endian u32 at an offset. `None` if the offset is outside of the data
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Write a little endian u32 at an offset, ignored if the offset is outside of the data
+fn write_u32(data: &mut [u8], offset: usize, value: u32) {
+    let Some(end) = offset.checked_add(4) else {
+        return;
+    };
+    if let Some(bytes) = data.get_mut(offset..end) {
+        bytes.copy_from_slice(&value.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArbitraryBookmark, Tweak};
+    use crate::parser::{parse_bookmark, BookmarkParser, ParseMode};
+    use arbitrary::{Arbitrary, Unstructured};
+
+    #[test]
+    fn test_generated_bookmarks() {
+        let mut seed = 0x2545f4914f6cdd1du64;
+        let mut bytes = vec![0u8; 4096];
+        for _ in 0..200 {
+            for byte in bytes.iter_mut() {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                *byte = seed as u8;
+            }
+            let mut unstructured = Unstructured::new(&bytes);
+            let bookmark = ArbitraryBookmark::arbitrary(&mut unstructured).unwrap();
+            let data = bookmark.to_bytes();
+            let _ = parse_bookmark(&data);
+            let _ = BookmarkParser::new().mode(ParseMode::Lenient).parse(&data);
+        }
+    }
+
+    #[test]
+    fn test_tweaks() {
+        let mut bookmark = ArbitraryBookmark {
+            path: vec![String::from("Applications"), String::from("Foo.app")],
+            cnid_path: vec![103, 706090],
+            creation: Some(665473989.0),
+            volume_path: Some(String::from("/")),
+            volume_url: None,
+            volume_name: Some(String::from("Macintosh HD")),
+            volume_flags: None,
+            localized_name: None,
+            security_extension: None,
+            uid: Some(501),
+            is_executable: None,
+            tweaks: Vec::new(),
+        };
+        let parsed = parse_bookmark(&bookmark.to_bytes()).unwrap();
+        assert_eq!(parsed.path, ["Applications", "Foo.app"]);
+        assert_eq!(parsed.volume_name, "Macintosh HD");
+
+        bookmark.tweaks = vec![Tweak::DuplicateEntry { from: 0, to: 1 }];
+        let parsed = parse_bookmark(&bookmark.to_bytes()).unwrap();
+        assert_eq!(parsed.record_conflicts().len(), 1);
+
+        bookmark.tweaks = vec![Tweak::RecordOffset {
+            entry: 0,
+            offset: 2,
+        }];
+        assert!(parse_bookmark(&bookmark.to_bytes()).is_err());
+        let lenient = BookmarkParser::new().mode(ParseMode::Lenient);
+        assert!(lenient.parse(&bookmark.to_bytes()).is_ok());
+    }
+}
@@ -0,0 +1,263 @@
+//! Write directory scan results grouped into one file per volume or user
+//!
+//! Fleet sweeps produce too many results for a single JSON document. `write_grouped` splits `scan::scan_directory`
+//! results by source volume (UUID, falling back to the volume name) or by user (the bookmark creator, falling back to
+//! the `Users/<name>` directory the bookmark was found in) and writes each group to `<group>.json` as a JSON array.
+//! `index.json` lists every group with its file and bookmark count. Bookmarks without a volume or user are grouped
+//! under `unknown`.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Component, Path},
+};
+
+use log::error;
+use serde::Serialize;
+
+use crate::{error::BookmarkError, scan::ScannedBookmark};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupBy {
+    /// Source volume UUID or name
+    Volume,
+    /// Bookmark creator or home directory the bookmark was found in
+    User,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct GroupEntry {
+    pub group: String, // Volume or user the results belong to
+    pub file: String,  // Name of the group file in the output directory
+    pub count: usize,  // Number of bookmarks in the group file
+}
+
+/// Group name used when a bookmark has no volume or user
+const UNKNOWN_GROUP: &str = "unknown";
+
+/// Windows device names, reserved with any extension
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Get the group a scan result belongs to
+pub fn group_key(result: &ScannedBookmark, group_by: GroupBy) -> String {
+    let key = match group_by {
+        GroupBy::Volume => [&result.bookmark.volume_uuid, &result.bookmark.volume_name]
+            .into_iter()
+            .find(|value| !value.is_empty())
+            .cloned(),
+        GroupBy::User => result
+            .bookmark
+            .username
+            .clone()
+            .filter(|username| !username.is_empty())
+            .or_else(|| home_user(&result.path)),
+    };
+    key.unwrap_or_else(|| UNKNOWN_GROUP.to_string())
+}
+
+/// Write scan results to one JSON file per group and an `index.json` listing the groups, sorted by group name
+pub fn write_grouped(
+    results: &[ScannedBookmark],
+    group_by: GroupBy,
+    directory: &Path,
+) -> Result<Vec<GroupEntry>, BookmarkError> {
+    if let Err(err) = fs::create_dir_all(directory) {
+        error!(
+            "failed to create output directory {:?}: {:?}",
+            directory, err
+        );
+        return Err(BookmarkError::OutputWriteError);
+    }
+
+    let mut groups: BTreeMap<String, Vec<&ScannedBookmark>> = BTreeMap::new();
+    for result in results {
+        groups
+            .entry(group_key(result, group_by))
+            .or_default()
+            .push(result);
+    }
+
+    let mut entries = Vec::new();
+    let mut files = HashSet::from([String::from("index.json")]);
+    for (group, results) in groups {
+        let file = unique_file_name(&group, "json", &mut files);
+        write_json(&directory.join(&file), &results)?;
+        entries.push(GroupEntry {
+            group,
+            file,
+            count: results.len(),
+        });
+    }
+    write_json(&directory.join("index.json"), &entries)?;
+    Ok(entries)
+}
+
+/// Get the user of a `Users/<name>` home directory in a path
+fn home_user(path: &Path) -> Option<String> {
+    let mut components = path.components();
+    while let Some(component) = components.next() {
+        if component == Component::Normal("Users".as_ref()) {
+            return match components.next() {
+                Some(Component::Normal(name)) => Some(name.to_string_lossy().to_string()),
+                _ => None,
+            };
+        }
+    }
+    None
+}
+
+/// Get a file name for a group or input. Characters that are unsafe in file names are replaced with `_` and Windows
+/// device names get a `_` prefix. Names already in `files` (lowercase, names differing only in case are the same file
+/// on Windows and macOS) get a numbered suffix
+pub(crate) fn unique_file_name(name: &str, extension: &str, files: &mut HashSet<String>) -> String {
+    let stem: String = name
+        .chars()
+        .map(|character| match character {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => character,
+            _ => '_',
+        })
+        .collect();
+    let stem = stem.trim_start_matches('.');
+    let stem = if stem.is_empty() { "_" } else { stem };
+    let device = stem.split('.').next().unwrap_or_default();
+    let stem = if RESERVED_NAMES.contains(&device.to_ascii_uppercase().as_str()) {
+        format!("_{stem}")
+    } else {
+        stem.to_string()
+    };
+
+    let mut file = format!("{stem}.{extension}");
+    let mut suffix = 1;
+    while files.contains(&file.to_lowercase()) {
+        file = format!("{stem}-{suffix}.{extension}");
+        suffix += 1;
+    }
+    files.insert(file.to_lowercase());
+    file
+}
+
+/// Serialize a value to a JSON file
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), BookmarkError> {
+    let file = match File::create(path) {
+        Ok(file) => file,
+        Err(err) => {
+            error!("failed to create output file {:?}: {:?}", path, err);
+            return Err(BookmarkError::OutputWriteError);
+        }
+    };
+    let mut writer = BufWriter::new(file);
+    if let Err(err) = serde_json::to_writer(&mut writer, value) {
+        error!("failed to write output file {:?}: {:?}", path, err);
+        return Err(BookmarkError::OutputWriteError);
+    }
+    if let Err(err) = writer.flush() {
+        error!("failed to write output file {:?}: {:?}", path, err);
+        return Err(BookmarkError::OutputWriteError);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{group_key, unique_file_name, write_grouped, GroupBy};
+    use crate::{
+        bookmark::BookmarkData,
+        scan::{ScanSource, ScannedBookmark},
+    };
+    use std::{collections::HashSet, env, fs, path::PathBuf};
+
+    fn scanned(path: &str, volume_uuid: &str, username: Option<&str>) -> ScannedBookmark {
+        ScannedBookmark {
+            path: PathBuf::from(path),
+            source: ScanSource::Bookmark,
+            location: String::new(),
+            metadata: Default::default(),
+            snapshot: None,
+            bookmark: BookmarkData {
+                volume_uuid: volume_uuid.to_string(),
+                username: username.map(str::to_string),
+                ..BookmarkData::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_group_key() {
+        let result = scanned("/mnt/image/Users/lab/Library/a.bookmark", "", None);
+        assert_eq!(group_key(&result, GroupBy::Volume), "unknown");
+        assert_eq!(group_key(&result, GroupBy::User), "lab");
+
+        let result = scanned("/mnt/image/Users", "0A81F3B1", Some("sur"));
+        assert_eq!(group_key(&result, GroupBy::Volume), "0A81F3B1");
+        assert_eq!(group_key(&result, GroupBy::User), "sur");
+
+        let mut result = scanned("/private/var/a.bookmark", "", None);
+        result.bookmark.volume_name = String::from("Macintosh HD");
+        assert_eq!(group_key(&result, GroupBy::Volume), "Macintosh HD");
+        assert_eq!(group_key(&result, GroupBy::User), "unknown");
+    }
+
+    #[test]
+    fn test_unique_file_name() {
+        let mut files = HashSet::from([String::from("index.json")]);
+        assert_eq!(
+            unique_file_name("Index", "json", &mut files),
+            "Index-1.json"
+        );
+        assert_eq!(unique_file_name("lab", "json", &mut files), "lab.json");
+        assert_eq!(unique_file_name("LAB", "json", &mut files), "LAB-1.json");
+        assert_eq!(unique_file_name("con", "json", &mut files), "_con.json");
+        assert_eq!(
+            unique_file_name("nul.plist", "jsonl", &mut files),
+            "_nul.plist.jsonl"
+        );
+        assert_eq!(
+            unique_file_name("console.plist", "jsonl", &mut files),
+            "console.plist.jsonl"
+        );
+    }
+
+    #[test]
+    fn test_write_grouped() {
+        let results = [
+            scanned("/Users/a/1.bookmark", "0A81F3B1", None),
+            scanned("/Users/b/2.bookmark", "", None),
+            scanned("/Users/a/3.bookmark", "0A81F3B1", None),
+            scanned("/Users/c/4.bookmark", "index", None),
+            scanned("/Users/c/5.bookmark", "../index", None),
+        ];
+        let directory = env::temp_dir().join("macos_bookmarks_group_test");
+        let _ = fs::remove_dir_all(&directory);
+
+        let entries = write_grouped(&results, GroupBy::Volume, &directory).unwrap();
+        let files: Vec<_> = entries.iter().map(|entry| entry.file.as_str()).collect();
+        assert_eq!(
+            files,
+            [
+                "_index.json",
+                "0A81F3B1.json",
+                "index-1.json",
+                "unknown.json"
+            ]
+        );
+        assert_eq!(entries[1].count, 2);
+
+        let group: Vec<serde_json::Value> =
+            serde_json::from_slice(&fs::read(directory.join("0A81F3B1.json")).unwrap()).unwrap();
+        assert_eq!(group.len(), 2);
+        assert_eq!(group[1]["path"], "/Users/a/3.bookmark");
+        let index: Vec<serde_json::Value> =
+            serde_json::from_slice(&fs::read(directory.join("index.json")).unwrap()).unwrap();
+        assert_eq!(index.len(), 4);
+        assert_eq!(index[2]["group"], "index");
+
+        let entries = write_grouped(&results, GroupBy::User, &directory).unwrap();
+        let counts: Vec<_> = entries.iter().map(|entry| entry.count).collect();
+        assert_eq!(counts, [2, 1, 2]);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
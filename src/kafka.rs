@@ -0,0 +1,205 @@
+//! Kafka output sink
+//!
+//! `KafkaSink` streams parse and scan output straight into an event pipeline. Every non-empty line of an output is
+//! produced as one message to the topic, keyed by the input path, so JSON Lines output (one result per line) becomes
+//! one message per result. librdkafka queues the messages and sends them in batches of up to `batch_size` messages,
+//! waiting up to `linger` for a batch to fill. When the local queue is full (`queue_size` messages) `Sink::write`
+//! waits for deliveries to free space instead of dropping results and fails once `send_timeout` passes without space.
+//! `Sink::finish` waits up to `send_timeout` for every queued message and fails if any message could not be delivered
+//! within `delivery_timeout`.
+
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use log::{error, warn};
+use rdkafka::{
+    config::ClientConfig,
+    error::KafkaError,
+    producer::{BaseProducer, BaseRecord, DeliveryResult, Producer, ProducerContext},
+    types::RDKafkaErrorCode,
+    ClientContext,
+};
+
+use crate::{error::BookmarkError, sink::Sink};
+
+#[derive(Debug, Clone)]
+pub struct KafkaOptions {
+    pub brokers: String,                  // Bootstrap servers (ex: `localhost:9092`)
+    pub topic: String,                    // Topic the messages are produced to
+    pub batch_size: usize,                // Maximum messages in one produce request
+    pub linger: Duration,                 // Time to wait for a batch to fill
+    pub queue_size: usize,                // Maximum messages waiting in the producer queue
+    pub send_timeout: Duration,           // Time to wait for queue space and for the final flush
+    pub delivery_timeout: Duration,       // Time a message can wait for delivery before it fails
+    pub config: BTreeMap<String, String>, // Other librdkafka settings (ex: `security.protocol`)
+}
+
+impl KafkaOptions {
+    /// Options for a topic with the default batching and backpressure settings
+    pub fn new(brokers: &str, topic: &str) -> KafkaOptions {
+        KafkaOptions {
+            brokers: brokers.to_string(),
+            topic: topic.to_string(),
+            batch_size: 10000,
+            linger: Duration::from_millis(100),
+            queue_size: 100000,
+            send_timeout: Duration::from_secs(30),
+            delivery_timeout: Duration::from_secs(300),
+            config: BTreeMap::new(),
+        }
+    }
+}
+
+/// Sink producing each output line as a Kafka message
+pub struct KafkaSink {
+    producer: BaseProducer<DeliveryContext>, // Producer queueing and batching the messages
+    topic: String,                           // Topic the messages are produced to
+    send_timeout: Duration,                  // Time to wait for queue space and for the final flush
+}
+
+impl KafkaSink {
+    /// Create a producer for the topic. Brokers are only contacted once messages are sent
+    pub fn new(options: &KafkaOptions) -> Result<KafkaSink, BookmarkError> {
+        let mut config = ClientConfig::new();
+        config
+            .set("bootstrap.servers", &options.brokers)
+            .set("batch.num.messages", options.batch_size.to_string())
+            .set("linger.ms", options.linger.as_millis().to_string())
+            .set(
+                "queue.buffering.max.messages",
+                options.queue_size.to_string(),
+            )
+            .set(
+                "message.timeout.ms",
+                options.delivery_timeout.as_millis().to_string(),
+            );
+        for (key, value) in &options.config {
+            config.set(key, value);
+        }
+
+        match config.create_with_context(DeliveryContext::default()) {
+            Ok(producer) => Ok(KafkaSink {
+                producer,
+                topic: options.topic.clone(),
+                send_timeout: options.send_timeout,
+            }),
+            Err(err) => {
+                error!("failed to create Kafka producer: {:?}", err);
+                Err(BookmarkError::OutputWriteError)
+            }
+        }
+    }
+
+    /// Queue a message, waiting for deliveries to free space while the queue is full
+    fn send(&self, key: &str, payload: &[u8]) -> Result<(), BookmarkError> {
+        let started = Instant::now();
+        let mut record = BaseRecord::to(&self.topic).key(key).payload(payload);
+        loop {
+            match self.producer.send(record) {
+                Ok(()) => return Ok(()),
+                Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), returned))
+                    if started.elapsed() < self.send_timeout =>
+                {
+                    record = returned;
+                    self.producer.poll(Duration::from_millis(100));
+                }
+                Err((err, _)) => {
+                    error!("failed to queue Kafka message for {}: {:?}", key, err);
+                    return Err(BookmarkError::OutputWriteError);
+                }
+            }
+        }
+    }
+}
+
+impl Sink for KafkaSink {
+    fn write(&mut self, input: &Path, output: &[u8]) -> Result<(), BookmarkError> {
+        let key = input.to_string_lossy();
+        for line in output.split(|byte| *byte == b'\n') {
+            if !line.is_empty() {
+                self.send(&key, line)?;
+            }
+        }
+        // Handle the delivery reports of earlier batches
+        self.producer.poll(Duration::ZERO);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), BookmarkError> {
+        if let Err(err) = self.producer.flush(self.send_timeout) {
+            error!("failed to flush Kafka messages: {:?}", err);
+            return Err(BookmarkError::OutputWriteError);
+        }
+        let failed = self.producer.context().failed.load(Ordering::Relaxed);
+        if failed != 0 {
+            error!("{} Kafka messages could not be delivered", failed);
+            return Err(BookmarkError::OutputWriteError);
+        }
+        Ok(())
+    }
+}
+
+/// Producer context counting failed deliveries
+#[derive(Default)]
+struct DeliveryContext {
+    failed: AtomicUsize, // Messages that could not be delivered
+}
+
+impl ClientContext for DeliveryContext {}
+
+impl ProducerContext for DeliveryContext {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, delivery_result: &DeliveryResult<'_>, _delivery_opaque: ()) {
+        if let Err((err, _)) = delivery_result {
+            warn!("Failed to deliver Kafka message: {:?}", err);
+            self.failed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KafkaOptions, KafkaSink};
+    use crate::sink::Sink;
+    use std::{path::Path, time::Duration};
+
+    /// Options for a broker that is not running. Nothing listens on port 1, so messages stay queued until they time out
+    fn test_options() -> KafkaOptions {
+        KafkaOptions::new("127.0.0.1:1", "bookmarks")
+    }
+
+    #[test]
+    fn test_kafka_sink_backpressure() {
+        let mut options = test_options();
+        options.queue_size = 1;
+        options.send_timeout = Duration::from_millis(200);
+        let mut sink = KafkaSink::new(&options).unwrap();
+
+        sink.write(Path::new("/a.bookmark"), b"{}\n").unwrap();
+        // Queue stays full while the broker is unreachable
+        assert!(sink.write(Path::new("/b.bookmark"), b"{}\n").is_err());
+    }
+
+    #[test]
+    fn test_kafka_sink_delivery() {
+        let mut options = test_options();
+        // Delivery timeout must be longer than the linger time
+        options.linger = Duration::from_millis(10);
+        options.delivery_timeout = Duration::from_millis(100);
+        options.send_timeout = Duration::from_secs(10);
+        let mut sink = KafkaSink::new(&options).unwrap();
+
+        sink.write(Path::new("/a.bookmark"), b"{}\n\n{}\n").unwrap();
+        assert!(sink.finish().is_err());
+
+        options
+            .config
+            .insert(String::from("not.a.setting"), String::from("1"));
+        assert!(KafkaSink::new(&options).is_err());
+    }
+}
@@ -0,0 +1,244 @@
+//! Locate bookmarks inside NSKeyedArchiver archives
+//!
+//! Keyed archives store every object in the `$objects` array and reference objects with UIDs. Bookmark data is
+//! referenced directly (ex: `_NSURLBookmarkData`, `bookmark`) or through an NSData wrapper object (`NS.data`). Every
+//! Data object starting with the bookmark signature is parsed, along with the key and class of the object
+//! referencing it when there is one.
+
+use std::{collections::HashSet, fs, io::Cursor, path::Path};
+
+use log::{error, warn};
+use plist::{Dictionary, Value};
+use serde::Serialize;
+
+use crate::{bookmark::BookmarkData, error::BookmarkError, parser::parse_bookmark};
+
+#[derive(Debug, Serialize)]
+pub struct ArchivedBookmark {
+    pub object_index: usize,        // Index of the bookmark data in $objects
+    pub class_name: Option<String>, // Class of the object referencing the data
+    pub key: Option<String>,        // Key referencing the data
+    pub bookmark: BookmarkData,     // Parsed bookmark
+}
+
+/// Keys of the wrapper objects that hold bookmark data
+const DATA_KEYS: [&str; 2] = ["data", "NS.data"];
+
+/// Parse every bookmark in a keyed archive (XML or binary plist). Bookmark data that fails to parse is skipped
+pub fn parse_keyed_archive_bookmarks(data: &[u8]) -> Result<Vec<ArchivedBookmark>, BookmarkError> {
+    let objects = keyed_objects(data)?;
+
+    // Bookmark data referenced by another object first, then any bookmark data left over
+    let mut found = Vec::new();
+    let mut seen = HashSet::new();
+    for object in objects.iter().filter_map(Value::as_dictionary) {
+        let class_name = class_name(&objects, object);
+        for (key, value) in object {
+            let values = match value {
+                Value::Array(values) => values.as_slice(),
+                value => std::slice::from_ref(value),
+            };
+            for index in values
+                .iter()
+                .filter_map(|value| bookmark_index(&objects, value))
+            {
+                if seen.insert(index) {
+                    found.push((index, class_name.clone(), Some(key.clone())));
+                }
+            }
+        }
+    }
+    for (index, object) in objects.iter().enumerate() {
+        if is_bookmark_data(object) && seen.insert(index) {
+            found.push((index, None, None));
+        }
+    }
+
+    let mut bookmarks = Vec::new();
+    for (object_index, class_name, key) in found {
+        let Some(data) = objects.get(object_index).and_then(Value::as_data) else {
+            continue;
+        };
+        match parse_bookmark(data) {
+            Ok(bookmark) => bookmarks.push(ArchivedBookmark {
+                object_index,
+                class_name,
+                key,
+                bookmark,
+            }),
+            Err(err) => warn!(
+                "Failed to parse bookmark at object {}: {:?}",
+                object_index, err
+            ),
+        }
+    }
+    Ok(bookmarks)
+}
+
+/// Read a keyed archive file and parse every bookmark stored in it
+pub fn parse_keyed_archive_bookmarks_file(
+    path: impl AsRef<Path>,
+) -> Result<Vec<ArchivedBookmark>, BookmarkError> {
+    match fs::read(path.as_ref()) {
+        Ok(data) => parse_keyed_archive_bookmarks(&data),
+        Err(err) => {
+            error!(
+                "failed to read keyed archive {:?}: {:?}",
+                path.as_ref(),
+                err
+            );
+            Err(BookmarkError::InvalidPlistData)
+        }
+    }
+}
+
+/// Get the `$objects` array of a keyed archive
+pub(crate) fn keyed_objects(data: &[u8]) -> Result<Vec<Value>, BookmarkError> {
+    let mut archive = match Value::from_reader(Cursor::new(data)) {
+        Ok(Value::Dictionary(archive)) => archive,
+        Ok(_) => {
+            error!("keyed archive is not a dictionary");
+            return Err(BookmarkError::InvalidPlistData);
+        }
+        Err(err) => {
+            error!("failed to parse keyed archive: {:?}", err);
+            return Err(BookmarkError::InvalidPlistData);
+        }
+    };
+    match archive.remove("$objects") {
+        Some(Value::Array(objects)) => Ok(objects),
+        _ => {
+            error!("plist is not a keyed archive");
+            Err(BookmarkError::InvalidPlistData)
+        }
+    }
+}
+
+/// Get the `$objects` index of the bookmark data referenced by a value, following one wrapper object
+pub(crate) fn bookmark_index(objects: &[Value], value: &Value) -> Option<usize> {
+    let index = usize::try_from(value.as_uid()?.get()).ok()?;
+    match objects.get(index)? {
+        object if is_bookmark_data(object) => Some(index),
+        Value::Dictionary(wrapper) => {
+            let data = DATA_KEYS.iter().find_map(|key| wrapper.get(key))?;
+            let index = usize::try_from(data.as_uid()?.get()).ok()?;
+            objects
+                .get(index)
+                .filter(|object| is_bookmark_data(object))
+                .map(|_| index)
+        }
+        _ => None,
+    }
+}
+
+/// Resolve a UID reference to the object in `$objects`, other values are returned as is
+pub(crate) fn resolve<'a>(objects: &'a [Value], value: &'a Value) -> Option<&'a Value> {
+    match value.as_uid() {
+        Some(uid) => objects.get(usize::try_from(uid.get()).ok()?),
+        None => Some(value),
+    }
+}
+
+/// Check if a value is Data starting with the bookmark signature
+pub(crate) fn is_bookmark_data(value: &Value) -> bool {
+    value
+        .as_data()
+        .is_some_and(|data| data.starts_with(BookmarkData::BOOKMARK_SIGNATURE))
+}
+
+/// Get the `$classname` of an archived object
+fn class_name(objects: &[Value], object: &Dictionary) -> Option<String> {
+    object
+        .get("$class")
+        .and_then(|class| resolve(objects, class))
+        .and_then(Value::as_dictionary)
+        .and_then(|class| class.get("$classname"))
+        .and_then(Value::as_string)
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_keyed_archive_bookmarks, parse_keyed_archive_bookmarks_file};
+    use plist::{Dictionary, Uid, Value};
+    use std::{fs, path::PathBuf};
+
+    fn test_archive() -> Vec<u8> {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let login_item = fs::read(test_location).unwrap();
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/downloads.bookmark");
+        let download = fs::read(test_location).unwrap();
+
+        let uid = |index: u64| Value::Uid(Uid::new(index));
+        let mut url = Dictionary::new();
+        url.insert(String::from("_NSURLBookmarkData"), uid(2));
+        url.insert(String::from("$class"), uid(3));
+        let mut class = Dictionary::new();
+        class.insert(
+            String::from("$classname"),
+            Value::String(String::from("NSURL")),
+        );
+        let mut array = Dictionary::new();
+        array.insert(String::from("NS.objects"), Value::Array(vec![uid(6)]));
+        let mut wrapper = Dictionary::new();
+        wrapper.insert(String::from("NS.data"), uid(5));
+
+        let objects = vec![
+            Value::String(String::from("$null")),
+            Value::Dictionary(url),
+            Value::Data(login_item),
+            Value::Dictionary(class),
+            Value::Dictionary(array),
+            Value::Data(download),
+            Value::Dictionary(wrapper),
+            Value::Data(b"book corrupted".to_vec()),
+        ];
+        let mut archive = Dictionary::new();
+        archive.insert(
+            String::from("$archiver"),
+            Value::String(String::from("NSKeyedArchiver")),
+        );
+        archive.insert(String::from("$objects"), Value::Array(objects));
+
+        let mut data = Vec::new();
+        Value::Dictionary(archive)
+            .to_writer_binary(&mut data)
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn test_parse_keyed_archive_bookmarks() {
+        let bookmarks = parse_keyed_archive_bookmarks(&test_archive()).unwrap();
+        assert_eq!(bookmarks.len(), 2);
+        assert_eq!(bookmarks[0].object_index, 2);
+        assert_eq!(bookmarks[0].class_name.as_deref(), Some("NSURL"));
+        assert_eq!(bookmarks[0].key.as_deref(), Some("_NSURLBookmarkData"));
+        assert_eq!(
+            bookmarks[0].bookmark.path,
+            ["Applications", "Syncthing.app"]
+        );
+        assert_eq!(bookmarks[1].object_index, 5);
+        assert_eq!(bookmarks[1].class_name, None);
+        assert_eq!(bookmarks[1].key.as_deref(), Some("NS.objects"));
+
+        let path = std::env::temp_dir().join("macos_bookmarks_test_keyed_archive.plist");
+        fs::write(&path, test_archive()).unwrap();
+        assert_eq!(parse_keyed_archive_bookmarks_file(&path).unwrap().len(), 2);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_keyed_archive_bookmarks_bad_data() {
+        assert!(parse_keyed_archive_bookmarks(b"not a plist").is_err());
+        assert!(parse_keyed_archive_bookmarks_file("/does/not/exist.plist").is_err());
+
+        let mut data = Vec::new();
+        Value::Dictionary(Dictionary::new())
+            .to_writer_xml(&mut data)
+            .unwrap();
+        assert!(parse_keyed_archive_bookmarks(&data).is_err());
+    }
+}
@@ -1,3 +1,86 @@
+// Library code must not panic on untrusted bookmark data (tests may still unwrap)
+#![cfg_attr(
+    not(test),
+    deny(clippy::panic, clippy::unwrap_used, clippy::expect_used)
+)]
+
+pub mod alias;
+pub mod anonymize;
+pub mod artemis;
+pub mod artifact;
 mod bookmark;
-mod error;
+#[cfg(feature = "plist")]
+pub mod btm;
+pub mod builder;
+#[cfg(feature = "bundle")]
+pub mod bundle;
+pub mod carve;
+pub mod catalog;
+pub mod checkpoint;
+#[cfg(feature = "native")]
+pub mod codesign;
+pub mod conformance;
+#[cfg(feature = "polars")]
+pub mod dataframe;
+#[cfg(feature = "plist")]
+pub mod dock;
+pub mod error;
+pub mod extension;
+pub mod extract;
+#[cfg(feature = "plist")]
+pub mod finder;
+#[cfg(feature = "flatbuffers")]
+pub mod flatbuffer;
+pub mod flatten;
+#[cfg(feature = "test-utils")]
+pub mod generator;
+pub mod group;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+#[cfg(feature = "plist")]
+pub mod keyed_archive;
+pub mod locale;
+#[cfg(feature = "plist")]
+pub mod login_items;
+pub mod matching;
+#[cfg(feature = "plist")]
+pub mod mdm;
+pub mod merge;
+pub mod misp;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+#[cfg(feature = "plist")]
+pub mod nav_recents;
+pub mod normalize;
+pub mod observer;
 pub mod parser;
+pub mod path_policy;
+#[cfg(feature = "plist")]
+pub mod plist;
+#[cfg(feature = "plist")]
+pub mod recent_items;
+pub mod resolve;
+#[cfg(feature = "plist")]
+pub mod safari;
+pub mod scan;
+#[cfg(feature = "serve")]
+pub mod serve;
+#[cfg(feature = "plist")]
+pub mod sharekit;
+pub mod sink;
+pub mod snapshot;
+#[cfg(feature = "plist")]
+pub mod spotlight;
+#[cfg(feature = "rusqlite")]
+pub mod sqlite;
+pub mod stats;
+pub mod timeline;
+pub mod types;
+pub mod url;
+pub mod value;
+
+pub use bookmark::{
+    BookmarkData, BookmarkHeader, DuplicateRecord, FieldConfidence, FieldProvenance, FileMetadata,
+    LossyString, NestedBookmark, ParseWarning, PathLengthMismatch, RawRecord, RecordConflict,
+    RecordOffset, TocHeaderMagic, TruncatedRecord, UnknownRecord, UrlComponentCounts,
+};
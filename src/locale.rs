@@ -0,0 +1,111 @@
+//! Localization of report strings
+//!
+//! Human readable messages are looked up by key from a message table.
+//! English is always available and is used when a translation is missing a key.
+
+use std::{collections::BTreeMap, sync::OnceLock};
+
+use log::error;
+
+use crate::error::BookmarkError;
+
+#[derive(Debug)]
+pub struct Localization {
+    messages: BTreeMap<String, String>, // Message key to translated message
+    default: BTreeMap<String, String>,  // Message key to English message
+}
+
+impl Localization {
+    /// Get the English message table
+    pub fn english() -> Localization {
+        let default = Localization::english_messages();
+        Localization {
+            messages: BTreeMap::new(),
+            default,
+        }
+    }
+
+    /// Load a translation from JSON data (an object of message key to message)
+    pub fn from_json(data: &str) -> Result<Localization, BookmarkError> {
+        let messages_result = serde_json::from_str(data);
+        match messages_result {
+            Ok(messages) => Ok(Localization {
+                messages,
+                default: Localization::english_messages(),
+            }),
+            Err(err) => {
+                error!("failed to parse localization data: {:?}", err);
+                Err(BookmarkError::InvalidLocalizationData)
+            }
+        }
+    }
+
+    /// Get the message for a key. Falls back to English, then to the key itself
+    pub fn message(&self, key: &str) -> String {
+        self.messages
+            .get(key)
+            .or_else(|| self.default.get(key))
+            .cloned()
+            .unwrap_or_else(|| key.to_string())
+    }
+
+    /// Get the message for a key and replace `{name}` placeholders with values
+    pub fn format(&self, key: &str, values: &[(&str, &str)]) -> String {
+        let mut message = self.message(key);
+        for (name, value) in values {
+            message = message.replace(&format!("{{{}}}", name), value);
+        }
+        message
+    }
+
+    /// Check if a key has a message in this table or the English default
+    pub fn contains(&self, key: &str) -> bool {
+        self.messages.contains_key(key) || self.default.contains_key(key)
+    }
+
+    /// Parse the English messages embedded in the library
+    fn english_messages() -> BTreeMap<String, String> {
+        serde_json::from_str(include_str!("data/locale_en.json")).unwrap_or_default()
+    }
+}
+
+/// Get an English message and replace `{name}` placeholders with values. The English table is only parsed once
+pub(crate) fn english_format(key: &str, values: &[(&str, &str)]) -> String {
+    static ENGLISH: OnceLock<Localization> = OnceLock::new();
+    ENGLISH
+        .get_or_init(Localization::english)
+        .format(key, values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Localization;
+
+    #[test]
+    fn test_english() {
+        let locale = Localization::english();
+        assert_eq!(locale.message("misp.bookmark"), "macOS bookmark");
+        assert_eq!(
+            locale.format("misp.creator_uid", &[("uid", "501")]),
+            "Bookmark creator UID 501"
+        );
+        assert_eq!(locale.message("unknown.key"), "unknown.key");
+    }
+
+    #[test]
+    fn test_from_json() {
+        let data = r#"{"misp.bookmark": "Marque-page macOS"}"#;
+        let locale = Localization::from_json(data).unwrap();
+        assert_eq!(locale.message("misp.bookmark"), "Marque-page macOS");
+        assert_eq!(
+            locale.format("misp.volume_uuid", &[("volume", "Macintosh HD")]),
+            "Volume UUID of Macintosh HD"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidLocalizationData")]
+    fn test_bad_localization() {
+        let _ = Localization::from_json("[1, 2]").unwrap();
+    }
+}
@@ -0,0 +1,203 @@
+//! Parse LoginItems from `com.apple.loginitems.plist`
+//!
+//! Login items are listed under `SessionItems` -> `CustomListItems`. Each item has a `Name`, the target as bookmark
+//! data (`Bookmark`) or as a classic alias record (`Alias`, bookmark data on newer versions) and an optional hide on
+//! launch flag in `CustomItemProperties`.
+
+use std::{fs, io::Cursor, path::Path};
+
+use log::{error, warn};
+use plist::{Dictionary, Value};
+use serde::Serialize;
+
+use crate::{
+    alias::{parse_alias, AliasData},
+    bookmark::BookmarkData,
+    error::BookmarkError,
+    parser::parse_bookmark,
+};
+
+#[derive(Debug, Serialize)]
+pub struct LoginItem {
+    pub name: String,                   // Name of the login item
+    pub hidden: bool,                   // Target is hidden on launch
+    pub bookmark: Option<BookmarkData>, // Target bookmark (converted from the alias record if needed)
+    pub alias: Option<AliasData>,       // Classic alias record of the target
+}
+
+/// Keys that can hold the hide on launch flag
+const HIDDEN_KEYS: [&str; 2] = ["com.apple.loginitem.HideOnLaunch", "Hide"];
+
+/// Parse every login item in a `com.apple.loginitems.plist` file (XML or binary). Items with target data that fails
+/// to parse are kept without a bookmark
+pub fn parse_login_items(data: &[u8]) -> Result<Vec<LoginItem>, BookmarkError> {
+    let plist = match Value::from_reader(Cursor::new(data)) {
+        Ok(Value::Dictionary(plist)) => plist,
+        Ok(_) => {
+            error!("LoginItems plist is not a dictionary");
+            return Err(BookmarkError::InvalidPlistData);
+        }
+        Err(err) => {
+            error!("failed to parse LoginItems plist: {:?}", err);
+            return Err(BookmarkError::InvalidPlistData);
+        }
+    };
+
+    let items = plist
+        .get("SessionItems")
+        .and_then(Value::as_dictionary)
+        .and_then(|session| session.get("CustomListItems"))
+        .and_then(Value::as_array)
+        .map_or(&[][..], Vec::as_slice);
+    Ok(items
+        .iter()
+        .filter_map(Value::as_dictionary)
+        .map(login_item)
+        .collect())
+}
+
+/// Read a `com.apple.loginitems.plist` file and parse every login item
+pub fn parse_login_items_file(path: impl AsRef<Path>) -> Result<Vec<LoginItem>, BookmarkError> {
+    match fs::read(path.as_ref()) {
+        Ok(data) => parse_login_items(&data),
+        Err(err) => {
+            error!(
+                "failed to read LoginItems plist {:?}: {:?}",
+                path.as_ref(),
+                err
+            );
+            Err(BookmarkError::InvalidPlistData)
+        }
+    }
+}
+
+/// Parse a single `CustomListItems` entry
+fn login_item(item: &Dictionary) -> LoginItem {
+    let name = item
+        .get("Name")
+        .and_then(Value::as_string)
+        .unwrap_or_default()
+        .to_string();
+    let properties = item
+        .get("CustomItemProperties")
+        .and_then(Value::as_dictionary);
+    let hidden = HIDDEN_KEYS.iter().any(|key| {
+        properties
+            .and_then(|properties| properties.get(key))
+            .or_else(|| item.get(key))
+            .and_then(Value::as_boolean)
+            .unwrap_or_default()
+    });
+
+    let (bookmark, alias) = item_target(item, &name);
+    LoginItem {
+        name,
+        hidden,
+        bookmark,
+        alias,
+    }
+}
+
+/// Parse the `Bookmark` or `Alias` target of a `CustomListItems` entry. Alias records are also converted to
+/// `BookmarkData`
+pub(crate) fn item_target(
+    item: &Dictionary,
+    name: &str,
+) -> (Option<BookmarkData>, Option<AliasData>) {
+    for key in ["Bookmark", "Alias"] {
+        let Some(data) = item.get(key).and_then(Value::as_data) else {
+            continue;
+        };
+        if data.starts_with(BookmarkData::BOOKMARK_SIGNATURE) {
+            match parse_bookmark(data) {
+                Ok(bookmark) => return (Some(bookmark), None),
+                Err(err) => warn!("Failed to parse {} bookmark: {:?}", name, err),
+            }
+        } else {
+            match parse_alias(data) {
+                Ok(alias) => return (Some(alias.to_bookmark()), Some(alias)),
+                Err(err) => warn!("Failed to parse {} alias: {:?}", name, err),
+            }
+        }
+    }
+    (None, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_login_items, parse_login_items_file};
+    use plist::{Dictionary, Value};
+    use std::{fs, path::PathBuf};
+
+    fn test_login_items() -> Vec<u8> {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+
+        let mut properties = Dictionary::new();
+        properties.insert(
+            String::from("com.apple.loginitem.HideOnLaunch"),
+            Value::Boolean(true),
+        );
+        let mut item = Dictionary::new();
+        item.insert(
+            String::from("Name"),
+            Value::String(String::from("Syncthing")),
+        );
+        item.insert(String::from("Alias"), Value::Data(buffer));
+        item.insert(
+            String::from("CustomItemProperties"),
+            Value::Dictionary(properties),
+        );
+
+        let mut broken = Dictionary::new();
+        broken.insert(String::from("Name"), Value::String(String::from("Old")));
+        broken.insert(String::from("Alias"), Value::Data(vec![0; 10]));
+
+        let mut session = Dictionary::new();
+        session.insert(
+            String::from("Controller"),
+            Value::String(String::from("CustomListItems")),
+        );
+        session.insert(
+            String::from("CustomListItems"),
+            Value::Array(vec![Value::Dictionary(item), Value::Dictionary(broken)]),
+        );
+        let mut plist = Dictionary::new();
+        plist.insert(String::from("SessionItems"), Value::Dictionary(session));
+
+        let mut data = Vec::new();
+        Value::Dictionary(plist)
+            .to_writer_binary(&mut data)
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn test_parse_login_items() {
+        let items = parse_login_items(&test_login_items()).unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].name, "Syncthing");
+        assert!(items[0].hidden);
+        assert_eq!(
+            items[0].bookmark.as_ref().unwrap().path,
+            ["Applications", "Syncthing.app"]
+        );
+        assert!(items[0].alias.is_none());
+        assert_eq!(items[1].name, "Old");
+        assert!(!items[1].hidden);
+        assert!(items[1].bookmark.is_none());
+
+        let path = std::env::temp_dir().join("macos_bookmarks_test_loginitems.plist");
+        fs::write(&path, test_login_items()).unwrap();
+        let items = parse_login_items_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(items.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_login_items_bad_data() {
+        assert!(parse_login_items(b"not a plist").is_err());
+        assert!(parse_login_items_file("/does/not/exist.plist").is_err());
+    }
+}
@@ -0,0 +1,147 @@
+//! Match bookmark paths against patterns
+//!
+//! Matching follows the APFS/HFS+ defaults: case insensitive and Unicode normalization insensitive (NFD and NFC forms compare equal).
+//! Patterns support `?` (one character), `*` (any characters within a path component) and `**` (any characters across components).
+
+use crate::{bookmark::BookmarkData, normalize::nfc};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MatchOptions {
+    pub case_sensitive: bool, // Compare case (only for case sensitive APFS/HFSX volumes)
+}
+
+#[derive(Debug, PartialEq)]
+enum PatternToken {
+    Char(char),
+    AnyChar,
+    Star,
+    DoubleStar,
+}
+
+impl BookmarkData {
+    /// Check if the bookmark target path matches a pattern using the APFS/HFS+ defaults
+    pub fn matches_path(&self, pattern: &str) -> bool {
+        self.matches_path_with(pattern, MatchOptions::default())
+    }
+
+    /// Check if the bookmark target path matches a pattern
+    pub fn matches_path_with(&self, pattern: &str, options: MatchOptions) -> bool {
+        path_matches(&self.target_path(), pattern, options)
+    }
+}
+
+/// Check if a path matches a pattern
+pub fn path_matches(path: &str, pattern: &str, options: MatchOptions) -> bool {
+    let path: Vec<char> = normalize(path, options).chars().collect();
+    let tokens = tokenize(&normalize(pattern, options));
+
+    // matches[token][char] is true when tokens[token..] matches path[char..]
+    let mut matches = vec![vec![false; path.len() + 1]; tokens.len() + 1];
+    matches[tokens.len()][path.len()] = true;
+
+    for token_index in (0..tokens.len()).rev() {
+        for char_index in (0..=path.len()).rev() {
+            let remaining = path.get(char_index);
+            matches[token_index][char_index] = match tokens[token_index] {
+                PatternToken::Char(value) => {
+                    remaining == Some(&value) && matches[token_index + 1][char_index + 1]
+                }
+                PatternToken::AnyChar => {
+                    remaining.is_some_and(|value| *value != '/')
+                        && matches[token_index + 1][char_index + 1]
+                }
+                PatternToken::Star => {
+                    matches[token_index + 1][char_index]
+                        || (remaining.is_some_and(|value| *value != '/')
+                            && matches[token_index][char_index + 1])
+                }
+                PatternToken::DoubleStar => {
+                    matches[token_index + 1][char_index]
+                        || (remaining.is_some() && matches[token_index][char_index + 1])
+                }
+            };
+        }
+    }
+    matches[0][0]
+}
+
+/// Normalize a string to NFC and (optionally) lowercase it
+fn normalize(value: &str, options: MatchOptions) -> String {
+    let normal = nfc(value);
+    if options.case_sensitive {
+        return normal;
+    }
+    normal.to_lowercase()
+}
+
+/// Split a pattern into wildcard and character tokens
+fn tokenize(pattern: &str) -> Vec<PatternToken> {
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars().peekable();
+    while let Some(value) = chars.next() {
+        let token = match value {
+            '?' => PatternToken::AnyChar,
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                PatternToken::DoubleStar
+            }
+            '*' => PatternToken::Star,
+            _ => PatternToken::Char(value),
+        };
+        tokens.push(token);
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{path_matches, tokenize, MatchOptions, PatternToken};
+    use crate::parser::parse_bookmark;
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn test_matches_path() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/systemevents.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+        let bookmark = parse_bookmark(&buffer).unwrap();
+
+        assert!(bookmark.matches_path("/System/Library/CoreServices/System Events.app"));
+        assert!(bookmark.matches_path("/system/library/coreservices/system events.app"));
+        assert!(bookmark.matches_path("/System/*/CoreServices/*.app"));
+        assert!(bookmark.matches_path("**/System Event?.app"));
+        assert!(!bookmark.matches_path("/System/*.app"));
+        assert!(!bookmark.matches_path_with(
+            "/system/library/coreservices/system events.app",
+            MatchOptions {
+                case_sensitive: true
+            }
+        ));
+    }
+
+    #[test]
+    fn test_path_matches_normalization() {
+        // "é" as a single code point (NFC) and as "e" + combining accent (NFD)
+        let nfc = "/Users/test/R\u{e9}sum\u{e9}.pdf";
+        let nfd = "/Users/test/Re\u{301}sume\u{301}.pdf";
+        let options = MatchOptions::default();
+
+        assert!(path_matches(nfd, nfc, options));
+        assert!(path_matches(nfc, nfd, options));
+        assert!(path_matches(nfd, "/users/*/r\u{c9}sum?.pdf", options));
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let tokens = tokenize("a*?**");
+        assert_eq!(
+            tokens,
+            [
+                PatternToken::Char('a'),
+                PatternToken::Star,
+                PatternToken::AnyChar,
+                PatternToken::DoubleStar
+            ]
+        );
+    }
+}
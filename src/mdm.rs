@@ -0,0 +1,187 @@
+//! Extract bookmarks embedded in configuration profiles (`.mobileconfig`)
+//!
+//! Managed LoginItems and Dock items may contain bookmark data. Signed profiles wrap the XML plist in a CMS
+//! container, the embedded plist is extracted without verifying the signature.
+
+use std::io::Cursor;
+
+use log::{error, warn};
+use plist::{Dictionary, Value};
+use serde::Serialize;
+
+use crate::{
+    bookmark::BookmarkData,
+    error::BookmarkError,
+    extract::{bookmark_range, locate_blob, ByteRange},
+    parser::parse_bookmark,
+    plist::find_bookmark_data,
+};
+
+#[derive(Debug, Serialize)]
+pub struct ManagedBookmark {
+    pub payload_identifier: String, // PayloadIdentifier of the payload containing the bookmark
+    pub payload_type: String,       // PayloadType (ex: com.apple.loginitems.managed)
+    pub key_path: String,           // Dotted path to the bookmark data within the payload
+    pub range: Option<ByteRange>,   // Bookmark bytes within the profile (binary plists only)
+    pub bookmark: BookmarkData,     // Parsed bookmark
+}
+
+/// Parse every bookmark embedded in a configuration profile
+pub fn parse_mobileconfig(data: &[u8]) -> Result<Vec<ManagedBookmark>, BookmarkError> {
+    let profile = match Value::from_reader(Cursor::new(profile_plist(data))) {
+        Ok(Value::Dictionary(profile)) => profile,
+        Ok(_) => {
+            error!("Configuration profile is not a dictionary");
+            return Err(BookmarkError::InvalidPlistData);
+        }
+        Err(err) => {
+            error!("failed to parse configuration profile: {:?}", err);
+            return Err(BookmarkError::InvalidPlistData);
+        }
+    };
+
+    let mut bookmarks = Vec::new();
+    let payloads = profile
+        .get("PayloadContent")
+        .and_then(Value::as_array)
+        .map_or(&[][..], Vec::as_slice);
+    for payload in payloads.iter().filter_map(Value::as_dictionary) {
+        let payload_identifier = string_value(payload, "PayloadIdentifier");
+        let payload_type = string_value(payload, "PayloadType");
+
+        let mut found = Vec::new();
+        for (key, value) in payload {
+            find_bookmark_data(key, value, &mut found);
+        }
+        for (key_path, bookmark_data) in found {
+            // XML plists store the bookmark base64 encoded, only binary plists contain the original bytes
+            let range = bookmark_range(bookmark_data, 0)
+                .and_then(|range| range.slice(bookmark_data))
+                .and_then(|blob| locate_blob(data, blob));
+            match parse_bookmark(bookmark_data) {
+                Ok(bookmark) => bookmarks.push(ManagedBookmark {
+                    payload_identifier: payload_identifier.clone(),
+                    payload_type: payload_type.clone(),
+                    key_path,
+                    range,
+                    bookmark,
+                }),
+                Err(err) => warn!("Failed to parse bookmark at {}: {:?}", key_path, err),
+            }
+        }
+    }
+    Ok(bookmarks)
+}
+
+/// Get the plist data from a profile, extracting the XML plist from signed profiles
+fn profile_plist(data: &[u8]) -> &[u8] {
+    let start = data.windows(5).position(|window| window == b"<?xml");
+    let end_tag = b"</plist>";
+    let end = data
+        .windows(end_tag.len())
+        .rposition(|window| window == end_tag);
+
+    match (start, end) {
+        // Signed profiles have DER data before and after the XML plist
+        (Some(start), Some(end)) if start > 0 && end > start => &data[start..end + end_tag.len()],
+        _ => data,
+    }
+}
+
+/// Get a string value from a dictionary, empty if missing
+fn string_value(dictionary: &Dictionary, key: &str) -> String {
+    dictionary
+        .get(key)
+        .and_then(Value::as_string)
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_mobileconfig, profile_plist};
+    use plist::{Dictionary, Value};
+    use std::{fs, path::PathBuf};
+
+    fn test_profile() -> Vec<u8> {
+        let mut data = Vec::new();
+        test_profile_value().to_writer_xml(&mut data).unwrap();
+        data
+    }
+
+    fn test_profile_value() -> Value {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+
+        let mut item = Dictionary::new();
+        item.insert(String::from("Bookmark"), Value::Data(buffer));
+        item.insert(String::from("Hide"), Value::Boolean(false));
+
+        let mut payload = Dictionary::new();
+        payload.insert(
+            String::from("PayloadIdentifier"),
+            Value::String(String::from("com.example.loginitems")),
+        );
+        payload.insert(
+            String::from("PayloadType"),
+            Value::String(String::from("com.apple.loginitems.managed")),
+        );
+        payload.insert(
+            String::from("AutoLaunchedApplicationDictionary-managed"),
+            Value::Array(vec![Value::Dictionary(item)]),
+        );
+
+        let mut profile = Dictionary::new();
+        profile.insert(
+            String::from("PayloadContent"),
+            Value::Array(vec![Value::Dictionary(payload)]),
+        );
+        Value::Dictionary(profile)
+    }
+
+    #[test]
+    fn test_parse_mobileconfig() {
+        let bookmarks = parse_mobileconfig(&test_profile()).unwrap();
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(bookmarks[0].payload_identifier, "com.example.loginitems");
+        assert_eq!(bookmarks[0].payload_type, "com.apple.loginitems.managed");
+        assert_eq!(
+            bookmarks[0].key_path,
+            "AutoLaunchedApplicationDictionary-managed.0.Bookmark"
+        );
+        assert_eq!(
+            bookmarks[0].bookmark.path,
+            ["Applications", "Syncthing.app"]
+        );
+        assert_eq!(bookmarks[0].range, None);
+    }
+
+    #[test]
+    fn test_parse_binary_mobileconfig() {
+        let mut profile = Vec::new();
+        test_profile_value().to_writer_binary(&mut profile).unwrap();
+
+        let bookmarks = parse_mobileconfig(&profile).unwrap();
+        assert_eq!(bookmarks.len(), 1);
+        let range = bookmarks[0].range.unwrap();
+        assert_eq!(range.len(), 756);
+        assert!(range.slice(&profile).unwrap().starts_with(b"book"));
+    }
+
+    #[test]
+    fn test_parse_signed_mobileconfig() {
+        let profile = test_profile();
+        let mut signed = vec![0x30, 0x80, 0x06, 0x09];
+        signed.extend_from_slice(&profile);
+        signed.extend_from_slice(&[0xa0, 0x80, 0x00, 0x00]);
+
+        assert_eq!(profile_plist(&signed), profile.trim_ascii_end());
+        assert_eq!(parse_mobileconfig(&signed).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_parse_mobileconfig_bad_data() {
+        assert!(parse_mobileconfig(b"not a profile").is_err());
+    }
+}
@@ -0,0 +1,242 @@
+//! Merge partial results from several damaged copies of the same bookmark
+//!
+//! Copies recovered from carved data, journals or snapshots are often corrupt in different places. Parse each copy
+//! (ex: with `ParseMode::Lenient`) and merge them: every field is taken from the copies that recovered
+//! it (using the field provenance), the most common value wins and differing values are reported as conflicts.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::bookmark::BookmarkData;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct MergedBookmark {
+    pub bookmark: BookmarkData,        // Most complete result from every copy
+    pub sources: Vec<FieldSource>,     // Copy used for each recovered field
+    pub conflicts: Vec<FieldConflict>, // Fields recovered with different values
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct FieldSource {
+    pub field: &'static str, // BookmarkData field name
+    pub copy: usize,         // Index of the copy the value was taken from
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct FieldConflict {
+    pub field: &'static str,        // BookmarkData field name
+    pub values: Vec<ConflictValue>, // Every distinct value, the merged value first
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ConflictValue {
+    pub value: Value,       // Field value
+    pub copies: Vec<usize>, // Index of each copy with the value
+}
+
+/// Fields populated from TOC records, in `BookmarkData` order
+const MERGED_FIELDS: [&str; 29] = [
+    "path",
+    "cnid_path",
+    "creation",
+    "target_filename",
+    "url_lengths",
+    "toc_path",
+    "volume_path",
+    "volume_url",
+    "volume_name",
+    "volume_uuid",
+    "volume_size",
+    "volume_creation",
+    "volume_flag",
+    "volume_root",
+    "volume_mount_point",
+    "localized_name",
+    "display_name",
+    "security_extension_rw",
+    "security_extension_ro",
+    "target_flags",
+    "username",
+    "folder_index",
+    "uid",
+    "creation_options",
+    "is_executable",
+    "file_ref_flag",
+    "relative_url",
+    "nested_bookmarks",
+    "file_metadata",
+];
+
+/// Merge several parsed copies of the same bookmark into the most complete result. `None` if there are no copies
+pub fn merge_bookmarks(copies: &[BookmarkData]) -> Option<MergedBookmark> {
+    // Start from the copy that recovered the most fields, keeping its warnings and raw records
+    let base = (0..copies.len()).max_by_key(|index| {
+        let count = recovered_fields(&copies[*index]);
+        (count, std::cmp::Reverse(*index))
+    })?;
+    let mut bookmark = copies[base].clone();
+    bookmark.provenance.clear();
+    bookmark.confidence.clear();
+
+    let serialized: Vec<Value> = copies
+        .iter()
+        .map(|copy| serde_json::to_value(copy).unwrap_or_default())
+        .collect();
+
+    let mut sources = Vec::new();
+    let mut conflicts = Vec::new();
+    for field in MERGED_FIELDS {
+        let mut values: Vec<ConflictValue> = Vec::new();
+        for (index, copy) in copies.iter().enumerate() {
+            if !copy.provenance.iter().any(|entry| entry.field == field) {
+                continue;
+            }
+            let value = serialized[index].get(field).cloned().unwrap_or_default();
+            match values.iter_mut().find(|existing| existing.value == value) {
+                Some(existing) => existing.copies.push(index),
+                None => values.push(ConflictValue {
+                    value,
+                    copies: vec![index],
+                }),
+            }
+        }
+        // Most common value first, ties go to the earliest copy
+        values.sort_by(|first, second| {
+            second
+                .copies
+                .len()
+                .cmp(&first.copies.len())
+                .then_with(|| first.copies[0].cmp(&second.copies[0]))
+        });
+        let Some(merged) = values.first() else {
+            continue;
+        };
+
+        let copy = &copies[merged.copies[0]];
+        copy_field(&mut bookmark, copy, field);
+        for entry in copy.provenance.iter().filter(|entry| entry.field == field) {
+            bookmark.track_confidence(entry.field, entry.confidence);
+            bookmark.provenance.push(entry.clone());
+        }
+        sources.push(FieldSource {
+            field,
+            copy: merged.copies[0],
+        });
+        if values.len() > 1 {
+            conflicts.push(FieldConflict { field, values });
+        }
+    }
+    bookmark.has_persistent_ids = bookmark.persistent_ids();
+    bookmark.record_set = bookmark.detect_record_set();
+
+    Some(MergedBookmark {
+        bookmark,
+        sources,
+        conflicts,
+    })
+}
+
+/// Count the distinct fields a copy recovered
+fn recovered_fields(bookmark: &BookmarkData) -> usize {
+    MERGED_FIELDS
+        .iter()
+        .filter(|field| {
+            bookmark
+                .provenance
+                .iter()
+                .any(|entry| entry.field == **field)
+        })
+        .count()
+}
+
+/// Copy a field from another bookmark
+fn copy_field(target: &mut BookmarkData, source: &BookmarkData, field: &str) {
+    match field {
+        "path" => target.path = source.path.clone(),
+        "cnid_path" => target.cnid_path = source.cnid_path.clone(),
+        "creation" => target.creation = source.creation,
+        "target_filename" => target.target_filename = source.target_filename.clone(),
+        "url_lengths" => target.url_lengths = source.url_lengths.clone(),
+        "toc_path" => target.toc_path = source.toc_path.clone(),
+        "volume_path" => target.volume_path = source.volume_path.clone(),
+        "volume_url" => target.volume_url = source.volume_url.clone(),
+        "volume_name" => target.volume_name = source.volume_name.clone(),
+        "volume_uuid" => target.volume_uuid = source.volume_uuid.clone(),
+        "volume_size" => target.volume_size = source.volume_size,
+        "volume_creation" => target.volume_creation = source.volume_creation,
+        "volume_flag" => target.volume_flag = source.volume_flag.clone(),
+        "volume_root" => target.volume_root = source.volume_root,
+        "volume_mount_point" => target.volume_mount_point = source.volume_mount_point.clone(),
+        "localized_name" => target.localized_name = source.localized_name.clone(),
+        "display_name" => target.display_name = source.display_name.clone(),
+        "security_extension_rw" => {
+            target.security_extension_rw = source.security_extension_rw.clone()
+        }
+        "security_extension_ro" => {
+            target.security_extension_ro = source.security_extension_ro.clone()
+        }
+        "target_flags" => target.target_flags = source.target_flags.clone(),
+        "username" => target.username = source.username.clone(),
+        "folder_index" => target.folder_index = source.folder_index,
+        "uid" => target.uid = source.uid,
+        "creation_options" => target.creation_options = source.creation_options,
+        "is_executable" => target.is_executable = source.is_executable,
+        "file_ref_flag" => target.file_ref_flag = source.file_ref_flag,
+        "relative_url" => target.relative_url = source.relative_url.clone(),
+        "nested_bookmarks" => target.nested_bookmarks = source.nested_bookmarks.clone(),
+        "file_metadata" => target.file_metadata = source.file_metadata.clone(),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::merge_bookmarks;
+    use crate::parser::parse_bookmark;
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn test_merge_bookmarks() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+        let bookmark = parse_bookmark(&buffer).unwrap();
+
+        // First copy lost the volume name, second copy lost the path
+        let mut first = bookmark.clone();
+        first.volume_name = String::new();
+        first
+            .provenance
+            .retain(|entry| entry.field != "volume_name");
+        let mut second = bookmark.clone();
+        second.path = Vec::new();
+        second.provenance.retain(|entry| entry.field != "path");
+        // Third copy has a corrupted volume UUID
+        let mut third = bookmark.clone();
+        third.volume_uuid = String::from("corrupt");
+
+        let merged = merge_bookmarks(&[first, second, third]).unwrap();
+        assert_eq!(merged.bookmark.path, ["Applications", "Syncthing.app"]);
+        assert_eq!(merged.bookmark.volume_name, "Macintosh HD");
+        assert_eq!(
+            merged.bookmark.volume_uuid,
+            "0A81F3B1-51D9-3335-B3E3-169C3640360D"
+        );
+        assert_eq!(merged.conflicts.len(), 1);
+        assert_eq!(merged.conflicts[0].field, "volume_uuid");
+        assert_eq!(merged.conflicts[0].values[0].copies, [0, 1]);
+        assert_eq!(merged.conflicts[0].values[1].copies, [2]);
+        assert_eq!(
+            merged.bookmark.field_provenance().len(),
+            bookmark.field_provenance().len()
+        );
+        let volume_name = merged
+            .sources
+            .iter()
+            .find(|source| source.field == "volume_name")
+            .unwrap();
+        assert_eq!(volume_name.copy, 1);
+
+        assert!(merge_bookmarks(&[]).is_none());
+    }
+}
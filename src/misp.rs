@@ -0,0 +1,182 @@
+//! Export flagged bookmarks as a MISP event
+//!
+//! Converts bookmarks and their signature matches into MISP event JSON that can be imported into a MISP instance.
+
+use serde::Serialize;
+
+use crate::{bookmark::BookmarkData, catalog::SignatureMatch, locale::Localization};
+
+#[derive(Debug, Serialize)]
+pub struct MispEvent {
+    #[serde(rename = "Event")]
+    pub event: MispEventData,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MispEventData {
+    pub info: String,            // Event description
+    pub date: String,            // Event date (YYYY-MM-DD)
+    pub threat_level_id: String, // 1 = High, 2 = Medium, 3 = Low, 4 = Undefined
+    pub analysis: String,        // 0 = Initial, 1 = Ongoing, 2 = Complete
+    pub distribution: String,    // 0 = Your organisation only
+    #[serde(rename = "Attribute")]
+    pub attributes: Vec<MispAttribute>,
+    #[serde(rename = "Tag")]
+    pub tags: Vec<MispTag>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct MispAttribute {
+    #[serde(rename = "type")]
+    pub attribute_type: String, // MISP attribute type
+    pub category: String, // MISP attribute category
+    pub value: String,    // Attribute value
+    pub to_ids: bool,     // Whether attribute should be used for detection
+    pub comment: String,  // Context for the attribute
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct MispTag {
+    pub name: String, // Tag name
+}
+
+/// Build a MISP event from bookmarks and the signatures they matched
+pub fn misp_event(
+    info: &str,
+    date: &str,
+    flagged: &[(&BookmarkData, Vec<SignatureMatch>)],
+) -> MispEvent {
+    misp_event_localized(info, date, flagged, &Localization::english())
+}
+
+/// Build a MISP event from bookmarks and the signatures they matched, using translated attribute comments
+pub fn misp_event_localized(
+    info: &str,
+    date: &str,
+    flagged: &[(&BookmarkData, Vec<SignatureMatch>)],
+    locale: &Localization,
+) -> MispEvent {
+    let mut attributes: Vec<MispAttribute> = Vec::new();
+    let mut tags: Vec<MispTag> = Vec::new();
+
+    for (bookmark, matches) in flagged {
+        let rule_ids: Vec<&str> = matches.iter().map(|entry| entry.id.as_str()).collect();
+        let comment = if rule_ids.is_empty() {
+            locale.message("misp.bookmark")
+        } else {
+            locale.format("misp.bookmark_matched", &[("rules", &rule_ids.join(", "))])
+        };
+
+        if !bookmark.path.is_empty() {
+            add_attribute(
+                &mut attributes,
+                MispAttribute {
+                    attribute_type: String::from("filename"),
+                    category: String::from("Persistence mechanism"),
+                    value: format!("/{}", bookmark.path.join("/")),
+                    to_ids: false,
+                    comment: comment.clone(),
+                },
+            );
+        }
+        if !bookmark.volume_uuid.is_empty() {
+            add_attribute(
+                &mut attributes,
+                MispAttribute {
+                    attribute_type: String::from("text"),
+                    category: String::from("Other"),
+                    value: bookmark.volume_uuid.clone(),
+                    to_ids: false,
+                    comment: locale
+                        .format("misp.volume_uuid", &[("volume", &bookmark.volume_name)]),
+                },
+            );
+        }
+        if let Some(username) = &bookmark.username {
+            add_attribute(
+                &mut attributes,
+                MispAttribute {
+                    attribute_type: String::from("target-user"),
+                    category: String::from("Targeting data"),
+                    value: username.clone(),
+                    to_ids: false,
+                    comment: bookmark
+                        .uid
+                        .map(|uid| locale.format("misp.creator_uid", &[("uid", &uid.to_string())]))
+                        .unwrap_or_default(),
+                },
+            );
+        }
+
+        for rule_id in rule_ids {
+            let tag = MispTag {
+                name: format!("macos-bookmarks:{}", rule_id),
+            };
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+    }
+
+    MispEvent {
+        event: MispEventData {
+            info: info.to_string(),
+            date: date.to_string(),
+            threat_level_id: String::from("4"),
+            analysis: String::from("0"),
+            distribution: String::from("0"),
+            attributes,
+            tags,
+        },
+    }
+}
+
+/// Add an attribute if an identical one has not been added already
+fn add_attribute(attributes: &mut Vec<MispAttribute>, attribute: MispAttribute) {
+    if !attributes.contains(&attribute) {
+        attributes.push(attribute);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::misp_event;
+    use crate::{
+        catalog::{default_signatures, match_bookmark},
+        parser::parse_bookmark,
+    };
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn test_misp_event() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/poisonapple.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+        let bookmark = parse_bookmark(&buffer).unwrap();
+
+        let signatures = default_signatures().unwrap();
+        let matches = match_bookmark(&bookmark, &signatures);
+        let event = misp_event("Bookmark triage", "2022-07-01", &[(&bookmark, matches)]);
+
+        assert_eq!(event.event.info, "Bookmark triage");
+        assert_eq!(event.event.attributes.len(), 3);
+        assert_eq!(event.event.attributes[0].attribute_type, "filename");
+        assert_eq!(
+            event.event.attributes[0].value,
+            "/Users/sur/Library/Python/3.8/lib/python/site-packages/poisonapple/auxiliary/testing.app"
+        );
+        assert_eq!(
+            event.event.attributes[0].comment,
+            "macOS bookmark matched: BM-0001"
+        );
+        assert_eq!(
+            event.event.attributes[1].value,
+            "0A81F3B1-51D9-3335-B3E3-169C3640360D"
+        );
+        assert_eq!(event.event.attributes[2].value, "sur");
+        assert_eq!(event.event.tags[0].name, "macos-bookmarks:BM-0001");
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["Event"]["Attribute"][2]["type"], "target-user");
+    }
+}
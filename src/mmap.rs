@@ -0,0 +1,101 @@
+//! Memory-mapped input for large containers
+//!
+//! Carving multi-gigabyte images or parsing large containers with `fs::read` copies the whole file into memory. A
+//! `MappedFile` maps the file read only and dereferences to `&[u8]`, so it can be passed to any parser or carver in
+//! the crate without an additional copy. The file must not be modified while it is mapped.
+
+use std::{fs::File, ops::Deref, path::Path};
+
+use log::error;
+use memmap2::Mmap;
+
+use crate::{
+    bookmark::BookmarkData,
+    carve::{find_bookmarks, CarvedBookmark},
+    error::BookmarkError,
+    parser::parse_bookmark,
+};
+
+#[derive(Debug)]
+pub struct MappedFile {
+    map: Mmap,
+}
+
+impl MappedFile {
+    /// Map a file read only
+    pub fn open(path: impl AsRef<Path>) -> Result<MappedFile, BookmarkError> {
+        let file = match File::open(path.as_ref()) {
+            Ok(file) => file,
+            Err(err) => {
+                error!("failed to open {:?}: {:?}", path.as_ref(), err);
+                return Err(BookmarkError::InvalidMappedFile);
+            }
+        };
+        // SAFETY: the map is read only, callers are told not to modify the file while it is mapped
+        match unsafe { Mmap::map(&file) } {
+            Ok(map) => Ok(MappedFile { map }),
+            Err(err) => {
+                error!("failed to map {:?}: {:?}", path.as_ref(), err);
+                Err(BookmarkError::InvalidMappedFile)
+            }
+        }
+    }
+
+    /// Get the mapped bytes
+    pub fn data(&self) -> &[u8] {
+        &self.map
+    }
+}
+
+impl Deref for MappedFile {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.data()
+    }
+}
+
+impl AsRef<[u8]> for MappedFile {
+    fn as_ref(&self) -> &[u8] {
+        self.data()
+    }
+}
+
+/// Map a bookmark file and parse it
+pub fn parse_mapped_bookmark(path: impl AsRef<Path>) -> Result<BookmarkData, BookmarkError> {
+    parse_bookmark(&MappedFile::open(path)?)
+}
+
+/// Map a file and carve every bookmark in it, see `carve::find_bookmarks`
+pub fn carve_mapped_file(path: impl AsRef<Path>) -> Result<Vec<CarvedBookmark>, BookmarkError> {
+    Ok(find_bookmarks(&MappedFile::open(path)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{carve_mapped_file, parse_mapped_bookmark, MappedFile};
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn test_mapped_file() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(&test_location).unwrap();
+
+        let map = MappedFile::open(&test_location).unwrap();
+        assert_eq!(map.data(), buffer.as_slice());
+        let bookmark = parse_mapped_bookmark(&test_location).unwrap();
+        assert_eq!(bookmark.path, ["Applications", "Syncthing.app"]);
+
+        let path = std::env::temp_dir().join("macos_bookmarks_test_mapped.bin");
+        let mut image = vec![0; 4096];
+        image.extend_from_slice(&buffer);
+        fs::write(&path, &image).unwrap();
+        let found = carve_mapped_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, 4096);
+
+        assert!(MappedFile::open("/does/not/exist.bin").is_err());
+    }
+}
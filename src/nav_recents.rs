@@ -0,0 +1,136 @@
+//! Parse open and save panel recent places (`NSNavRecentPlaces`)
+//!
+//! NSOpenPanel and NSSavePanel remember the folders recently used in the "Where" popup under `NSNavRecentPlaces`, in
+//! `.GlobalPreferences.plist` and in the preferences of sandboxed and non-sandboxed apps. Newer releases store each
+//! place as a path string (ex: `~/Documents`), older ones as bookmark data or a classic alias record.
+
+use std::{fs, io::Cursor, path::Path};
+
+use log::{error, warn};
+use plist::Value;
+use serde::Serialize;
+
+use crate::{
+    alias::{parse_alias, AliasData},
+    bookmark::BookmarkData,
+    error::BookmarkError,
+    parser::parse_bookmark,
+};
+
+#[derive(Debug, Serialize)]
+pub struct RecentPlace {
+    pub index: usize,                   // Position in the list (most recent first)
+    pub path: Option<String>,           // Folder path (path string entries)
+    pub bookmark: Option<BookmarkData>, // Parsed folder bookmark or alias target
+    pub alias: Option<AliasData>,       // Classic alias record
+}
+
+/// Preferences key listing the recent places
+pub const NAV_RECENT_PLACES: &str = "NSNavRecentPlaces";
+
+/// Parse every recent place in a preferences plist file (XML or binary). Preferences without `NSNavRecentPlaces` have
+/// no recent places, entries with data that fails to parse are kept without a bookmark
+pub fn parse_nav_recent_places(data: &[u8]) -> Result<Vec<RecentPlace>, BookmarkError> {
+    let plist = match Value::from_reader(Cursor::new(data)) {
+        Ok(Value::Dictionary(plist)) => plist,
+        Ok(_) => {
+            error!("Preferences plist is not a dictionary");
+            return Err(BookmarkError::InvalidPlistData);
+        }
+        Err(err) => {
+            error!("failed to parse preferences plist: {:?}", err);
+            return Err(BookmarkError::InvalidPlistData);
+        }
+    };
+
+    let entries = plist
+        .get(NAV_RECENT_PLACES)
+        .and_then(Value::as_array)
+        .map_or(&[][..], Vec::as_slice);
+    Ok(entries
+        .iter()
+        .enumerate()
+        .filter_map(|(index, entry)| recent_place(index, entry))
+        .collect())
+}
+
+/// Read a preferences plist file and parse every recent place
+pub fn parse_nav_recent_places_file(
+    path: impl AsRef<Path>,
+) -> Result<Vec<RecentPlace>, BookmarkError> {
+    match fs::read(path.as_ref()) {
+        Ok(data) => parse_nav_recent_places(&data),
+        Err(err) => {
+            error!(
+                "failed to read preferences plist {:?}: {:?}",
+                path.as_ref(),
+                err
+            );
+            Err(BookmarkError::InvalidPlistData)
+        }
+    }
+}
+
+/// Parse a single recent place. `None` if the entry is not a string or data
+fn recent_place(index: usize, entry: &Value) -> Option<RecentPlace> {
+    let mut place = RecentPlace {
+        index,
+        path: None,
+        bookmark: None,
+        alias: None,
+    };
+    match entry {
+        Value::String(path) => place.path = Some(path.clone()),
+        Value::Data(data) if data.starts_with(BookmarkData::BOOKMARK_SIGNATURE) => {
+            match parse_bookmark(data) {
+                Ok(bookmark) => place.bookmark = Some(bookmark),
+                Err(err) => warn!("Failed to parse recent place {} bookmark: {:?}", index, err),
+            }
+        }
+        Value::Data(data) => match parse_alias(data) {
+            Ok(alias) => {
+                place.bookmark = Some(alias.to_bookmark());
+                place.alias = Some(alias);
+            }
+            Err(err) => warn!("Failed to parse recent place {} alias: {:?}", index, err),
+        },
+        _ => return None,
+    }
+    Some(place)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_nav_recent_places, parse_nav_recent_places_file};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_parse_nav_recent_places() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/GlobalPreferences.plist");
+
+        let places = parse_nav_recent_places_file(&test_location).unwrap();
+        assert_eq!(places.len(), 4);
+        assert_eq!(places[0].path.as_deref(), Some("~/Documents"));
+        assert!(places[0].bookmark.is_none());
+        assert_eq!(
+            places[1].bookmark.as_ref().unwrap().path,
+            ["Applications", "Syncthing.app"]
+        );
+        assert!(places[1].alias.is_none());
+        assert_eq!(places[2].index, 2);
+        assert!(places[2].bookmark.is_none());
+        assert_eq!(places[3].path.as_deref(), Some("~/Downloads"));
+
+        // Preferences without recent places
+        let plist = br#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0"><dict><key>AppleLocale</key><string>en_US</string></dict></plist>"#;
+        assert!(parse_nav_recent_places(plist).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_nav_recent_places_bad_data() {
+        assert!(parse_nav_recent_places(b"not a plist").is_err());
+        assert!(parse_nav_recent_places_file("/does/not/exist.plist").is_err());
+    }
+}
@@ -0,0 +1,78 @@
+//! Unicode normalization of parsed strings
+//!
+//! HFS+ stores names decomposed (NFD) while APFS preserves whatever form was provided.
+//! Parsed values are kept exactly as stored, `normalized` provides an NFC copy for comparisons.
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::bookmark::BookmarkData;
+
+impl BookmarkData {
+    /// Get a copy of the bookmark with every string field (including nested bookmarks) normalized to NFC
+    pub fn normalized(&self) -> BookmarkData {
+        let mut bookmark = self.clone();
+        bookmark.normalize_strings();
+        bookmark
+    }
+
+    /// Normalize every string field to NFC in place
+    fn normalize_strings(&mut self) {
+        for component in self.path.iter_mut() {
+            *component = nfc(component);
+        }
+        self.volume_path = nfc(&self.volume_path);
+        self.volume_url = nfc(&self.volume_url);
+        self.volume_mount_point = self.volume_mount_point.as_deref().map(nfc);
+        self.volume_name = nfc(&self.volume_name);
+        self.volume_uuid = nfc(&self.volume_uuid);
+        self.localized_name = self.localized_name.as_deref().map(nfc);
+        self.display_name = self.display_name.as_deref().map(nfc);
+        self.target_filename = self.target_filename.as_deref().map(nfc);
+        self.toc_path = self.toc_path.as_deref().map(nfc);
+        self.security_extension_rw = self.security_extension_rw.as_deref().map(nfc);
+        self.security_extension_ro = self.security_extension_ro.as_deref().map(nfc);
+        self.username = self.username.as_deref().map(nfc);
+        for nested in self.nested_bookmarks.iter_mut() {
+            nested.bookmark.normalize_strings();
+        }
+        for duplicate in self.duplicate_records.iter_mut() {
+            duplicate.bookmark.normalize_strings();
+        }
+    }
+}
+
+/// Normalize a string to NFC
+pub fn nfc(value: &str) -> String {
+    value.nfc().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::nfc;
+    use crate::parser::parse_bookmark;
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn test_nfc() {
+        assert_eq!(nfc("Re\u{301}sume\u{301}.pdf"), "R\u{e9}sum\u{e9}.pdf");
+        assert_eq!(nfc("R\u{e9}sum\u{e9}.pdf"), "R\u{e9}sum\u{e9}.pdf");
+    }
+
+    #[test]
+    fn test_normalized() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+        let mut bookmark = parse_bookmark(&buffer).unwrap();
+        bookmark.path[1] = String::from("Synce\u{301}thing.app");
+        bookmark.volume_name = String::from("Macintosh He\u{301}D");
+
+        let normal = bookmark.normalized();
+        assert_eq!(normal.path, ["Applications", "Sync\u{e9}thing.app"]);
+        assert_eq!(normal.volume_name, "Macintosh H\u{e9}D");
+        assert_eq!(normal.localized_name.as_deref(), Some("Syncthing"));
+
+        // Original values are left untouched
+        assert_eq!(bookmark.path[1], "Synce\u{301}thing.app");
+    }
+}
@@ -0,0 +1,107 @@
+//! Hooks for observing parse outcomes
+//!
+//! Register a `ParseObserver` once and it is called after every top level bookmark parse.
+//! The library never sends the outcomes anywhere itself.
+
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use log::warn;
+
+#[derive(Debug, Clone)]
+pub struct ParseOutcome {
+    pub success: bool,         // If the bookmark was parsed
+    pub duration: Duration,    // Time spent parsing
+    pub data_size: usize,      // Size of the provided data
+    pub warning_count: usize,  // Number of records that could not be parsed
+    pub error: Option<String>, // Error message if parsing failed
+}
+
+pub trait ParseObserver: Send + Sync {
+    /// Called after every top level bookmark parse
+    fn on_parse(&self, outcome: &ParseOutcome);
+}
+
+static OBSERVER: RwLock<Option<Arc<dyn ParseObserver>>> = RwLock::new(None);
+
+/// Register an observer, replacing any observer registered before
+pub fn set_parse_observer(observer: Arc<dyn ParseObserver>) {
+    match OBSERVER.write() {
+        Ok(mut current) => *current = Some(observer),
+        Err(err) => warn!("Failed to register parse observer: {:?}", err),
+    }
+}
+
+/// Remove the registered observer
+pub fn clear_parse_observer() {
+    match OBSERVER.write() {
+        Ok(mut current) => *current = None,
+        Err(err) => warn!("Failed to remove parse observer: {:?}", err),
+    }
+}
+
+/// Send a parse outcome to the registered observer (if any)
+pub(crate) fn notify(outcome: &ParseOutcome) {
+    let observer = match OBSERVER.read() {
+        Ok(current) => current.clone(),
+        Err(_) => return,
+    };
+    if let Some(observer) = observer {
+        observer.on_parse(outcome);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clear_parse_observer, set_parse_observer, ParseObserver, ParseOutcome};
+    use crate::parser::parse_bookmark;
+    use std::{
+        fs,
+        path::PathBuf,
+        sync::{Arc, Mutex},
+    };
+
+    struct TestObserver {
+        outcomes: Mutex<Vec<ParseOutcome>>,
+    }
+
+    impl ParseObserver for TestObserver {
+        fn on_parse(&self, outcome: &ParseOutcome) {
+            self.outcomes.lock().unwrap().push(outcome.clone());
+        }
+    }
+
+    #[test]
+    fn test_parse_observer() {
+        let observer = Arc::new(TestObserver {
+            outcomes: Mutex::new(Vec::new()),
+        });
+        set_parse_observer(observer.clone());
+
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+        let _ = parse_bookmark(&buffer).unwrap();
+        let _ = parse_bookmark(&[0, 1, 2]);
+        clear_parse_observer();
+
+        // Other tests may parse bookmarks while the observer is registered
+        let outcomes = observer.outcomes.lock().unwrap();
+        let success = outcomes
+            .iter()
+            .find(|outcome| outcome.data_size == buffer.len())
+            .unwrap();
+        assert!(success.success);
+        assert_eq!(success.warning_count, 0);
+        assert!(success.error.is_none());
+
+        let failure = outcomes
+            .iter()
+            .find(|outcome| outcome.data_size == 3)
+            .unwrap();
+        assert!(!failure.success);
+        assert_eq!(failure.error.as_deref(), Some("Incorrect bookmark header"));
+    }
+}
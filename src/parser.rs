@@ -1,14 +1,409 @@
-use log::{error, info};
+use std::{fs, io::Read, panic::catch_unwind, path::Path, str::from_utf8, time::Instant};
 
-use crate::{bookmark::BookmarkData, error::BookmarkError};
+use log::{error, info, warn};
+use nom::error::ErrorKind;
+
+use crate::{
+    bookmark::{is_offset_before_data, BookmarkData},
+    error::{BookmarkError, FileContext, ParseFailure},
+    locale::english_format,
+    observer::{notify, ParseOutcome},
+    path_policy::PathPolicy,
+};
+
+/// Default number of nested bookmark levels to parse
+pub const DEFAULT_MAX_DEPTH: usize = 4;
+
+/// How to handle a record type that appears more than once in a bookmark
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DuplicatePolicy {
+    /// Keep the value of the first record
+    First,
+    /// Keep the value of the last record
+    #[default]
+    Last,
+    /// Fail with `BookmarkError::DuplicateRecord`
+    Error,
+    /// Keep the first record and parse each duplicate into `duplicate_records`
+    CollectAll,
+}
+
+/// Maximum accepted record data length by data type. Longer records are truncated and listed in `truncated_records`
+/// instead of being fully copied. Only the size of each copy is limited, the total size of array elements that reference
+/// the same record many times is capped by `ResourceLimits::max_decoded_bytes`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LengthLimits {
+    pub string: usize, // String and URL records
+    pub data: usize,   // Data records (including embedded bookmarks)
+}
+
+impl Default for LengthLimits {
+    fn default() -> Self {
+        LengthLimits {
+            string: usize::MAX,
+            data: usize::MAX,
+        }
+    }
+}
+
+impl LengthLimits {
+    /// Limits for parsing untrusted bookmarks in bulk: strings up to 64 KiB and data up to 1 MiB
+    pub const BOUNDED: LengthLimits = LengthLimits {
+        string: 64 * 1024,
+        data: 1024 * 1024,
+    };
+
+    /// Get the record data kept for a data type. Strings are truncated on a UTF-8 character boundary
+    pub(crate) fn truncate<'a>(&self, data_type: u32, data: &'a [u8]) -> &'a [u8] {
+        let limit = match data_type {
+            BookmarkData::STRING_TYPE | BookmarkData::URL => self.string,
+            BookmarkData::DATA_TYPE => self.data,
+            _ => return data,
+        };
+        let Some(truncated) = data.get(..limit) else {
+            return data;
+        };
+        if data_type == BookmarkData::DATA_TYPE {
+            return truncated;
+        }
+        match from_utf8(truncated) {
+            // Drop a character that was split by the limit
+            Err(err) if err.error_len().is_none() => &truncated[..err.valid_up_to()],
+            _ => truncated,
+        }
+    }
+}
+
+/// Caps on untrusted bookmark data. Record counts and sizes are checked before records are read, array elements are
+/// checked before they are decoded. Bookmarks (including nested bookmarks) exceeding a limit fail with
+/// `BookmarkError::ResourceLimit`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResourceLimits {
+    pub max_records: usize,        // TOC records across every chained TOC
+    pub max_record_size: usize,    // Declared data length of a TOC record
+    pub max_size: usize,           // Size of the bookmark data
+    pub max_array_elements: usize, // Elements of a single array record
+    pub max_decoded_bytes: usize, // Record data decoded for array elements, across every array of a bookmark
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        ResourceLimits {
+            max_records: usize::MAX,
+            max_record_size: usize::MAX,
+            max_size: usize::MAX,
+            max_array_elements: usize::MAX,
+            max_decoded_bytes: usize::MAX,
+        }
+    }
+}
+
+impl ResourceLimits {
+    /// Limits for parsing untrusted bookmarks in bulk: 4096 records, records up to 16 MiB, bookmarks up to 64 MiB,
+    /// 4096 elements per array and 64 MiB of array element data
+    pub const BOUNDED: ResourceLimits = ResourceLimits {
+        max_records: 4096,
+        max_record_size: 16 * 1024 * 1024,
+        max_size: 64 * 1024 * 1024,
+        max_array_elements: 4096,
+        max_decoded_bytes: 64 * 1024 * 1024,
+    };
+}
 
 /// Parse provided bookmark data
 pub fn parse_bookmark(data: &[u8]) -> Result<BookmarkData, BookmarkError> {
+    BookmarkParser::new().parse(data)
+}
+
+/// Parse provided bookmark data, never panicking
+///
+/// The parser is checked against mutated samples to not panic, this additionally catches any panic
+/// (including from a registered parse observer) and returns `BookmarkError::BadBookmarkData` instead.
+/// Builds using `panic = "abort"` cannot catch panics.
+pub fn parse_bookmark_no_panic(data: &[u8]) -> Result<BookmarkData, BookmarkError> {
+    match catch_unwind(|| parse_bookmark(data)) {
+        Ok(results) => results,
+        Err(_) => {
+            error!("Panic while parsing bookmark data");
+            Err(BookmarkError::BadBookmarkData)
+        }
+    }
+}
+
+/// How to handle records that cannot be read or are not understood
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ParseMode {
+    /// Fail if a record cannot be read, list other problems in `warnings`
+    #[default]
+    Normal,
+    /// Skip records that cannot be read instead of failing, listing them in `warnings`. Unknown header versions are
+    /// accepted
+    Lenient,
+    /// Fail with `BookmarkError::StrictViolation` on unknown records, length inconsistencies and warnings
+    Strict,
+}
+
+/// Options used while parsing a bookmark and its nested bookmarks
+#[derive(Debug, Clone, Copy)]
+pub struct ParserOptions {
+    pub max_depth: usize,                  // Nested bookmark levels left to parse
+    pub duplicate_policy: DuplicatePolicy, // Handling of duplicated record types
+    pub preserve_unknown: bool,            // Keep records that did not populate a field
+    pub preserve_raw: bool,                // Keep the raw bytes and decoded value of every record
+    pub lossy_strings: bool, // Replace invalid UTF-8 in strings instead of dropping the field
+    pub limits: LengthLimits, // Maximum record data lengths
+    pub resource_limits: ResourceLimits, // Maximum record count and sizes
+    pub path_policy: Option<PathPolicy>, // Hook called for every path component
+    pub mode: ParseMode,     // Handling of unreadable or unknown records
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        ParserOptions {
+            max_depth: DEFAULT_MAX_DEPTH,
+            duplicate_policy: DuplicatePolicy::default(),
+            preserve_unknown: false,
+            preserve_raw: false,
+            lossy_strings: false,
+            limits: LengthLimits::default(),
+            resource_limits: ResourceLimits::default(),
+            path_policy: None,
+            mode: ParseMode::default(),
+        }
+    }
+}
+
+/// Configurable bookmark parser. Parse options are set on the parser instead of separate `parse_bookmark_*`
+/// functions, `parse_bookmark` and the file, reader and base64 shorthands use the default options
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BookmarkParser {
+    options: ParserOptions,
+}
+
+impl BookmarkParser {
+    /// Create a parser with the default options
+    pub fn new() -> BookmarkParser {
+        BookmarkParser::default()
+    }
+
+    /// Create a parser with the provided options
+    pub fn with_options(options: ParserOptions) -> BookmarkParser {
+        BookmarkParser { options }
+    }
+
+    /// Get the parser options
+    pub fn options(&self) -> &ParserOptions {
+        &self.options
+    }
+
+    /// Set the number of nested bookmark levels to parse
+    pub fn max_depth(mut self, max_depth: usize) -> BookmarkParser {
+        self.options.max_depth = max_depth;
+        self
+    }
+
+    /// Set the handling of duplicated record types
+    pub fn duplicate_policy(mut self, policy: DuplicatePolicy) -> BookmarkParser {
+        self.options.duplicate_policy = policy;
+        self
+    }
+
+    /// Keep the raw bytes of every record that did not populate a field in `unknown_records`
+    pub fn preserve_unknown(mut self, preserve_unknown: bool) -> BookmarkParser {
+        self.options.preserve_unknown = preserve_unknown;
+        self
+    }
+
+    /// Keep a copy of every record, available from `BookmarkData::raw_records` and `BookmarkData::records`. Records
+    /// are not copied by default
+    pub fn preserve_raw(mut self, preserve_raw: bool) -> BookmarkParser {
+        self.options.preserve_raw = preserve_raw;
+        self
+    }
+
+    /// Decode strings with invalid UTF-8 (ex: MacRoman volume names) lossily, keeping the original bytes in
+    /// `lossy_strings`
+    pub fn lossy_strings(mut self, lossy_strings: bool) -> BookmarkParser {
+        self.options.lossy_strings = lossy_strings;
+        self
+    }
+
+    /// Set the maximum record data lengths
+    pub fn length_limits(mut self, limits: LengthLimits) -> BookmarkParser {
+        self.options.limits = limits;
+        self
+    }
+
+    /// Set the maximum record count and sizes
+    pub fn resource_limits(mut self, resource_limits: ResourceLimits) -> BookmarkParser {
+        self.options.resource_limits = resource_limits;
+        self
+    }
+
+    /// Set the hook called for every target path component
+    pub fn path_policy(mut self, path_policy: PathPolicy) -> BookmarkParser {
+        self.options.path_policy = Some(path_policy);
+        self
+    }
+
+    /// Set the handling of unreadable or unknown records
+    pub fn mode(mut self, mode: ParseMode) -> BookmarkParser {
+        self.options.mode = mode;
+        self
+    }
+
+    /// Parse provided bookmark data
+    pub fn parse(&self, data: &[u8]) -> Result<BookmarkData, BookmarkError> {
+        parse_bookmark_observed(data, &self.options)
+    }
+
+    /// Read and parse a bookmark file. Files larger than the `max_size` resource limit are not read. Errors are
+    /// returned as `BookmarkError::BadFile` with the path
+    pub fn parse_file(&self, path: impl AsRef<Path>) -> Result<BookmarkData, BookmarkError> {
+        let path = path.as_ref();
+        let file_error = |error: BookmarkError| {
+            BookmarkError::BadFile(FileContext {
+                path: path.to_path_buf(),
+                error: Box::new(error),
+            })
+        };
+
+        let size = match fs::metadata(path) {
+            Ok(metadata) if metadata.is_file() => metadata.len(),
+            Ok(_) => {
+                error!("{:?} is not a file", path);
+                return Err(file_error(BookmarkError::ReadError));
+            }
+            Err(err) => {
+                error!("failed to read {:?}: {:?}", path, err);
+                return Err(file_error(BookmarkError::ReadError));
+            }
+        };
+        if usize::try_from(size).map_or(true, |size| size > self.options.resource_limits.max_size) {
+            error!("{:?} is larger than the size limit: {}", path, size);
+            return Err(file_error(BookmarkError::ResourceLimit));
+        }
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(err) => {
+                error!("failed to read {:?}: {:?}", path, err);
+                return Err(file_error(BookmarkError::ReadError));
+            }
+        };
+        self.parse(&data).map_err(file_error)
+    }
+
+    /// Read and parse a bookmark from a reader. Only the header and the total size it declares are read, the reader is
+    /// left at the end of the bookmark. Declared sizes over the `max_size` resource limit fail before the data is read
+    pub fn parse_reader<R: Read>(&self, mut reader: R) -> Result<BookmarkData, BookmarkError> {
+        let mut data = Vec::new();
+        if let Err(err) = reader
+            .by_ref()
+            .take(u64::from(BookmarkData::HEADER_SIZE))
+            .read_to_end(&mut data)
+        {
+            error!("failed to read bookmark header: {:?}", err);
+            return Err(BookmarkError::ReadError);
+        }
+        // Short or invalid headers are reported by the parser
+        let Ok((_, header)) = BookmarkData::parse_bookmark_header(&data) else {
+            return self.parse(&data);
+        };
+
+        let size = header.bookmark_data_length as usize;
+        if size > self.options.resource_limits.max_size {
+            error!("Bookmark size {} exceeds the size limit", size);
+            return Err(BookmarkError::ResourceLimit);
+        }
+        let remaining = size.saturating_sub(data.len()) as u64;
+        if let Err(err) = reader.take(remaining).read_to_end(&mut data) {
+            error!("failed to read bookmark data: {:?}", err);
+            return Err(BookmarkError::ReadError);
+        }
+        self.parse(&data)
+    }
+
+    /// Decode base64 encoded bookmark data and parse it. Whitespace (ex: line breaks in XML plists) is ignored, the
+    /// standard and URL safe alphabets are accepted with or without padding
+    #[cfg(feature = "base64")]
+    pub fn parse_base64(&self, encoded: &str) -> Result<BookmarkData, BookmarkError> {
+        use base64::{
+            alphabet,
+            engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+            Engine,
+        };
+
+        let config =
+            GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent);
+        let encoded: String = encoded.split_whitespace().collect();
+        let alphabet = if encoded.contains(['-', '_']) {
+            &alphabet::URL_SAFE
+        } else {
+            &alphabet::STANDARD
+        };
+        match GeneralPurpose::new(alphabet, config).decode(encoded) {
+            Ok(data) => self.parse(&data),
+            Err(err) => {
+                error!("failed to decode base64 bookmark: {:?}", err);
+                Err(BookmarkError::InvalidBase64Data)
+            }
+        }
+    }
+}
+
+/// Read and parse a bookmark from a reader with the default options, see `BookmarkParser::parse_reader`
+pub fn parse_bookmark_reader<R: Read>(reader: R) -> Result<BookmarkData, BookmarkError> {
+    BookmarkParser::new().parse_reader(reader)
+}
+
+/// Decode base64 encoded bookmark data and parse it with the default options, see `BookmarkParser::parse_base64`
+#[cfg(feature = "base64")]
+pub fn parse_bookmark_base64(encoded: &str) -> Result<BookmarkData, BookmarkError> {
+    BookmarkParser::new().parse_base64(encoded)
+}
+
+/// Read and parse a bookmark file with the default options
+pub fn parse_bookmark_file(path: impl AsRef<Path>) -> Result<BookmarkData, BookmarkError> {
+    BookmarkParser::new().parse_file(path)
+}
+
+/// Parse bookmark data and notify the parse observer
+fn parse_bookmark_observed(
+    data: &[u8],
+    settings: &ParserOptions,
+) -> Result<BookmarkData, BookmarkError> {
+    let start = Instant::now();
+    let results = parse_nested_bookmark(data, settings);
+
+    let outcome = ParseOutcome {
+        success: results.is_ok(),
+        duration: start.elapsed(),
+        data_size: data.len(),
+        warning_count: results.as_ref().map_or(0, total_warnings),
+        error: results.as_ref().err().map(|err| err.to_string()),
+    };
+    notify(&outcome);
+    results
+}
+
+/// Parse bookmark data without notifying the parse observer
+pub(crate) fn parse_nested_bookmark(
+    data: &[u8],
+    settings: &ParserOptions,
+) -> Result<BookmarkData, BookmarkError> {
     let header_size = 48;
     if data.len() < header_size {
         error!("Data size less than bookmark header size");
         return Err(BookmarkError::BadHeader);
     }
+    if data.len() > settings.resource_limits.max_size {
+        error!(
+            "Data size {} larger than the maximum size {}",
+            data.len(),
+            settings.resource_limits.max_size
+        );
+        return Err(BookmarkError::ResourceLimit);
+    }
 
     // Read first 48 bytes of bookmark header
     let header_results = BookmarkData::parse_bookmark_header(data);
@@ -19,33 +414,130 @@ pub fn parse_bookmark(data: &[u8]) -> Result<BookmarkData, BookmarkError> {
             return Err(BookmarkError::BadHeader);
         }
     };
-    let book_sig: u32 = 1802465122;
-    let book_data_offset: u32 = 48;
-
-    // Check for bookmark signature and expected offset
-    if header.signature != book_sig || header.bookmark_data_offset != book_data_offset {
-        error!("Data is not a bookmark got incorrect signature and offset");
-        info!(
-            "Expected signature: {}, got: {}.",
-            book_sig, header.signature
-        );
-        info!(
-            "Expected offset: {}, got: {}.",
-            book_data_offset, header.bookmark_data_offset
-        );
-        return Err(BookmarkError::BadHeader);
+    if let Err(err) = header.validate() {
+        match err {
+            BookmarkError::UnsupportedVersion(version) if settings.mode == ParseMode::Lenient => {
+                warn!("Unsupported bookmark version: {:#x}", version);
+            }
+            _ => {
+                error!("Data is not a bookmark: {:?}", err);
+                info!(
+                    "Got signature: {:#x}, version: {:#x}, offset: {}",
+                    header.signature, header.version, header.bookmark_data_offset
+                );
+                return Err(err);
+            }
+        }
     }
 
-    let data_results = BookmarkData::parse_bookmark_data(bookmark_data);
+    let data_results = BookmarkData::parse_bookmark_data_nested(bookmark_data, settings);
     match data_results {
-        Ok((_, bookmark_results)) => Ok(bookmark_results),
+        Ok((_, bookmark_results)) => {
+            if bookmark_results.offset_before_data {
+                error!("Bookmark record offset points before the bookmark data");
+                return Err(BookmarkError::BadBookmarkData);
+            }
+            if let Some(context) = bookmark_results.failed_record {
+                error!("failed to get bookmark record: {:?}", context);
+                return Err(BookmarkError::BadRecord(context));
+            }
+            if settings.duplicate_policy == DuplicatePolicy::Error
+                && !bookmark_results.record_conflicts().is_empty()
+            {
+                error!("Bookmark contains duplicate record types");
+                return Err(BookmarkError::DuplicateRecord);
+            }
+            if bookmark_results.exceeded_limit {
+                error!("Bookmark exceeds the resource limits");
+                return Err(BookmarkError::ResourceLimit);
+            }
+            if bookmark_results.rejected_path {
+                error!("Bookmark path rejected by path policy");
+                return Err(BookmarkError::RejectedPath);
+            }
+            if settings.mode == ParseMode::Strict {
+                let violations =
+                    strict_violations(&bookmark_results, header.bookmark_data_length, data.len());
+                if !violations.is_empty() {
+                    error!("Bookmark failed strict validation: {:?}", violations);
+                    return Err(BookmarkError::StrictViolation);
+                }
+            }
+            Ok(bookmark_results)
+        }
         Err(err) => {
             error!("failed to get bookmark data: {:?}", err);
-            Err(BookmarkError::BadBookmarkData)
+            Err(toc_error(data, &err))
         }
     }
 }
 
+/// Get the error for a TOC that could not be read
+fn toc_error(data: &[u8], err: &nom::Err<nom::error::Error<&[u8]>>) -> BookmarkError {
+    if is_offset_before_data(err) {
+        return BookmarkError::BadBookmarkData;
+    }
+    let failure = ParseFailure::from_nom(data, 0, err);
+    match failure.kind {
+        ErrorKind::Eof => BookmarkError::TruncatedData(failure),
+        // Offsets before the start of the data fail verification
+        ErrorKind::Verify => BookmarkError::InvalidOffset(failure),
+        _ => BookmarkError::BadToc(failure),
+    }
+}
+
+/// Get every unknown record, length inconsistency and warning of a bookmark for strict validation
+fn strict_violations(bookmark: &BookmarkData, header_length: u32, data_size: usize) -> Vec<String> {
+    let mut violations = Vec::new();
+    if header_length as usize != data_size {
+        violations.push(english_format(
+            "strict.header_length",
+            &[
+                ("length", &header_length.to_string()),
+                ("size", &data_size.to_string()),
+            ],
+        ));
+    }
+    for record in &bookmark.record_offsets {
+        let record_type = format!("{:#x}", record.record_type);
+        if !BookmarkData::KNOWN_RECORD_TYPES.contains(&record.record_type) {
+            violations.push(english_format(
+                "strict.unknown_record_type",
+                &[("record_type", &record_type)],
+            ));
+        }
+        if !BookmarkData::KNOWN_DATA_TYPES.contains(&record.data_type) {
+            violations.push(english_format(
+                "strict.unknown_data_type",
+                &[
+                    ("data_type", &format!("{:#x}", record.data_type)),
+                    ("record_type", &record_type),
+                ],
+            ));
+        }
+    }
+    for lossy in &bookmark.lossy_strings {
+        violations.push(english_format(
+            "strict.invalid_utf8",
+            &[("record_type", &format!("{:#x}", lossy.record_type))],
+        ));
+    }
+    for warning in &bookmark.warnings {
+        violations.push(warning.message.clone());
+    }
+    violations
+}
+
+/// Count warnings for a bookmark and all of its nested bookmarks
+fn total_warnings(bookmark: &BookmarkData) -> usize {
+    bookmark.warnings.len()
+        + bookmark
+            .nested_bookmarks
+            .iter()
+            .map(|nested| total_warnings(&nested.bookmark))
+            .sum::<usize>()
+}
+
 #[test]
 fn test_parse_bookmark() {
     let data = [
@@ -94,14 +586,14 @@ fn test_parse_bookmark() {
     let volume_uuid = "96FB41C0-6CE9-4DA2-8435-35BC19C735A3";
     let volume_size = 2000662327296;
     let volume_flag = [4294967425, 4294972399, 0];
-    let volume_root = true;
-    let localized_name = String::new();
+    let volume_root = Some(true);
+    let localized_name = None;
     let target_flags = [1, 15, 0];
-    let username = "puffycid";
+    let username = Some("puffycid");
     let folder_index = 2;
-    let uid = 501;
-    let creation_options = 671094784;
-    let security_extension = String::new();
+    let uid = Some(501);
+    let creation_options = Some(671094784);
+    let security_extension = None;
 
     let cnid_path_len = 4;
     let target_creation = 677388100.0747445;
@@ -123,13 +615,32 @@ fn test_parse_bookmark() {
     assert_eq!(bookmark.volume_size, volume_size);
     assert_eq!(bookmark.volume_flag, volume_flag);
     assert_eq!(bookmark.volume_root, volume_root);
-    assert_eq!(bookmark.localized_name, localized_name);
+    assert_eq!(bookmark.localized_name.as_deref(), localized_name);
     assert_eq!(bookmark.target_flags, target_flags);
-    assert_eq!(bookmark.username, username);
+    assert_eq!(bookmark.username.as_deref(), username);
     assert_eq!(bookmark.folder_index, folder_index);
     assert_eq!(bookmark.uid, uid);
     assert_eq!(bookmark.creation_options, creation_options);
-    assert_eq!(bookmark.security_extension_rw, security_extension);
-    assert_eq!(bookmark.security_extension_ro, security_extension);
-    assert_eq!(bookmark.file_ref_flag, false);
+    assert_eq!(
+        bookmark.security_extension_rw.as_deref(),
+        security_extension
+    );
+    assert_eq!(
+        bookmark.security_extension_ro.as_deref(),
+        security_extension
+    );
+    assert_eq!(bookmark.file_ref_flag, None);
+}
+
+#[test]
+fn test_length_limits_truncate() {
+    let limits = LengthLimits { string: 4, data: 2 };
+    // "é" is two bytes and would be split by the limit
+    assert_eq!(
+        limits.truncate(BookmarkData::STRING_TYPE, "abcé".as_bytes()),
+        b"abc"
+    );
+    assert_eq!(limits.truncate(BookmarkData::URL, b"file:///"), b"file");
+    assert_eq!(limits.truncate(BookmarkData::DATA_TYPE, &[1, 2, 3]), [1, 2]);
+    assert_eq!(limits.truncate(BookmarkData::DATE, &[1, 2, 3]), [1, 2, 3]);
 }
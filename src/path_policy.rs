@@ -0,0 +1,107 @@
+//! Validate decoded path components
+//!
+//! A path policy is called for every target path component while parsing and can keep, replace or reject the
+//! component. Filenames using Unicode tricks (bidirectional overrides hiding a file extension, control characters,
+//! Cyrillic or Greek letters mixed into Latin names) are common in malware lures, the built-in policies flag them.
+
+/// Result of a path policy for a path component
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathAction {
+    /// Keep the component as is
+    Keep,
+    /// Replace the component
+    Replace(String),
+    /// Fail parsing with `BookmarkError::RejectedPath`
+    Reject,
+}
+
+/// Hook called for every decoded path component
+pub type PathPolicy = fn(&str) -> PathAction;
+
+/// Reject components containing control characters, bidirectional formatting characters or mixed scripts
+pub fn reject_suspicious(component: &str) -> PathAction {
+    if is_suspicious(component) {
+        return PathAction::Reject;
+    }
+    PathAction::Keep
+}
+
+/// Replace control characters, bidirectional formatting characters and (in mixed script components) non-ASCII
+/// letters with `\u{..}` escapes so the component is displayed as stored
+pub fn escape_suspicious(component: &str) -> PathAction {
+    if !is_suspicious(component) {
+        return PathAction::Keep;
+    }
+    let mixed = is_mixed_script(component);
+    let escaped = component
+        .chars()
+        .map(|character| {
+            if is_hidden_character(character) || (mixed && is_confusable(character)) {
+                format!("\\u{{{:x}}}", character as u32)
+            } else {
+                character.to_string()
+            }
+        })
+        .collect();
+    PathAction::Replace(escaped)
+}
+
+/// Check if a component contains control characters, bidirectional formatting characters or mixed scripts
+pub fn is_suspicious(component: &str) -> bool {
+    component.chars().any(is_hidden_character) || is_mixed_script(component)
+}
+
+/// Control characters and invisible characters that change how text is displayed
+fn is_hidden_character(character: char) -> bool {
+    character.is_control()
+        || matches!(
+            character,
+            '\u{200b}'..='\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}' | '\u{061c}' | '\u{feff}'
+        )
+}
+
+/// Cyrillic and Greek letters that can pass for Latin letters (ex: Cyrillic `а` in `pаypal`)
+fn is_confusable(character: char) -> bool {
+    matches!(character, '\u{0370}'..='\u{03ff}' | '\u{0400}'..='\u{04ff}')
+}
+
+/// Latin letters mixed with Cyrillic or Greek letters in the same component
+fn is_mixed_script(component: &str) -> bool {
+    component
+        .chars()
+        .any(|character| character.is_ascii_alphabetic())
+        && component.chars().any(is_confusable)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape_suspicious, is_suspicious, reject_suspicious, PathAction};
+
+    #[test]
+    fn test_is_suspicious() {
+        assert!(!is_suspicious("Syncthing.app"));
+        assert!(!is_suspicious("Документы"));
+        assert!(!is_suspicious("résumé.pdf"));
+        assert!(is_suspicious("invoice\u{202e}fdp.app"));
+        assert!(is_suspicious("line\nbreak"));
+        assert!(is_suspicious("p\u{0430}ypal.app"));
+    }
+
+    #[test]
+    fn test_policies() {
+        assert_eq!(reject_suspicious("Applications"), PathAction::Keep);
+        assert_eq!(
+            reject_suspicious("invoice\u{202e}fdp.app"),
+            PathAction::Reject
+        );
+        assert_eq!(escape_suspicious("Applications"), PathAction::Keep);
+        assert_eq!(
+            escape_suspicious("invoice\u{202e}fdp.app"),
+            PathAction::Replace(String::from("invoice\\u{202e}fdp.app"))
+        );
+        assert_eq!(
+            escape_suspicious("p\u{0430}ypal.app"),
+            PathAction::Replace(String::from("p\\u{430}ypal.app"))
+        );
+    }
+}
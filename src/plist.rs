@@ -0,0 +1,195 @@
+//! Extract and parse bookmarks stored in property lists
+//!
+//! Most bookmarks live inside plists (ex: Safari `Downloads.plist`, `com.apple.backgroundtaskmanagementagent`,
+//! sidebar lists). XML and binary plists are walked recursively and every Data value starting with the bookmark
+//! signature is parsed.
+
+use std::{
+    fs,
+    io::Cursor,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::{error, warn};
+use plist::Value;
+use serde::Serialize;
+
+use crate::{
+    bookmark::BookmarkData,
+    error::BookmarkError,
+    extract::{bookmark_range, locate_blob, ByteRange},
+    parser::parse_bookmark,
+    types::CocoaTime,
+};
+
+#[derive(Debug, Serialize)]
+pub struct PlistBookmark {
+    pub key_path: String,         // Dotted path to the bookmark data
+    pub range: Option<ByteRange>, // Bookmark bytes within the plist (binary plists only)
+    pub bookmark: BookmarkData,   // Parsed bookmark
+}
+
+/// Parse every bookmark stored in a plist (XML or binary). Data values that fail to parse are skipped
+pub fn parse_bookmarks_from_plist(data: &[u8]) -> Result<Vec<PlistBookmark>, BookmarkError> {
+    let value = match Value::from_reader(Cursor::new(data)) {
+        Ok(value) => value,
+        Err(err) => {
+            error!("failed to parse plist: {:?}", err);
+            return Err(BookmarkError::InvalidPlistData);
+        }
+    };
+
+    let mut found = Vec::new();
+    find_bookmark_data("", &value, &mut found);
+
+    let mut bookmarks = Vec::new();
+    for (key_path, bookmark_data) in found {
+        // XML plists store the bookmark base64 encoded, only binary plists contain the original bytes
+        let range = bookmark_range(bookmark_data, 0)
+            .and_then(|range| range.slice(bookmark_data))
+            .and_then(|blob| locate_blob(data, blob));
+        match parse_bookmark(bookmark_data) {
+            Ok(bookmark) => bookmarks.push(PlistBookmark {
+                key_path,
+                range,
+                bookmark,
+            }),
+            Err(err) => warn!("Failed to parse bookmark at {}: {:?}", key_path, err),
+        }
+    }
+    Ok(bookmarks)
+}
+
+/// Read a plist file and parse every bookmark stored in it
+pub fn parse_bookmarks_from_plist_file(
+    path: impl AsRef<Path>,
+) -> Result<Vec<PlistBookmark>, BookmarkError> {
+    match fs::read(path.as_ref()) {
+        Ok(data) => parse_bookmarks_from_plist(&data),
+        Err(err) => {
+            error!("failed to read plist {:?}: {:?}", path.as_ref(), err);
+            Err(BookmarkError::InvalidPlistData)
+        }
+    }
+}
+
+/// Recursively collect Data values that start with the bookmark signature
+pub(crate) fn find_bookmark_data<'a>(
+    key_path: &str,
+    value: &'a Value,
+    found: &mut Vec<(String, &'a [u8])>,
+) {
+    let child_path = |key: &dyn std::fmt::Display| {
+        if key_path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{key_path}.{key}")
+        }
+    };
+    match value {
+        Value::Data(data) if data.starts_with(BookmarkData::BOOKMARK_SIGNATURE) => {
+            found.push((key_path.to_string(), data))
+        }
+        Value::Array(values) => {
+            for (index, value) in values.iter().enumerate() {
+                find_bookmark_data(&child_path(&index), value, found);
+            }
+        }
+        Value::Dictionary(values) => {
+            for (key, value) in values {
+                find_bookmark_data(&child_path(key), value, found);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Convert a plist Date value to a timestamp. `None` if the value is not a Date
+pub(crate) fn plist_date(value: &Value) -> Option<CocoaTime> {
+    let date = SystemTime::from(value.as_date()?);
+    let seconds = match date.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs_f64(),
+        Err(err) => -err.duration().as_secs_f64(),
+    };
+    Some(CocoaTime::from_unix(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_bookmarks_from_plist, parse_bookmarks_from_plist_file};
+    use plist::{Dictionary, Value};
+    use std::{fs, path::PathBuf};
+
+    // Safari Downloads.plist layout
+    fn test_plist() -> Value {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/downloads.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+
+        let mut entry = Dictionary::new();
+        entry.insert(
+            String::from("DownloadEntryBookmarkBlob"),
+            Value::Data(buffer),
+        );
+        entry.insert(
+            String::from("DownloadEntryURL"),
+            Value::String(String::from("https://example.com/file.pkg")),
+        );
+        let mut bad_entry = Dictionary::new();
+        bad_entry.insert(
+            String::from("DownloadEntryBookmarkBlob"),
+            Value::Data(b"book corrupted".to_vec()),
+        );
+
+        let mut downloads = Dictionary::new();
+        downloads.insert(
+            String::from("DownloadHistory"),
+            Value::Array(vec![Value::Dictionary(entry), Value::Dictionary(bad_entry)]),
+        );
+        Value::Dictionary(downloads)
+    }
+
+    #[test]
+    fn test_parse_bookmarks_from_plist() {
+        let mut xml = Vec::new();
+        test_plist().to_writer_xml(&mut xml).unwrap();
+        let bookmarks = parse_bookmarks_from_plist(&xml).unwrap();
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(
+            bookmarks[0].key_path,
+            "DownloadHistory.0.DownloadEntryBookmarkBlob"
+        );
+        assert_eq!(bookmarks[0].range, None);
+        assert!(!bookmarks[0].bookmark.path.is_empty());
+
+        let mut binary = Vec::new();
+        test_plist().to_writer_binary(&mut binary).unwrap();
+        let bookmarks = parse_bookmarks_from_plist(&binary).unwrap();
+        assert_eq!(bookmarks.len(), 1);
+        let range = bookmarks[0].range.unwrap();
+        assert!(range.slice(&binary).unwrap().starts_with(b"book"));
+
+        let path = std::env::temp_dir().join("macos_bookmarks_test_downloads.plist");
+        fs::write(&path, &binary).unwrap();
+        let bookmarks = parse_bookmarks_from_plist_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(bookmarks.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_bookmarks_from_plist_bad_data() {
+        assert!(parse_bookmarks_from_plist(b"not a plist").is_err());
+        assert!(parse_bookmarks_from_plist_file("/does/not/exist.plist").is_err());
+
+        // Bookmark data stored directly as the root value
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let mut xml = Vec::new();
+        Value::Data(fs::read(test_location).unwrap())
+            .to_writer_xml(&mut xml)
+            .unwrap();
+        let bookmarks = parse_bookmarks_from_plist(&xml).unwrap();
+        assert_eq!(bookmarks[0].key_path, "");
+    }
+}
@@ -0,0 +1,189 @@
+//! Parse recent items from `com.apple.recentitems.plist` (macOS 10.12 and older)
+//!
+//! Recent applications, documents, servers and hosts are listed under `RecentApplications`, `RecentDocuments`,
+//! `RecentServers` and `Hosts` -> `CustomListItems`. Items use the same layout as LoginItems: a `Name` and the target
+//! as bookmark data (`Bookmark`) or a classic alias record (`Alias`). Hosts only have a `URL`.
+
+use std::{fs, io::Cursor, path::Path};
+
+use log::error;
+use plist::{Dictionary, Value};
+use serde::Serialize;
+
+use crate::{
+    alias::AliasData, bookmark::BookmarkData, error::BookmarkError, login_items::item_target,
+};
+
+#[derive(Debug, Serialize)]
+pub struct RecentItem {
+    pub category: String,               // List of the item (ex: Hosts)
+    pub name: String,                   // Name of the item
+    pub url: Option<String>,            // URL of the item (hosts)
+    pub bookmark: Option<BookmarkData>, // Target bookmark
+    pub alias: Option<AliasData>,       // Classic alias record
+}
+
+/// Lists in `com.apple.recentitems.plist`
+const CATEGORIES: [&str; 4] = [
+    "RecentApplications",
+    "RecentDocuments",
+    "RecentServers",
+    "Hosts",
+];
+
+/// Parse every item in a `com.apple.recentitems.plist` file (XML or binary). Items with target data that fails to
+/// parse are kept without a bookmark
+pub fn parse_recent_items(data: &[u8]) -> Result<Vec<RecentItem>, BookmarkError> {
+    let plist = match Value::from_reader(Cursor::new(data)) {
+        Ok(Value::Dictionary(plist)) => plist,
+        Ok(_) => {
+            error!("RecentItems plist is not a dictionary");
+            return Err(BookmarkError::InvalidPlistData);
+        }
+        Err(err) => {
+            error!("failed to parse RecentItems plist: {:?}", err);
+            return Err(BookmarkError::InvalidPlistData);
+        }
+    };
+
+    let mut items = Vec::new();
+    for category in CATEGORIES {
+        let entries = plist
+            .get(category)
+            .and_then(Value::as_dictionary)
+            .and_then(|list| list.get("CustomListItems"))
+            .and_then(Value::as_array)
+            .map_or(&[][..], Vec::as_slice);
+        items.extend(
+            entries
+                .iter()
+                .filter_map(Value::as_dictionary)
+                .map(|entry| recent_item(category, entry)),
+        );
+    }
+    Ok(items)
+}
+
+/// Read a `com.apple.recentitems.plist` file and parse every item
+pub fn parse_recent_items_file(path: impl AsRef<Path>) -> Result<Vec<RecentItem>, BookmarkError> {
+    match fs::read(path.as_ref()) {
+        Ok(data) => parse_recent_items(&data),
+        Err(err) => {
+            error!(
+                "failed to read RecentItems plist {:?}: {:?}",
+                path.as_ref(),
+                err
+            );
+            Err(BookmarkError::InvalidPlistData)
+        }
+    }
+}
+
+/// Parse a single `CustomListItems` entry
+fn recent_item(category: &str, entry: &Dictionary) -> RecentItem {
+    let name = entry
+        .get("Name")
+        .and_then(Value::as_string)
+        .unwrap_or_default()
+        .to_string();
+    let (bookmark, alias) = item_target(entry, &name);
+
+    RecentItem {
+        category: category.to_string(),
+        url: entry
+            .get("URL")
+            .and_then(Value::as_string)
+            .map(str::to_string),
+        name,
+        bookmark,
+        alias,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_recent_items, parse_recent_items_file};
+    use plist::{Dictionary, Value};
+    use std::{fs, path::PathBuf};
+
+    fn list(items: Vec<Dictionary>) -> Value {
+        let mut list = Dictionary::new();
+        list.insert(
+            String::from("CustomListItems"),
+            Value::Array(items.into_iter().map(Value::Dictionary).collect()),
+        );
+        Value::Dictionary(list)
+    }
+
+    fn test_recent_items() -> Vec<u8> {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let app = fs::read(test_location).unwrap();
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/downloads.bookmark");
+        let document = fs::read(test_location).unwrap();
+
+        let mut application = Dictionary::new();
+        application.insert(
+            String::from("Name"),
+            Value::String(String::from("Syncthing")),
+        );
+        application.insert(String::from("Bookmark"), Value::Data(app));
+        let mut recent_document = Dictionary::new();
+        recent_document.insert(
+            String::from("Name"),
+            Value::String(String::from("powershell-7.2.4-osx-x64.pkg")),
+        );
+        recent_document.insert(String::from("Alias"), Value::Data(document));
+        let mut host = Dictionary::new();
+        host.insert(
+            String::from("Name"),
+            Value::String(String::from("fileserver")),
+        );
+        host.insert(
+            String::from("URL"),
+            Value::String(String::from("smb://fileserver/share")),
+        );
+
+        let mut plist = Dictionary::new();
+        plist.insert(String::from("RecentApplications"), list(vec![application]));
+        plist.insert(String::from("RecentDocuments"), list(vec![recent_document]));
+        plist.insert(String::from("Hosts"), list(vec![host]));
+
+        let mut data = Vec::new();
+        Value::Dictionary(plist)
+            .to_writer_binary(&mut data)
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn test_parse_recent_items() {
+        let items = parse_recent_items(&test_recent_items()).unwrap();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].category, "RecentApplications");
+        assert_eq!(
+            items[0].bookmark.as_ref().unwrap().path,
+            ["Applications", "Syncthing.app"]
+        );
+        assert_eq!(items[1].category, "RecentDocuments");
+        assert_eq!(
+            items[1].bookmark.as_ref().unwrap().path.last().unwrap(),
+            "powershell-7.2.4-osx-x64.pkg"
+        );
+        assert_eq!(items[2].category, "Hosts");
+        assert_eq!(items[2].url.as_deref(), Some("smb://fileserver/share"));
+        assert!(items[2].bookmark.is_none());
+
+        let path = std::env::temp_dir().join("macos_bookmarks_test_recentitems.plist");
+        fs::write(&path, test_recent_items()).unwrap();
+        assert_eq!(parse_recent_items_file(&path).unwrap().len(), 3);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_recent_items_bad_data() {
+        assert!(parse_recent_items(b"not a plist").is_err());
+        assert!(parse_recent_items_file("/does/not/exist.plist").is_err());
+    }
+}
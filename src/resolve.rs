@@ -0,0 +1,316 @@
+//! Predict whether macOS would be able to resolve a bookmark
+//!
+//! The prediction combines the stored volume information, the CNID path, the creation options and (when a root
+//! directory such as a mounted disk image is provided) a check that the target path still exists.
+//! It is a heuristic to prioritize review, macOS may still resolve a moved target by its file ID.
+//! Target paths with components that would escape the root (absolute, `.`, `..` or containing a separator) are
+//! treated as missing instead of being checked against the analysis host's filesystem.
+//!
+//! Bookmarks with the file reference flag set (ex: aliases created by mac_alias) are resolved by their file ID
+//! first, so those bookmarks are scored on their CNID path and a missing target path only lowers the score.
+
+use std::path::{is_separator, Component, Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::{bookmark::BookmarkData, locale::Localization};
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub enum ResolutionOutcome {
+    WouldResolve,
+    WouldPromptForVolume,
+    WouldFail,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub enum ResolutionMode {
+    /// Resolve the target path first, falling back to the file ID
+    PathFirst,
+    /// Resolve the file ID (CNID) first, the target path is only used if the file ID is gone
+    FileReference,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ResolutionPrediction {
+    pub outcome: ResolutionOutcome, // Predicted resolution outcome
+    pub score: f64,                 // Likelihood of resolving (0.0 - 1.0)
+    pub reasons: Vec<String>,       // Reasons that lowered the score
+    pub mode: ResolutionMode,       // How the bookmark was interpreted
+    pub reference_style: bool,      // Bookmark resolves by file ID before path
+    pub explanation: String,        // How macOS resolves bookmarks in this mode
+}
+
+// NSURLBookmarkCreationWithSecurityScope
+const WITH_SECURITY_SCOPE: i32 = 0x800;
+
+impl ResolutionMode {
+    /// Describe how macOS resolves a bookmark in this mode
+    pub fn explanation(&self) -> String {
+        self.explanation_localized(&Localization::english())
+    }
+
+    /// Describe how macOS resolves a bookmark in this mode, using a message table
+    pub fn explanation_localized(&self, locale: &Localization) -> String {
+        match self {
+            ResolutionMode::PathFirst => locale.message("resolve.explanation_path_first"),
+            ResolutionMode::FileReference => locale.message("resolve.explanation_file_reference"),
+        }
+    }
+}
+
+impl BookmarkData {
+    /// Get how macOS would resolve the bookmark, based on the file reference flag
+    pub fn resolution_mode(&self) -> ResolutionMode {
+        if self.file_ref_flag == Some(true) {
+            ResolutionMode::FileReference
+        } else {
+            ResolutionMode::PathFirst
+        }
+    }
+
+    /// Predict if the bookmark would resolve. If `root` is provided the target path is checked relative to it
+    pub fn predict_resolution(&self, root: Option<&Path>) -> ResolutionPrediction {
+        self.predict_resolution_with_mode(root, self.resolution_mode())
+    }
+
+    /// Predict if the bookmark would resolve, interpreting it with the provided resolution mode
+    pub fn predict_resolution_with_mode(
+        &self,
+        root: Option<&Path>,
+        mode: ResolutionMode,
+    ) -> ResolutionPrediction {
+        self.predict_resolution_localized(root, mode, &Localization::english())
+    }
+
+    /// Predict if the bookmark would resolve, interpreting it with the provided resolution mode. The reasons and
+    /// explanation are looked up in the message table
+    pub fn predict_resolution_localized(
+        &self,
+        root: Option<&Path>,
+        mode: ResolutionMode,
+        locale: &Localization,
+    ) -> ResolutionPrediction {
+        let reference_style = mode == ResolutionMode::FileReference;
+        let mut reasons = Vec::new();
+        let mut score: f64 = 1.0;
+
+        // File references only need the CNID path, other bookmarks need a CNID for each path component
+        let valid_cnids = !self.cnid_path.is_empty()
+            && (reference_style || self.cnid_path.len() == self.path.len())
+            && self.cnid_path.iter().all(|cnid| !cnid.is_reserved());
+
+        if self.path.is_empty() && !(reference_style && valid_cnids) {
+            reasons.push(locale.message("resolve.no_target_path"));
+            return ResolutionPrediction {
+                outcome: ResolutionOutcome::WouldFail,
+                score: 0.0,
+                reasons,
+                mode,
+                reference_style,
+                explanation: mode.explanation_localized(locale),
+            };
+        }
+
+        if !valid_cnids {
+            reasons.push(locale.message("resolve.invalid_cnid_path"));
+            // File references are resolved by the CNID
+            score -= if reference_style { 0.5 } else { 0.2 };
+        }
+        if self.volume_uuid.is_empty() {
+            reasons.push(locale.message("resolve.missing_volume_uuid"));
+            score -= 0.2;
+        }
+        if self
+            .creation_options
+            .is_some_and(|options| options & WITH_SECURITY_SCOPE != 0)
+            && self.security_extension_rw.is_none()
+            && self.security_extension_ro.is_none()
+        {
+            reasons.push(locale.message("resolve.missing_security_extension"));
+            score -= 0.3;
+        }
+
+        let target = root
+            .filter(|_| !self.path.is_empty())
+            .map(|root| target_under_root(root, &self.path));
+        let target_exists = target
+            .as_ref()
+            .map(|target| target.as_ref().is_some_and(|target| target.exists()));
+        let resolves_by_cnid = reference_style && valid_cnids;
+        if target.as_ref().is_some_and(Option::is_none) {
+            reasons.push(locale.message("resolve.target_escapes_root"));
+            score -= 0.5;
+        } else if target_exists == Some(false) && resolves_by_cnid {
+            reasons.push(locale.message("resolve.target_missing_file_reference"));
+            score -= 0.1;
+        } else if target_exists == Some(false) {
+            reasons.push(locale.message("resolve.target_missing"));
+            score -= 0.5;
+        }
+
+        let score = score.max(0.0);
+        let outcome = match target_exists {
+            Some(true) => ResolutionOutcome::WouldResolve,
+            _ if self.volume_root != Some(true) && !self.volume_uuid.is_empty() => {
+                ResolutionOutcome::WouldPromptForVolume
+            }
+            Some(false) if resolves_by_cnid && score >= 0.5 => ResolutionOutcome::WouldResolve,
+            Some(false) => ResolutionOutcome::WouldFail,
+            None if score >= 0.5 => ResolutionOutcome::WouldResolve,
+            None => ResolutionOutcome::WouldFail,
+        };
+
+        ResolutionPrediction {
+            outcome,
+            score,
+            reasons,
+            mode,
+            reference_style,
+            explanation: mode.explanation_localized(locale),
+        }
+    }
+}
+
+/// Join the target path components to the root. `None` if a component is absolute, `.`, `..` or contains a path
+/// separator, the bookmark data is untrusted and must not point outside of the root
+fn target_under_root(root: &Path, path: &[String]) -> Option<PathBuf> {
+    let mut target = root.to_path_buf();
+    for component in path {
+        if component.contains(is_separator) {
+            return None;
+        }
+        let mut components = Path::new(component).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(name)), None) => target.push(name),
+            _ => return None,
+        }
+    }
+    Some(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{target_under_root, ResolutionMode, ResolutionOutcome};
+    use crate::{locale::Localization, parser::parse_bookmark};
+    use std::{env, fs, path::PathBuf};
+
+    /// Get a root directory that is not shared with other tests or test runs
+    fn test_root(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("macos_bookmarks_{name}_{}", std::process::id()))
+    }
+
+    #[test]
+    fn test_predict_resolution() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+        let mut bookmark = parse_bookmark(&buffer).unwrap();
+
+        let prediction = bookmark.predict_resolution(None);
+        assert_eq!(prediction.outcome, ResolutionOutcome::WouldResolve);
+        assert_eq!(prediction.score, 1.0);
+        assert!(prediction.reasons.is_empty());
+
+        let root = test_root("resolve");
+        let prediction = bookmark.predict_resolution(Some(&root));
+        assert_eq!(prediction.outcome, ResolutionOutcome::WouldFail);
+        assert_eq!(prediction.score, 0.5);
+
+        fs::create_dir_all(root.join("Applications/Syncthing.app")).unwrap();
+        let prediction = bookmark.predict_resolution(Some(&root));
+        assert_eq!(prediction.outcome, ResolutionOutcome::WouldResolve);
+        fs::remove_dir_all(&root).unwrap();
+
+        // Target on a volume that is not the root volume
+        bookmark.volume_root = Some(false);
+        let prediction = bookmark.predict_resolution(None);
+        assert_eq!(prediction.outcome, ResolutionOutcome::WouldPromptForVolume);
+
+        bookmark.path.clear();
+        let prediction = bookmark.predict_resolution(None);
+        assert_eq!(prediction.outcome, ResolutionOutcome::WouldFail);
+    }
+
+    #[test]
+    fn test_predict_resolution_file_reference() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+        let mut bookmark = parse_bookmark(&buffer).unwrap();
+        assert_eq!(bookmark.resolution_mode(), ResolutionMode::PathFirst);
+
+        bookmark.file_ref_flag = Some(true);
+        let root = test_root("reference");
+        let prediction = bookmark.predict_resolution(Some(&root));
+        assert!(prediction.reference_style);
+        assert_eq!(prediction.mode, ResolutionMode::FileReference);
+        assert_eq!(prediction.outcome, ResolutionOutcome::WouldResolve);
+        assert_eq!(prediction.score, 0.9);
+        assert!(prediction.explanation.contains("file ID"));
+
+        let locale = Localization::from_json(
+            r#"{"resolve.target_missing_file_reference": "Chemin introuvable"}"#,
+        )
+        .unwrap();
+        let prediction = bookmark.predict_resolution_localized(
+            Some(&root),
+            ResolutionMode::FileReference,
+            &locale,
+        );
+        assert_eq!(prediction.reasons, ["Chemin introuvable"]);
+        assert_eq!(
+            prediction.explanation,
+            ResolutionMode::FileReference.explanation()
+        );
+
+        // Same bookmark interpreted by its path
+        let prediction =
+            bookmark.predict_resolution_with_mode(Some(&root), ResolutionMode::PathFirst);
+        assert_eq!(prediction.outcome, ResolutionOutcome::WouldFail);
+
+        // File references do not need a path
+        bookmark.path.clear();
+        let prediction = bookmark.predict_resolution(None);
+        assert_eq!(prediction.outcome, ResolutionOutcome::WouldResolve);
+
+        bookmark.cnid_path.clear();
+        let prediction = bookmark.predict_resolution(None);
+        assert_eq!(prediction.outcome, ResolutionOutcome::WouldFail);
+    }
+
+    #[test]
+    fn test_predict_resolution_hostile_path() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+        let mut bookmark = parse_bookmark(&buffer).unwrap();
+
+        // Existing paths on the analysis host must not be checked
+        let root = test_root("hostile");
+        let host = env::temp_dir();
+        for path in [
+            vec![host.to_string_lossy().to_string()],
+            vec![String::from(".."), String::from("..")],
+            vec![String::from("tmp/../..")],
+            vec![String::from(".")],
+            vec![String::new()],
+        ] {
+            assert!(target_under_root(&root, &path).is_none(), "{path:?}");
+            bookmark.path = path;
+            let prediction = bookmark.predict_resolution(Some(&root));
+            assert_ne!(prediction.outcome, ResolutionOutcome::WouldResolve);
+            assert!(prediction
+                .reasons
+                .iter()
+                .any(|reason| reason.contains("escape")));
+        }
+
+        assert_eq!(
+            target_under_root(
+                &root,
+                &[String::from("Applications"), String::from("Foo.app")]
+            ),
+            Some(root.join("Applications/Foo.app"))
+        );
+    }
+}
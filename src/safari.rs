@@ -0,0 +1,205 @@
+//! Parse Safari download history from `Downloads.plist`
+//!
+//! Every entry in `DownloadHistory` stores the downloaded file as a bookmark (`DownloadEntryBookmarkBlob`) next to
+//! the download URL, timestamps and progress counters.
+
+use std::{fs, io::Cursor, path::Path};
+
+use log::{error, warn};
+use plist::{Dictionary, Value};
+use serde::Serialize;
+
+use crate::{
+    bookmark::BookmarkData, error::BookmarkError, parser::parse_bookmark, plist::plist_date,
+    types::CocoaTime,
+};
+
+#[derive(Debug, Serialize)]
+pub struct SafariDownload {
+    pub identifier: String,                 // Download entry identifier (UUID)
+    pub url: String,                        // Download URL
+    pub path: String,                       // Path the file was downloaded to
+    pub bytes_so_far: Option<u64>,          // Bytes downloaded
+    pub total_bytes: Option<u64>,           // Expected size of the download
+    pub date_added: Option<CocoaTime>,      // Download started timestamp
+    pub date_finished: Option<CocoaTime>,   // Download finished timestamp
+    pub remove_when_done: bool,             // Entry is removed from the list once finished
+    pub sandbox_identifier: Option<String>, // Sandbox extension identifier for the file
+    pub bookmark: Option<BookmarkData>,     // Parsed DownloadEntryBookmarkBlob
+}
+
+/// Parse every entry in a Safari `Downloads.plist` file (XML or binary). Entries with bookmark data that fails to
+/// parse are kept without a bookmark
+pub fn parse_safari_downloads(data: &[u8]) -> Result<Vec<SafariDownload>, BookmarkError> {
+    let plist = match Value::from_reader(Cursor::new(data)) {
+        Ok(Value::Dictionary(plist)) => plist,
+        Ok(_) => {
+            error!("Downloads plist is not a dictionary");
+            return Err(BookmarkError::InvalidPlistData);
+        }
+        Err(err) => {
+            error!("failed to parse Downloads plist: {:?}", err);
+            return Err(BookmarkError::InvalidPlistData);
+        }
+    };
+
+    let entries = plist
+        .get("DownloadHistory")
+        .and_then(Value::as_array)
+        .map_or(&[][..], Vec::as_slice);
+    Ok(entries
+        .iter()
+        .filter_map(Value::as_dictionary)
+        .map(safari_download)
+        .collect())
+}
+
+/// Read a Safari `Downloads.plist` file and parse every entry
+pub fn parse_safari_downloads_file(
+    path: impl AsRef<Path>,
+) -> Result<Vec<SafariDownload>, BookmarkError> {
+    match fs::read(path.as_ref()) {
+        Ok(data) => parse_safari_downloads(&data),
+        Err(err) => {
+            error!(
+                "failed to read Downloads plist {:?}: {:?}",
+                path.as_ref(),
+                err
+            );
+            Err(BookmarkError::InvalidPlistData)
+        }
+    }
+}
+
+/// Parse a single `DownloadHistory` entry
+fn safari_download(entry: &Dictionary) -> SafariDownload {
+    let string = |key: &str| {
+        entry
+            .get(key)
+            .and_then(Value::as_string)
+            .map(str::to_string)
+    };
+    let integer = |key: &str| entry.get(key).and_then(Value::as_unsigned_integer);
+    let date = |key: &str| entry.get(key).and_then(plist_date);
+
+    let identifier = string("DownloadEntryIdentifier").unwrap_or_default();
+    let bookmark = match entry
+        .get("DownloadEntryBookmarkBlob")
+        .and_then(Value::as_data)
+        .map(parse_bookmark)
+    {
+        Some(Ok(bookmark)) => Some(bookmark),
+        Some(Err(err)) => {
+            warn!(
+                "Failed to parse bookmark for download {}: {:?}",
+                identifier, err
+            );
+            None
+        }
+        None => None,
+    };
+
+    SafariDownload {
+        url: string("DownloadEntryURL").unwrap_or_default(),
+        path: string("DownloadEntryPath").unwrap_or_default(),
+        bytes_so_far: integer("DownloadEntryProgressBytesSoFar"),
+        total_bytes: integer("DownloadEntryProgressTotalToLoad"),
+        date_added: date("DownloadEntryDateAddedKey"),
+        date_finished: date("DownloadEntryDateFinishedKey"),
+        remove_when_done: entry
+            .get("DownloadEntryRemoveWhenDoneKey")
+            .and_then(Value::as_boolean)
+            .unwrap_or_default(),
+        sandbox_identifier: string("DownloadEntrySandboxIdentifier"),
+        identifier,
+        bookmark,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_safari_downloads, parse_safari_downloads_file};
+    use plist::{Date, Dictionary, Value};
+    use std::{
+        fs,
+        path::PathBuf,
+        time::{Duration, UNIX_EPOCH},
+    };
+
+    fn test_downloads() -> Vec<u8> {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/downloads.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+
+        let mut entry = Dictionary::new();
+        let strings = [
+            (
+                "DownloadEntryIdentifier",
+                "9B8A1C5E-1D0F-4C1B-9D57-2B1D4E0F6A11",
+            ),
+            ("DownloadEntryURL", "https://example.com/powershell.pkg"),
+            ("DownloadEntryPath", "~/Downloads/powershell.pkg"),
+        ];
+        for (key, value) in strings {
+            entry.insert(String::from(key), Value::String(String::from(value)));
+        }
+        entry.insert(
+            String::from("DownloadEntryProgressBytesSoFar"),
+            Value::Integer(67322368.into()),
+        );
+        entry.insert(
+            String::from("DownloadEntryProgressTotalToLoad"),
+            Value::Integer(67322368.into()),
+        );
+        let added = UNIX_EPOCH + Duration::from_secs(1643781189);
+        entry.insert(
+            String::from("DownloadEntryDateAddedKey"),
+            Value::Date(Date::from(added)),
+        );
+        entry.insert(
+            String::from("DownloadEntryRemoveWhenDoneKey"),
+            Value::Boolean(false),
+        );
+        entry.insert(
+            String::from("DownloadEntryBookmarkBlob"),
+            Value::Data(buffer),
+        );
+
+        let mut history = Dictionary::new();
+        history.insert(
+            String::from("DownloadHistory"),
+            Value::Array(vec![Value::Dictionary(entry)]),
+        );
+        let mut data = Vec::new();
+        Value::Dictionary(history).to_writer_xml(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_parse_safari_downloads() {
+        let downloads = parse_safari_downloads(&test_downloads()).unwrap();
+        assert_eq!(downloads.len(), 1);
+        let download = &downloads[0];
+        assert_eq!(download.identifier, "9B8A1C5E-1D0F-4C1B-9D57-2B1D4E0F6A11");
+        assert_eq!(download.url, "https://example.com/powershell.pkg");
+        assert_eq!(download.total_bytes, Some(67322368));
+        assert_eq!(download.date_added.unwrap().to_unix(), 1643781189.0);
+        assert_eq!(download.date_finished, None);
+        assert!(!download.remove_when_done);
+        assert_eq!(
+            download.bookmark.as_ref().unwrap().path.last().unwrap(),
+            "powershell-7.2.4-osx-x64.pkg"
+        );
+
+        let path = std::env::temp_dir().join("macos_bookmarks_test_safari_downloads.plist");
+        fs::write(&path, test_downloads()).unwrap();
+        assert_eq!(parse_safari_downloads_file(&path).unwrap().len(), 1);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_safari_downloads_bad_data() {
+        assert!(parse_safari_downloads(b"not a plist").is_err());
+        assert!(parse_safari_downloads_file("/does/not/exist.plist").is_err());
+    }
+}
@@ -0,0 +1,726 @@
+//! Scan a directory tree for bookmarks
+//!
+//! Walks a directory (ex: a mounted disk image) and parses every file likely to contain bookmarks: raw bookmark files
+//! (`book` signature), property lists (`.plist`, `.sfl*`, `.btm` or a plist signature) and, with the `rusqlite`
+//! feature, SQLite databases. Plists are walked with the `plist` feature and carved otherwise. Other files are only
+//! carved when `carve_unknown` is set. Files and directories that cannot be read are skipped. With the `mmap` feature
+//! files are memory mapped instead of read.
+//!
+//! Known artifacts are read by a dedicated parser that adds the artifact's metadata (ex: share service, last used time)
+//! to each result: plists with a known file name (`ARTIFACT_PARSERS`) instead of being walked, and plists containing a
+//! known key (`KEY_PARSERS`, ex: `NSNavRecentPlaces` in any preferences file) for that key only.
+//!
+//! `scan_snapshots` scans the same directory on the live volume and in mounted APFS snapshots (see `snapshot`) and
+//! tags each result with the snapshot it was found in.
+//!
+//! `scan_directory_checkpointed` hands over the results of each file as soon as it is scanned and records the file in a
+//! `checkpoint::Checkpoint`, so an interrupted scan can be resumed without scanning the same files again.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::{error, warn};
+use serde::Serialize;
+
+use crate::{
+    bookmark::BookmarkData, carve::find_bookmarks, checkpoint::Checkpoint, error::BookmarkError,
+    parser::parse_bookmark, snapshot::Snapshot,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct ScanOptions {
+    pub max_depth: usize,      // Maximum directory depth below the root
+    pub max_file_size: u64,    // Larger files are skipped
+    pub follow_symlinks: bool, // Follow symbolic links to files and directories
+    pub carve_unknown: bool,   // Carve bookmarks from files that are not a known format
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            max_depth: 64,
+            max_file_size: 256 * 1024 * 1024,
+            follow_symlinks: false,
+            carve_unknown: false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub enum ScanSource {
+    Bookmark, // Raw bookmark file
+    Plist,    // Walked property list
+    Sqlite,   // SQLite BLOB value
+    Carved,   // Carved from the file data
+    Artifact, // Read by the parser for a known artifact
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScannedBookmark {
+    pub path: PathBuf,      // File containing the bookmark
+    pub source: ScanSource, // How the bookmark was found
+    pub location: String,   // Key path, table.column[rowid] or offset within the file
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>, // Fields stored next to the bookmark (artifact parsers only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<Snapshot>, // Snapshot the file was found in (snapshot scans only)
+    pub bookmark: BookmarkData, // Parsed bookmark
+}
+
+/// Bookmark read by an artifact parser
+#[cfg(feature = "plist")]
+struct ArtifactBookmark {
+    location: String,                   // Location of the bookmark within the artifact
+    metadata: BTreeMap<String, String>, // Artifact fields stored next to the bookmark
+    bookmark: BookmarkData,             // Parsed bookmark
+}
+
+/// Parser for an artifact with a known file name
+#[cfg(feature = "plist")]
+type ArtifactParser = fn(&[u8]) -> Result<Vec<ArtifactBookmark>, BookmarkError>;
+
+/// Plists read by a dedicated parser instead of being walked, by file name
+#[cfg(feature = "plist")]
+const ARTIFACT_PARSERS: [(&str, ArtifactParser); 2] = [
+    ("com.apple.sharekit.recents.plist", share_recents),
+    ("com.apple.spotlight.plist", spotlight_shortcuts),
+];
+
+/// Top level plist keys read by a dedicated parser, bookmarks stored under other keys of the plist are still walked
+#[cfg(feature = "plist")]
+const KEY_PARSERS: [(&str, ArtifactParser); 1] =
+    [(crate::nav_recents::NAV_RECENT_PLACES, nav_recent_places)];
+
+/// Extensions of property list files
+const PLIST_EXTENSIONS: [&str; 5] = ["plist", "sfl", "sfl2", "sfl3", "btm"];
+
+/// Scan a directory tree and parse every bookmark found. Fails with `InvalidScanRoot` if the root cannot be read
+pub fn scan_directory(
+    root: impl AsRef<Path>,
+    options: &ScanOptions,
+) -> Result<Vec<ScannedBookmark>, BookmarkError> {
+    let root = root.as_ref();
+    if let Err(err) = fs::read_dir(root) {
+        error!("failed to read scan directory {:?}: {:?}", root, err);
+        return Err(BookmarkError::InvalidScanRoot);
+    }
+    let mut results = Vec::new();
+    scan_entries(root, 0, options, &mut |path, _| {
+        scan_file(path, options, &mut results);
+        true
+    });
+    Ok(results)
+}
+
+/// Scan a directory tree on the live volume and at the same location in every snapshot. Live results come first
+/// (without a snapshot), followed by the results of each snapshot tagged with it. Fails with `InvalidScanRoot` if the
+/// live root cannot be read, snapshots where the directory does not exist are skipped
+pub fn scan_snapshots(
+    root: impl AsRef<Path>,
+    snapshots: &[Snapshot],
+    options: &ScanOptions,
+) -> Result<Vec<ScannedBookmark>, BookmarkError> {
+    let root = root.as_ref();
+    let mut results = scan_directory(root, options)?;
+    for snapshot in snapshots {
+        let snapshot_root = snapshot.path_in_snapshot(root);
+        let snapshot_results = match scan_directory(&snapshot_root, options) {
+            Ok(snapshot_results) => snapshot_results,
+            Err(err) => {
+                warn!("Skipping snapshot {}: {:?}", snapshot.name, err);
+                continue;
+            }
+        };
+        results.extend(snapshot_results.into_iter().map(|mut result| {
+            result.snapshot = Some(snapshot.clone());
+            result
+        }));
+    }
+    Ok(results)
+}
+
+/// Scan a directory tree, skipping files already recorded in the checkpoint. The results of each file (possibly none)
+/// are passed to `output` and the file is recorded in the checkpoint once `output` returns true. The scan stops if
+/// `output` returns false, the file is then scanned again when the scan is resumed. Returns the number of files scanned
+pub fn scan_directory_checkpointed(
+    root: impl AsRef<Path>,
+    options: &ScanOptions,
+    checkpoint: &mut Checkpoint,
+    mut output: impl FnMut(Vec<ScannedBookmark>) -> bool,
+) -> Result<usize, BookmarkError> {
+    let root = root.as_ref();
+    if let Err(err) = fs::read_dir(root) {
+        error!("failed to read scan directory {:?}: {:?}", root, err);
+        return Err(BookmarkError::InvalidScanRoot);
+    }
+    let mut scanned = 0;
+    let mut result = Ok(());
+    scan_entries(root, 0, options, &mut |path, size| {
+        if checkpoint.contains(path, size) {
+            return true;
+        }
+        let mut results = Vec::new();
+        scan_file(path, options, &mut results);
+        if !output(results) {
+            return false;
+        }
+        scanned += 1;
+        result = checkpoint.record(path, size);
+        result.is_ok()
+    });
+    result.map(|_| scanned)
+}
+
+/// Scan the entries of a directory, sorted by name. `visit` is called with the path and size of every file to scan and
+/// stops the scan by returning false. Returns false if the scan was stopped
+fn scan_entries(
+    directory: &Path,
+    depth: usize,
+    options: &ScanOptions,
+    visit: &mut impl FnMut(&Path, u64) -> bool,
+) -> bool {
+    let mut paths = match fs::read_dir(directory) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .collect::<Vec<_>>(),
+        Err(err) => {
+            warn!("Failed to read directory {:?}: {:?}", directory, err);
+            return true;
+        }
+    };
+    paths.sort();
+
+    for path in paths {
+        let metadata = if options.follow_symlinks {
+            fs::metadata(&path)
+        } else {
+            fs::symlink_metadata(&path)
+        };
+        let Ok(metadata) = metadata else {
+            warn!("Failed to read metadata of {:?}", path);
+            continue;
+        };
+        let keep_going = if metadata.is_dir() {
+            depth >= options.max_depth || scan_entries(&path, depth + 1, options, visit)
+        } else if metadata.is_file() && metadata.len() <= options.max_file_size {
+            visit(&path, metadata.len())
+        } else {
+            true
+        };
+        if !keep_going {
+            return false;
+        }
+    }
+    true
+}
+
+/// Parse the bookmarks in a single file based on its signature and extension
+fn scan_file(path: &Path, options: &ScanOptions, results: &mut Vec<ScannedBookmark>) {
+    #[cfg(feature = "mmap")]
+    let Ok(data) = crate::mmap::MappedFile::open(path) else {
+        return;
+    };
+    #[cfg(not(feature = "mmap"))]
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(err) => {
+            warn!("Failed to read {:?}: {:?}", path, err);
+            return;
+        }
+    };
+    let mut push = |source: ScanSource,
+                    location: String,
+                    metadata: BTreeMap<String, String>,
+                    bookmark: BookmarkData| {
+        results.push(ScannedBookmark {
+            path: path.to_path_buf(),
+            source,
+            location,
+            metadata,
+            snapshot: None,
+            bookmark,
+        });
+    };
+
+    if data.starts_with(BookmarkData::BOOKMARK_SIGNATURE) {
+        match parse_bookmark(&data) {
+            Ok(bookmark) => push(
+                ScanSource::Bookmark,
+                String::new(),
+                BTreeMap::new(),
+                bookmark,
+            ),
+            Err(err) => warn!("Failed to parse bookmark {:?}: {:?}", path, err),
+        }
+        return;
+    }
+    if is_plist(path, &data) {
+        #[cfg(feature = "plist")]
+        if let Some(parser) = artifact_parser(path) {
+            match parser(&data) {
+                Ok(bookmarks) => {
+                    for found in bookmarks {
+                        push(
+                            ScanSource::Artifact,
+                            found.location,
+                            found.metadata,
+                            found.bookmark,
+                        );
+                    }
+                    return;
+                }
+                Err(err) => warn!("Failed to parse artifact {:?}: {:?}", path, err),
+            }
+        }
+        #[cfg(feature = "plist")]
+        let mut parsed_keys = Vec::new();
+        #[cfg(feature = "plist")]
+        for (key, parser) in KEY_PARSERS {
+            // Keys are stored as plain strings in XML and binary plists
+            if !data
+                .windows(key.len())
+                .any(|window| window == key.as_bytes())
+            {
+                continue;
+            }
+            match parser(&data) {
+                Ok(bookmarks) => {
+                    for found in bookmarks {
+                        push(
+                            ScanSource::Artifact,
+                            found.location,
+                            found.metadata,
+                            found.bookmark,
+                        );
+                    }
+                    parsed_keys.push(key);
+                }
+                Err(err) => warn!("Failed to parse {} in {:?}: {:?}", key, path, err),
+            }
+        }
+        #[cfg(feature = "plist")]
+        match crate::plist::parse_bookmarks_from_plist(&data) {
+            Ok(bookmarks) => {
+                for found in bookmarks {
+                    let top_key = found.key_path.split('.').next().unwrap_or_default();
+                    if parsed_keys.contains(&top_key) {
+                        continue;
+                    }
+                    push(
+                        ScanSource::Plist,
+                        found.key_path,
+                        BTreeMap::new(),
+                        found.bookmark,
+                    );
+                }
+                return;
+            }
+            Err(err) => warn!("Failed to parse plist {:?}: {:?}", path, err),
+        }
+    } else if data.starts_with(b"SQLite format 3\0") {
+        #[cfg(feature = "rusqlite")]
+        match crate::sqlite::scan_sqlite(path) {
+            Ok(bookmarks) => {
+                for found in bookmarks {
+                    let location = format!("{}.{}[{}]", found.table, found.column, found.rowid);
+                    push(
+                        ScanSource::Sqlite,
+                        location,
+                        BTreeMap::new(),
+                        found.bookmark,
+                    );
+                }
+                return;
+            }
+            Err(err) => warn!("Failed to scan SQLite database {:?}: {:?}", path, err),
+        }
+    } else if !options.carve_unknown {
+        return;
+    }
+
+    // Plists and databases that could not be walked are carved
+    for (offset, result) in find_bookmarks(&data) {
+        if let Ok(bookmark) = result {
+            push(
+                ScanSource::Carved,
+                format!("{offset:#x}"),
+                BTreeMap::new(),
+                bookmark,
+            );
+        }
+    }
+}
+
+/// Get the artifact parser for a file name
+#[cfg(feature = "plist")]
+fn artifact_parser(path: &Path) -> Option<ArtifactParser> {
+    let name = path.file_name()?.to_str()?;
+    ARTIFACT_PARSERS
+        .iter()
+        .find(|(artifact, _)| artifact.eq_ignore_ascii_case(name))
+        .map(|(_, parser)| *parser)
+}
+
+/// Read AirDrop and share sheet recents, keeping the service, display name and last used time
+#[cfg(feature = "plist")]
+fn share_recents(data: &[u8]) -> Result<Vec<ArtifactBookmark>, BookmarkError> {
+    let recents = crate::sharekit::parse_share_recents(data)?;
+    Ok(recents
+        .into_iter()
+        .filter_map(|recent| {
+            let mut metadata = BTreeMap::from([(String::from("service"), recent.service.clone())]);
+            if let Some(name) = recent.name {
+                metadata.insert(String::from("name"), name);
+            }
+            if let Some(last_used) = recent.last_used {
+                metadata.insert(String::from("last_used"), last_used.to_string());
+            }
+            Some(ArtifactBookmark {
+                location: format!("{}.{}", recent.service, recent.index),
+                metadata,
+                bookmark: recent.bookmark?,
+            })
+        })
+        .collect())
+}
+
+/// Read Spotlight shortcuts, keeping the typed text, display name, URL and last used time
+#[cfg(feature = "plist")]
+fn spotlight_shortcuts(data: &[u8]) -> Result<Vec<ArtifactBookmark>, BookmarkError> {
+    let shortcuts = crate::spotlight::parse_spotlight_shortcuts(data)?;
+    Ok(shortcuts
+        .into_iter()
+        .filter_map(|shortcut| {
+            let mut metadata = BTreeMap::from([(String::from("typed"), shortcut.typed.clone())]);
+            if let Some(display_name) = shortcut.display_name {
+                metadata.insert(String::from("display_name"), display_name);
+            }
+            if let Some(url) = shortcut.url {
+                metadata.insert(String::from("url"), url);
+            }
+            if let Some(last_used) = shortcut.last_used {
+                metadata.insert(String::from("last_used"), last_used.to_string());
+            }
+            Some(ArtifactBookmark {
+                location: format!("UserShortcuts.{}", shortcut.typed),
+                metadata,
+                bookmark: shortcut.bookmark?,
+            })
+        })
+        .collect())
+}
+
+/// Read open and save panel recent places stored as bookmark data or alias records. Path string entries have no
+/// bookmark and are skipped
+#[cfg(feature = "plist")]
+fn nav_recent_places(data: &[u8]) -> Result<Vec<ArtifactBookmark>, BookmarkError> {
+    let places = crate::nav_recents::parse_nav_recent_places(data)?;
+    Ok(places
+        .into_iter()
+        .filter_map(|place| {
+            let format = if place.alias.is_some() {
+                "alias"
+            } else {
+                "bookmark"
+            };
+            Some(ArtifactBookmark {
+                location: format!("{}.{}", crate::nav_recents::NAV_RECENT_PLACES, place.index),
+                metadata: BTreeMap::from([(String::from("format"), String::from(format))]),
+                bookmark: place.bookmark?,
+            })
+        })
+        .collect())
+}
+
+/// Check if a file is a property list by its extension or signature
+fn is_plist(path: &Path, data: &[u8]) -> bool {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_lowercase)
+        .unwrap_or_default();
+    PLIST_EXTENSIONS.contains(&extension.as_str())
+        || data.starts_with(b"bplist00")
+        || data.starts_with(b"<?xml")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        scan_directory, scan_directory_checkpointed, scan_snapshots, ScanOptions, ScanSource,
+    };
+    use crate::{checkpoint::Checkpoint, snapshot::Snapshot};
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn test_scan_directory() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+
+        let root = std::env::temp_dir().join("macos_bookmarks_test_scan");
+        let _ = fs::remove_dir_all(&root);
+        let nested = root.join("Users/test/Library");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(root.join("a.bookmark"), &buffer).unwrap();
+        fs::write(root.join("notes.txt"), b"nothing here").unwrap();
+        let mut unknown = vec![0; 64];
+        unknown.extend_from_slice(&buffer);
+        fs::write(nested.join("dump.bin"), &unknown).unwrap();
+
+        let results = scan_directory(&root, &ScanOptions::default()).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, root.join("a.bookmark"));
+        assert_eq!(results[0].source, ScanSource::Bookmark);
+
+        let options = ScanOptions {
+            carve_unknown: true,
+            ..ScanOptions::default()
+        };
+        let results = scan_directory(&root, &options).unwrap();
+        // Entries are sorted by name, "Users" comes before "a.bookmark"
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].path, nested.join("dump.bin"));
+        assert_eq!(results[0].source, ScanSource::Carved);
+        assert_eq!(results[0].location, "0x40");
+
+        let options = ScanOptions {
+            carve_unknown: true,
+            max_depth: 1,
+            ..ScanOptions::default()
+        };
+        assert_eq!(scan_directory(&root, &options).unwrap().len(), 1);
+
+        fs::remove_dir_all(&root).unwrap();
+        assert!(scan_directory(&root, &ScanOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_scan_directory_resume() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+
+        let root = std::env::temp_dir().join("macos_bookmarks_test_scan_resume");
+        let checkpoint_path = std::env::temp_dir().join("macos_bookmarks_test_scan_resume.jsonl");
+        let _ = fs::remove_dir_all(&root);
+        let _ = fs::remove_file(&checkpoint_path);
+        fs::create_dir_all(root.join("nested")).unwrap();
+        fs::write(root.join("a.bookmark"), &buffer).unwrap();
+        fs::write(root.join("b.txt"), b"nothing here").unwrap();
+        fs::write(root.join("nested/c.bookmark"), &buffer).unwrap();
+        let options = ScanOptions::default();
+
+        // Interrupted after the first file with bookmarks
+        let mut checkpoint = Checkpoint::open(&checkpoint_path).unwrap();
+        let mut found = Vec::new();
+        let scanned = scan_directory_checkpointed(&root, &options, &mut checkpoint, |results| {
+            if found.len() == 1 && !results.is_empty() {
+                return false;
+            }
+            found.extend(results);
+            true
+        })
+        .unwrap();
+        assert_eq!(scanned, 2);
+        assert_eq!(found[0].path, root.join("a.bookmark"));
+        drop(checkpoint);
+
+        // Resumed from the checkpoint file, only the remaining file is scanned
+        let mut checkpoint = Checkpoint::open(&checkpoint_path).unwrap();
+        let mut found = Vec::new();
+        let scanned = scan_directory_checkpointed(&root, &options, &mut checkpoint, |results| {
+            found.extend(results);
+            true
+        })
+        .unwrap();
+        assert_eq!(scanned, 1);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path, root.join("nested/c.bookmark"));
+        assert_eq!(checkpoint.len(), 3);
+
+        // Files that changed are scanned again
+        fs::write(root.join("b.txt"), b"changed").unwrap();
+        let scanned =
+            scan_directory_checkpointed(&root, &options, &mut checkpoint, |_| true).unwrap();
+        assert_eq!(scanned, 1);
+
+        fs::remove_dir_all(&root).unwrap();
+        fs::remove_file(&checkpoint_path).unwrap();
+    }
+
+    #[test]
+    fn test_scan_snapshots() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data");
+
+        let base = std::env::temp_dir().join("macos_bookmarks_test_scan_snapshots");
+        let _ = fs::remove_dir_all(&base);
+        let live = base.join("live/Users/lab");
+        let mount_point = base.join("com.apple.TimeMachine.2022-06-30-021614.local");
+        let snapshot = Snapshot::new(&mount_point);
+        let missing = Snapshot::new(base.join("not-mounted"));
+        fs::create_dir_all(&live).unwrap();
+        fs::create_dir_all(snapshot.path_in_snapshot(&live)).unwrap();
+        fs::copy(
+            test_location.join("ventura.bookmark"),
+            live.join("item.bookmark"),
+        )
+        .unwrap();
+        // The snapshot still has the old login item
+        fs::copy(
+            test_location.join("poisonapple.bookmark"),
+            snapshot.path_in_snapshot(&live).join("item.bookmark"),
+        )
+        .unwrap();
+
+        let results =
+            scan_snapshots(&live, &[snapshot.clone(), missing], &ScanOptions::default()).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].snapshot.is_none());
+        assert_eq!(results[0].path, live.join("item.bookmark"));
+        assert_eq!(results[1].snapshot.as_ref(), Some(&snapshot));
+        assert_eq!(
+            results[1].path,
+            snapshot.path_in_snapshot(&live).join("item.bookmark")
+        );
+        assert_ne!(results[0].bookmark.path, results[1].bookmark.path);
+
+        let json = serde_json::to_value(&results[1]).unwrap();
+        assert_eq!(json["snapshot"]["created"], 678248174.0);
+        assert!(serde_json::to_value(&results[0])
+            .unwrap()
+            .get("snapshot")
+            .is_none());
+
+        fs::remove_dir_all(&base).unwrap();
+        assert!(scan_snapshots(&live, &[snapshot], &ScanOptions::default()).is_err());
+    }
+
+    #[cfg(feature = "plist")]
+    #[test]
+    fn test_scan_directory_plist() {
+        use plist::{Dictionary, Value};
+
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let mut item = Dictionary::new();
+        item.insert(
+            String::from("Bookmark"),
+            Value::Data(fs::read(test_location).unwrap()),
+        );
+
+        let root = std::env::temp_dir().join("macos_bookmarks_test_scan_plist");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        Value::Dictionary(item)
+            .to_file_xml(root.join("com.apple.loginitems.plist"))
+            .unwrap();
+
+        let results = scan_directory(&root, &ScanOptions::default()).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].source, ScanSource::Plist);
+        assert_eq!(results[0].location, "Bookmark");
+    }
+
+    #[cfg(feature = "plist")]
+    #[test]
+    fn test_scan_directory_artifact() {
+        use plist::{Date, Dictionary, Value};
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let mut target = Dictionary::new();
+        target.insert(
+            String::from("bookmark"),
+            Value::Data(fs::read(test_location).unwrap()),
+        );
+        target.insert(
+            String::from("lastUsedDate"),
+            Value::Date(Date::from(UNIX_EPOCH + Duration::from_secs(1656555374))),
+        );
+        let mut recents = Dictionary::new();
+        recents.insert(
+            String::from("com.apple.share.AirDrop.send"),
+            Value::Array(vec![Value::Dictionary(target)]),
+        );
+
+        let root = std::env::temp_dir().join("macos_bookmarks_test_scan_artifact");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let recents = Value::Dictionary(recents);
+        recents
+            .to_file_binary(root.join("com.apple.sharekit.recents.plist"))
+            .unwrap();
+        recents.to_file_binary(root.join("other.plist")).unwrap();
+
+        let results = scan_directory(&root, &ScanOptions::default()).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].source, ScanSource::Artifact);
+        assert_eq!(results[0].location, "com.apple.share.AirDrop.send.0");
+        assert_eq!(
+            results[0].metadata["service"],
+            "com.apple.share.AirDrop.send"
+        );
+        assert_eq!(results[0].metadata["last_used"], "2022-06-30T02:16:14Z");
+        // Other plists are walked without metadata
+        assert_eq!(results[1].source, ScanSource::Plist);
+        assert_eq!(
+            results[1].location,
+            "com.apple.share.AirDrop.send.0.bookmark"
+        );
+        assert!(results[1].metadata.is_empty());
+    }
+
+    #[cfg(feature = "plist")]
+    #[test]
+    fn test_scan_directory_preferences() {
+        use plist::Value;
+
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data");
+        let root = std::env::temp_dir().join("macos_bookmarks_test_scan_preferences");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        fs::copy(
+            test_location.join("com.apple.spotlight.plist"),
+            root.join("com.apple.spotlight.plist"),
+        )
+        .unwrap();
+        // Binary preferences with another bookmark next to the recent places
+        let mut preferences =
+            Value::from_file(test_location.join("GlobalPreferences.plist")).unwrap();
+        let bookmark = fs::read(test_location.join("ventura.bookmark")).unwrap();
+        preferences
+            .as_dictionary_mut()
+            .unwrap()
+            .insert(String::from("LastBookmark"), Value::Data(bookmark));
+        preferences
+            .to_file_binary(root.join(".GlobalPreferences.plist"))
+            .unwrap();
+
+        let results = scan_directory(&root, &ScanOptions::default()).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(results.len(), 3);
+
+        assert_eq!(results[0].path, root.join(".GlobalPreferences.plist"));
+        assert_eq!(results[0].source, ScanSource::Artifact);
+        assert_eq!(results[0].location, "NSNavRecentPlaces.1");
+        assert_eq!(results[0].metadata["format"], "bookmark");
+        assert_eq!(results[1].source, ScanSource::Plist);
+        assert_eq!(results[1].location, "LastBookmark");
+
+        assert_eq!(results[2].source, ScanSource::Artifact);
+        assert_eq!(results[2].location, "UserShortcuts.sync");
+        assert_eq!(results[2].metadata["display_name"], "Syncthing");
+        assert_eq!(results[2].metadata["last_used"], "2022-07-01T08:00:00Z");
+        assert_eq!(results[2].metadata["url"], "/Applications/Syncthing.app");
+    }
+}
@@ -0,0 +1,194 @@
+//! HTTP parse service
+//!
+//! `ParseServer` lets ingestion pipelines written in other languages call the parser as a sidecar service instead of
+//! through bindings. `POST /parse` takes bookmark data as the request body, raw or base64 encoded
+//! (`POST /parse?encoding=base64`), and returns the parsed bookmark as a JSON object. Timestamps are raw Cocoa seconds
+//! unless `timestamps=converted` or `timestamps=both` is in the query. Failures return `{"error": "<message>"}` with a
+//! 4xx status. Bodies are parsed with `ResourceLimits::BOUNDED` and bodies larger than `MAX_BODY_SIZE` are rejected.
+//! Requests are handled one at a time on the thread calling `ParseServer::run`.
+
+use std::{io::Read, net::SocketAddr};
+
+use log::{error, warn};
+use serde_json::{json, Value};
+use tiny_http::{Header, Request, Response, Server};
+
+use crate::{
+    error::BookmarkError,
+    parser::{BookmarkParser, ResourceLimits},
+    types::TimestampFormat,
+};
+
+/// Largest request body accepted (16 MiB)
+pub const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+pub struct ParseServer {
+    server: Server, // Listening HTTP server
+}
+
+impl ParseServer {
+    /// Listen on an address (ex: `127.0.0.1:8080`, port 0 picks a free port)
+    pub fn bind(address: &str) -> Result<ParseServer, BookmarkError> {
+        match Server::http(address) {
+            Ok(server) => Ok(ParseServer { server }),
+            Err(err) => {
+                error!("failed to listen on {}: {:?}", address, err);
+                Err(BookmarkError::ServeError)
+            }
+        }
+    }
+
+    /// Get the address the server is listening on
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.server.server_addr().to_ip()
+    }
+
+    /// Handle requests until the server is dropped or fails
+    pub fn run(&self) {
+        for request in self.server.incoming_requests() {
+            handle_request(request);
+        }
+    }
+}
+
+/// Respond to a single request
+fn handle_request(mut request: Request) {
+    let mut body = Vec::new();
+    let read = request
+        .as_reader()
+        .take(MAX_BODY_SIZE as u64 + 1)
+        .read_to_end(&mut body);
+    let (status, value) = match read {
+        Ok(_) => respond(request.method().as_str(), request.url(), &body),
+        Err(err) => {
+            warn!("Failed to read request body: {:?}", err);
+            (400, json!({"error": "Failed to read request body"}))
+        }
+    };
+
+    let mut response = Response::from_string(value.to_string()).with_status_code(status);
+    if let Ok(header) = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]) {
+        response = response.with_header(header);
+    }
+    if let Err(err) = request.respond(response) {
+        warn!("Failed to send response: {:?}", err);
+    }
+}
+
+/// Get the status code and JSON response for a request
+pub fn respond(method: &str, url: &str, body: &[u8]) -> (u16, Value) {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    if path != "/parse" {
+        return (404, json!({"error": "Not found"}));
+    }
+    if method != "POST" {
+        return (405, json!({"error": "Method not allowed"}));
+    }
+    if body.len() > MAX_BODY_SIZE {
+        return (413, json!({"error": "Request body is too large"}));
+    }
+
+    let mut base64 = false;
+    let mut timestamps = TimestampFormat::Raw;
+    for parameter in query.split('&').filter(|parameter| !parameter.is_empty()) {
+        match parameter.split_once('=').unwrap_or((parameter, "")) {
+            ("encoding", "raw") => base64 = false,
+            ("encoding", "base64") => base64 = true,
+            ("timestamps", "raw") => timestamps = TimestampFormat::Raw,
+            ("timestamps", "converted") => timestamps = TimestampFormat::Converted,
+            ("timestamps", "both") => timestamps = TimestampFormat::ConvertedWithRaw,
+            _ => {
+                return (
+                    400,
+                    json!({"error": format!("Unsupported query parameter {parameter}")}),
+                )
+            }
+        }
+    }
+
+    let parser = BookmarkParser::new().resource_limits(ResourceLimits::BOUNDED);
+    let parsed = if base64 {
+        match std::str::from_utf8(body) {
+            Ok(encoded) => parser.parse_base64(encoded),
+            Err(_) => Err(BookmarkError::InvalidBase64Data),
+        }
+    } else {
+        parser.parse(body)
+    };
+    match parsed {
+        Ok(bookmark) => (200, bookmark.to_json_value(timestamps)),
+        Err(err) => (422, json!({"error": err.to_string()})),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{respond, ParseServer, MAX_BODY_SIZE};
+    use std::{
+        fs,
+        io::{Read, Write},
+        net::TcpStream,
+        path::PathBuf,
+        thread,
+    };
+
+    fn test_bookmark() -> Vec<u8> {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        fs::read(test_location).unwrap()
+    }
+
+    #[test]
+    fn test_respond() {
+        let data = test_bookmark();
+        let (status, value) = respond("POST", "/parse", &data);
+        assert_eq!(status, 200);
+        assert_eq!(value["path"][1], "Syncthing.app");
+        assert_eq!(value["creation"], 665473989.0);
+
+        let encoded = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &data);
+        let (status, value) = respond(
+            "POST",
+            "/parse?encoding=base64&timestamps=converted",
+            encoded.as_bytes(),
+        );
+        assert_eq!(status, 200);
+        assert_eq!(value["creation"], "2022-02-02T05:53:09Z");
+
+        assert_eq!(respond("POST", "/parse", b"book corrupted").0, 422);
+        assert_eq!(respond("POST", "/parse?encoding=base64", b"!!").0, 422);
+        assert_eq!(respond("POST", "/parse?encoding=zip", &data).0, 400);
+        assert_eq!(respond("GET", "/parse", b"").0, 405);
+        assert_eq!(respond("POST", "/", &data).0, 404);
+        assert_eq!(
+            respond("POST", "/parse", &vec![0; MAX_BODY_SIZE + 1]).0,
+            413
+        );
+    }
+
+    #[test]
+    fn test_parse_server() {
+        let server = ParseServer::bind("127.0.0.1:0").unwrap();
+        let address = server.local_addr().unwrap();
+        thread::spawn(move || server.run());
+
+        let data = test_bookmark();
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "POST /parse HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            data.len()
+        )
+        .unwrap();
+        stream.write_all(&data).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains("application/json"));
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        let value: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(value["localized_name"], "Syncthing");
+
+        assert!(ParseServer::bind("not an address").is_err());
+    }
+}
@@ -0,0 +1,176 @@
+//! Parse AirDrop and share sheet recents from `com.apple.sharekit.recents.plist`
+//!
+//! The sharing subsystem keeps the recent targets of each share service (ex: `com.apple.share.AirDrop.send`) as an
+//! array of dictionaries under the service identifier. A target dictionary holds the shared item or destination as
+//! bookmark data, the last time it was used and a display name. Key names differ between releases, so the first Data
+//! value with the bookmark signature, the first Date value and the `displayName`, `name` or `title` string are used.
+
+use std::{fs, io::Cursor, path::Path};
+
+use log::{error, warn};
+use plist::{Dictionary, Value};
+use serde::Serialize;
+
+use crate::{
+    bookmark::BookmarkData, error::BookmarkError, parser::parse_bookmark, plist::plist_date,
+    types::CocoaTime,
+};
+
+#[derive(Debug, Serialize)]
+pub struct ShareRecent {
+    pub service: String,                // Share service identifier
+    pub index: usize,                   // Position in the recents list of the service
+    pub name: Option<String>,           // Display name of the share target
+    pub last_used: Option<CocoaTime>,   // Last time the target was shared with
+    pub bookmark: Option<BookmarkData>, // Parsed share target bookmark
+}
+
+/// Keys that can hold the display name of a share target
+const NAME_KEYS: [&str; 3] = ["displayName", "name", "title"];
+
+/// Parse every recent share target in a `com.apple.sharekit.recents.plist` file (XML or binary). Targets with bookmark
+/// data that fails to parse are kept without a bookmark, values that are not lists of dictionaries are skipped
+pub fn parse_share_recents(data: &[u8]) -> Result<Vec<ShareRecent>, BookmarkError> {
+    let plist = match Value::from_reader(Cursor::new(data)) {
+        Ok(Value::Dictionary(plist)) => plist,
+        Ok(_) => {
+            error!("ShareKit recents plist is not a dictionary");
+            return Err(BookmarkError::InvalidPlistData);
+        }
+        Err(err) => {
+            error!("failed to parse ShareKit recents plist: {:?}", err);
+            return Err(BookmarkError::InvalidPlistData);
+        }
+    };
+
+    let mut recents = Vec::new();
+    for (service, targets) in &plist {
+        let Some(targets) = targets.as_array() else {
+            continue;
+        };
+        for (index, target) in targets.iter().enumerate() {
+            if let Some(target) = target.as_dictionary() {
+                recents.push(share_recent(service, index, target));
+            }
+        }
+    }
+    Ok(recents)
+}
+
+/// Read a `com.apple.sharekit.recents.plist` file and parse every recent share target
+pub fn parse_share_recents_file(path: impl AsRef<Path>) -> Result<Vec<ShareRecent>, BookmarkError> {
+    match fs::read(path.as_ref()) {
+        Ok(data) => parse_share_recents(&data),
+        Err(err) => {
+            error!(
+                "failed to read ShareKit recents plist {:?}: {:?}",
+                path.as_ref(),
+                err
+            );
+            Err(BookmarkError::InvalidPlistData)
+        }
+    }
+}
+
+/// Parse a single share target
+fn share_recent(service: &str, index: usize, target: &Dictionary) -> ShareRecent {
+    let bookmark = match target
+        .values()
+        .filter_map(Value::as_data)
+        .find(|data| data.starts_with(BookmarkData::BOOKMARK_SIGNATURE))
+        .map(parse_bookmark)
+    {
+        Some(Ok(bookmark)) => Some(bookmark),
+        Some(Err(err)) => {
+            warn!(
+                "Failed to parse bookmark for {} recent {}: {:?}",
+                service, index, err
+            );
+            None
+        }
+        None => None,
+    };
+
+    ShareRecent {
+        service: service.to_string(),
+        index,
+        name: NAME_KEYS
+            .iter()
+            .find_map(|key| target.get(key).and_then(Value::as_string))
+            .map(str::to_string),
+        last_used: target.values().find_map(plist_date),
+        bookmark,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_share_recents, parse_share_recents_file};
+    use plist::{Date, Dictionary, Value};
+    use std::{
+        fs,
+        path::PathBuf,
+        time::{Duration, UNIX_EPOCH},
+    };
+
+    fn test_recents() -> Vec<u8> {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+
+        let mut target = Dictionary::new();
+        target.insert(String::from("bookmark"), Value::Data(buffer));
+        target.insert(
+            String::from("displayName"),
+            Value::String(String::from("Syncthing")),
+        );
+        target.insert(
+            String::from("lastUsedDate"),
+            Value::Date(Date::from(UNIX_EPOCH + Duration::from_secs(1656555374))),
+        );
+        let mut broken = Dictionary::new();
+        broken.insert(
+            String::from("bookmark"),
+            Value::Data(b"book corrupted".to_vec()),
+        );
+
+        let mut plist = Dictionary::new();
+        plist.insert(
+            String::from("com.apple.share.AirDrop.send"),
+            Value::Array(vec![Value::Dictionary(target), Value::Dictionary(broken)]),
+        );
+        plist.insert(String::from("version"), Value::Integer(2.into()));
+        let mut data = Vec::new();
+        Value::Dictionary(plist)
+            .to_writer_binary(&mut data)
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn test_parse_share_recents() {
+        let recents = parse_share_recents(&test_recents()).unwrap();
+        assert_eq!(recents.len(), 2);
+        assert_eq!(recents[0].service, "com.apple.share.AirDrop.send");
+        assert_eq!(recents[0].name.as_deref(), Some("Syncthing"));
+        assert_eq!(recents[0].last_used.unwrap().to_unix(), 1656555374.0);
+        assert_eq!(
+            recents[0].bookmark.as_ref().unwrap().path,
+            ["Applications", "Syncthing.app"]
+        );
+        assert_eq!(recents[1].index, 1);
+        assert!(recents[1].bookmark.is_none());
+        assert!(recents[1].last_used.is_none());
+
+        let path = std::env::temp_dir().join("macos_bookmarks_test_sharekit.plist");
+        fs::write(&path, test_recents()).unwrap();
+        assert_eq!(parse_share_recents_file(&path).unwrap().len(), 2);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_share_recents_bad_data() {
+        assert!(parse_share_recents(b"not a plist").is_err());
+        assert!(parse_share_recents_file("/does/not/exist.plist").is_err());
+    }
+}
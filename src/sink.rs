@@ -0,0 +1,377 @@
+//! Output sinks for command line results
+//!
+//! A `Sink` receives the serialized output of each input file (a parsed bookmark file or a scanned file) and writes it
+//! to its destination: `StreamSink` appends every output to a single stream (stdout, a file or a UNIX socket read by
+//! another process) and `DirectorySink` writes a file per input to an output directory. Both can compress their output
+//! with gzip (`gzip` feature) or zstd (`zstd` feature). A header (ex: the CSV column row) is written at the start of
+//! every stream and every file. Compressed streams are only complete once `Sink::finish` is called.
+
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use log::error;
+
+use crate::{error::BookmarkError, group::unique_file_name};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Compression {
+    /// Uncompressed output
+    #[default]
+    None,
+    /// gzip compressed output (`.gz`)
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// zstd compressed output (`.zst`)
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl Compression {
+    /// Get the file extension added to compressed output files
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => Some("gz"),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Some("zst"),
+        }
+    }
+}
+
+/// Destination of the output of each input file
+pub trait Sink {
+    /// Write the output of one input file
+    fn write(&mut self, input: &Path, output: &[u8]) -> Result<(), BookmarkError>;
+    /// Finish the output and flush it to the destination. Nothing can be written afterwards
+    fn finish(&mut self) -> Result<(), BookmarkError>;
+}
+
+/// Sink writing every output to a single stream
+pub struct StreamSink<W: Write> {
+    encoder: Option<Encoder<W>>, // Stream encoder, None once finished
+}
+
+impl<W: Write> StreamSink<W> {
+    /// Create a sink writing to a stream. The header is written immediately
+    pub fn new(
+        writer: W,
+        compression: Compression,
+        header: &[u8],
+    ) -> Result<StreamSink<W>, BookmarkError> {
+        let mut encoder = match Encoder::new(writer, compression) {
+            Ok(encoder) => encoder,
+            Err(err) => {
+                error!("failed to create {:?} encoder: {:?}", compression, err);
+                return Err(BookmarkError::OutputWriteError);
+            }
+        };
+        if let Err(err) = encoder.write_all(header) {
+            error!("failed to write output header: {:?}", err);
+            return Err(BookmarkError::OutputWriteError);
+        }
+        Ok(StreamSink {
+            encoder: Some(encoder),
+        })
+    }
+}
+
+impl StreamSink<BufWriter<File>> {
+    /// Create a sink writing to a file, replacing an existing file
+    pub fn create(
+        path: impl AsRef<Path>,
+        compression: Compression,
+        header: &[u8],
+    ) -> Result<StreamSink<BufWriter<File>>, BookmarkError> {
+        match File::create(path.as_ref()) {
+            Ok(file) => StreamSink::new(BufWriter::new(file), compression, header),
+            Err(err) => {
+                error!(
+                    "failed to create output file {:?}: {:?}",
+                    path.as_ref(),
+                    err
+                );
+                Err(BookmarkError::OutputWriteError)
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+impl StreamSink<BufWriter<std::os::unix::net::UnixStream>> {
+    /// Create a sink writing to a UNIX socket another process is listening on
+    pub fn connect(
+        path: impl AsRef<Path>,
+        compression: Compression,
+        header: &[u8],
+    ) -> Result<StreamSink<BufWriter<std::os::unix::net::UnixStream>>, BookmarkError> {
+        match std::os::unix::net::UnixStream::connect(path.as_ref()) {
+            Ok(stream) => StreamSink::new(BufWriter::new(stream), compression, header),
+            Err(err) => {
+                error!("failed to connect to socket {:?}: {:?}", path.as_ref(), err);
+                Err(BookmarkError::OutputWriteError)
+            }
+        }
+    }
+}
+
+impl<W: Write> Sink for StreamSink<W> {
+    fn write(&mut self, input: &Path, output: &[u8]) -> Result<(), BookmarkError> {
+        let Some(encoder) = &mut self.encoder else {
+            error!("output for {:?} written after the sink finished", input);
+            return Err(BookmarkError::OutputWriteError);
+        };
+        if let Err(err) = encoder.write_all(output) {
+            error!("failed to write output for {:?}: {:?}", input, err);
+            return Err(BookmarkError::OutputWriteError);
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), BookmarkError> {
+        let Some(encoder) = self.encoder.take() else {
+            return Ok(());
+        };
+        if let Err(err) = encoder.finish() {
+            error!("failed to finish output: {:?}", err);
+            return Err(BookmarkError::OutputWriteError);
+        }
+        Ok(())
+    }
+}
+
+/// Sink writing the output of each input to its own file in a directory
+pub struct DirectorySink {
+    directory: PathBuf,       // Output directory
+    extension: String,        // Output file extension, including the compression extension
+    compression: Compression, // Output file compression
+    header: Vec<u8>,          // Header written at the start of each file
+    files: HashSet<String>,   // File names already used in the directory
+}
+
+impl DirectorySink {
+    /// Create a sink writing to a directory, creating the directory if needed. Each output is written to the input file
+    /// name with `extension` (and the compression extension) added. Existing files are never replaced, names already
+    /// used in the directory get a numbered suffix
+    pub fn new(
+        directory: impl AsRef<Path>,
+        extension: &str,
+        compression: Compression,
+        header: &[u8],
+    ) -> Result<DirectorySink, BookmarkError> {
+        let directory = directory.as_ref();
+        if let Err(err) = fs::create_dir_all(directory) {
+            error!(
+                "failed to create output directory {:?}: {:?}",
+                directory, err
+            );
+            return Err(BookmarkError::OutputWriteError);
+        }
+        let files = match fs::read_dir(directory) {
+            Ok(entries) => entries
+                .filter_map(|entry| Some(entry.ok()?.file_name().to_string_lossy().to_lowercase()))
+                .collect(),
+            Err(err) => {
+                error!("failed to read output directory {:?}: {:?}", directory, err);
+                return Err(BookmarkError::OutputWriteError);
+            }
+        };
+        let extension = match compression.extension() {
+            Some(compressed) => format!("{extension}.{compressed}"),
+            None => extension.to_string(),
+        };
+        Ok(DirectorySink {
+            directory: directory.to_path_buf(),
+            extension,
+            compression,
+            header: header.to_vec(),
+            files,
+        })
+    }
+}
+
+impl Sink for DirectorySink {
+    fn write(&mut self, input: &Path, output: &[u8]) -> Result<(), BookmarkError> {
+        let name = input
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let file = unique_file_name(&name, &self.extension, &mut self.files);
+        let mut sink =
+            StreamSink::create(self.directory.join(file), self.compression, &self.header)?;
+        sink.write(input, output)?;
+        sink.finish()
+    }
+
+    fn finish(&mut self) -> Result<(), BookmarkError> {
+        Ok(())
+    }
+}
+
+/// Stream writer with optional compression
+enum Encoder<W: Write> {
+    Plain(W),
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzEncoder<W>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::write::Encoder<'static, W>),
+}
+
+impl<W: Write> Encoder<W> {
+    /// Create an encoder for a compression
+    fn new(writer: W, compression: Compression) -> io::Result<Encoder<W>> {
+        Ok(match compression {
+            Compression::None => Encoder::Plain(writer),
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(
+                writer,
+                flate2::Compression::default(),
+            )),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Encoder::Zstd(zstd::stream::write::Encoder::new(writer, 0)?),
+        })
+    }
+
+    /// Write the end of the compressed stream and flush the writer
+    #[cfg_attr(
+        not(any(feature = "gzip", feature = "zstd")),
+        allow(clippy::infallible_destructuring_match)
+    )]
+    fn finish(self) -> io::Result<()> {
+        let mut writer = match self {
+            Encoder::Plain(writer) => writer,
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(encoder) => encoder.finish()?,
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(encoder) => encoder.finish()?,
+        };
+        writer.flush()
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Encoder::Plain(writer) => writer.write(buf),
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(encoder) => encoder.write(buf),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Encoder::Plain(writer) => writer.flush(),
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(encoder) => encoder.flush(),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Compression, DirectorySink, Sink, StreamSink};
+    use std::{env, fs, path::Path};
+
+    #[test]
+    fn test_stream_sink() {
+        let mut output = Vec::new();
+        {
+            let mut sink = StreamSink::new(&mut output, Compression::None, b"header\n").unwrap();
+            sink.write(Path::new("/a.bookmark"), b"a\n").unwrap();
+            sink.write(Path::new("/b.bookmark"), b"b\n").unwrap();
+            sink.finish().unwrap();
+            assert!(sink.write(Path::new("/c.bookmark"), b"c\n").is_err());
+        }
+        assert_eq!(output, b"header\na\nb\n");
+    }
+
+    #[test]
+    fn test_directory_sink() {
+        let directory = env::temp_dir().join("macos_bookmarks_test_directory_sink");
+        let _ = fs::remove_dir_all(&directory);
+
+        let mut sink = DirectorySink::new(&directory, "csv", Compression::None, b"file\n").unwrap();
+        sink.write(Path::new("/a/loginitem.bookmark"), b"a\n")
+            .unwrap();
+        sink.write(Path::new("/b/loginitem.bookmark"), b"b\n")
+            .unwrap();
+        sink.finish().unwrap();
+        assert_eq!(
+            fs::read(directory.join("loginitem.bookmark.csv")).unwrap(),
+            b"file\na\n"
+        );
+        assert_eq!(
+            fs::read(directory.join("loginitem.bookmark-1.csv")).unwrap(),
+            b"file\nb\n"
+        );
+
+        // Files from an earlier run are kept
+        let mut sink = DirectorySink::new(&directory, "csv", Compression::None, b"").unwrap();
+        sink.write(Path::new("/loginitem.bookmark"), b"c\n")
+            .unwrap();
+        assert_eq!(
+            fs::read(directory.join("loginitem.bookmark-2.csv")).unwrap(),
+            b"c\n"
+        );
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_socket_sink() {
+        use std::{io::Read, os::unix::net::UnixListener};
+
+        let path = env::temp_dir().join("macos_bookmarks_test_sink.sock");
+        let _ = fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let mut sink = StreamSink::connect(&path, Compression::None, b"").unwrap();
+        sink.write(Path::new("/a.bookmark"), b"a\n").unwrap();
+        sink.finish().unwrap();
+        drop(sink);
+
+        let mut output = String::new();
+        listener
+            .accept()
+            .unwrap()
+            .0
+            .read_to_string(&mut output)
+            .unwrap();
+        assert_eq!(output, "a\n");
+        fs::remove_file(&path).unwrap();
+        assert!(StreamSink::connect(&path, Compression::None, b"").is_err());
+    }
+
+    #[test]
+    #[cfg(all(feature = "gzip", feature = "zstd"))]
+    fn test_compressed_sink() {
+        use std::io::Read;
+
+        let mut output = Vec::new();
+        let mut sink = StreamSink::new(&mut output, Compression::Gzip, b"header\n").unwrap();
+        sink.write(Path::new("/a.bookmark"), b"a\n").unwrap();
+        sink.finish().unwrap();
+        drop(sink);
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(output.as_slice())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, "header\na\n");
+
+        let directory = env::temp_dir().join("macos_bookmarks_test_compressed_sink");
+        let _ = fs::remove_dir_all(&directory);
+        let mut sink = DirectorySink::new(&directory, "jsonl", Compression::Zstd, b"").unwrap();
+        sink.write(Path::new("/a.bookmark"), b"a\n").unwrap();
+        let data = fs::read(directory.join("a.bookmark.jsonl.zst")).unwrap();
+        assert_eq!(zstd::decode_all(data.as_slice()).unwrap(), b"a\n");
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,172 @@
+//! APFS snapshots for historical directory scans
+//!
+//! A mounted APFS local snapshot (ex: `com.apple.TimeMachine.2022-06-30-021614.local`, mounted with `mount_apfs -s`)
+//! contains the volume as it was when the snapshot was taken. `scan::scan_snapshots` scans the same directory on the
+//! live volume and in each snapshot and tags every result with the `Snapshot` it came from, so persistence state can
+//! be diffed over time from a single machine. Snapshots are created from user supplied mount points with
+//! `Snapshot::new` or, on macOS, read from the mounted file systems with `mounted_snapshots`.
+
+use std::path::{Component, Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::types::{days_from_civil, CocoaTime};
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Snapshot {
+    pub name: String,               // Snapshot name
+    pub mount_point: PathBuf,       // Directory the snapshot is mounted at
+    pub created: Option<CocoaTime>, // Time in the snapshot name (machine local time)
+}
+
+impl Snapshot {
+    /// Create a snapshot from its mount point. The name is the last component of the mount point
+    pub fn new(mount_point: impl AsRef<Path>) -> Snapshot {
+        let mount_point = mount_point.as_ref();
+        let name = mount_point
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Snapshot::with_name(&name, mount_point)
+    }
+
+    /// Create a snapshot with a known name mounted at a directory
+    pub fn with_name(name: &str, mount_point: impl AsRef<Path>) -> Snapshot {
+        Snapshot {
+            name: name.to_string(),
+            mount_point: mount_point.as_ref().to_path_buf(),
+            created: snapshot_time(name),
+        }
+    }
+
+    /// Get the location of a live volume path inside the snapshot. The root and drive prefix of the path are dropped
+    /// (ex: `C:\Users` is `<mount point>\Users`)
+    pub fn path_in_snapshot(&self, path: &Path) -> PathBuf {
+        let relative = path
+            .components()
+            .filter(|component| !matches!(component, Component::Prefix(_) | Component::RootDir));
+        self.mount_point.join(relative.collect::<PathBuf>())
+    }
+}
+
+/// Get the snapshots in `mount` output. Snapshot mounts use the `<snapshot name>@<device>` form as the mounted device
+/// (ex: `com.apple.TimeMachine.2022-06-30-021614.local@/dev/disk1s1 on /Volumes/snapshot (apfs, local, read-only)`)
+pub fn parse_mount_output(output: &str) -> Vec<Snapshot> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (device, rest) = line.split_once(" on ")?;
+            let (name, _) = device.split_once('@')?;
+            let mount_point = match rest.rfind(" (") {
+                Some(options) => &rest[..options],
+                None => rest,
+            };
+            Some(Snapshot::with_name(name, mount_point))
+        })
+        .collect()
+}
+
+/// Get the APFS snapshots mounted on the live system. Empty if `mount` cannot be run
+#[cfg(target_os = "macos")]
+pub fn mounted_snapshots() -> Vec<Snapshot> {
+    match std::process::Command::new("/sbin/mount").output() {
+        Ok(output) => parse_mount_output(&String::from_utf8_lossy(&output.stdout)),
+        Err(err) => {
+            log::warn!("Failed to list mounted file systems: {:?}", err);
+            Vec::new()
+        }
+    }
+}
+
+/// Get the `YYYY-MM-DD-HHMMSS` timestamp in a snapshot name
+fn snapshot_time(name: &str) -> Option<CocoaTime> {
+    let bytes = name.as_bytes();
+    let stamp_len = "YYYY-MM-DD-HHMMSS".len();
+    (0..=bytes.len().checked_sub(stamp_len)?).find_map(|start| {
+        let stamp = bytes.get(start..start + stamp_len)?;
+        let dashes = [4, 7, 10].iter().all(|index| stamp[*index] == b'-');
+        let digits = stamp
+            .iter()
+            .enumerate()
+            .all(|(index, byte)| [4, 7, 10].contains(&index) || byte.is_ascii_digit());
+        if !dashes || !digits {
+            return None;
+        }
+        let number = |range: std::ops::Range<usize>| {
+            stamp[range]
+                .iter()
+                .fold(0, |value, digit| value * 10 + i64::from(digit - b'0'))
+        };
+        let (month, day) = (number(5..7), number(8..10));
+        let (hour, minute, second) = (number(11..13), number(13..15), number(15..17));
+        if !(1..=12).contains(&month)
+            || !(1..=31).contains(&day)
+            || hour > 23
+            || minute > 59
+            || second > 59
+        {
+            return None;
+        }
+        let days = days_from_civil(number(0..4), month, day);
+        let seconds = days * 86400 + hour * 3600 + minute * 60 + second;
+        Some(CocoaTime::from_unix(seconds as f64))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_mount_output, Snapshot};
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_snapshot() {
+        let snapshot = Snapshot::new("/Volumes/com.apple.TimeMachine.2022-06-30-021614.local");
+        assert_eq!(
+            snapshot.name,
+            "com.apple.TimeMachine.2022-06-30-021614.local"
+        );
+        assert_eq!(
+            snapshot.created.unwrap().to_string(),
+            "2022-06-30T02:16:14Z"
+        );
+        assert_eq!(
+            snapshot.path_in_snapshot(Path::new("/Users/sur/Library")),
+            PathBuf::from(
+                "/Volumes/com.apple.TimeMachine.2022-06-30-021614.local/Users/sur/Library"
+            )
+        );
+
+        assert_eq!(
+            snapshot.path_in_snapshot(Path::new("Users/sur")),
+            snapshot.path_in_snapshot(Path::new("/Users/sur"))
+        );
+        #[cfg(windows)]
+        assert_eq!(
+            Snapshot::new(r"D:\snapshot").path_in_snapshot(Path::new(r"C:\Users\sur")),
+            PathBuf::from(r"D:\snapshot\Users\sur")
+        );
+
+        assert!(Snapshot::new("/mnt/before-update").created.is_none());
+        assert!(Snapshot::new("/mnt/2022-13-30-021614").created.is_none());
+        assert!(Snapshot::new("/").created.is_none());
+    }
+
+    #[test]
+    fn test_parse_mount_output() {
+        let output = "/dev/disk3s1s1 on / (apfs, sealed, local, read-only, journaled)
+/dev/disk3s5 on /System/Volumes/Data (apfs, local, journaled, nobrowse, protect)
+com.apple.TimeMachine.2022-06-30-021614.local@/dev/disk3s5 on /Volumes/Snapshots/before (update) (apfs, local, read-only, journaled)
+map auto_home on /System/Volumes/Data/home (autofs, automounted, nobrowse)";
+        let snapshots = parse_mount_output(output);
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(
+            snapshots[0].name,
+            "com.apple.TimeMachine.2022-06-30-021614.local"
+        );
+        assert_eq!(
+            snapshots[0].mount_point,
+            PathBuf::from("/Volumes/Snapshots/before (update)")
+        );
+        assert!(snapshots[0].created.is_some());
+    }
+}
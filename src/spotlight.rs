@@ -0,0 +1,139 @@
+//! Parse Spotlight shortcuts from `com.apple.spotlight.plist`
+//!
+//! Spotlight remembers which result was opened for a typed search under `UserShortcuts`: the typed text maps to a
+//! dictionary with the result name (`DISPLAY_NAME`), the last time it was opened (`LAST_USED`) and its location
+//! (`URL`, a path or file URL). Some entries also keep the result as bookmark data, the first Data value with the
+//! bookmark signature is parsed.
+
+use std::{fs, io::Cursor, path::Path};
+
+use log::{error, warn};
+use plist::{Dictionary, Value};
+use serde::Serialize;
+
+use crate::{
+    bookmark::BookmarkData, error::BookmarkError, parser::parse_bookmark, plist::plist_date,
+    types::CocoaTime,
+};
+
+#[derive(Debug, Serialize)]
+pub struct SpotlightShortcut {
+    pub typed: String,                  // Search text typed in Spotlight
+    pub display_name: Option<String>,   // Name of the opened result
+    pub url: Option<String>,            // Path or URL of the opened result
+    pub last_used: Option<CocoaTime>,   // Last time the result was opened
+    pub bookmark: Option<BookmarkData>, // Parsed result bookmark
+}
+
+/// Parse every shortcut in a `com.apple.spotlight.plist` file (XML or binary). Shortcuts with bookmark data that fails
+/// to parse are kept without a bookmark
+pub fn parse_spotlight_shortcuts(data: &[u8]) -> Result<Vec<SpotlightShortcut>, BookmarkError> {
+    let plist = match Value::from_reader(Cursor::new(data)) {
+        Ok(Value::Dictionary(plist)) => plist,
+        Ok(_) => {
+            error!("Spotlight plist is not a dictionary");
+            return Err(BookmarkError::InvalidPlistData);
+        }
+        Err(err) => {
+            error!("failed to parse Spotlight plist: {:?}", err);
+            return Err(BookmarkError::InvalidPlistData);
+        }
+    };
+
+    let Some(shortcuts) = plist.get("UserShortcuts").and_then(Value::as_dictionary) else {
+        return Ok(Vec::new());
+    };
+    Ok(shortcuts
+        .iter()
+        .filter_map(|(typed, shortcut)| Some(spotlight_shortcut(typed, shortcut.as_dictionary()?)))
+        .collect())
+}
+
+/// Read a `com.apple.spotlight.plist` file and parse every shortcut
+pub fn parse_spotlight_shortcuts_file(
+    path: impl AsRef<Path>,
+) -> Result<Vec<SpotlightShortcut>, BookmarkError> {
+    match fs::read(path.as_ref()) {
+        Ok(data) => parse_spotlight_shortcuts(&data),
+        Err(err) => {
+            error!(
+                "failed to read Spotlight plist {:?}: {:?}",
+                path.as_ref(),
+                err
+            );
+            Err(BookmarkError::InvalidPlistData)
+        }
+    }
+}
+
+/// Parse a single `UserShortcuts` entry
+fn spotlight_shortcut(typed: &str, shortcut: &Dictionary) -> SpotlightShortcut {
+    let string = |key: &str| {
+        shortcut
+            .get(key)
+            .and_then(Value::as_string)
+            .map(str::to_string)
+    };
+    let bookmark = match shortcut
+        .values()
+        .filter_map(Value::as_data)
+        .find(|data| data.starts_with(BookmarkData::BOOKMARK_SIGNATURE))
+        .map(parse_bookmark)
+    {
+        Some(Ok(bookmark)) => Some(bookmark),
+        Some(Err(err)) => {
+            warn!("Failed to parse bookmark for shortcut {}: {:?}", typed, err);
+            None
+        }
+        None => None,
+    };
+
+    SpotlightShortcut {
+        typed: typed.to_string(),
+        display_name: string("DISPLAY_NAME"),
+        url: string("URL"),
+        last_used: shortcut.get("LAST_USED").and_then(plist_date),
+        bookmark,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_spotlight_shortcuts, parse_spotlight_shortcuts_file};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_parse_spotlight_shortcuts() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/com.apple.spotlight.plist");
+
+        let shortcuts = parse_spotlight_shortcuts_file(&test_location).unwrap();
+        assert_eq!(shortcuts.len(), 2);
+        assert_eq!(shortcuts[0].typed, "saf");
+        assert_eq!(shortcuts[0].display_name.as_deref(), Some("Safari"));
+        assert_eq!(
+            shortcuts[0].url.as_deref(),
+            Some("/Applications/Safari.app")
+        );
+        assert_eq!(
+            shortcuts[0].last_used.unwrap().to_string(),
+            "2022-06-30T02:16:14Z"
+        );
+        assert!(shortcuts[0].bookmark.is_none());
+        assert_eq!(shortcuts[1].typed, "sync");
+        assert_eq!(
+            shortcuts[1].bookmark.as_ref().unwrap().path,
+            ["Applications", "Syncthing.app"]
+        );
+
+        let plist = br#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0"><dict><key>version</key><integer>9</integer></dict></plist>"#;
+        assert!(parse_spotlight_shortcuts(plist).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_spotlight_shortcuts_bad_data() {
+        assert!(parse_spotlight_shortcuts(b"not a plist").is_err());
+        assert!(parse_spotlight_shortcuts_file("/does/not/exist.plist").is_err());
+    }
+}
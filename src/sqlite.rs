@@ -0,0 +1,207 @@
+//! Find bookmarks stored in SQLite BLOB columns
+//!
+//! Applications such as Notes and Mail store bookmark data in SQLite databases. Every BLOB value containing the
+//! bookmark signature is parsed, bookmarks may also be embedded inside a larger BLOB (ex: a serialized plist).
+
+use std::{fs, path::Path};
+
+use log::{error, warn};
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
+
+use crate::{
+    bookmark::BookmarkData,
+    error::BookmarkError,
+    extract::{bookmark_range, locate_blob, ByteRange},
+    parser::parse_bookmark,
+};
+
+#[derive(Debug, Serialize)]
+pub struct SqliteBookmark {
+    pub table: String,                 // Table containing the bookmark
+    pub column: String,                // Column containing the bookmark
+    pub rowid: i64,                    // Row ID of the row containing the bookmark
+    pub offset: usize,                 // Offset to the bookmark within the BLOB value
+    pub range: ByteRange,              // Bookmark bytes within the BLOB value
+    pub file_range: Option<ByteRange>, // Bookmark bytes within the database file (if stored contiguously)
+    pub bookmark: BookmarkData,        // Parsed bookmark
+}
+
+/// Scan every BLOB column in a SQLite database for bookmarks. The database is opened read only
+pub fn scan_sqlite(path: &Path) -> Result<Vec<SqliteBookmark>, BookmarkError> {
+    let connection = match Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY) {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("failed to open SQLite database: {:?}", err);
+            return Err(BookmarkError::InvalidSqliteData);
+        }
+    };
+
+    // The database file is only read to locate the bookmark bytes
+    let database = fs::read(path).unwrap_or_default();
+    let mut bookmarks = Vec::new();
+    for table in query_strings(
+        &connection,
+        "SELECT name FROM sqlite_master WHERE type = 'table'",
+    )? {
+        let columns = query_strings(
+            &connection,
+            &format!(
+                "SELECT name FROM pragma_table_info({})",
+                quote_literal(&table)
+            ),
+        )?;
+        for column in columns {
+            if let Err(err) = scan_column(&connection, &table, &column, &database, &mut bookmarks) {
+                // Tables created WITHOUT ROWID cannot be scanned
+                warn!("Failed to scan {}.{}: {:?}", table, column, err);
+            }
+        }
+    }
+    Ok(bookmarks)
+}
+
+/// Parse every bookmark found in the BLOB values of a column
+fn scan_column(
+    connection: &Connection,
+    table: &str,
+    column: &str,
+    database: &[u8],
+    bookmarks: &mut Vec<SqliteBookmark>,
+) -> rusqlite::Result<()> {
+    let column_name = quote_identifier(column);
+    let query = format!(
+        "SELECT rowid, {column_name} FROM {} WHERE typeof({column_name}) = 'blob' AND instr({column_name}, X'{}') > 0",
+        quote_identifier(table),
+        hex(BookmarkData::BOOKMARK_SIGNATURE)
+    );
+    let mut statement = connection.prepare(&query)?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        let rowid: i64 = row.get(0)?;
+        let value: Vec<u8> = row.get(1)?;
+
+        let signature = BookmarkData::BOOKMARK_SIGNATURE;
+        let offsets = value
+            .windows(signature.len())
+            .enumerate()
+            .filter(|(_, window)| *window == signature)
+            .map(|(offset, _)| offset);
+        for offset in offsets {
+            let Some(range) = bookmark_range(&value, offset) else {
+                continue;
+            };
+            if let Ok(bookmark) = parse_bookmark(&value[offset..]) {
+                bookmarks.push(SqliteBookmark {
+                    table: table.to_string(),
+                    column: column.to_string(),
+                    rowid,
+                    offset,
+                    range,
+                    file_range: range
+                        .slice(&value)
+                        .and_then(|blob| locate_blob(database, blob)),
+                    bookmark,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Get the first column of every row returned by a query as strings
+fn query_strings(connection: &Connection, query: &str) -> Result<Vec<String>, BookmarkError> {
+    let values = connection.prepare(query).and_then(|mut statement| {
+        statement
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()
+    });
+    match values {
+        Ok(values) => Ok(values),
+        Err(err) => {
+            error!("failed to query SQLite database: {:?}", err);
+            Err(BookmarkError::InvalidSqliteData)
+        }
+    }
+}
+
+/// Quote a table/column name
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Quote a string literal
+fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Encode bytes as hex for a BLOB literal
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{byte:02X}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{quote_identifier, scan_sqlite};
+    use rusqlite::{params, Connection};
+    use std::{env, fs, path::PathBuf};
+
+    #[test]
+    fn test_scan_sqlite() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+
+        let path = env::temp_dir().join("macos_bookmarks_scan_test.sqlite");
+        let _ = fs::remove_file(&path);
+        let connection = Connection::open(&path).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE items (name TEXT, \"item data\" BLOB);
+                CREATE TABLE keyed (id TEXT PRIMARY KEY, data BLOB) WITHOUT ROWID;",
+            )
+            .unwrap();
+
+        let mut embedded = b"bplist00 bookshelf ".to_vec();
+        embedded.extend_from_slice(&buffer);
+        for (name, data) in [
+            ("plain", buffer.clone()),
+            ("embedded", embedded),
+            ("other", b"not a bookmark".to_vec()),
+        ] {
+            connection
+                .execute("INSERT INTO items VALUES (?1, ?2)", params![name, data])
+                .unwrap();
+        }
+        connection
+            .execute("INSERT INTO keyed VALUES ('a', ?1)", params![buffer])
+            .unwrap();
+        drop(connection);
+
+        let bookmarks = scan_sqlite(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(bookmarks.len(), 2);
+        assert_eq!(bookmarks[0].table, "items");
+        assert_eq!(bookmarks[0].column, "item data");
+        assert_eq!(bookmarks[0].rowid, 1);
+        assert_eq!(bookmarks[0].offset, 0);
+        assert_eq!(bookmarks[1].rowid, 2);
+        assert_eq!(bookmarks[1].offset, 19);
+        assert_eq!(bookmarks[1].range.start, 19);
+        assert_eq!(bookmarks[1].range.len(), buffer.len());
+        let database = bookmarks[0].file_range.unwrap();
+        assert_eq!(database.len(), buffer.len());
+        assert_eq!(
+            bookmarks[1].bookmark.path,
+            ["Applications", "Syncthing.app"]
+        );
+    }
+
+    #[test]
+    fn test_scan_sqlite_bad_file() {
+        let path = env::temp_dir().join("macos_bookmarks_missing.sqlite");
+        assert!(scan_sqlite(&path).is_err());
+        assert_eq!(quote_identifier("a\"b"), "\"a\"\"b\"");
+    }
+}
@@ -0,0 +1,154 @@
+//! Record type statistics for bookmarks
+//!
+//! Provides a compact profile of the TOC keys in a bookmark that can be used to baseline bookmarks across many systems.
+
+use serde::Serialize;
+
+use crate::bookmark::BookmarkData;
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct KeyProfile {
+    pub key_bitmap: u64,   // Bit set for each known TOC key present (see `KEY_BITS`)
+    pub record_count: u32, // Number of TOC records
+    pub unknown_keys: u32, // Number of TOC records with unknown keys
+    pub path_components: u32, // Number of target path components
+    pub cnid_components: u32, // Number of target CNID path components
+    pub nested_bookmarks: u32, // Number of embedded bookmarks
+    pub total_size: u64,   // Total size of record data referenced by the TOC
+}
+
+/// Bit of each known TOC key in `KeyProfile::key_bitmap`. Bits are fixed so profiles stay comparable when keys are
+/// added, new keys get the next unused bit (at most 64 keys)
+pub const KEY_BITS: [(u32, u32); 35] = [
+    (0x1003, 0),   // Unknown
+    (0x1004, 1),   // Target path
+    (0x1005, 2),   // Target CNID path
+    (0x1010, 3),   // Target flags
+    (0x1020, 4),   // Target filename
+    (0x1040, 5),   // Target creation date
+    (0x1054, 6),   // File metadata
+    (0x1055, 7),   // File metadata
+    (0x1056, 8),   // File metadata
+    (0x1057, 9),   // File metadata
+    (0x1101, 10),  // Unknown
+    (0x1102, 11),  // Unknown
+    (0x2000, 12),  // TOC path
+    (0x2002, 13),  // Volume path
+    (0x2005, 14),  // Volume URL
+    (0x2010, 15),  // Volume name
+    (0x2011, 16),  // Volume UUID
+    (0x2012, 17),  // Volume size
+    (0x2013, 18),  // Volume creation date
+    (0x2020, 19),  // Volume flags
+    (0x2030, 20),  // Volume is root
+    (0x2040, 21),  // Volume bookmark
+    (0x2050, 22),  // Volume mount point
+    (0x2070, 23),  // Unknown
+    (0xc001, 24),  // Containing folder index
+    (0xc011, 25),  // Creator username
+    (0xc012, 26),  // Creator UID
+    (0xd001, 27),  // File reference flag
+    (0xd010, 28),  // Creation options
+    (0xe003, 29),  // URL length array
+    (0xf017, 30),  // Localized name
+    (0xf022, 31),  // Display name
+    (0xf080, 32),  // Security extension (read write)
+    (0xf081, 33),  // Security extension (read only)
+    (0xf000f, 34), // Is executable
+];
+
+/// Get the `KeyProfile::key_bitmap` bit of a TOC key. `None` for unknown keys
+pub fn key_bit(record_type: u32) -> Option<u32> {
+    KEY_BITS
+        .iter()
+        .find(|(key, _)| *key == record_type)
+        .map(|(_, bit)| *bit)
+}
+
+impl KeyProfile {
+    /// Flatten the profile into a feature vector (one value per `KEY_BITS` bit followed by the counts)
+    pub fn features(&self) -> Vec<f64> {
+        let mut features: Vec<f64> = (0..KEY_BITS.len())
+            .map(|bit| ((self.key_bitmap >> bit) & 1) as f64)
+            .collect();
+        features.push(self.record_count as f64);
+        features.push(self.unknown_keys as f64);
+        features.push(self.path_components as f64);
+        features.push(self.cnid_components as f64);
+        features.push(self.nested_bookmarks as f64);
+        features.push(self.total_size as f64);
+        features
+    }
+}
+
+/// Build a key profile for a parsed bookmark
+pub fn key_profile(bookmark: &BookmarkData) -> KeyProfile {
+    let mut profile = KeyProfile {
+        key_bitmap: 0,
+        record_count: bookmark.record_offsets.len() as u32,
+        unknown_keys: 0,
+        path_components: bookmark.path.len() as u32,
+        cnid_components: bookmark.cnid_path.len() as u32,
+        nested_bookmarks: bookmark.nested_bookmarks.len() as u32,
+        total_size: 0,
+    };
+
+    for record in &bookmark.record_offsets {
+        profile.total_size += record.data_length as u64;
+        match key_bit(record.record_type).and_then(|bit| 1u64.checked_shl(bit)) {
+            Some(bit) => profile.key_bitmap |= bit,
+            None => profile.unknown_keys += 1,
+        }
+    }
+    profile
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{key_bit, key_profile, KEY_BITS};
+    use crate::{bookmark::BookmarkData, parser::parse_bookmark};
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn test_key_profile() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+        let bookmark = parse_bookmark(&buffer).unwrap();
+
+        let profile = key_profile(&bookmark);
+        assert_eq!(profile.record_count, 14);
+        assert_eq!(profile.key_bitmap.count_ones(), 14);
+        assert_eq!(profile.unknown_keys, 0);
+        assert_eq!(profile.path_components, 2);
+        assert_eq!(profile.cnid_components, 2);
+        assert_eq!(profile.nested_bookmarks, 0);
+        assert_eq!(profile.total_size, 320);
+
+        assert_eq!(
+            profile.key_bitmap & (1 << key_bit(BookmarkData::TARGET_PATH).unwrap()),
+            1 << 1
+        );
+
+        let features = profile.features();
+        assert_eq!(features.len(), 41);
+        assert_eq!(features[35], 14.0);
+    }
+
+    #[test]
+    fn test_key_bits() {
+        let mut keys: Vec<u32> = KEY_BITS.iter().map(|(key, _)| *key).collect();
+        let mut known = BookmarkData::KNOWN_RECORD_TYPES.to_vec();
+        keys.sort_unstable();
+        known.sort_unstable();
+        assert_eq!(keys, known);
+
+        let mut bits: Vec<u32> = KEY_BITS.iter().map(|(_, bit)| *bit).collect();
+        bits.sort_unstable();
+        bits.dedup();
+        assert_eq!(bits.len(), KEY_BITS.len());
+        assert!(bits.iter().all(|bit| *bit < u64::BITS));
+        assert_eq!(key_bit(BookmarkData::IS_EXECUTABLE), Some(34));
+        assert_eq!(key_bit(0xdead), None);
+    }
+}
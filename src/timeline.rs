@@ -0,0 +1,167 @@
+//! Build a timeline from many bookmarks
+//!
+//! Target and volume creation timestamps from every bookmark are merged into one sorted timeline. The same event
+//! found in several sources (ex: one volume referenced by many bookmarks) is listed once with every source.
+
+use std::{collections::BTreeMap, io::Write};
+
+use serde::Serialize;
+
+use crate::{bookmark::BookmarkData, types::CocoaTime};
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct TimelineEvent {
+    pub timestamp: String,    // RFC 3339 UTC timestamp
+    pub unix_seconds: f64,    // Seconds since the Unix epoch
+    pub event: &'static str,  // target_created or volume_created
+    pub path: String,         // Target path, or the volume path for volume events
+    pub volume_name: String,  // Name of the volume
+    pub sources: Vec<String>, // Sources containing the event (ex: file paths)
+}
+
+/// Merge the creation timestamps of bookmarks into a timeline sorted by time. Each bookmark is paired with a
+/// description of its source, missing (zero) timestamps are skipped
+pub fn build_timeline(bookmarks: &[(&str, &BookmarkData)]) -> Vec<TimelineEvent> {
+    let mut events: BTreeMap<(u64, &'static str, String, String), Vec<String>> = BTreeMap::new();
+    for (source, bookmark) in bookmarks {
+        let target_path = format!("/{}", bookmark.path.join("/"));
+        for (event, time, path) in [
+            ("target_created", bookmark.creation, target_path),
+            (
+                "volume_created",
+                bookmark.volume_creation,
+                bookmark.volume_path.clone(),
+            ),
+        ] {
+            if time == CocoaTime::default() {
+                continue;
+            }
+            let key = (time.0.to_bits(), event, path, bookmark.volume_name.clone());
+            let sources = events.entry(key).or_default();
+            if !sources.iter().any(|existing| existing == source) {
+                sources.push(source.to_string());
+            }
+        }
+    }
+
+    let mut timeline: Vec<TimelineEvent> = events
+        .into_iter()
+        .map(|((bits, event, path, volume_name), sources)| {
+            let time = CocoaTime(f64::from_bits(bits));
+            TimelineEvent {
+                timestamp: time.to_string(),
+                unix_seconds: time.to_unix(),
+                event,
+                path,
+                volume_name,
+                sources,
+            }
+        })
+        .collect();
+    timeline.sort_by(|first, second| {
+        first
+            .unix_seconds
+            .total_cmp(&second.unix_seconds)
+            .then_with(|| first.event.cmp(second.event))
+            .then_with(|| first.path.cmp(&second.path))
+            .then_with(|| first.volume_name.cmp(&second.volume_name))
+    });
+    timeline
+}
+
+/// Write a timeline as CSV with a header row. Multiple sources are separated by `;`
+pub fn write_timeline_csv<W: Write>(
+    events: &[TimelineEvent],
+    mut writer: W,
+) -> std::io::Result<()> {
+    writeln!(
+        writer,
+        "timestamp,unix_seconds,event,path,volume_name,sources"
+    )?;
+    for event in events {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{}",
+            csv_field(&event.timestamp),
+            event.unix_seconds,
+            event.event,
+            csv_field(&event.path),
+            csv_field(&event.volume_name),
+            csv_field(&event.sources.join(";"))
+        )?;
+    }
+    Ok(())
+}
+
+/// Write a timeline as JSON Lines, one event per line
+pub fn write_timeline_jsonl<W: Write>(
+    events: &[TimelineEvent],
+    mut writer: W,
+) -> std::io::Result<()> {
+    for event in events {
+        serde_json::to_writer(&mut writer, event)?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        return format!("\"{}\"", value.replace('"', "\"\""));
+    }
+    value.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_timeline, csv_field, write_timeline_csv, write_timeline_jsonl};
+    use crate::{parser::parse_bookmark, types::CocoaTime};
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn test_build_timeline() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let buffer = fs::read(test_location).unwrap();
+
+        let bookmark = parse_bookmark(&buffer).unwrap();
+        let mut newer = bookmark.clone();
+        newer.path = vec![String::from("Users"), String::from("a,b")];
+        newer.creation = CocoaTime(bookmark.creation.0 + 60.0);
+
+        let timeline = build_timeline(&[
+            ("first.bookmark", &bookmark),
+            ("second.bookmark", &bookmark),
+            ("third.bookmark", &newer),
+        ]);
+        // Volume creation is shared by all three bookmarks
+        assert_eq!(timeline.len(), 3);
+        assert_eq!(timeline[0].event, "volume_created");
+        assert_eq!(timeline[0].sources.len(), 3);
+        assert_eq!(timeline[1].event, "target_created");
+        assert_eq!(timeline[1].path, "/Applications/Syncthing.app");
+        assert_eq!(timeline[1].sources, ["first.bookmark", "second.bookmark"]);
+        assert_eq!(timeline[2].path, "/Users/a,b");
+        assert!(timeline[1].unix_seconds < timeline[2].unix_seconds);
+
+        let mut csv = Vec::new();
+        write_timeline_csv(&timeline, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.lines().last().unwrap().contains("\"/Users/a,b\""));
+
+        let mut jsonl = Vec::new();
+        write_timeline_jsonl(&timeline, &mut jsonl).unwrap();
+        let jsonl = String::from_utf8(jsonl).unwrap();
+        assert_eq!(jsonl.lines().count(), 3);
+        let event: serde_json::Value = serde_json::from_str(jsonl.lines().nth(1).unwrap()).unwrap();
+        assert_eq!(event["event"], "target_created");
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
@@ -0,0 +1,243 @@
+//! Typed wrappers for bookmark timestamps, catalog node IDs and record sets
+//!
+//! Bookmark timestamps are seconds since the Cocoa epoch (2001-01-01 UTC), not the Unix epoch. CNIDs are HFS+
+//! catalog node IDs, on APFS they are the inode number of the file.
+
+use std::fmt;
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Seconds since 2001-01-01 00:00:00 UTC
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct CocoaTime(pub f64);
+
+/// How timestamps are written to serialized output
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TimestampFormat {
+    /// Seconds since the Cocoa epoch as stored in the bookmark
+    #[default]
+    Raw,
+    /// RFC 3339 UTC timestamp
+    Converted,
+    /// RFC 3339 UTC timestamp, with the stored seconds in a `<name>_raw` field
+    ConvertedWithRaw,
+}
+
+/// Whether a bookmark has every volume record current macOS releases write. A hint only, a reduced record set may
+/// come from an older release or from another writer, it does not identify the release that created the bookmark
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecordSet {
+    /// Too few records to tell (ex: damaged data or converted alias records)
+    #[default]
+    Unknown,
+    /// Some of the target flags and volume UUID, creation, flags and root records are missing
+    Reduced,
+    /// Every volume record current macOS releases write is present
+    Full,
+}
+
+/// HFS+ Catalog Node ID (APFS inode number)
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Cnid(pub i64);
+
+impl CocoaTime {
+    /// Seconds between the Unix epoch and the Cocoa epoch
+    pub const UNIX_EPOCH_OFFSET: f64 = 978307200.0;
+
+    /// Create from seconds since the Unix epoch
+    pub fn from_unix(seconds: f64) -> CocoaTime {
+        CocoaTime(seconds - CocoaTime::UNIX_EPOCH_OFFSET)
+    }
+
+    /// Get the raw seconds since the Cocoa epoch
+    pub fn seconds(&self) -> f64 {
+        self.0
+    }
+
+    /// Get the seconds since the Unix epoch
+    pub fn to_unix(&self) -> f64 {
+        self.0 + CocoaTime::UNIX_EPOCH_OFFSET
+    }
+}
+
+impl TimestampFormat {
+    /// Get the flattened key/value pairs of a timestamp
+    pub fn entries(&self, key: &str, time: CocoaTime) -> Vec<(String, String)> {
+        let raw = (format!("{key}_raw"), time.seconds().to_string());
+        match self {
+            TimestampFormat::Raw => vec![(key.to_string(), time.seconds().to_string())],
+            TimestampFormat::Converted => vec![(key.to_string(), time.to_string())],
+            TimestampFormat::ConvertedWithRaw => vec![(key.to_string(), time.to_string()), raw],
+        }
+    }
+
+    /// Get the JSON fields of a timestamp. Raw seconds stay JSON numbers
+    pub fn json_entries(&self, key: &str, time: CocoaTime) -> Vec<(String, Value)> {
+        let raw = || Value::from(time.seconds());
+        match self {
+            TimestampFormat::Raw => vec![(key.to_string(), raw())],
+            TimestampFormat::Converted => vec![(key.to_string(), Value::from(time.to_string()))],
+            TimestampFormat::ConvertedWithRaw => vec![
+                (key.to_string(), Value::from(time.to_string())),
+                (format!("{key}_raw"), raw()),
+            ],
+        }
+    }
+}
+
+impl fmt::Display for CocoaTime {
+    /// Format as an RFC 3339 UTC timestamp (ex: `2022-02-02T05:53:09Z`)
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Timestamps after year 9999 (or NaN/infinite) are shown as raw seconds
+        let max_unix_seconds = 253402300800.0;
+        let unix = self.to_unix().floor();
+        if !unix.is_finite() || unix.abs() >= max_unix_seconds {
+            return write!(f, "{}", self.0);
+        }
+
+        let unix = unix as i64;
+        let (year, month, day) = civil_from_days(unix.div_euclid(86400));
+        let seconds = unix.rem_euclid(86400);
+        write!(
+            f,
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+            seconds / 3600,
+            seconds % 3600 / 60,
+            seconds % 60
+        )
+    }
+}
+
+impl PartialEq<f64> for CocoaTime {
+    fn eq(&self, other: &f64) -> bool {
+        self.0 == *other
+    }
+}
+
+impl Cnid {
+    // First CNID available for user files, lower values are reserved by the filesystem
+    const FIRST_USER_CNID: i64 = 16;
+
+    /// Get the raw CNID value
+    pub fn value(&self) -> i64 {
+        self.0
+    }
+
+    /// Check if the CNID is reserved by the filesystem (root folder, catalog files, etc)
+    pub fn is_reserved(&self) -> bool {
+        self.0 < Cnid::FIRST_USER_CNID
+    }
+
+    /// Get the CNID as an inode number (APFS), `None` if negative
+    pub fn to_inode(&self) -> Option<u64> {
+        u64::try_from(self.0).ok()
+    }
+}
+
+impl fmt::Display for RecordSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            RecordSet::Unknown => "Unknown",
+            RecordSet::Reduced => "Reduced",
+            RecordSet::Full => "Full",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl fmt::Display for Cnid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl PartialEq<i64> for Cnid {
+    fn eq(&self, other: &i64) -> bool {
+        self.0 == *other
+    }
+}
+
+/// Convert days since the Unix epoch to a (year, month, day) date
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Convert a (year, month, day) date to days since the Unix epoch
+pub(crate) fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_index = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cnid, CocoaTime, TimestampFormat};
+    use serde_json::json;
+
+    #[test]
+    fn test_cocoa_time() {
+        let time = CocoaTime(665473989.0);
+        assert_eq!(time.to_unix(), 1643781189.0);
+        assert_eq!(CocoaTime::from_unix(1643781189.0), time);
+        assert_eq!(time.to_string(), "2022-02-02T05:53:09Z");
+        assert_eq!(CocoaTime(0.0).to_string(), "2001-01-01T00:00:00Z");
+        assert_eq!(CocoaTime(-978307200.5).to_string(), "1969-12-31T23:59:59Z");
+        assert_eq!(CocoaTime(f64::MAX).to_string(), f64::MAX.to_string());
+        assert_eq!(CocoaTime(f64::NAN).to_string(), "NaN");
+    }
+
+    #[test]
+    fn test_timestamp_format() {
+        let time = CocoaTime(665473989.5);
+        assert_eq!(
+            TimestampFormat::Raw.entries("creation", time),
+            [(String::from("creation"), String::from("665473989.5"))]
+        );
+        assert_eq!(
+            TimestampFormat::ConvertedWithRaw.entries("creation", time),
+            [
+                (
+                    String::from("creation"),
+                    String::from("2022-02-02T05:53:09Z")
+                ),
+                (String::from("creation_raw"), String::from("665473989.5"))
+            ]
+        );
+        assert_eq!(
+            TimestampFormat::Converted.json_entries("creation", time),
+            [(String::from("creation"), json!("2022-02-02T05:53:09Z"))]
+        );
+        assert_eq!(
+            TimestampFormat::ConvertedWithRaw.json_entries("creation", time)[1],
+            (String::from("creation_raw"), json!(665473989.5))
+        );
+    }
+
+    #[test]
+    fn test_cnid() {
+        assert!(Cnid(2).is_reserved());
+        assert!(!Cnid(706090).is_reserved());
+        assert_eq!(Cnid(706090).to_inode(), Some(706090));
+        assert_eq!(Cnid(-1).to_inode(), None);
+        assert_eq!(Cnid(706090).to_string(), "706090");
+    }
+}
@@ -0,0 +1,149 @@
+//! Resolve relative URL records (data type 0x0902)
+//!
+//! Bookmarks created relative to a document store the URL as a base URL and a relative path.
+//! Resolution follows RFC 3986 section 5.2 (query and fragment components are not supported).
+
+use serde::Serialize;
+
+use crate::bookmark::BookmarkData;
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct RelativeUrl {
+    pub record_type: u32, // Record/Key type containing the relative URL
+    pub base: String,     // Base URL (may be empty)
+    pub relative: String, // URL relative to the base URL
+}
+
+impl RelativeUrl {
+    /// Resolve the relative URL against its base URL
+    pub fn resolve(&self) -> String {
+        resolve_url(&self.base, &self.relative)
+    }
+}
+
+impl BookmarkData {
+    /// Resolve the relative URL against its base URL, or against the volume URL if the bookmark has no base URL
+    pub fn resolve_relative_url(&self) -> Option<String> {
+        let relative_url = self.relative_url.as_ref()?;
+        if relative_url.base.is_empty() {
+            return Some(resolve_url(&self.volume_url, &relative_url.relative));
+        }
+        Some(relative_url.resolve())
+    }
+}
+
+/// Resolve a URL reference against a base URL
+pub(crate) fn resolve_url(base: &str, reference: &str) -> String {
+    if has_scheme(reference) || base.is_empty() {
+        return reference.to_string();
+    }
+    if reference.is_empty() {
+        return base.to_string();
+    }
+
+    let base = base.split(['?', '#']).next().unwrap_or_default();
+    let scheme_end = base.find("://").map_or(0, |index| index + 3);
+    let path_start = base[scheme_end..]
+        .find('/')
+        .map_or(base.len(), |index| scheme_end + index);
+    let (authority, base_path) = base.split_at(path_start);
+
+    if let Some(network_path) = reference.strip_prefix("//") {
+        let scheme = base.split(':').next().unwrap_or_default();
+        return format!("{scheme}://{network_path}");
+    }
+    if reference.starts_with('/') {
+        return format!("{authority}{}", remove_dot_segments(reference));
+    }
+
+    // Merge the reference with the base path directory
+    let directory = base_path
+        .rfind('/')
+        .map_or("/", |index| &base_path[..=index]);
+    format!(
+        "{authority}{}",
+        remove_dot_segments(&format!("{directory}{reference}"))
+    )
+}
+
+/// Check if a URL starts with a scheme (ex: `file:`)
+fn has_scheme(url: &str) -> bool {
+    match url.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+/// Remove `.` and `..` path segments
+fn remove_dot_segments(path: &str) -> String {
+    let segments: Vec<&str> = path.split('/').collect();
+    let mut output: Vec<&str> = Vec::new();
+    for (index, segment) in segments.iter().enumerate() {
+        let last = index == segments.len() - 1;
+        match *segment {
+            "." => {}
+            ".." => {
+                // Never remove the root
+                if output.len() > 1 {
+                    output.pop();
+                }
+            }
+            _ => {
+                output.push(segment);
+                continue;
+            }
+        }
+        // Keep the trailing slash for paths ending in a dot segment
+        if last {
+            output.push("");
+        }
+    }
+    output.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{remove_dot_segments, resolve_url};
+
+    #[test]
+    fn test_resolve_url() {
+        let base = "file:///Users/lab/Documents/report.pages";
+        assert_eq!(
+            resolve_url(base, "../Downloads/report.pdf"),
+            "file:///Users/lab/Downloads/report.pdf"
+        );
+        assert_eq!(
+            resolve_url(base, "image.png"),
+            "file:///Users/lab/Documents/image.png"
+        );
+        assert_eq!(
+            resolve_url(base, "/Applications/Foo.app/"),
+            "file:///Applications/Foo.app/"
+        );
+        assert_eq!(
+            resolve_url(base, "file:///Volumes/USB/"),
+            "file:///Volumes/USB/"
+        );
+        assert_eq!(
+            resolve_url("file:///", "../../etc/passwd"),
+            "file:///etc/passwd"
+        );
+        assert_eq!(
+            resolve_url("smb://server/share/dir/", "file.txt"),
+            "smb://server/share/dir/file.txt"
+        );
+        assert_eq!(resolve_url("", "Documents/"), "Documents/");
+    }
+
+    #[test]
+    fn test_remove_dot_segments() {
+        assert_eq!(remove_dot_segments("/a/b/c/./../../g"), "/a/g");
+        assert_eq!(remove_dot_segments("/a/b/.."), "/a/");
+        assert_eq!(remove_dot_segments("/.."), "/");
+    }
+}
@@ -0,0 +1,247 @@
+//! Generic typed values for every TOC record
+//!
+//! Every record is decoded based on its data type only, so records the library does not have a field for can
+//! still be consumed. Array and dictionary values are resolved from the offsets they contain.
+
+use std::mem::size_of;
+
+use nom::{
+    bytes::complete::take,
+    number::complete::{be_f64, le_f32, le_f64, le_i16, le_i32, le_i64, le_i8, le_u32},
+};
+use serde::Serialize;
+
+use crate::{parser::LengthLimits, types::CocoaTime, url::resolve_url};
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub enum BookmarkValue {
+    String(String),
+    Data(Vec<u8>),
+    Int(i64),
+    Float(f64),
+    Date(CocoaTime),
+    Bool(bool),
+    Array(Vec<BookmarkValue>),
+    Dictionary(Vec<(BookmarkValue, BookmarkValue)>),
+    Url(String),
+    Uuid(String),
+    Unknown { data_type: u32, data: Vec<u8> },
+}
+
+// Limit nested arrays/dictionaries, offsets in crafted bookmarks could point back to a parent record
+const MAX_VALUE_DEPTH: usize = 8;
+// Limit the values decoded for one record, elements reusing offsets would otherwise grow exponentially with the depth
+const MAX_VALUE_NODES: usize = 65_536;
+const MAX_VALUE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Values and bytes left to decode for one record, shared by every array and dictionary it references
+struct DecodeBudget {
+    nodes: usize,
+    bytes: usize,
+}
+
+/// Decode the record at an offset (relative to the start of the bookmark data), truncating strings and data to the limits
+pub(crate) fn decode_record(
+    core_data: &[u8],
+    offset: u32,
+    limits: &LengthLimits,
+) -> Option<BookmarkValue> {
+    let mut budget = DecodeBudget {
+        nodes: MAX_VALUE_NODES,
+        bytes: MAX_VALUE_BYTES,
+    };
+    decode_value(core_data, offset, limits, 0, &mut budget)
+}
+
+/// Decode a record value, resolving arrays and dictionaries up to `MAX_VALUE_DEPTH` levels deep. `None` once the
+/// budget is used up
+fn decode_value(
+    core_data: &[u8],
+    offset: u32,
+    limits: &LengthLimits,
+    depth: usize,
+    budget: &mut DecodeBudget,
+) -> Option<BookmarkValue> {
+    if depth > MAX_VALUE_DEPTH {
+        return None;
+    }
+    let (data_type, data) = record_at(core_data, offset)?;
+    let data = limits.truncate(data_type, data);
+    budget.nodes = budget.nodes.checked_sub(1)?;
+    budget.bytes = budget.bytes.checked_sub(data.len())?;
+
+    let value = match data_type {
+        0x0101 => BookmarkValue::String(String::from_utf8(data.to_vec()).ok()?),
+        0x0201 => BookmarkValue::Data(data.to_vec()),
+        0x0301 => BookmarkValue::Int(le_i8::<_, ()>(data).ok()?.1 as i64),
+        0x0302 => BookmarkValue::Int(le_i16::<_, ()>(data).ok()?.1 as i64),
+        0x0303 => BookmarkValue::Int(le_i32::<_, ()>(data).ok()?.1 as i64),
+        0x0304 => BookmarkValue::Int(le_i64::<_, ()>(data).ok()?.1),
+        0x0305 => BookmarkValue::Float(le_f32::<_, ()>(data).ok()?.1 as f64),
+        0x0306 => BookmarkValue::Float(le_f64::<_, ()>(data).ok()?.1),
+        // Apple stores timestamps as Big Endian Float64
+        0x0400 => BookmarkValue::Date(CocoaTime(be_f64::<_, ()>(data).ok()?.1)),
+        0x0500 => BookmarkValue::Bool(false),
+        0x0501 => BookmarkValue::Bool(true),
+        0x0601 => {
+            let mut values = Vec::new();
+            for value_offset in offsets(data)? {
+                values.push(decode_value(
+                    core_data,
+                    value_offset,
+                    limits,
+                    depth + 1,
+                    budget,
+                )?);
+            }
+            BookmarkValue::Array(values)
+        }
+        0x0701 => {
+            let mut values = Vec::new();
+            for pair in offsets(data)?.chunks_exact(2) {
+                let key = decode_value(core_data, pair[0], limits, depth + 1, budget)?;
+                let value = decode_value(core_data, pair[1], limits, depth + 1, budget)?;
+                values.push((key, value));
+            }
+            BookmarkValue::Dictionary(values)
+        }
+        0x0801 if data.len() == 16 => BookmarkValue::Uuid(uuid_string(data)),
+        0x0901 => BookmarkValue::Url(String::from_utf8(data.to_vec()).ok()?),
+        // Relative URLs point to a base URL record and a relative path record
+        0x0902 => {
+            let mut parts = Vec::new();
+            for url_offset in offsets(data)? {
+                match decode_value(core_data, url_offset, limits, depth + 1, budget)? {
+                    BookmarkValue::String(part) | BookmarkValue::Url(part) => parts.push(part),
+                    _ => return None,
+                }
+            }
+            let [base, relative] = &parts[..] else {
+                return None;
+            };
+            BookmarkValue::Url(resolve_url(base, relative))
+        }
+        _ => BookmarkValue::Unknown {
+            data_type,
+            data: data.to_vec(),
+        },
+    };
+    Some(value)
+}
+
+/// Get the data type and data of the record at an offset
+fn record_at(core_data: &[u8], offset: u32) -> Option<(u32, &[u8])> {
+    // Offsets include the 4 byte TOC offset that is not part of the core data
+    let toc_offset_size = 4;
+    let (input, _) = take::<_, _, ()>(offset.checked_sub(toc_offset_size)?)(core_data).ok()?;
+    let (input, length) = le_u32::<_, ()>(input).ok()?;
+    let (input, data_type) = le_u32::<_, ()>(input).ok()?;
+    let (_, data) = take::<_, _, ()>(length)(input).ok()?;
+    Some((data_type, data))
+}
+
+/// Get the record offsets stored in array/dictionary data
+fn offsets(data: &[u8]) -> Option<Vec<u32>> {
+    let mut values = Vec::new();
+    let mut input = data;
+    while !input.is_empty() {
+        let (remaining, offset) = take::<_, _, ()>(size_of::<u32>())(input).ok()?;
+        values.push(le_u32::<_, ()>(offset).ok()?.1);
+        input = remaining;
+    }
+    Some(values)
+}
+
+/// Format UUID bytes as an uppercase hyphenated string
+pub(crate) fn uuid_string(data: &[u8]) -> String {
+    let hex: Vec<String> = data.iter().map(|byte| format!("{byte:02X}")).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        hex[0..4].concat(),
+        hex[4..6].concat(),
+        hex[6..8].concat(),
+        hex[8..10].concat(),
+        hex[10..16].concat()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_record, uuid_string, BookmarkValue};
+    use crate::parser::LengthLimits;
+
+    #[test]
+    fn test_decode_record() {
+        // TOC offset is not part of the core data, offsets start at 4
+        let core_data = [
+            5, 0, 0, 0, 1, 1, 0, 0, 85, 115, 101, 114, 115, 0, 0, 0, // String "Users"
+            4, 0, 0, 0, 3, 3, 0, 0, 245, 1, 0, 0, // Int 501
+            8, 0, 0, 0, 1, 6, 0, 0, 4, 0, 0, 0, 20, 0, 0, 0, // Array of both
+            8, 0, 0, 0, 1, 6, 0, 0, 36, 0, 0, 0, 36, 0, 0, 0, // Array pointing to itself
+        ];
+        let limits = LengthLimits::default();
+
+        assert_eq!(
+            decode_record(&core_data, 4, &limits),
+            Some(BookmarkValue::String(String::from("Users")))
+        );
+        assert_eq!(
+            decode_record(&core_data, 20, &limits),
+            Some(BookmarkValue::Int(501))
+        );
+        assert_eq!(
+            decode_record(&core_data, 32, &limits),
+            Some(BookmarkValue::Array(vec![
+                BookmarkValue::String(String::from("Users")),
+                BookmarkValue::Int(501)
+            ]))
+        );
+        assert_eq!(decode_record(&core_data, 48, &limits), None);
+        assert_eq!(decode_record(&core_data, 2, &limits), None);
+
+        let limits = LengthLimits { string: 3, data: 0 };
+        assert_eq!(
+            decode_record(&core_data, 4, &limits),
+            Some(BookmarkValue::String(String::from("Use")))
+        );
+    }
+
+    #[test]
+    fn test_decode_shared_offsets() {
+        let limits = LengthLimits::default();
+
+        // Array with 256 elements pointing to itself, 256^8 values without a budget
+        let mut core_data = vec![0, 4, 0, 0, 1, 6, 0, 0];
+        core_data.extend((0..256).flat_map(|_| 4u32.to_le_bytes()));
+        assert_eq!(decode_record(&core_data, 4, &limits), None);
+
+        // Chain of arrays, each element of an array points to the next array
+        let fan_out = 64u32;
+        let length = fan_out * 4;
+        let mut core_data = Vec::new();
+        for level in 1..=8 {
+            core_data.extend_from_slice(&length.to_le_bytes());
+            core_data.extend_from_slice(&[1, 6, 0, 0]);
+            let next = 4 + level * (length + 8);
+            core_data.extend((0..fan_out).flat_map(|_| next.to_le_bytes()));
+        }
+        core_data.extend_from_slice(&[4, 0, 0, 0, 3, 3, 0, 0, 245, 1, 0, 0]);
+        assert_eq!(decode_record(&core_data, 4, &limits), None);
+
+        // Shared elements within the budget are still decoded
+        let level = 7 * (length + 8) + 4;
+        let Some(BookmarkValue::Array(values)) = decode_record(&core_data, level, &limits) else {
+            panic!("array not decoded");
+        };
+        assert_eq!(values.len(), 64);
+        assert!(values.iter().all(|value| *value == BookmarkValue::Int(501)));
+    }
+
+    #[test]
+    fn test_uuid_string() {
+        let data = [
+            10, 129, 243, 177, 81, 217, 51, 53, 179, 227, 22, 156, 54, 64, 54, 13,
+        ];
+        assert_eq!(uuid_string(&data), "0A81F3B1-51D9-3335-B3E3-169C3640360D");
+    }
+}
@@ -20,17 +20,17 @@ fn test_loginitems_file() {
     let volume_size = 160851517440;
     let volume_creation = 241134516.0;
     let volume_flags = [4294967425, 4294972399, 0];
-    let volume_root = true;
-    let localized_name = "Syncthing";
-    let extension = "64cb7eaa9a1bbccc4e1397c9f2a411ebe539cd29;00000000;00000000;0000000000000020;com.apple.app-sandbox.read-write;01;01000004;00000000000ac62a;/applications/syncthing.app\u{0}";
+    let volume_root = Some(true);
+    let localized_name = Some("Syncthing");
+    let extension = Some("64cb7eaa9a1bbccc4e1397c9f2a411ebe539cd29;00000000;00000000;0000000000000020;com.apple.app-sandbox.read-write;01;01000004;00000000000ac62a;/applications/syncthing.app\u{0}");
     let target_flags = [2, 15, 0];
     let folder_index = 0;
-    let uid = 0;
-    let username = String::new();
-    let creation_options = 0;
-    let is_executable = false;
+    let uid = None;
+    let username = None;
+    let creation_options = None;
+    let is_executable = None;
 
-    let extension_ro = String::new();
+    let extension_ro = None;
 
     assert_eq!(bookmark_data.creation, creation);
     assert_eq!(bookmark_data.path, path);
@@ -43,16 +43,16 @@ fn test_loginitems_file() {
     assert_eq!(bookmark_data.volume_size, volume_size);
     assert_eq!(bookmark_data.volume_flag, volume_flags);
     assert_eq!(bookmark_data.volume_root, volume_root);
-    assert_eq!(bookmark_data.localized_name, localized_name);
-    assert_eq!(bookmark_data.security_extension_rw, extension);
+    assert_eq!(bookmark_data.localized_name.as_deref(), localized_name);
+    assert_eq!(bookmark_data.security_extension_rw.as_deref(), extension);
     assert_eq!(bookmark_data.target_flags, target_flags);
     assert_eq!(bookmark_data.folder_index, folder_index);
     assert_eq!(bookmark_data.uid, uid);
-    assert_eq!(bookmark_data.username, username);
+    assert_eq!(bookmark_data.username.as_deref(), username);
     assert_eq!(bookmark_data.creation_options, creation_options);
     assert_eq!(bookmark_data.is_executable, is_executable);
-    assert_eq!(bookmark_data.security_extension_ro, extension_ro);
-    assert_eq!(bookmark_data.file_ref_flag, false);
+    assert_eq!(bookmark_data.security_extension_ro.as_deref(), extension_ro);
+    assert_eq!(bookmark_data.file_ref_flag, None);
 }
 
 #[test]
@@ -99,15 +99,15 @@ fn test_poisonapple() {
     let volume_size = 85555372032;
     let volume_creation = 599558400.0;
     let volume_flags = [4294967425, 4294972399, 0];
-    let volume_root = true;
-    let localized_name = "testing";
-    let extension = String::new();
+    let volume_root = Some(true);
+    let localized_name = Some("testing");
+    let extension = None;
     let target_flags = [530, 543, 538];
     let folder_index = 9;
-    let uid = 501;
-    let username = "sur";
-    let creation_options = 536870912;
-    let is_executable = true;
+    let uid = Some(501);
+    let username = Some("sur");
+    let creation_options = Some(536870912);
+    let is_executable = Some(true);
 
     assert_eq!(bookmark_data.creation, creation);
     assert_eq!(bookmark_data.path, path);
@@ -120,16 +120,16 @@ fn test_poisonapple() {
     assert_eq!(bookmark_data.volume_size, volume_size);
     assert_eq!(bookmark_data.volume_flag, volume_flags);
     assert_eq!(bookmark_data.volume_root, volume_root);
-    assert_eq!(bookmark_data.localized_name, localized_name);
-    assert_eq!(bookmark_data.security_extension_rw, extension);
+    assert_eq!(bookmark_data.localized_name.as_deref(), localized_name);
+    assert_eq!(bookmark_data.security_extension_rw.as_deref(), extension);
     assert_eq!(bookmark_data.target_flags, target_flags);
     assert_eq!(bookmark_data.folder_index, folder_index);
     assert_eq!(bookmark_data.uid, uid);
-    assert_eq!(bookmark_data.username, username);
+    assert_eq!(bookmark_data.username.as_deref(), username);
     assert_eq!(bookmark_data.creation_options, creation_options);
     assert_eq!(bookmark_data.is_executable, is_executable);
-    assert_eq!(bookmark_data.security_extension_ro, extension);
-    assert_eq!(bookmark_data.file_ref_flag, false);
+    assert_eq!(bookmark_data.security_extension_ro.as_deref(), extension);
+    assert_eq!(bookmark_data.file_ref_flag, None);
 }
 
 #[test]
@@ -166,17 +166,20 @@ fn test_security_extension_ro() {
     assert_eq!(bookmark_data.volume_creation, 599558400.0);
     assert_eq!(bookmark_data.volume_size, 85555372032);
     assert_eq!(bookmark_data.volume_flag, [4294967425, 4294972399, 0]);
-    assert_eq!(bookmark_data.volume_root, true);
-    assert_eq!(bookmark_data.localized_name, "System Events");
-    assert_eq!(bookmark_data.security_extension_rw, String::new());
+    assert_eq!(bookmark_data.volume_root, Some(true));
+    assert_eq!(
+        bookmark_data.localized_name.as_deref(),
+        Some("System Events")
+    );
+    assert_eq!(bookmark_data.security_extension_rw, None);
     assert_eq!(bookmark_data.target_flags, [530, 543, 538]);
     assert_eq!(bookmark_data.folder_index, 0);
-    assert_eq!(bookmark_data.uid, 0);
-    assert_eq!(bookmark_data.username, String::new());
-    assert_eq!(bookmark_data.creation_options, 0);
-    assert_eq!(bookmark_data.is_executable, true);
-    assert_eq!(bookmark_data.file_ref_flag, false);
-    assert_eq!(bookmark_data.security_extension_ro, "46d8327f9637aa681e789f0fc10ad53b5ab5343e2ccace15d15e508c16c64fbc;00;00000000;00000000;00000000;000000000000001a;com.apple.app-sandbox.read;01;0100000a;0fffffff0004db59;02;/system/library/coreservices/system events.app\0");
+    assert_eq!(bookmark_data.uid, None);
+    assert_eq!(bookmark_data.username, None);
+    assert_eq!(bookmark_data.creation_options, None);
+    assert_eq!(bookmark_data.is_executable, Some(true));
+    assert_eq!(bookmark_data.file_ref_flag, None);
+    assert_eq!(bookmark_data.security_extension_ro.as_deref(), Some("46d8327f9637aa681e789f0fc10ad53b5ab5343e2ccace15d15e508c16c64fbc;00;00000000;00000000;00000000;000000000000001a;com.apple.app-sandbox.read;01;0100000a;0fffffff0004db59;02;/system/library/coreservices/system events.app\0"));
 }
 
 #[test]
@@ -215,17 +218,17 @@ fn test_mac_alias_bookmark() {
     assert_eq!(bookmark_data.volume_creation, 616544347.691502);
     assert_eq!(bookmark_data.volume_size, 2000662327296);
     assert_eq!(bookmark_data.volume_flag, [4294967425, 4294972399, 0]);
-    assert_eq!(bookmark_data.volume_root, false);
-    assert_eq!(bookmark_data.localized_name, String::new());
-    assert_eq!(bookmark_data.security_extension_rw, String::new());
+    assert_eq!(bookmark_data.volume_root, Some(false));
+    assert_eq!(bookmark_data.localized_name, None);
+    assert_eq!(bookmark_data.security_extension_rw, None);
     assert_eq!(bookmark_data.target_flags, [1, 15, 0]);
     assert_eq!(bookmark_data.folder_index, 5);
-    assert_eq!(bookmark_data.uid, 99);
-    assert_eq!(bookmark_data.username, "unknown");
-    assert_eq!(bookmark_data.creation_options, 512);
-    assert_eq!(bookmark_data.is_executable, false);
-    assert_eq!(bookmark_data.file_ref_flag, true);
-    assert_eq!(bookmark_data.security_extension_ro, String::new());
+    assert_eq!(bookmark_data.uid, Some(99));
+    assert_eq!(bookmark_data.username.as_deref(), Some("unknown"));
+    assert_eq!(bookmark_data.creation_options, Some(512));
+    assert_eq!(bookmark_data.is_executable, None);
+    assert_eq!(bookmark_data.file_ref_flag, Some(true));
+    assert_eq!(bookmark_data.security_extension_ro, None);
 }
 
 #[test]
@@ -253,14 +256,14 @@ fn test_safari_downloads_bookmark_file() {
     let volume_uuid = "96FB41C0-6CE9-4DA2-8435-35BC19C735A3";
     let volume_size = 2000662327296;
     let volume_flag = [4294967425, 4294972399, 0];
-    let volume_root = true;
-    let localized_name = String::new();
+    let volume_root = Some(true);
+    let localized_name = None;
     let target_flags = [1, 15, 0];
-    let username = "puffycid";
+    let username = Some("puffycid");
     let folder_index = 2;
-    let uid = 501;
-    let creation_options = 671094784;
-    let security_extension = String::new();
+    let uid = Some(501);
+    let creation_options = Some(671094784);
+    let security_extension = None;
 
     let cnid_path_len = 4;
     let target_creation = 677388100.0747445;
@@ -282,15 +285,21 @@ fn test_safari_downloads_bookmark_file() {
     assert_eq!(bookmark.volume_size, volume_size);
     assert_eq!(bookmark.volume_flag, volume_flag);
     assert_eq!(bookmark.volume_root, volume_root);
-    assert_eq!(bookmark.localized_name, localized_name);
+    assert_eq!(bookmark.localized_name.as_deref(), localized_name);
     assert_eq!(bookmark.target_flags, target_flags);
-    assert_eq!(bookmark.username, username);
+    assert_eq!(bookmark.username.as_deref(), username);
     assert_eq!(bookmark.folder_index, folder_index);
     assert_eq!(bookmark.uid, uid);
     assert_eq!(bookmark.creation_options, creation_options);
-    assert_eq!(bookmark.security_extension_rw, security_extension);
-    assert_eq!(bookmark.security_extension_ro, security_extension);
-    assert_eq!(bookmark.file_ref_flag, false);
+    assert_eq!(
+        bookmark.security_extension_rw.as_deref(),
+        security_extension
+    );
+    assert_eq!(
+        bookmark.security_extension_ro.as_deref(),
+        security_extension
+    );
+    assert_eq!(bookmark.file_ref_flag, None);
 }
 
 #[test]
@@ -304,7 +313,7 @@ fn test_ventura_bookmark() {
 }
 
 #[test]
-#[should_panic(expected = "BadHeader")]
+#[should_panic(expected = "WrongSignature(1686073002)")]
 fn test_bad_sig() {
     let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     test_location.push("tests/test_data/bad_header.bookmark");
@@ -316,7 +325,7 @@ fn test_bad_sig() {
 }
 
 #[test]
-#[should_panic(expected = "BadBookmarkData")]
+#[should_panic(expected = "TruncatedData(ParseFailure { kind: Eof, offset: 52 })")]
 fn test_bad_content() {
     let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     test_location.push("tests/test_data/bad_content.bookmark");
@@ -326,3 +335,1776 @@ fn test_bad_content() {
 
     let _ = macos_bookmarks::parser::parse_bookmark(&buffer).unwrap();
 }
+
+// Wrap data records into a minimal bookmark (header, records, single TOC)
+fn build_bookmark(records: &[(u32, u32, &[u8])]) -> Vec<u8> {
+    build_chained_bookmark(&[records])
+}
+
+// Wrap data records into a minimal bookmark with one chained TOC per record list
+fn build_chained_bookmark(tocs: &[&[(u32, u32, &[u8])]]) -> Vec<u8> {
+    let mut body: Vec<u8> = Vec::new();
+    let mut offsets = Vec::new();
+    for (_, data_type, data) in tocs.iter().flat_map(|records| records.iter()) {
+        offsets.push(body.len() as u32 + 4);
+        body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        body.extend_from_slice(&data_type.to_le_bytes());
+        body.extend_from_slice(data);
+        while !body.len().is_multiple_of(4) {
+            body.push(0);
+        }
+    }
+    let toc_offset = body.len() as u32 + 4;
+    let mut offsets = offsets.into_iter();
+    for (index, records) in tocs.iter().enumerate() {
+        let record_count = records.len() as u32;
+        // Next TOC starts right after this one (20 byte TOC header and data, 12 bytes per record)
+        let next_toc = if index + 1 < tocs.len() {
+            body.len() as u32 + 4 + 20 + 12 * record_count
+        } else {
+            0
+        };
+        body.extend_from_slice(&(12 + 12 * record_count).to_le_bytes());
+        body.extend_from_slice(&[254, 255, 255, 255]);
+        body.extend_from_slice(&1u32.to_le_bytes());
+        body.extend_from_slice(&next_toc.to_le_bytes());
+        body.extend_from_slice(&record_count.to_le_bytes());
+        for ((record_type, _, _), offset) in records.iter().zip(offsets.by_ref()) {
+            body.extend_from_slice(&record_type.to_le_bytes());
+            body.extend_from_slice(&offset.to_le_bytes());
+            body.extend_from_slice(&0u32.to_le_bytes());
+        }
+    }
+
+    let mut bookmark = b"book".to_vec();
+    bookmark.extend_from_slice(&(body.len() as u32 + 52).to_le_bytes());
+    bookmark.extend_from_slice(&[0, 0, 4, 16]);
+    bookmark.extend_from_slice(&48u32.to_le_bytes());
+    bookmark.extend_from_slice(&[0; 32]);
+    bookmark.extend_from_slice(&toc_offset.to_le_bytes());
+    bookmark.extend_from_slice(&body);
+    bookmark
+}
+
+#[test]
+// Test a bookmark embedded in the VOLUME_BOOKMARK record of another bookmark
+fn test_nested_bookmark() {
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push("tests/test_data/downloads.bookmark");
+    let buffer = fs::read(test_location).unwrap();
+
+    let volume_bookmark = 0x2040;
+    let data_type = 0x0201;
+    let outer = build_bookmark(&[(volume_bookmark, data_type, &buffer)]);
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&outer).unwrap();
+    assert_eq!(bookmark_data.nested_bookmarks.len(), 1);
+
+    let nested = &bookmark_data.nested_bookmarks[0];
+    assert_eq!(nested.record_type, volume_bookmark);
+    assert_eq!(
+        nested.bookmark.path,
+        [
+            "Users",
+            "puffycid",
+            "Downloads",
+            "powershell-7.2.4-osx-x64.pkg",
+        ]
+    );
+    assert_eq!(nested.bookmark.volume_name, "Macintosh HD");
+    assert_eq!(
+        bookmark_data.volume_bookmark().unwrap().volume_name,
+        "Macintosh HD"
+    );
+
+    let bookmark_data = macos_bookmarks::parser::BookmarkParser::new()
+        .max_depth(0)
+        .parse(&outer)
+        .unwrap();
+    assert!(bookmark_data.nested_bookmarks.is_empty());
+    assert!(bookmark_data.volume_bookmark().is_none());
+}
+
+#[test]
+// Test a hand-crafted bookmark with the VOLUME_NAME record duplicated
+fn test_duplicate_records() {
+    use macos_bookmarks::parser::{BookmarkParser, DuplicatePolicy};
+
+    let parse_with_policy =
+        |data: &[u8], policy| BookmarkParser::new().duplicate_policy(policy).parse(data);
+
+    let volume_name = 0x2010;
+    let string_type = 0x0101;
+    let data = build_bookmark(&[
+        (volume_name, string_type, b"Macintosh HD"),
+        (volume_name, string_type, b"Evil HD"),
+    ]);
+
+    let last = macos_bookmarks::parser::parse_bookmark(&data).unwrap();
+    assert_eq!(last.volume_name, "Evil HD");
+    assert_eq!(last.record_conflicts().len(), 1);
+    assert_eq!(last.record_conflicts()[0].record_type, volume_name);
+    assert_eq!(last.record_conflicts()[0].offsets, [52, 72]);
+    assert!(last.duplicate_records.is_empty());
+
+    let first = parse_with_policy(&data, DuplicatePolicy::First).unwrap();
+    assert_eq!(first.volume_name, "Macintosh HD");
+
+    let all = parse_with_policy(&data, DuplicatePolicy::CollectAll).unwrap();
+    assert_eq!(all.volume_name, "Macintosh HD");
+    assert_eq!(all.duplicate_records.len(), 1);
+    assert_eq!(all.duplicate_records[0].offset, 72);
+    assert_eq!(all.duplicate_records[0].bookmark.volume_name, "Evil HD");
+
+    assert!(parse_with_policy(&data, DuplicatePolicy::Error).is_err());
+}
+
+#[test]
+// Test a duplicated array record, the last array replaces the earlier components instead of extending them
+fn test_duplicate_array_records() {
+    use macos_bookmarks::parser::{BookmarkParser, DuplicatePolicy};
+
+    let target_path = 0x1004u32;
+    let string_type = 0x0101u32;
+    let array_type = 0x0601u32;
+
+    // String records for every component, followed by one array record per path
+    let paths: [&[&str]; 2] = [&["Users", "sur"], &["evil"]];
+    let mut body: Vec<u8> = Vec::new();
+    let mut arrays = Vec::new();
+    for path in paths {
+        let mut elements = Vec::new();
+        for component in path {
+            elements.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+            body.extend_from_slice(&(component.len() as u32).to_le_bytes());
+            body.extend_from_slice(&string_type.to_le_bytes());
+            body.extend_from_slice(component.as_bytes());
+            while !body.len().is_multiple_of(4) {
+                body.push(0);
+            }
+        }
+        arrays.push(elements);
+    }
+    let mut toc = Vec::new();
+    for elements in arrays {
+        toc.push(body.len() as u32 + 4);
+        body.extend_from_slice(&(elements.len() as u32).to_le_bytes());
+        body.extend_from_slice(&array_type.to_le_bytes());
+        body.extend_from_slice(&elements);
+    }
+    let toc_offset = body.len() as u32 + 4;
+    body.extend_from_slice(&(12 + 12 * toc.len() as u32).to_le_bytes());
+    body.extend_from_slice(&[254, 255, 255, 255]);
+    body.extend_from_slice(&1u32.to_le_bytes());
+    body.extend_from_slice(&0u32.to_le_bytes());
+    body.extend_from_slice(&(toc.len() as u32).to_le_bytes());
+    for offset in &toc {
+        body.extend_from_slice(&target_path.to_le_bytes());
+        body.extend_from_slice(&offset.to_le_bytes());
+        body.extend_from_slice(&0u32.to_le_bytes());
+    }
+    let mut data = b"book".to_vec();
+    data.extend_from_slice(&(body.len() as u32 + 52).to_le_bytes());
+    data.extend_from_slice(&[0, 0, 4, 16]);
+    data.extend_from_slice(&48u32.to_le_bytes());
+    data.extend_from_slice(&[0; 32]);
+    data.extend_from_slice(&toc_offset.to_le_bytes());
+    data.extend_from_slice(&body);
+
+    let last = macos_bookmarks::parser::parse_bookmark(&data).unwrap();
+    assert_eq!(last.path, ["evil"]);
+    assert_eq!(last.record_conflicts().len(), 1);
+    let provenance = last.field_provenance();
+    assert_eq!(provenance.len(), 1);
+    assert_eq!(provenance[0].offset, toc[1] + 48);
+
+    let first = BookmarkParser::new()
+        .duplicate_policy(DuplicatePolicy::First)
+        .parse(&data)
+        .unwrap();
+    assert_eq!(first.path, ["Users", "sur"]);
+
+    let all = BookmarkParser::new()
+        .duplicate_policy(DuplicatePolicy::CollectAll)
+        .parse(&data)
+        .unwrap();
+    assert_eq!(all.path, ["Users", "sur"]);
+    assert_eq!(all.duplicate_records[0].bookmark.path, ["evil"]);
+}
+
+#[test]
+// Test keeping the raw bytes of records the parser does not understand
+fn test_preserve_unknown_records() {
+    let volume_name = 0x2010;
+    let unknown = 0xf022;
+    let string_type = 0x0101;
+    let data_type = 0x0201;
+    let data = build_bookmark(&[
+        (volume_name, string_type, b"Macintosh HD"),
+        (unknown, data_type, &[1, 2, 3, 4, 5]),
+    ]);
+
+    let bookmark = macos_bookmarks::parser::parse_bookmark(&data).unwrap();
+    assert!(bookmark.unknown_records.is_empty());
+
+    let bookmark = macos_bookmarks::parser::BookmarkParser::new()
+        .preserve_unknown(true)
+        .parse(&data)
+        .unwrap();
+    assert_eq!(bookmark.volume_name, "Macintosh HD");
+    assert_eq!(bookmark.unknown_records.len(), 1);
+
+    let record = &bookmark.unknown_records[0];
+    assert_eq!(record.record_type, unknown);
+    assert_eq!(record.data_type, data_type);
+    assert_eq!(record.offset, 72);
+    assert_eq!(record.data, [1, 2, 3, 4, 5]);
+    assert_eq!(&data[80..85], record.data);
+}
+
+#[test]
+// Test a bookmark to a file on a FAT formatted USB stick (no persistent IDs)
+fn test_fat_volume_bookmark() {
+    use macos_bookmarks::builder::BookmarkBuilder;
+
+    // Local, ejectable, removable and external, persistent IDs marked valid but not supported
+    let volume_flags = [0x161, 0x1000013ef, 0];
+    let data = BookmarkBuilder::new()
+        .target_path("/Volumes/NO NAME/report.docx")
+        .volume_path("/Volumes/NO NAME")
+        .volume_url("file:///Volumes/NO%20NAME/")
+        .volume_name("NO NAME")
+        .volume_uuid("A1B2C3D4-0000-0000-0000-000000000000")
+        .volume_flags(&volume_flags)
+        .volume_root(false)
+        .build();
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&data).unwrap();
+    assert_eq!(bookmark_data.path, ["Volumes", "NO NAME", "report.docx"]);
+    assert!(bookmark_data.cnid_path.is_empty());
+    assert_eq!(bookmark_data.volume_flag, volume_flags);
+    assert!(!bookmark_data.has_persistent_ids);
+
+    // An empty CNID array is not an error
+    let target_cnid_path = 0x1005;
+    let array_type = 0x0601;
+    let data = build_bookmark(&[(target_cnid_path, array_type, &[])]);
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&data).unwrap();
+    assert!(bookmark_data.cnid_path.is_empty());
+    assert!(!bookmark_data.has_persistent_ids);
+
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push("tests/test_data/loginitem.bookmark");
+    let buffer = fs::read(test_location).unwrap();
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&buffer).unwrap();
+    assert!(bookmark_data.has_persistent_ids);
+}
+
+#[test]
+// Test the record set hint of bookmarks with and without the volume records current releases write
+fn test_record_set() {
+    use macos_bookmarks::{
+        builder::BookmarkBuilder,
+        parser::{BookmarkParser, ParseMode},
+        types::{Cnid, CocoaTime, RecordSet},
+    };
+
+    // Target flags, volume UUID, creation, flags and root records are missing
+    let reduced = BookmarkBuilder::new()
+        .target_path("/Applications/TextEdit.app")
+        .cnid_path(&[Cnid(103), Cnid(4152)])
+        .creation(CocoaTime(331257600.0))
+        .volume_path("/")
+        .volume_url("file:///")
+        .volume_name("Macintosh HD")
+        .volume_size(250140434432)
+        .localized_name("TextEdit");
+    let bookmark_data = BookmarkParser::new()
+        .mode(ParseMode::Strict)
+        .parse(&reduced.build())
+        .unwrap();
+    assert_eq!(bookmark_data.record_set, RecordSet::Reduced);
+
+    let full = reduced
+        .target_flags(&[2, 15, 0])
+        .volume_uuid("0A81F3B1-51D9-3335-B3E3-169C3640360D")
+        .volume_creation(CocoaTime(241134516.0))
+        .volume_flags(&[4294967425, 4294972399, 0])
+        .volume_root(true);
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&full.build()).unwrap();
+    assert_eq!(bookmark_data.record_set, RecordSet::Full);
+
+    // Fragments without a volume path cannot be placed
+    let fragment = BookmarkBuilder::new().target_path("/Applications/TextEdit.app");
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&fragment.build()).unwrap();
+    assert_eq!(bookmark_data.record_set, RecordSet::Unknown);
+
+    for sample in [
+        "downloads.bookmark",
+        "loginitem.bookmark",
+        "macAlias.bookmark",
+        "poisonapple.bookmark",
+        "systemevents.bookmark",
+        "ventura.bookmark",
+    ] {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data");
+        test_location.push(sample);
+        let buffer = fs::read(test_location).unwrap();
+        let bookmark_data = macos_bookmarks::parser::parse_bookmark(&buffer).unwrap();
+        assert_eq!(bookmark_data.record_set, RecordSet::Full, "{sample}");
+    }
+}
+
+#[test]
+fn test_binary_volume_uuid() {
+    let volume_uuid = 0x2011;
+    let uuid_type = 0x0801;
+    let uuid = [
+        150, 251, 65, 192, 108, 233, 77, 162, 132, 53, 53, 188, 25, 199, 53, 163,
+    ];
+    let data = build_bookmark(&[(volume_uuid, uuid_type, &uuid)]);
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&data).unwrap();
+    assert_eq!(
+        bookmark_data.volume_uuid,
+        "96FB41C0-6CE9-4DA2-8435-35BC19C735A3"
+    );
+    assert_eq!(bookmark_data.field_provenance()[0].field, "volume_uuid");
+}
+
+#[test]
+fn test_relative_url() {
+    let url_type = 0x0901;
+    let string_type = 0x0101;
+    let url_relative_type = 0x0902;
+    let volume_url = 0x2005;
+    let base = b"file:///Users/lab/Documents/report.pages";
+    let relative = b"../Downloads/report.pdf";
+
+    // Base URL record at offset 4 (48 bytes), relative path record at offset 52
+    let mut offsets = 4u32.to_le_bytes().to_vec();
+    offsets.extend_from_slice(&52u32.to_le_bytes());
+    let data = build_bookmark(&[
+        (0xf100, url_type, base),
+        (0xf101, string_type, relative),
+        (volume_url, url_relative_type, &offsets),
+    ]);
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&data).unwrap();
+
+    let relative_url = bookmark_data.relative_url.as_ref().unwrap();
+    assert_eq!(
+        relative_url.base,
+        "file:///Users/lab/Documents/report.pages"
+    );
+    assert_eq!(relative_url.relative, "../Downloads/report.pdf");
+    assert_eq!(
+        bookmark_data.volume_url,
+        "file:///Users/lab/Downloads/report.pdf"
+    );
+    assert_eq!(
+        bookmark_data.resolve_relative_url().unwrap(),
+        "file:///Users/lab/Downloads/report.pdf"
+    );
+}
+
+#[test]
+fn test_float_records() {
+    let float_type = 0x0305;
+    let float64_type = 0x0306;
+    let volume_size = 0x2012;
+    let folder_index = 0xc001;
+    let data = build_bookmark(&[
+        (volume_size, float64_type, &160851517440.0f64.to_le_bytes()),
+        (folder_index, float_type, &2.0f32.to_le_bytes()),
+        (0xf100, float64_type, &0.5f64.to_le_bytes()),
+    ]);
+    let bookmark_data = macos_bookmarks::parser::BookmarkParser::new()
+        .preserve_unknown(true)
+        .parse(&data)
+        .unwrap();
+    assert_eq!(bookmark_data.volume_size, 160851517440);
+    assert_eq!(bookmark_data.folder_index, 2);
+    assert_eq!(bookmark_data.unknown_records.len(), 1);
+    assert_eq!(bookmark_data.unknown_records[0].record_type, 0xf100);
+}
+
+#[test]
+fn test_small_integer_records() {
+    let one_byte_type = 0x0301;
+    let two_byte_type = 0x0302;
+    let eight_byte_type = 0x0304;
+    let folder_index = 0xc001;
+    let creator_uid = 0xc012;
+    let creation_options = 0xd010;
+    let data = build_bookmark(&[
+        (folder_index, one_byte_type, &[3]),
+        (creator_uid, two_byte_type, &501i16.to_le_bytes()),
+        (
+            creation_options,
+            eight_byte_type,
+            &536870912i64.to_le_bytes(),
+        ),
+    ]);
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&data).unwrap();
+    assert_eq!(bookmark_data.folder_index, 3);
+    assert_eq!(bookmark_data.uid, Some(501));
+    assert_eq!(bookmark_data.creation_options, Some(536870912));
+}
+
+#[test]
+fn test_bool_false_records() {
+    let bool_false = 0x0500;
+    let bool_true = 0x0501;
+    let volume_root = 0x2030;
+    let file_ref_flag = 0xd001;
+    let data = build_bookmark(&[
+        (volume_root, bool_false, &[]),
+        (file_ref_flag, bool_false, &[]),
+    ]);
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&data).unwrap();
+    assert_eq!(bookmark_data.volume_root, Some(false));
+    assert_eq!(bookmark_data.file_ref_flag, Some(false));
+    // Missing records are not the same as false
+    assert_eq!(bookmark_data.is_executable, None);
+
+    let data = build_bookmark(&[(file_ref_flag, bool_true, &[])]);
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&data).unwrap();
+    assert_eq!(bookmark_data.file_ref_flag, Some(true));
+    assert_eq!(bookmark_data.volume_root, None);
+}
+
+#[test]
+fn test_length_limits() {
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push("tests/test_data/loginitem.bookmark");
+    let buffer = fs::read(test_location).unwrap();
+
+    let limits = macos_bookmarks::parser::LengthLimits {
+        string: 4,
+        data: 16,
+    };
+    let bookmark_data = macos_bookmarks::parser::BookmarkParser::new()
+        .length_limits(limits)
+        .parse(&buffer)
+        .unwrap();
+    assert_eq!(bookmark_data.path, ["Appl", "Sync"]);
+    assert_eq!(bookmark_data.localized_name.as_deref(), Some("Sync"));
+    assert_eq!(
+        bookmark_data.security_extension_rw.as_deref(),
+        Some("64cb7eaa9a1bbccc")
+    );
+    assert!(bookmark_data
+        .truncated_records
+        .iter()
+        .any(|record| record.length == 13 && record.kept_length == 4));
+
+    let unlimited = macos_bookmarks::parser::parse_bookmark(&buffer).unwrap();
+    assert!(unlimited.truncated_records.is_empty());
+    let bounded = macos_bookmarks::parser::BookmarkParser::new()
+        .length_limits(macos_bookmarks::parser::LengthLimits::BOUNDED)
+        .parse(&buffer)
+        .unwrap();
+    assert!(bounded.truncated_records.is_empty());
+}
+
+#[test]
+fn test_target_filename() {
+    let string_type = 0x0101;
+    let target_filename = 0x1020;
+    let data = build_bookmark(&[(target_filename, string_type, b"report.pdf")]);
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&data).unwrap();
+    assert_eq!(bookmark_data.target_filename.as_deref(), Some("report.pdf"));
+    // Available even without a path array
+    assert!(bookmark_data.path.is_empty());
+    assert_eq!(bookmark_data.filename(), Some("report.pdf"));
+
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push("tests/test_data/loginitem.bookmark");
+    let buffer = fs::read(test_location).unwrap();
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&buffer).unwrap();
+    assert_eq!(bookmark_data.target_filename, None);
+    assert_eq!(bookmark_data.filename(), Some("Syncthing.app"));
+}
+
+#[test]
+fn test_url_length_array() {
+    let string_type = 0x0101;
+    let number_type = 0x0303;
+    let array_type = 0x0601;
+    let target_path = 0x1004;
+    let url_lengths = 0xe003;
+
+    // "Users" at offset 4, "lab" at 20, 5 at 32 and 4 at 44
+    let path_offsets = [4u32.to_le_bytes(), 20u32.to_le_bytes()].concat();
+    let length_offsets = [32u32.to_le_bytes(), 44u32.to_le_bytes()].concat();
+    let data = build_bookmark(&[
+        (0xf100, string_type, b"Users"),
+        (0xf101, string_type, b"lab"),
+        (0xf102, number_type, &5i32.to_le_bytes()),
+        (0xf103, number_type, &4i32.to_le_bytes()),
+        (target_path, array_type, &path_offsets),
+        (url_lengths, array_type, &length_offsets),
+    ]);
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&data).unwrap();
+    assert_eq!(bookmark_data.path, ["Users", "lab"]);
+    assert_eq!(bookmark_data.url_lengths, [5, 4]);
+
+    // Path component was changed from a 4 byte name
+    let mismatches = bookmark_data.path_length_mismatches();
+    assert_eq!(mismatches.len(), 1);
+    assert_eq!(mismatches[0].index, 1);
+    assert_eq!(mismatches[0].expected, Some(4));
+    assert_eq!(mismatches[0].actual, Some(3));
+
+    assert_eq!(
+        bookmark_data.split_with_url_lengths("/Users/labs"),
+        Some(vec![String::from("Users"), String::from("labs")])
+    );
+    assert_eq!(bookmark_data.split_with_url_lengths("/Users/lab"), None);
+    assert_eq!(bookmark_data.split_with_url_lengths("/User/labs"), None);
+}
+
+#[test]
+fn test_url_component_counts() {
+    let string_type = 0x0101;
+    let number_type = 0x0303;
+    let array_type = 0x0601;
+    let target_path = 0x1004;
+    let url_lengths = 0xe003;
+
+    let names: [&[u8]; 5] = [b"Data", b"Users", b"lab", b"..", b"shared"];
+    let lengths = [5i32, 3, 2, 6];
+    let mut records: Vec<(u32, u32, Vec<u8>)> = Vec::new();
+    for (index, name) in names.iter().enumerate() {
+        records.push((0xf100 + index as u32, string_type, name.to_vec()));
+    }
+    for (index, length) in lengths.iter().enumerate() {
+        records.push((
+            0xf110 + index as u32,
+            number_type,
+            length.to_le_bytes().to_vec(),
+        ));
+    }
+
+    // Each record is 8 bytes of length/type plus the data padded to 4 bytes
+    let mut offsets = Vec::new();
+    let mut offset = 4u32;
+    for (_, _, data) in &records {
+        offsets.push(offset);
+        offset += 8 + (data.len() as u32).div_ceil(4) * 4;
+    }
+    let path_offsets: Vec<u8> = offsets[..5].iter().flat_map(|o| o.to_le_bytes()).collect();
+    let length_offsets: Vec<u8> = offsets[5..].iter().flat_map(|o| o.to_le_bytes()).collect();
+    records.push((target_path, array_type, path_offsets));
+    records.push((url_lengths, array_type, length_offsets));
+
+    let records: Vec<(u32, u32, &[u8])> = records
+        .iter()
+        .map(|(record_type, data_type, data)| (*record_type, *data_type, data.as_slice()))
+        .collect();
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&build_bookmark(&records)).unwrap();
+    assert_eq!(bookmark_data.path, ["Data", "Users", "lab", "..", "shared"]);
+
+    let counts = bookmark_data.url_component_counts().unwrap();
+    assert_eq!(counts.system, 1);
+    assert_eq!(counts.visible, 4);
+    assert_eq!(bookmark_data.visible_path().unwrap(), ["Users", "shared"]);
+    assert!(bookmark_data.path_length_mismatches().is_empty());
+}
+
+#[test]
+fn test_path_policy() {
+    use macos_bookmarks::{
+        parser::BookmarkParser,
+        path_policy::{escape_suspicious, reject_suspicious},
+    };
+
+    let string_type = 0x0101;
+    let array_type = 0x0601;
+    let target_path = 0x1004;
+
+    // "Users" at offset 4, right-to-left override lure at offset 20
+    let path_offsets = [4u32.to_le_bytes(), 20u32.to_le_bytes()].concat();
+    let data = build_bookmark(&[
+        (0xf100, string_type, b"Users"),
+        (0xf101, string_type, "invoice\u{202e}fdp.app".as_bytes()),
+        (target_path, array_type, &path_offsets),
+    ]);
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&data).unwrap();
+    assert_eq!(bookmark_data.path, ["Users", "invoice\u{202e}fdp.app"]);
+
+    let bookmark_data = BookmarkParser::new()
+        .path_policy(escape_suspicious)
+        .parse(&data)
+        .unwrap();
+    assert_eq!(bookmark_data.path, ["Users", "invoice\\u{202e}fdp.app"]);
+
+    let err = BookmarkParser::new()
+        .path_policy(reject_suspicious)
+        .parse(&data)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Bookmark path rejected by path policy");
+}
+
+#[test]
+fn test_file_metadata() {
+    use macos_bookmarks::value::BookmarkValue;
+
+    let date_type = 0x0400;
+    let number_type = 0x0304;
+    let bool_true = 0x0501;
+    let string_type = 0x0101;
+
+    let data = build_bookmark(&[
+        (0x1054, date_type, &665473989.0f64.to_be_bytes()),
+        (0x1055, number_type, &12345i64.to_le_bytes()),
+        (0x1056, bool_true, &[]),
+        (0x1057, string_type, b"id"),
+    ]);
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&data).unwrap();
+    let values: Vec<(u32, BookmarkValue)> = bookmark_data
+        .file_metadata
+        .iter()
+        .map(|metadata| (metadata.record_type, metadata.value.clone()))
+        .collect();
+    assert_eq!(values.len(), 4);
+    assert_eq!(values[0].0, 0x1054);
+    assert!(matches!(values[0].1, BookmarkValue::Date(date) if date == 665473989.0));
+    assert_eq!(values[1], (0x1055, BookmarkValue::Int(12345)));
+    assert_eq!(values[2], (0x1056, BookmarkValue::Bool(true)));
+    assert_eq!(
+        values[3],
+        (0x1057, BookmarkValue::String(String::from("id")))
+    );
+
+    let flat = bookmark_data.to_flat_map();
+    assert_eq!(flat["target.metadata.0x1055"], "12345");
+    let provenance = bookmark_data.field_provenance();
+    assert!(provenance
+        .iter()
+        .all(|field| field.field == "file_metadata"));
+}
+
+#[test]
+fn test_display_name() {
+    let string_type = 0x0101;
+    let display_name = 0xf022;
+
+    let data = build_bookmark(&[(display_name, string_type, b"Syncthing Helper")]);
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&data).unwrap();
+    assert_eq!(
+        bookmark_data.display_name.as_deref(),
+        Some("Syncthing Helper")
+    );
+    assert_eq!(bookmark_data.field_provenance()[0].field, "display_name");
+}
+
+#[test]
+fn test_chained_toc() {
+    let string_type = 0x0101;
+    let volume_name = 0x2010;
+    let display_name = 0xf022;
+
+    let first: &[(u32, u32, &[u8])] = &[(volume_name, string_type, b"Macintosh HD")];
+    let second: &[(u32, u32, &[u8])] = &[(display_name, string_type, b"Helper")];
+    let data = build_chained_bookmark(&[first, second]);
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&data).unwrap();
+    assert_eq!(bookmark_data.volume_name, "Macintosh HD");
+    assert_eq!(bookmark_data.display_name.as_deref(), Some("Helper"));
+
+    // TOC chain pointing back to the first TOC is only followed once
+    let mut looped = data.clone();
+    let first_toc = u32::from_le_bytes(looped[48..52].try_into().unwrap());
+    let second_toc = first_toc as usize + 20 + 12 + 48;
+    looped[second_toc + 12..second_toc + 16].copy_from_slice(&first_toc.to_le_bytes());
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&looped).unwrap();
+    assert_eq!(bookmark_data.display_name.as_deref(), Some("Helper"));
+    assert_eq!(bookmark_data.offsets().count(), 2);
+}
+
+#[test]
+fn test_toc_header_magic() {
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push("tests/test_data/loginitem.bookmark");
+    let buffer = fs::read(test_location).unwrap();
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&buffer).unwrap();
+    assert_eq!(bookmark_data.toc_headers().len(), 1);
+    assert_eq!(bookmark_data.toc_headers()[0].record_type, 0xfffe);
+    assert_eq!(bookmark_data.toc_headers()[0].flags, 0xffff);
+    assert!(!bookmark_data.has_nonstandard_toc_header());
+
+    let string_type = 0x0101;
+    let volume_name = 0x2010;
+    let mut data = build_bookmark(&[(volume_name, string_type, b"Macintosh HD")]);
+    let toc = u32::from_le_bytes(data[48..52].try_into().unwrap()) as usize + 48;
+    data[toc + 4..toc + 8].copy_from_slice(&[0x34, 0x12, 0, 0]);
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&data).unwrap();
+    assert_eq!(bookmark_data.volume_name, "Macintosh HD");
+    assert_eq!(bookmark_data.toc_headers()[0].offset, toc as u32);
+    assert_eq!(bookmark_data.toc_headers()[0].record_type, 0x1234);
+    assert_eq!(bookmark_data.toc_headers()[0].flags, 0);
+    assert!(bookmark_data.has_nonstandard_toc_header());
+}
+
+#[test]
+fn test_parse_lenient() {
+    use macos_bookmarks::{
+        parser::{parse_bookmark, BookmarkParser, ParseMode},
+        FieldConfidence,
+    };
+
+    let parse_lenient = |data: &[u8]| BookmarkParser::new().mode(ParseMode::Lenient).parse(data);
+
+    let string_type = 0x0101;
+    let volume_name = 0x2010;
+    let display_name = 0xf022;
+
+    let mut data = build_bookmark(&[
+        (volume_name, string_type, b"Macintosh HD"),
+        (display_name, string_type, b"Helper"),
+    ]);
+    // Point the second TOC record past the end of the data
+    let toc = u32::from_le_bytes(data[48..52].try_into().unwrap()) as usize + 48;
+    let second_offset = toc + 20 + 12 + 4;
+    data[second_offset..second_offset + 4].copy_from_slice(&0xfff0u32.to_le_bytes());
+    assert!(parse_bookmark(&data).is_err());
+
+    let bookmark_data = parse_lenient(&data).unwrap();
+    assert_eq!(bookmark_data.volume_name, "Macintosh HD");
+    assert_eq!(
+        bookmark_data.field_confidence("volume_name"),
+        Some(FieldConfidence::Exact)
+    );
+    assert_eq!(bookmark_data.display_name, None);
+    assert_eq!(bookmark_data.field_confidence("display_name"), None);
+    assert_eq!(bookmark_data.warnings.len(), 1);
+    assert_eq!(bookmark_data.warnings[0].record_type, display_name);
+    assert_eq!(bookmark_data.warnings[0].offset, 0xfff0 + 48);
+
+    // TOC lists more records than are present
+    let mut truncated = build_bookmark(&[(volume_name, string_type, b"Macintosh HD")]);
+    let toc = u32::from_le_bytes(truncated[48..52].try_into().unwrap()) as usize + 48;
+    truncated[toc + 16..toc + 20].copy_from_slice(&5u32.to_le_bytes());
+    assert!(parse_bookmark(&truncated).is_err());
+    let bookmark_data = parse_lenient(&truncated).unwrap();
+    assert_eq!(bookmark_data.volume_name, "Macintosh HD");
+    assert_eq!(bookmark_data.warnings.len(), 1);
+    assert_eq!(
+        bookmark_data.field_confidence("volume_name"),
+        Some(FieldConfidence::Recovered)
+    );
+    assert_eq!(
+        bookmark_data.confidence.get("volume_name"),
+        Some(&FieldConfidence::Recovered)
+    );
+}
+
+#[test]
+fn test_parse_warnings() {
+    use macos_bookmarks::locale::Localization;
+
+    let number_eight_byte = 0x0304;
+    let volume_size = 0x2012;
+    let string_type = 0x0101;
+    let volume_name = 0x2010;
+
+    // Volume size is too short for an eight byte number
+    let data = build_bookmark(&[
+        (volume_size, number_eight_byte, &[1, 0]),
+        (volume_name, string_type, b"Macintosh HD"),
+    ]);
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&data).unwrap();
+    assert_eq!(bookmark_data.volume_name, "Macintosh HD");
+    assert_eq!(bookmark_data.warnings.len(), 1);
+    assert_eq!(bookmark_data.warnings[0].record_type, volume_size);
+    assert_eq!(bookmark_data.warnings[0].key, "warning.volume_size");
+    assert!(bookmark_data.warnings[0]
+        .message
+        .starts_with("Failed to parse Volume size"));
+
+    // Messages are looked up by key, missing translations fall back to English
+    let locale = Localization::from_json(r#"{"warning.volume_size": "Taille illisible"}"#).unwrap();
+    assert_eq!(
+        bookmark_data.warnings[0].message_localized(&locale),
+        "Taille illisible"
+    );
+    assert_eq!(
+        bookmark_data.warnings[0].message_localized(&Localization::english()),
+        bookmark_data.warnings[0].message
+    );
+}
+
+#[test]
+fn test_parse_strict() {
+    use macos_bookmarks::parser::{parse_bookmark, BookmarkParser, ParseMode};
+
+    let parse_strict = |data: &[u8]| BookmarkParser::new().mode(ParseMode::Strict).parse(data);
+
+    for sample in [
+        "loginitem.bookmark",
+        "systemevents.bookmark",
+        "ventura.bookmark",
+    ] {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data");
+        test_location.push(sample);
+        let buffer = fs::read(test_location).unwrap();
+        assert!(parse_strict(&buffer).is_ok(), "{sample}");
+    }
+
+    let string_type = 0x0101;
+    let volume_name = 0x2010;
+
+    let data = build_bookmark(&[(volume_name, string_type, b"Macintosh HD")]);
+    assert!(parse_strict(&data).is_ok());
+
+    // Unknown TOC key
+    let unknown_key = build_bookmark(&[
+        (volume_name, string_type, b"Macintosh HD"),
+        (0x9999, string_type, b"unknown"),
+    ]);
+    assert!(parse_bookmark(&unknown_key).is_ok());
+    let err = parse_strict(&unknown_key).unwrap_err();
+    assert_eq!(err.to_string(), "Bookmark failed strict validation");
+
+    // Unknown data type
+    let unknown_type = build_bookmark(&[(volume_name, 0x0a01, b"Macintosh HD")]);
+    assert!(parse_bookmark(&unknown_type).is_ok());
+    assert!(parse_strict(&unknown_type).is_err());
+
+    // Header length does not match the data
+    let mut trailing = data.clone();
+    trailing.extend_from_slice(&[0; 8]);
+    assert!(parse_bookmark(&trailing).is_ok());
+    assert!(parse_strict(&trailing).is_err());
+
+    // TOC data length is 8 bytes short, like some bookmarks created by mac_alias
+    let mut toc_length = data.clone();
+    let toc = u32::from_le_bytes(data[48..52].try_into().unwrap()) as usize + 48;
+    toc_length[toc..toc + 4].copy_from_slice(&16u32.to_le_bytes());
+    assert!(parse_bookmark(&toc_length).is_ok());
+    assert!(parse_strict(&toc_length).is_err());
+}
+
+#[test]
+fn test_bookmark_parser() {
+    use macos_bookmarks::parser::{BookmarkParser, DuplicatePolicy, ParseMode, ParserOptions};
+
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push("tests/test_data/loginitem.bookmark");
+    let buffer = fs::read(test_location).unwrap();
+
+    let parser = BookmarkParser::new();
+    assert_eq!(parser.options().mode, ParseMode::Normal);
+    let bookmark_data: macos_bookmarks::BookmarkData = parser.parse(&buffer).unwrap();
+    assert_eq!(bookmark_data.path, ["Applications", "Syncthing.app"]);
+
+    let parser = BookmarkParser::new()
+        .mode(ParseMode::Strict)
+        .duplicate_policy(DuplicatePolicy::Error)
+        .preserve_unknown(true);
+    assert!(parser.parse(&buffer).is_ok());
+
+    // Options set directly or with the builder methods are the same
+    let options = ParserOptions {
+        mode: ParseMode::Lenient,
+        ..Default::default()
+    };
+    let lenient = BookmarkParser::with_options(options)
+        .parse(&buffer)
+        .unwrap();
+    assert_eq!(
+        serde_json::to_string(&lenient).unwrap(),
+        serde_json::to_string(
+            &BookmarkParser::new()
+                .mode(ParseMode::Lenient)
+                .parse(&buffer)
+                .unwrap()
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn test_resource_limits() {
+    use macos_bookmarks::parser::{BookmarkParser, ResourceLimits};
+
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push("tests/test_data/loginitem.bookmark");
+    let buffer = fs::read(test_location).unwrap();
+
+    assert!(BookmarkParser::new()
+        .resource_limits(ResourceLimits::BOUNDED)
+        .parse(&buffer)
+        .is_ok());
+
+    for limits in [
+        ResourceLimits {
+            max_size: 100,
+            ..Default::default()
+        },
+        ResourceLimits {
+            max_records: 5,
+            ..Default::default()
+        },
+        ResourceLimits {
+            max_record_size: 16,
+            ..Default::default()
+        },
+    ] {
+        let err = BookmarkParser::new()
+            .resource_limits(limits)
+            .parse(&buffer)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Bookmark exceeds the resource limits");
+    }
+
+    // Limits apply to nested bookmarks
+    let string_type = 0x0101;
+    let data_type = 0x0201;
+    let volume_bookmark = 0x2040;
+    let data = build_bookmark(&[
+        (0x2010, string_type, b"Macintosh HD"),
+        (volume_bookmark, data_type, &buffer),
+    ]);
+    let limits = ResourceLimits {
+        max_records: 10,
+        ..Default::default()
+    };
+    assert!(BookmarkParser::new().parse(&data).is_ok());
+    let err = BookmarkParser::new()
+        .resource_limits(limits)
+        .parse(&data)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Bookmark exceeds the resource limits");
+}
+
+#[test]
+// Test an array whose elements all point to the same large string record
+fn test_array_fan_out_limits() {
+    use macos_bookmarks::{
+        error::BookmarkError,
+        parser::{BookmarkParser, LengthLimits, ResourceLimits},
+    };
+
+    let string_type = 0x0101;
+    let array_type = 0x0601;
+    let volume_name = 0x2010;
+    let target_path = 0x1004;
+
+    // The volume name is the first record (offset 4), 20,000 path components reference it
+    let name = vec![b'a'; 60_000];
+    let elements: Vec<u8> = (0..20_000).flat_map(|_| 4u32.to_le_bytes()).collect();
+    let data = build_bookmark(&[
+        (volume_name, string_type, &name),
+        (target_path, array_type, &elements),
+    ]);
+    assert!(data.len() < 141_000);
+
+    let parse = |limits| {
+        BookmarkParser::new()
+            .length_limits(LengthLimits::BOUNDED)
+            .resource_limits(limits)
+            .parse(&data)
+    };
+    assert!(matches!(
+        parse(ResourceLimits::BOUNDED),
+        Err(BookmarkError::ResourceLimit)
+    ));
+    // Total decoded size is checked even when the element count is allowed
+    let limits = ResourceLimits {
+        max_array_elements: usize::MAX,
+        ..ResourceLimits::BOUNDED
+    };
+    assert!(matches!(parse(limits), Err(BookmarkError::ResourceLimit)));
+
+    // Arrays within the budget still parse
+    let elements: Vec<u8> = (0..3).flat_map(|_| 4u32.to_le_bytes()).collect();
+    let data = build_bookmark(&[
+        (volume_name, string_type, b"Macintosh HD"),
+        (target_path, array_type, &elements),
+    ]);
+    let bookmark_data = BookmarkParser::new()
+        .resource_limits(ResourceLimits::BOUNDED)
+        .parse(&data)
+        .unwrap();
+    assert_eq!(bookmark_data.path.len(), 3);
+    let limits = ResourceLimits {
+        max_array_elements: 2,
+        ..ResourceLimits::BOUNDED
+    };
+    assert!(BookmarkParser::new()
+        .resource_limits(limits)
+        .parse(&data)
+        .is_err());
+}
+
+#[test]
+fn test_error_context() {
+    use macos_bookmarks::{
+        error::{BookmarkError, ParseFailure, RecordContext},
+        parser::parse_bookmark,
+    };
+    use nom::error::ErrorKind;
+    use std::error::Error;
+
+    let string_type = 0x0101;
+    let volume_name = 0x2010;
+    let display_name = 0xf022;
+
+    let mut data = build_bookmark(&[
+        (volume_name, string_type, b"Macintosh HD"),
+        (display_name, string_type, b"Helper"),
+    ]);
+    // Declare a record length past the end of the data
+    let toc = u32::from_le_bytes(data[48..52].try_into().unwrap()) as usize + 48;
+    let record = u32::from_le_bytes(data[toc + 36..toc + 40].try_into().unwrap()) as usize + 48;
+    data[record..record + 4].copy_from_slice(&0xfff0u32.to_le_bytes());
+
+    match parse_bookmark(&data) {
+        Err(BookmarkError::BadRecord(context)) => assert_eq!(
+            context,
+            RecordContext {
+                record_type: display_name,
+                data_type: Some(string_type),
+                offset: record as u32,
+                failure: ParseFailure {
+                    kind: ErrorKind::Eof,
+                    offset: record + 8,
+                },
+            }
+        ),
+        results => panic!("unexpected results: {results:?}"),
+    }
+    let err = parse_bookmark(&data).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!("Failed to parse Record Type 0xf022 (Data type 0x101) at offset {record:#x}")
+    );
+    assert_eq!(
+        err.source().unwrap().to_string(),
+        format!("End of file error at offset {:#x}", record + 8)
+    );
+
+    // TOC record count past the end of the data, reading fails at the third TOC entry
+    data[toc + 16..toc + 20].copy_from_slice(&5u32.to_le_bytes());
+    assert!(matches!(
+        parse_bookmark(&data),
+        Err(BookmarkError::TruncatedData(failure)) if failure.offset == toc + 20 + 2 * 12
+    ));
+
+    // TOC record count with a size past the u32 range
+    data[toc + 16..toc + 20].copy_from_slice(&0x2000_0000u32.to_le_bytes());
+    assert!(matches!(
+        parse_bookmark(&data),
+        Err(BookmarkError::InvalidOffset(failure)) if failure.kind == ErrorKind::Verify
+    ));
+
+    // TOC offset before the start of the data
+    data[48..52].copy_from_slice(&2u32.to_le_bytes());
+    assert!(matches!(
+        parse_bookmark(&data),
+        Err(BookmarkError::BadBookmarkData)
+    ));
+}
+
+#[test]
+fn test_lossy_strings() {
+    use macos_bookmarks::{
+        parser::{parse_bookmark, BookmarkParser, ParseMode},
+        FieldConfidence,
+    };
+
+    let parse_strict = |data: &[u8]| BookmarkParser::new().mode(ParseMode::Strict).parse(data);
+
+    let string_type = 0x0101;
+    let volume_name = 0x2010;
+    let display_name = 0xf022;
+
+    // "Café" encoded as MacRoman
+    let data = build_bookmark(&[
+        (volume_name, string_type, b"Caf\x8e"),
+        (display_name, string_type, b"Helper"),
+    ]);
+    let bookmark = parse_bookmark(&data).unwrap();
+    assert_eq!(bookmark.volume_name, "");
+    assert!(bookmark.lossy_strings.is_empty());
+
+    let bookmark = BookmarkParser::new()
+        .lossy_strings(true)
+        .parse(&data)
+        .unwrap();
+    assert_eq!(bookmark.volume_name, "Caf\u{fffd}");
+    assert_eq!(bookmark.display_name.as_deref(), Some("Helper"));
+    assert_eq!(bookmark.lossy_strings.len(), 1);
+    assert_eq!(bookmark.lossy_strings[0].record_type, volume_name);
+    assert_eq!(bookmark.lossy_strings[0].raw, b"Caf\x8e");
+    assert_eq!(
+        bookmark.field_confidence("volume_name"),
+        Some(FieldConfidence::Recovered)
+    );
+    assert_eq!(
+        bookmark.field_confidence("display_name"),
+        Some(FieldConfidence::Exact)
+    );
+    let json = serde_json::to_value(&bookmark).unwrap();
+    assert_eq!(
+        json["confidence"],
+        serde_json::json!({"volume_name": "Recovered"})
+    );
+
+    assert!(parse_strict(&data).is_err());
+}
+
+#[test]
+fn test_utf16_names() {
+    use macos_bookmarks::{parser::parse_bookmark, FieldConfidence};
+
+    let data_type = 0x0201;
+    let volume_name = 0x2010;
+    let localized_name = 0xf017;
+
+    let name: Vec<u8> = [0xff, 0xfe]
+        .into_iter()
+        .chain("Données".encode_utf16().flat_map(|unit| unit.to_le_bytes()))
+        .collect();
+    let localized: Vec<u8> = "Systemeinstellungen"
+        .encode_utf16()
+        .flat_map(|unit| unit.to_be_bytes())
+        .collect();
+    let data = build_bookmark(&[
+        (volume_name, data_type, &name),
+        (localized_name, data_type, &localized),
+    ]);
+
+    let bookmark = parse_bookmark(&data).unwrap();
+    assert_eq!(bookmark.volume_name, "Données");
+    assert_eq!(
+        bookmark.localized_name.as_deref(),
+        Some("Systemeinstellungen")
+    );
+    // Byte order of the localized name is guessed without a BOM
+    assert_eq!(
+        bookmark.field_confidence("volume_name"),
+        Some(FieldConfidence::Exact)
+    );
+    assert_eq!(
+        bookmark.field_confidence("localized_name"),
+        Some(FieldConfidence::Heuristic)
+    );
+}
+
+#[test]
+fn test_header_validation() {
+    use macos_bookmarks::{
+        error::BookmarkError,
+        parser::{parse_bookmark, BookmarkParser, ParseMode},
+    };
+
+    let parse_lenient = |data: &[u8]| BookmarkParser::new().mode(ParseMode::Lenient).parse(data);
+    let parse_strict = |data: &[u8]| BookmarkParser::new().mode(ParseMode::Strict).parse(data);
+
+    let mut data = build_bookmark(&[(0x2010, 0x0101, b"Macintosh HD")]);
+    assert!(parse_strict(&data).is_ok());
+
+    data[..4].copy_from_slice(b"alis");
+    assert!(parse_bookmark(&data).is_ok());
+
+    data[..4].copy_from_slice(b"boo\0");
+    assert!(matches!(
+        parse_bookmark(&data),
+        Err(BookmarkError::WrongSignature(0x006f6f62))
+    ));
+
+    data[..4].copy_from_slice(b"book");
+    data[11] = 0x20;
+    assert!(matches!(
+        parse_bookmark(&data),
+        Err(BookmarkError::UnsupportedVersion(0x420))
+    ));
+    assert!(matches!(
+        parse_strict(&data),
+        Err(BookmarkError::UnsupportedVersion(0x420))
+    ));
+    assert!(parse_lenient(&data).is_ok());
+}
+
+#[test]
+fn test_toc_path() {
+    let string_type = 0x0101;
+    let array_type = 0x0601;
+    let target_path = 0x1004;
+    let toc_path = 0x2000;
+
+    // "Users" at offset 4, "lab" at offset 20
+    let path_offsets = [4u32.to_le_bytes(), 20u32.to_le_bytes()].concat();
+    let data = build_bookmark(&[
+        (0xf100, string_type, b"Users"),
+        (0xf101, string_type, b"lab"),
+        (target_path, array_type, &path_offsets),
+        (toc_path, string_type, b"/Users/lab"),
+    ]);
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&data).unwrap();
+    assert_eq!(bookmark_data.toc_path.as_deref(), Some("/Users/lab"));
+    assert_eq!(bookmark_data.toc_path_matches(), Some(true));
+
+    let data = build_bookmark(&[
+        (0xf100, string_type, b"Users"),
+        (0xf101, string_type, b"lab"),
+        (target_path, array_type, &path_offsets),
+        (toc_path, string_type, b"/Users/admin"),
+    ]);
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&data).unwrap();
+    assert_eq!(bookmark_data.toc_path_matches(), Some(false));
+
+    let data = build_bookmark(&[(target_path, array_type, &path_offsets[..0])]);
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&data).unwrap();
+    assert_eq!(bookmark_data.toc_path_matches(), None);
+}
+
+#[test]
+fn test_volume_mount_point() {
+    let url_type = 0x0901;
+    let volume_mount_point = 0x2050;
+    let data = build_bookmark(&[(volume_mount_point, url_type, b"file:///Volumes/USB/")]);
+    let bookmark_data = macos_bookmarks::parser::parse_bookmark(&data).unwrap();
+    assert_eq!(
+        bookmark_data.volume_mount_point.as_deref(),
+        Some("file:///Volumes/USB/")
+    );
+    assert_eq!(
+        bookmark_data.field_provenance()[0].field,
+        "volume_mount_point"
+    );
+}
+
+#[test]
+fn test_parse_bookmark_file() {
+    use macos_bookmarks::{
+        error::BookmarkError,
+        parser::{parse_bookmark_file, BookmarkParser, ResourceLimits},
+    };
+
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push("tests/test_data/loginitem.bookmark");
+    let bookmark_data = parse_bookmark_file(&test_location).unwrap();
+    assert_eq!(bookmark_data.path, ["Applications", "Syncthing.app"]);
+
+    let limits = ResourceLimits {
+        max_size: 100,
+        ..ResourceLimits::BOUNDED
+    };
+    let err = BookmarkParser::new()
+        .resource_limits(limits)
+        .parse_file(&test_location)
+        .unwrap_err();
+    let BookmarkError::BadFile(context) = &err else {
+        panic!("unexpected error: {err:?}");
+    };
+    assert_eq!(context.path, test_location);
+    assert!(matches!(*context.error, BookmarkError::ResourceLimit));
+    assert!(err.to_string().contains("loginitem.bookmark"));
+
+    let err = parse_bookmark_file("/does/not/exist.bookmark").unwrap_err();
+    assert!(err.to_string().contains("/does/not/exist.bookmark"));
+    assert!(std::error::Error::source(&err).is_some());
+
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push("tests/test_data");
+    assert!(parse_bookmark_file(&test_location).is_err());
+}
+
+#[test]
+fn test_parse_bookmark_reader() {
+    use macos_bookmarks::{
+        error::BookmarkError,
+        parser::{parse_bookmark_reader, BookmarkParser, ResourceLimits},
+    };
+    use std::io::{Cursor, Read};
+
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push("tests/test_data/loginitem.bookmark");
+    let login_item = fs::read(test_location).unwrap();
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push("tests/test_data/downloads.bookmark");
+    let download = fs::read(test_location).unwrap();
+
+    // Bookmarks streamed back to back, each read stops at the end of the bookmark
+    let mut stream = Cursor::new([login_item.as_slice(), &download, b"tail"].concat());
+    let bookmark_data = parse_bookmark_reader(&mut stream).unwrap();
+    assert_eq!(bookmark_data.path, ["Applications", "Syncthing.app"]);
+    assert_eq!(stream.position(), login_item.len() as u64);
+    assert!(parse_bookmark_reader(&mut stream).is_ok());
+    let mut tail = String::new();
+    stream.read_to_string(&mut tail).unwrap();
+    assert_eq!(tail, "tail");
+
+    let limits = ResourceLimits {
+        max_size: 100,
+        ..ResourceLimits::BOUNDED
+    };
+    let result = BookmarkParser::new()
+        .resource_limits(limits)
+        .parse_reader(Cursor::new(&login_item));
+    assert!(matches!(result, Err(BookmarkError::ResourceLimit)));
+
+    assert!(parse_bookmark_reader(Cursor::new(&login_item[..300])).is_err());
+    assert!(parse_bookmark_reader(Cursor::new(&login_item[..20])).is_err());
+}
+
+#[cfg(feature = "base64")]
+#[test]
+fn test_parse_bookmark_base64() {
+    use base64::{engine::general_purpose, Engine};
+    use macos_bookmarks::{error::BookmarkError, parser::parse_bookmark_base64};
+
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push("tests/test_data/loginitem.bookmark");
+    let buffer = fs::read(test_location).unwrap();
+
+    // Line wrapped like an XML plist <data> value
+    let encoded = general_purpose::STANDARD.encode(&buffer);
+    let wrapped = encoded
+        .as_bytes()
+        .chunks(68)
+        .map(|line| format!("\t{}\n", std::str::from_utf8(line).unwrap()))
+        .collect::<String>();
+    let bookmark_data = parse_bookmark_base64(&wrapped).unwrap();
+    assert_eq!(bookmark_data.path, ["Applications", "Syncthing.app"]);
+
+    let url_safe = general_purpose::URL_SAFE_NO_PAD.encode(&buffer);
+    assert!(parse_bookmark_base64(&url_safe).is_ok());
+
+    assert!(matches!(
+        parse_bookmark_base64("not base64!"),
+        Err(BookmarkError::InvalidBase64Data)
+    ));
+}
+
+#[test]
+fn test_cli_parse() {
+    use std::process::Command;
+
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push("tests/test_data/loginitem.bookmark");
+    let cli = env!("CARGO_BIN_EXE_macos-bookmarks");
+
+    let output = Command::new(cli)
+        .arg("parse")
+        .arg(&test_location)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with(&format!("{}\n", test_location.display())));
+    assert!(stdout.contains("  target.path.1                 Syncthing.app\n"));
+
+    let output = Command::new(cli)
+        .args(["parse", "--json"])
+        .arg(&test_location)
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["path"][1], "Syncthing.app");
+
+    let output = Command::new(cli)
+        .args(["parse", "--format", "jsonl"])
+        .args([&test_location, &test_location])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 2);
+    for line in stdout.lines() {
+        let json: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert_eq!(json["file"], test_location.to_str().unwrap());
+        assert_eq!(json["localized_name"], "Syncthing");
+    }
+
+    let output = Command::new(cli)
+        .args(["parse", "--format=csv"])
+        .arg(&test_location)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let mut lines = stdout.lines();
+    let header: Vec<&str> = lines.next().unwrap().split(',').collect();
+    assert_eq!(header.len(), 29);
+    assert_eq!(header[..4], ["file", "path", "cnid_path", "creation"]);
+    assert_eq!(header[26], "has_persistent_ids");
+    assert_eq!(header[27], "creation_raw");
+    let row: Vec<&str> = lines.next().unwrap().split(',').collect();
+    assert_eq!(row.len(), 29);
+    assert_eq!(row[1], "/Applications/Syncthing.app");
+    assert_eq!(row[2], "103;706090");
+    assert_eq!(row[6], "Macintosh HD");
+    assert_eq!(row[13], "");
+    assert_eq!(row[3], "665473989.0");
+    assert_eq!(row[26], "true");
+    assert_eq!(row[27], "");
+    assert!(lines.next().is_none());
+
+    // Converted timestamps keep the raw value next to them
+    let output = Command::new(cli)
+        .args(["parse", "--format=csv", "--timestamps", "both"])
+        .arg(&test_location)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let row: Vec<&str> = stdout.lines().nth(1).unwrap().split(',').collect();
+    assert_eq!(row[3], "2022-02-02T05:53:09Z");
+    assert_eq!(row[27], "665473989.0");
+
+    let output = Command::new(cli)
+        .args(["parse", "--timestamps=converted"])
+        .arg(&test_location)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("  target.creation               2022-02-02T05:53:09Z\n"));
+    assert!(!stdout.contains("target.creation_raw"));
+
+    let output = Command::new(cli)
+        .args(["parse", "--timestamps", "local"])
+        .arg(&test_location)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = Command::new(cli)
+        .args(["parse", "--format", "xml"])
+        .arg(&test_location)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = Command::new(cli)
+        .args(["parse", "/does/not/exist.bookmark"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert_eq!(Command::new(cli).output().unwrap().status.code(), Some(2));
+}
+
+#[test]
+fn test_cli_scan() {
+    use macos_bookmarks::snapshot::Snapshot;
+    use std::process::Command;
+
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push("tests/test_data");
+    let cli = env!("CARGO_BIN_EXE_macos-bookmarks");
+
+    let root = std::env::temp_dir().join("macos_bookmarks_test_cli_scan");
+    let output_dir = std::env::temp_dir().join("macos_bookmarks_test_cli_scan_output");
+    let _ = fs::remove_dir_all(&root);
+    let _ = fs::remove_dir_all(&output_dir);
+    fs::create_dir_all(root.join("Users/lab")).unwrap();
+    fs::copy(
+        test_location.join("loginitem.bookmark"),
+        root.join("Users/lab/loginitem.bookmark"),
+    )
+    .unwrap();
+    fs::copy(
+        test_location.join("poisonapple.bookmark"),
+        root.join("poisonapple.bookmark"),
+    )
+    .unwrap();
+
+    let output = Command::new(cli).arg("scan").arg(&root).output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 2);
+
+    let output = Command::new(cli)
+        .args(["scan", "--group-by", "user", "--output"])
+        .args([&output_dir, &root])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let index: Vec<serde_json::Value> =
+        serde_json::from_slice(&fs::read(output_dir.join("index.json")).unwrap()).unwrap();
+    assert_eq!(index.len(), 2);
+    assert_eq!(index[0]["group"], "lab");
+    assert_eq!(index[1]["group"], "sur");
+    let group: Vec<serde_json::Value> =
+        serde_json::from_slice(&fs::read(output_dir.join("sur.json")).unwrap()).unwrap();
+    assert_eq!(group[0]["bookmark"]["username"], "sur");
+
+    // Grouping needs an output directory
+    let output = Command::new(cli)
+        .args(["scan", "--group-by", "volume"])
+        .arg(&root)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+
+    // A second run with the same checkpoint only scans new files
+    let checkpoint = output_dir.join("checkpoint.jsonl");
+    let scan = || {
+        let output = Command::new(cli)
+            .args(["scan", "--checkpoint"])
+            .args([&checkpoint, &root])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    assert_eq!(scan().lines().count(), 2);
+    assert_eq!(scan().lines().count(), 0);
+    fs::copy(
+        test_location.join("ventura.bookmark"),
+        root.join("ventura.bookmark"),
+    )
+    .unwrap();
+    let stdout = scan();
+    assert_eq!(stdout.lines().count(), 1);
+    assert!(stdout.contains("ventura.bookmark"));
+
+    // The same directory in a mounted snapshot
+    let snapshot = output_dir.join("com.apple.TimeMachine.2022-06-30-021614.local");
+    let snapshot_root = Snapshot::new(&snapshot).path_in_snapshot(&root);
+    fs::create_dir_all(&snapshot_root).unwrap();
+    fs::copy(
+        test_location.join("loginitem.bookmark"),
+        snapshot_root.join("loginitem.bookmark"),
+    )
+    .unwrap();
+    let output = Command::new(cli)
+        .args(["scan", "--snapshot"])
+        .args([&snapshot, &root])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let results: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(results.len(), 4);
+    assert!(results[2]["snapshot"].is_null());
+    assert_eq!(
+        results[3]["snapshot"]["name"],
+        "com.apple.TimeMachine.2022-06-30-021614.local"
+    );
+
+    fs::remove_dir_all(&root).unwrap();
+    fs::remove_dir_all(&output_dir).unwrap();
+}
+
+#[test]
+fn test_cli_output() {
+    use std::process::Command;
+
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push("tests/test_data/loginitem.bookmark");
+    let cli = env!("CARGO_BIN_EXE_macos-bookmarks");
+
+    let output_dir = std::env::temp_dir().join("macos_bookmarks_test_cli_output");
+    let _ = fs::remove_dir_all(&output_dir);
+
+    // A file per input, each with the CSV header
+    let output = Command::new(cli)
+        .args(["parse", "--format", "csv", "--output-dir"])
+        .args([&output_dir, &test_location, &test_location])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    for file in ["loginitem.bookmark.csv", "loginitem.bookmark-1.csv"] {
+        let csv = fs::read_to_string(output_dir.join(file)).unwrap();
+        assert_eq!(csv.lines().count(), 2);
+        assert!(csv.starts_with("file,path,"));
+    }
+
+    // A single file
+    let jsonl = output_dir.join("scan.jsonl");
+    let output = Command::new(cli)
+        .args(["scan", "--output-file"])
+        .args([&jsonl, test_location.parent().unwrap()])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(fs::read_to_string(&jsonl).unwrap().lines().count() > 1);
+
+    // One destination at a time, grouped results are not written to a sink
+    let output = Command::new(cli)
+        .args(["parse", "--output-file"])
+        .arg(&jsonl)
+        .arg("--output-dir")
+        .args([&output_dir, &test_location])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let output = Command::new(cli)
+        .args(["scan", "--group-by", "user", "--output"])
+        .arg(&output_dir)
+        .arg("--output-file")
+        .args([&jsonl, &test_location])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+
+    #[cfg(unix)]
+    {
+        use std::{io::Read, os::unix::net::UnixListener};
+
+        let socket = output_dir.join("output.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+        let mut child = Command::new(cli)
+            .args(["parse", "--format", "jsonl", "--socket"])
+            .args([&socket, &test_location])
+            .spawn()
+            .unwrap();
+        let mut received = String::new();
+        listener
+            .accept()
+            .unwrap()
+            .0
+            .read_to_string(&mut received)
+            .unwrap();
+        assert!(child.wait().unwrap().success());
+        let json: serde_json::Value = serde_json::from_str(received.trim_end()).unwrap();
+        assert_eq!(json["localized_name"], "Syncthing");
+
+        // Nothing listening on the socket
+        fs::remove_file(&socket).unwrap();
+        let output = Command::new(cli)
+            .args(["parse", "--socket"])
+            .args([&socket, &test_location])
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+    }
+
+    #[cfg(feature = "gzip")]
+    {
+        use std::io::Read;
+
+        let output = Command::new(cli)
+            .args(["parse", "--format", "jsonl", "--compress", "gzip"])
+            .arg(&test_location)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(output.stdout.as_slice())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded.lines().count(), 1);
+    }
+    #[cfg(not(feature = "gzip"))]
+    {
+        let output = Command::new(cli)
+            .args(["parse", "--compress", "gzip"])
+            .arg(&test_location)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(2));
+    }
+
+    fs::remove_dir_all(&output_dir).unwrap();
+}
+
+#[test]
+fn test_cli_serve() {
+    use std::process::Command;
+
+    let cli = env!("CARGO_BIN_EXE_macos-bookmarks");
+    let output = Command::new(cli)
+        .args(["serve", "--port", "80"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+
+    #[cfg(feature = "serve")]
+    {
+        use std::{
+            io::{BufRead, BufReader, Read, Write},
+            net::TcpStream,
+            process::Stdio,
+        };
+
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitem.bookmark");
+        let data = fs::read(test_location).unwrap();
+
+        let mut child = Command::new(cli)
+            .args(["serve", "--address", "127.0.0.1:0"])
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut listening = String::new();
+        BufReader::new(child.stderr.take().unwrap())
+            .read_line(&mut listening)
+            .unwrap();
+        let address = listening.trim_end().strip_prefix("Listening on ").unwrap();
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "POST /parse HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            data.len()
+        )
+        .unwrap();
+        stream.write_all(&data).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains("\"localized_name\":\"Syncthing\""));
+    }
+}
+
+#[test]
+fn test_cli_kafka() {
+    use std::process::Command;
+
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push("tests/test_data/loginitem.bookmark");
+    let cli = env!("CARGO_BIN_EXE_macos-bookmarks");
+    let parse = |args: &[&str]| {
+        Command::new(cli)
+            .arg("parse")
+            .args(args)
+            .arg(&test_location)
+            .output()
+            .unwrap()
+            .status
+            .code()
+    };
+
+    // Topic is required, messages are JSON Lines and Kafka is the only destination
+    assert_eq!(parse(&["--format=jsonl", "--kafka=127.0.0.1:1"]), Some(2));
+    assert_eq!(
+        parse(&["--kafka=127.0.0.1:1", "--kafka-topic=bookmarks"]),
+        Some(2)
+    );
+    assert_eq!(
+        parse(&[
+            "--format=jsonl",
+            "--kafka=127.0.0.1:1",
+            "--kafka-topic=bookmarks",
+            "--output-file=/dev/null"
+        ]),
+        Some(2)
+    );
+    #[cfg(feature = "gzip")]
+    assert_eq!(
+        parse(&[
+            "--format=jsonl",
+            "--kafka=127.0.0.1:1",
+            "--kafka-topic=bookmarks",
+            "--compress=gzip"
+        ]),
+        Some(2)
+    );
+    #[cfg(not(feature = "kafka"))]
+    assert_eq!(
+        parse(&[
+            "--format=jsonl",
+            "--kafka=127.0.0.1:1",
+            "--kafka-topic=bookmarks"
+        ]),
+        Some(2)
+    );
+}
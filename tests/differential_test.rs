@@ -0,0 +1,198 @@
+//! Differential test against the python mac_alias bookmark parser
+//!
+//! Requires python3 with mac_alias installed (`pip install mac_alias`), run with:
+//! `cargo test --features differential --test differential_test`
+//! The python interpreter can be changed with the `MAC_ALIAS_PYTHON` environment variable. The test is skipped if the
+//! interpreter or mac_alias is not available.
+//!
+//! The sample bookmarks and bookmarks generated with `generator::ArbitraryBookmark` are parsed by both
+//! implementations. Inputs only one of them accepts and fields with different values are reported as divergences.
+#![cfg(feature = "differential")]
+
+use arbitrary::{Arbitrary, Unstructured};
+use macos_bookmarks::{generator::ArbitraryBookmark, parser::parse_bookmark};
+use serde_json::{json, Value};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+// Prints the compared mac_alias values of each file as a JSON line, keyed by the same names as BookmarkData
+const MAC_ALIAS_SCRIPT: &str = r#"
+import json, sys
+from mac_alias import Bookmark
+
+keys = {
+    "path": 0x1004,
+    "cnid_path": 0x1005,
+    "volume_path": 0x2002,
+    "volume_name": 0x2010,
+    "volume_uuid": 0x2011,
+    "volume_size": 0x2012,
+    "username": 0xc011,
+    "uid": 0xc012,
+    "creation_options": 0xd010,
+    "localized_name": 0xf017,
+}
+for path in sys.argv[1:]:
+    try:
+        bookmark = Bookmark.from_bytes(open(path, "rb").read())
+    except Exception as err:
+        print(json.dumps({"error": repr(err)}))
+        continue
+    values = {}
+    for name, key in keys.items():
+        try:
+            values[name] = bookmark[key]
+        except KeyError:
+            values[name] = None
+    print(json.dumps(values, default=str))
+"#;
+
+/// Samples that are not valid bookmarks
+const BAD_SAMPLES: [&str; 2] = ["bad_header.bookmark", "bad_content.bookmark"];
+
+/// Number of generated bookmarks
+const GENERATED: usize = 200;
+
+/// Python interpreter used to run mac_alias
+fn python() -> String {
+    env::var("MAC_ALIAS_PYTHON").unwrap_or_else(|_| String::from("python3"))
+}
+
+/// Check that the interpreter can import mac_alias
+fn mac_alias_available() -> bool {
+    Command::new(python())
+        .args(["-c", "import mac_alias"])
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Parse bookmark files with mac_alias. Files mac_alias cannot parse have an `error` value
+fn mac_alias_values(paths: &[PathBuf]) -> Vec<Value> {
+    let output = Command::new(python())
+        .arg("-c")
+        .arg(MAC_ALIAS_SCRIPT)
+        .args(paths)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "mac_alias failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+        .stdout
+        .split(|byte| *byte == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| serde_json::from_slice(line).unwrap())
+        .collect()
+}
+
+/// Parse a bookmark file with this crate, empty/zero values of required fields are treated as missing
+fn crate_values(path: &Path) -> Value {
+    let buffer = fs::read(path).unwrap();
+    let bookmark = match parse_bookmark(&buffer) {
+        Ok(bookmark) => bookmark,
+        Err(err) => return json!({ "error": format!("{err:?}") }),
+    };
+
+    let string = |value: &str| (!value.is_empty()).then(|| json!(value));
+    json!({
+        "path": (!bookmark.path.is_empty()).then(|| json!(bookmark.path)),
+        "cnid_path": (!bookmark.cnid_path.is_empty()).then(|| json!(bookmark.cnid_path)),
+        "volume_path": string(&bookmark.volume_path),
+        "volume_name": string(&bookmark.volume_name),
+        "volume_uuid": string(&bookmark.volume_uuid),
+        "volume_size": (bookmark.volume_size != 0).then(|| json!(bookmark.volume_size)),
+        "username": bookmark.username,
+        "uid": bookmark.uid,
+        "creation_options": bookmark.creation_options,
+        "localized_name": bookmark.localized_name,
+    })
+}
+
+/// Write generated bookmarks without tweaks, both implementations should accept them
+fn generated_bookmarks(directory: &Path) -> Vec<PathBuf> {
+    let mut seed = 0x9e3779b97f4a7c15u64;
+    let mut bytes = vec![0u8; 1024];
+    let mut paths = Vec::new();
+    for index in 0..GENERATED {
+        for byte in bytes.iter_mut() {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            *byte = seed as u8;
+        }
+        let mut bookmark = ArbitraryBookmark::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+        bookmark.tweaks.clear();
+
+        let path = directory.join(format!("generated_{index}.bookmark"));
+        fs::write(&path, bookmark.to_bytes()).unwrap();
+        paths.push(path);
+    }
+    paths
+}
+
+#[test]
+fn test_mac_alias_differential() {
+    if !mac_alias_available() {
+        eprintln!(
+            "skipping differential test, {} cannot import mac_alias",
+            python()
+        );
+        return;
+    }
+
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push("tests/test_data");
+    let mut paths: Vec<PathBuf> = fs::read_dir(test_location)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| !BAD_SAMPLES.contains(&name))
+        })
+        .collect();
+    let generated = env::temp_dir().join(format!(
+        "macos_bookmarks_differential_{}",
+        std::process::id()
+    ));
+    fs::create_dir_all(&generated).unwrap();
+    paths.extend(generated_bookmarks(&generated));
+
+    let expected_values = mac_alias_values(&paths);
+    assert_eq!(expected_values.len(), paths.len());
+
+    let mut divergences = Vec::new();
+    for (path, expected) in paths.iter().zip(expected_values) {
+        let actual = crate_values(path);
+        match (expected.get("error"), actual.get("error")) {
+            (None, None) => {}
+            (Some(_), Some(_)) => continue,
+            (expected_error, actual_error) => {
+                divergences.push(format!(
+                    "{}: mac_alias: {} macos-bookmarks: {}",
+                    path.display(),
+                    expected_error.unwrap_or(&json!("parsed")),
+                    actual_error.unwrap_or(&json!("parsed"))
+                ));
+                continue;
+            }
+        }
+
+        for (name, expected_value) in expected.as_object().unwrap() {
+            if actual[name] != *expected_value {
+                divergences.push(format!(
+                    "{}: {name} mac_alias: {expected_value} macos-bookmarks: {}",
+                    path.display(),
+                    actual[name]
+                ));
+            }
+        }
+    }
+    fs::remove_dir_all(&generated).unwrap();
+    assert!(divergences.is_empty(), "{}", divergences.join("\n"));
+}
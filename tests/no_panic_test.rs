@@ -0,0 +1,118 @@
+use std::{fs, path::PathBuf};
+
+// Small deterministic PRNG so mutations are reproducible without extra dependencies
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+fn samples() -> Vec<Vec<u8>> {
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push("tests/test_data");
+    let mut samples = Vec::new();
+    for entry in fs::read_dir(test_location).unwrap() {
+        samples.push(fs::read(entry.unwrap().path()).unwrap());
+    }
+    samples
+}
+
+#[test]
+// Parse every sample truncated at every length
+fn test_truncated_samples() {
+    for sample in samples() {
+        for size in 0..sample.len() {
+            let _ = macos_bookmarks::parser::parse_bookmark(&sample[..size]);
+        }
+    }
+}
+
+#[test]
+// Parse every sample with each byte replaced by boundary values
+fn test_boundary_byte_samples() {
+    for sample in samples() {
+        for index in 0..sample.len() {
+            for value in [0x00, 0x01, 0x03, 0x7f, 0x80, 0xfe, 0xff] {
+                let mut mutated = sample.clone();
+                mutated[index] = value;
+                let _ = macos_bookmarks::parser::parse_bookmark(&mutated);
+                let _ = macos_bookmarks::anonymize::anonymize(&mutated);
+            }
+        }
+    }
+}
+
+#[test]
+// Parse every sample with random bytes and u32 values overwritten
+fn test_random_mutation_samples() {
+    let mut rng = XorShift(0x2545f4914f6cdd1d);
+    for sample in samples() {
+        for _ in 0..5000 {
+            let mut mutated = sample.clone();
+            let mutations = 1 + rng.next() % 8;
+            for _ in 0..mutations {
+                let index = (rng.next() % mutated.len() as u64) as usize;
+                if rng.next().is_multiple_of(2) || index + 4 > mutated.len() {
+                    mutated[index] = rng.next() as u8;
+                } else {
+                    let value = (rng.next() as u32).to_le_bytes();
+                    mutated[index..index + 4].copy_from_slice(&value);
+                }
+            }
+            let _ = macos_bookmarks::parser::parse_bookmark(&mutated);
+            let _ = macos_bookmarks::anonymize::anonymize(&mutated);
+        }
+    }
+}
+
+#[test]
+// Offsets below the 4 byte TOC offset field must fail instead of underflowing
+fn test_small_offsets() {
+    use macos_bookmarks::{
+        error::BookmarkError,
+        parser::{parse_bookmark, BookmarkParser, ParseMode},
+    };
+
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push("tests/test_data/loginitem.bookmark");
+    let buffer = fs::read(test_location).unwrap();
+    let toc = u32::from_le_bytes(buffer[48..52].try_into().unwrap()) as usize + 48;
+    let count = u32::from_le_bytes(buffer[toc + 16..toc + 20].try_into().unwrap()) as usize;
+
+    for offset in 0..4u32 {
+        let mut mutated = buffer.clone();
+        mutated[48..52].copy_from_slice(&offset.to_le_bytes());
+        let err = parse_bookmark(&mutated).unwrap_err();
+        assert!(matches!(err, BookmarkError::BadBookmarkData), "{err:?}");
+        let _ = macos_bookmarks::anonymize::anonymize(&mutated);
+
+        for record in 0..count {
+            let entry = toc + 20 + record * 12 + 4;
+            let mut mutated = buffer.clone();
+            mutated[entry..entry + 4].copy_from_slice(&offset.to_le_bytes());
+            let err = parse_bookmark(&mutated).unwrap_err();
+            assert!(matches!(err, BookmarkError::BadBookmarkData), "{err:?}");
+            assert!(BookmarkParser::new()
+                .mode(ParseMode::Lenient)
+                .parse(&mutated)
+                .is_ok());
+            let _ = macos_bookmarks::anonymize::anonymize(&mutated);
+        }
+    }
+}
+
+#[test]
+fn test_parse_bookmark_no_panic() {
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push("tests/test_data/loginitem.bookmark");
+    let buffer = fs::read(test_location).unwrap();
+
+    let bookmark = macos_bookmarks::parser::parse_bookmark_no_panic(&buffer).unwrap();
+    assert_eq!(bookmark.path, ["Applications", "Syncthing.app"]);
+    assert!(macos_bookmarks::parser::parse_bookmark_no_panic(&buffer[..60]).is_err());
+}